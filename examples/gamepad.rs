@@ -0,0 +1,112 @@
+//! Gamepad teleoperation, see [`reachy_mini_motor_controller::gamepad`].
+//!
+//! The head pose goes through the kinematics of the control loop: install yours with
+//! `set_kinematics` (e.g. a `RotaryStewart` with the geometry of your robot), without it
+//! only the body yaw is driven. A (south) enables the torque, B (east) disables it.
+
+use std::{thread::sleep, time::Duration};
+
+use clap::Parser;
+use reachy_mini_motor_controller::{
+    cli::PortArgs,
+    control_loop::{MotorCommand, MotorError, ReachyMiniControlLoop},
+    gamepad::{AxisCurve, Button, GamepadTeleop, TeleopMapping},
+    simulation::{Simulation, SimulationConfig},
+};
+
+/// Drive the head and the body with a gamepad
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    port: PortArgs,
+
+    /// Stick values taken as 0 (fraction of the stick travel)
+    #[clap(long, default_value_t = 0.1)]
+    deadzone: f64,
+
+    /// Power of the response curve of the sticks, 1 for linear
+    #[clap(long, default_value_t = 2.0)]
+    exponent: f64,
+
+    /// Frequency of the goals sent (Hz)
+    #[clap(short, long, default_value_t = 50.0)]
+    frequency: f64,
+
+    /// Drive simulated motors instead of the hardware
+    #[clap(long)]
+    simulated: bool,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let args = Args::parse();
+
+    let mut mapping = TeleopMapping::default();
+    for axis in &mut mapping.axes {
+        axis.curve = AxisCurve {
+            deadzone: args.deadzone,
+            exponent: args.exponent,
+        };
+    }
+    let mut teleop = GamepadTeleop::new(mapping)?;
+
+    let control_loop = if args.simulated {
+        ReachyMiniControlLoop::with_controller(
+            Simulation::new(SimulationConfig::default()).controller(),
+            Duration::from_millis(10),
+            None,
+            5,
+            Duration::from_secs(30),
+            args.port.motors()?,
+        )?
+    } else {
+        ReachyMiniControlLoop::new(
+            args.port.port()?,
+            Duration::from_millis(10),
+            None,
+            5,
+            Duration::from_secs(30),
+            args.port.motors()?,
+        )?
+    };
+
+    let period =
+        Duration::try_from_secs_f64(1.0 / args.frequency).map_err(|_| "Invalid frequency")?;
+    let mut head_driven = true;
+    let mut was_pressed = (false, false);
+    println!("Waiting for a gamepad...");
+    loop {
+        sleep(period);
+        let Some(goal) = teleop.poll() else {
+            continue;
+        };
+
+        let pressed = (
+            teleop.is_pressed(Button::South),
+            teleop.is_pressed(Button::East),
+        );
+        if pressed.0 && !was_pressed.0 {
+            control_loop.push_command(MotorCommand::EnableTorque())?;
+        }
+        if pressed.1 && !was_pressed.1 {
+            control_loop.push_command(MotorCommand::DisableTorque())?;
+        }
+        was_pressed = pressed;
+
+        if head_driven {
+            match control_loop.set_head_pose(goal.pose, Some(goal.body_yaw)) {
+                Err(MotorError::NoKinematicsError()) => {
+                    println!("No kinematics installed, only the body yaw is driven");
+                    head_driven = false;
+                }
+                res => res?,
+            }
+        }
+        if !head_driven {
+            control_loop.push_command(MotorCommand::SetBodyRotation {
+                position: goal.body_yaw,
+            })?;
+        }
+    }
+}
@@ -1,31 +1,50 @@
 use std::f64::consts::PI;
 
-use reachy_mini_motor_controller::ReachyMiniMotorController;
+use clap::Parser;
+use reachy_mini_motor_controller::cli::{MotionArgs, PortArgs};
 
-fn main() {
-    let serialport = "/dev/tty.usbmodem58FA0959031";
-    let mut c = ReachyMiniMotorController::new(serialport).unwrap();
+/// Move the joints along a sine around 0 and print the tracking errors
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    port: PortArgs,
 
-    c.enable_torque().unwrap();
+    #[command(flatten)]
+    motion: MotionArgs,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let joints = args.motion.joint_mask()?;
+    let mut c = args.port.open()?;
+
+    // The other joints hold their position
+    let mut goals = c.read_all_positions()?;
+
+    c.enable_torque()?;
 
     let t0 = std::time::Instant::now();
 
-    let amp = 30.0_f64.to_radians();
-    let freq = 0.25;
+    let amp = args.motion.amplitude_rad();
+    let freq = args.motion.frequency;
 
     loop {
         let t = t0.elapsed().as_secs_f64();
         let pos = (2.0 * PI * freq * t).sin() * amp;
+        for (goal, _) in goals.iter_mut().zip(joints).filter(|(_, moves)| *moves) {
+            *goal = pos;
+        }
 
-        c.set_all_goal_positions([pos; 9]).unwrap();
+        c.set_all_goal_positions(goals)?;
 
-        let cur = c.read_all_positions().unwrap();
+        let cur = c.read_all_positions()?;
 
         let errors = cur
             .iter()
-            .zip([pos; 9].iter())
+            .zip(goals.iter())
             .map(|(cur, goal)| (cur - goal).abs())
             .collect::<Vec<_>>();
         println!("Errors: {:?}", errors);
     }
-}
\ No newline at end of file
+}
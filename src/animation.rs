@@ -0,0 +1,389 @@
+//! Import of animations made in animation tools (e.g. exported from Blender) as
+//! [`Trajectory`]s.
+//!
+//! Two formats are read, both giving keyframes by time or frame number with a subset
+//! of the joints, by name (see [`JOINT_NAMES`]), or a head pose instead of the stewart
+//! joints. Joints not animated stay at 0, their home position. Head poses are converted
+//! with a [`Kinematics`], and every position is checked against the joint bounds, so an
+//! animation which cannot be played is rejected on import rather than at the first
+//! keyframe out of reach.
+//!
+//! CSV, with a header line naming the columns:
+//!
+//! ```text
+//! frame,body_rotation,left_antenna,right_antenna,easing
+//! 1,0,0,0,ease_in_out
+//! 25,30,-20,20,linear
+//! ```
+//!
+//! * `time` (s) or `frame` (see [`ImportOptions::fps`]),
+//! * joint names,
+//! * or `x`, `y`, `z` (m), `roll`, `pitch`, `yaw` for the head pose,
+//! * and optionally `easing` (see [`Easing::name`]).
+//!
+//! JSON (with the `json` feature), where `fps` and `units` ("radians" or "degrees")
+//! are optional and override the [`ImportOptions`]:
+//!
+//! ```json
+//! {
+//!   "fps": 24,
+//!   "units": "degrees",
+//!   "keyframes": [
+//!     {"frame": 1, "joints": {"body_rotation": 0}, "head": {"z": 0.01, "pitch": 10}},
+//!     {"frame": 25, "joints": {"body_rotation": 30}, "easing": "ease_out"}
+//!   ]
+//! }
+//! ```
+//!
+//! The times are counted from the first keyframe in both formats.
+
+use std::{fmt, path::Path};
+
+use crate::{
+    kinematics::{HeadPose, Kinematics, KinematicsError},
+    position_bounds::{
+        DEFAULT_POSITION_BOUNDS, JOINT_NAMES, PositionBounds, PositionOutOfBounds,
+        validate_positions,
+    },
+    smoothing::NUM_JOINTS,
+    trajectory::{Easing, InvalidTrajectory, Keyframe, Trajectory},
+};
+
+/// Names of the head pose values: translation (m) then rotation.
+const HEAD_POSE_NAMES: [&str; 6] = ["x", "y", "z", "roll", "pitch", "yaw"];
+
+/// How the values of an animation are read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportOptions {
+    /// Frame rate (frames per second) of the animations timed by frame number.
+    pub fps: f64,
+    /// Whether the joint positions and the head rotation are in degrees (radians if not).
+    pub degrees: bool,
+    /// Bounds every joint position must be within.
+    pub bounds: [PositionBounds; NUM_JOINTS],
+}
+
+impl Default for ImportOptions {
+    /// 24 fps (the default of Blender), radians and the default position bounds.
+    fn default() -> Self {
+        ImportOptions {
+            fps: 24.0,
+            degrees: false,
+            bounds: DEFAULT_POSITION_BOUNDS,
+        }
+    }
+}
+
+/// An animation which cannot be imported.
+#[derive(Debug)]
+pub enum ImportError {
+    /// The file does not follow the format.
+    Format(String),
+    /// The head pose of a keyframe is out of reach.
+    Kinematics {
+        keyframe: usize,
+        error: KinematicsError,
+    },
+    /// A joint position of a keyframe is out of its bounds.
+    OutOfBounds {
+        keyframe: usize,
+        error: PositionOutOfBounds,
+    },
+    /// The keyframes do not form a trajectory, e.g. they are not in time order.
+    Trajectory(InvalidTrajectory),
+}
+
+impl std::error::Error for ImportError {}
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Format(message) => write!(f, "Invalid animation: {}", message),
+            ImportError::Kinematics { keyframe, error } => {
+                write!(f, "Keyframe {}: {}", keyframe, error)
+            }
+            ImportError::OutOfBounds { keyframe, error } => {
+                write!(f, "Keyframe {}: {}", keyframe, error)
+            }
+            ImportError::Trajectory(error) => error.fmt(f),
+        }
+    }
+}
+
+impl From<InvalidTrajectory> for ImportError {
+    fn from(error: InvalidTrajectory) -> Self {
+        ImportError::Trajectory(error)
+    }
+}
+
+/// A keyframe as read from a file, before the unit conversions and the kinematics.
+#[derive(Debug, Default)]
+struct RawKeyframe {
+    time: Option<f64>,
+    frame: Option<f64>,
+    joints: [Option<f64>; NUM_JOINTS],
+    head: [Option<f64>; 6],
+    easing: Easing,
+}
+
+/// Import a CSV animation, see the [module documentation](self).
+///
+/// A `kinematics` is needed if the animation gives head poses.
+pub fn import_csv(
+    text: &str,
+    options: &ImportOptions,
+    kinematics: Option<&dyn Kinematics>,
+) -> Result<Trajectory, ImportError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    let Some((_, header)) = lines.next() else {
+        return Err(ImportError::Format("empty file".to_string()));
+    };
+    let columns: Vec<String> = header
+        .split(',')
+        .map(|column| column.trim().trim_matches('"').to_lowercase())
+        .collect();
+    for column in &columns {
+        let known = ["time", "frame", "easing"].contains(&column.as_str())
+            || JOINT_NAMES.contains(&column.as_str())
+            || HEAD_POSE_NAMES.contains(&column.as_str());
+        if !known {
+            return Err(ImportError::Format(format!("unknown column {:?}", column)));
+        }
+    }
+
+    let mut keyframes = Vec::new();
+    for (number, line) in lines {
+        let values: Vec<&str> = line.split(',').map(str::trim).collect();
+        if values.len() != columns.len() {
+            return Err(ImportError::Format(format!(
+                "line {}: {} values for {} columns",
+                number,
+                values.len(),
+                columns.len()
+            )));
+        }
+        let mut keyframe = RawKeyframe::default();
+        for (column, value) in columns.iter().zip(values) {
+            if column == "easing" {
+                keyframe.easing = Easing::from_name(value).ok_or_else(|| {
+                    ImportError::Format(format!("line {}: unknown easing {:?}", number, value))
+                })?;
+                continue;
+            }
+            let value: f64 = value.parse().map_err(|_| {
+                ImportError::Format(format!(
+                    "line {}: invalid {} value {:?}",
+                    number, column, value
+                ))
+            })?;
+            let slot = match column.as_str() {
+                "time" => &mut keyframe.time,
+                "frame" => &mut keyframe.frame,
+                name => match JOINT_NAMES.iter().position(|joint| *joint == name) {
+                    Some(joint) => &mut keyframe.joints[joint],
+                    None => {
+                        let index = HEAD_POSE_NAMES.iter().position(|n| *n == name);
+                        &mut keyframe.head[index.expect("columns are checked")]
+                    }
+                },
+            };
+            *slot = Some(value);
+        }
+        keyframes.push(keyframe);
+    }
+    build(keyframes, options, kinematics)
+}
+
+/// Import a JSON animation, see the [module documentation](self).
+///
+/// A `kinematics` is needed if the animation gives head poses.
+#[cfg(feature = "json")]
+pub fn import_json(
+    text: &str,
+    options: &ImportOptions,
+    kinematics: Option<&dyn Kinematics>,
+) -> Result<Trajectory, ImportError> {
+    use serde_json::Value;
+
+    fn number(value: &Value, field: &str, keyframe: usize) -> Result<f64, ImportError> {
+        value.as_f64().ok_or_else(|| {
+            ImportError::Format(format!("keyframe {}: {} is not a number", keyframe, field))
+        })
+    }
+
+    let root: Value = serde_json::from_str(text)
+        .map_err(|e| ImportError::Format(format!("invalid JSON: {}", e)))?;
+    let mut options = options.clone();
+    if let Some(fps) = root.get("fps") {
+        options.fps = fps
+            .as_f64()
+            .ok_or_else(|| ImportError::Format("fps is not a number".to_string()))?;
+    }
+    match root.get("units").map(|units| units.as_str()) {
+        None => {}
+        Some(Some("radians")) => options.degrees = false,
+        Some(Some("degrees")) => options.degrees = true,
+        Some(_) => {
+            return Err(ImportError::Format(
+                "units must be \"radians\" or \"degrees\"".to_string(),
+            ));
+        }
+    }
+    let Some(entries) = root.get("keyframes").and_then(Value::as_array) else {
+        return Err(ImportError::Format("no keyframes list".to_string()));
+    };
+
+    let mut keyframes = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        let mut keyframe = RawKeyframe::default();
+        if let Some(time) = entry.get("time") {
+            keyframe.time = Some(number(time, "time", index)?);
+        }
+        if let Some(frame) = entry.get("frame") {
+            keyframe.frame = Some(number(frame, "frame", index)?);
+        }
+        if let Some(joints) = entry.get("joints") {
+            let Some(joints) = joints.as_object() else {
+                return Err(ImportError::Format(format!(
+                    "keyframe {}: joints is not an object",
+                    index
+                )));
+            };
+            for (name, value) in joints {
+                let Some(joint) = JOINT_NAMES.iter().position(|joint| joint == name) else {
+                    return Err(ImportError::Format(format!(
+                        "keyframe {}: unknown joint {:?}",
+                        index, name
+                    )));
+                };
+                keyframe.joints[joint] = Some(number(value, name, index)?);
+            }
+        }
+        if let Some(head) = entry.get("head") {
+            let Some(head) = head.as_object() else {
+                return Err(ImportError::Format(format!(
+                    "keyframe {}: head is not an object",
+                    index
+                )));
+            };
+            for (name, value) in head {
+                let Some(i) = HEAD_POSE_NAMES.iter().position(|n| n == name) else {
+                    return Err(ImportError::Format(format!(
+                        "keyframe {}: unknown head pose value {:?}",
+                        index, name
+                    )));
+                };
+                keyframe.head[i] = Some(number(value, name, index)?);
+            }
+        }
+        if let Some(easing) = entry.get("easing") {
+            keyframe.easing = easing.as_str().and_then(Easing::from_name).ok_or_else(|| {
+                ImportError::Format(format!("keyframe {}: unknown easing {}", index, easing))
+            })?;
+        }
+        keyframes.push(keyframe);
+    }
+    build(keyframes, &options, kinematics)
+}
+
+/// Read and import an animation file, as JSON if its extension is `.json` and as CSV
+/// otherwise.
+pub fn import_file(
+    path: impl AsRef<Path>,
+    options: &ImportOptions,
+    kinematics: Option<&dyn Kinematics>,
+) -> Result<Trajectory, Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)?;
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        #[cfg(feature = "json")]
+        return Ok(import_json(&text, options, kinematics)?);
+        #[cfg(not(feature = "json"))]
+        return Err("JSON animations need the json feature".into());
+    }
+    Ok(import_csv(&text, options, kinematics)?)
+}
+
+/// Convert the raw keyframes to a trajectory, checking the joint bounds.
+fn build(
+    keyframes: Vec<RawKeyframe>,
+    options: &ImportOptions,
+    kinematics: Option<&dyn Kinematics>,
+) -> Result<Trajectory, ImportError> {
+    if !(options.fps > 0.0 && options.fps.is_finite()) {
+        return Err(ImportError::Format(format!(
+            "invalid frame rate ({})",
+            options.fps
+        )));
+    }
+    let angle = |value: f64| {
+        if options.degrees {
+            value.to_radians()
+        } else {
+            value
+        }
+    };
+
+    let mut imported: Vec<Keyframe> = Vec::with_capacity(keyframes.len());
+    let mut start = None;
+    for (index, keyframe) in keyframes.into_iter().enumerate() {
+        let time = match (keyframe.time, keyframe.frame) {
+            (Some(time), None) => time,
+            (None, Some(frame)) => frame / options.fps,
+            _ => {
+                return Err(ImportError::Format(format!(
+                    "keyframe {} needs either a time or a frame",
+                    index
+                )));
+            }
+        };
+        let mut positions: [f64; NUM_JOINTS] =
+            std::array::from_fn(|joint| keyframe.joints[joint].map_or(0.0, angle));
+
+        if keyframe.head.iter().any(Option::is_some) {
+            if keyframe.joints[1..7].iter().any(Option::is_some) {
+                return Err(ImportError::Format(format!(
+                    "keyframe {} gives both a head pose and stewart joints",
+                    index
+                )));
+            }
+            let Some(kinematics) = kinematics else {
+                return Err(ImportError::Format(
+                    "head poses need a kinematics".to_string(),
+                ));
+            };
+            let head = keyframe.head.map(|value| value.unwrap_or(0.0));
+            let pose = HeadPose {
+                translation: [head[0], head[1], head[2]],
+                rotation: [angle(head[3]), angle(head[4]), angle(head[5])],
+            };
+            let stewart = kinematics
+                .inverse(&pose)
+                .map_err(|error| ImportError::Kinematics {
+                    keyframe: index,
+                    error,
+                })?;
+            positions[1..7].copy_from_slice(&stewart);
+        }
+
+        validate_positions(0, &positions, &options.bounds).map_err(|error| {
+            ImportError::OutOfBounds {
+                keyframe: index,
+                error,
+            }
+        })?;
+        let start = *start.get_or_insert(time);
+        imported.push(Keyframe {
+            time: time - start,
+            positions,
+            easing: keyframe.easing,
+        });
+    }
+    Ok(Trajectory::new(imported)?)
+}
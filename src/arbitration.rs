@@ -0,0 +1,99 @@
+//! Arbitration between several named command sources.
+//!
+//! Each source (e.g. an animation engine and a user teleop stream) tags its commands
+//! with its name. A source stays active for a while after each command it sends, and
+//! while it is active, commands from sources with a lower priority are dropped.
+//! Sources with the same priority do not preempt each other (last command wins).
+//!
+//! Commands sent without a source bypass the arbitration.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// How long a default source keeps the control after its last command.
+pub const DEFAULT_SOURCE_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+struct Source {
+    priority: i32,
+    timeout: Duration,
+    last_command: Option<Instant>,
+}
+
+impl Source {
+    fn is_active(&self, now: Instant) -> bool {
+        self.last_command
+            .is_some_and(|t| now.saturating_duration_since(t) < self.timeout)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Arbiter {
+    sources: HashMap<String, Source>,
+}
+
+impl Default for Arbiter {
+    /// `safety` > `teleop` > `animation`.
+    fn default() -> Self {
+        let mut arbiter = Arbiter {
+            sources: HashMap::new(),
+        };
+        arbiter.register("safety", 100, DEFAULT_SOURCE_TIMEOUT);
+        arbiter.register("teleop", 50, DEFAULT_SOURCE_TIMEOUT);
+        arbiter.register("animation", 10, DEFAULT_SOURCE_TIMEOUT);
+        arbiter
+    }
+}
+
+impl Arbiter {
+    /// Register (or update) a source. Higher priorities preempt lower ones.
+    pub fn register(&mut self, name: &str, priority: i32, timeout: Duration) {
+        let last_command = self.sources.get(name).and_then(|s| s.last_command);
+        self.sources.insert(
+            name.to_string(),
+            Source {
+                priority,
+                timeout,
+                last_command,
+            },
+        );
+    }
+
+    /// Give up the control immediately instead of waiting for the source timeout.
+    pub fn release(&mut self, name: &str) {
+        if let Some(source) = self.sources.get_mut(name) {
+            source.last_command = None;
+        }
+    }
+
+    /// Whether a command from this source should be executed, marking the source active if so.
+    ///
+    /// Commands from unregistered sources are always rejected.
+    pub fn accept(&mut self, name: &str, now: Instant) -> bool {
+        let Some(priority) = self.sources.get(name).map(|s| s.priority) else {
+            return false;
+        };
+        let preempted = self
+            .sources
+            .iter()
+            .any(|(other, s)| other != name && s.priority > priority && s.is_active(now));
+        if preempted {
+            return false;
+        }
+        if let Some(source) = self.sources.get_mut(name) {
+            source.last_command = Some(now);
+        }
+        true
+    }
+
+    /// The active source with the highest priority, if any.
+    pub fn active_source(&self, now: Instant) -> Option<&str> {
+        self.sources
+            .iter()
+            .filter(|(_, s)| s.is_active(now))
+            .max_by_key(|(_, s)| s.priority)
+            .map(|(name, _)| name.as_str())
+    }
+}
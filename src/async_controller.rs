@@ -0,0 +1,250 @@
+//! Async version of [`ReachyMiniMotorController`].
+//!
+//! The Dynamixel protocol implementation (rustypot) is blocking, so rather than
+//! re-implementing it on top of an async serial port, the controller is owned by a
+//! single worker thread and each `async fn` sends it a job and awaits the reply.
+//! Async applications can then use the bus without blocking their executor and
+//! without spawning a blocking task per call. Calls are executed in order.
+
+use std::{fmt, thread::JoinHandle, time::Duration};
+
+use tokio::sync::oneshot;
+
+use crate::{MotorControllerError, ReachyMiniMotorController};
+
+type Job = Box<dyn FnOnce(&mut ReachyMiniMotorController) + Send>;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum AsyncControllerError {
+    /// The worker thread is gone (e.g. it panicked).
+    Closed,
+    /// The controller call failed.
+    Controller(MotorControllerError),
+}
+
+impl std::error::Error for AsyncControllerError {}
+impl fmt::Display for AsyncControllerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsyncControllerError::Closed => write!(f, "Motor controller worker is closed!"),
+            AsyncControllerError::Controller(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+pub struct AsyncReachyMiniMotorController {
+    tx: Option<std::sync::mpsc::Sender<Job>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl AsyncReachyMiniMotorController {
+    /// Open the serial port and start the worker thread owning the controller.
+    pub async fn new(serialport: &str) -> Result<Self, AsyncControllerError> {
+        let (tx, rx) = std::sync::mpsc::channel::<Job>();
+        let (ready_tx, ready_rx) = oneshot::channel();
+
+        let serialport = serialport.to_string();
+        let worker = std::thread::spawn(move || {
+            let mut controller = match ReachyMiniMotorController::new(&serialport) {
+                Ok(controller) => {
+                    let _ = ready_tx.send(Ok(()));
+                    controller
+                }
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            for job in rx {
+                job(&mut controller);
+            }
+        });
+
+        ready_rx
+            .await
+            .map_err(|_| AsyncControllerError::Closed)?
+            .map_err(AsyncControllerError::Controller)?;
+
+        Ok(AsyncReachyMiniMotorController {
+            tx: Some(tx),
+            worker: Some(worker),
+        })
+    }
+
+    /// Run any controller call on the worker thread and await its result.
+    pub async fn call<T, F>(&self, f: F) -> Result<T, AsyncControllerError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut ReachyMiniMotorController) -> Result<T, MotorControllerError>
+            + Send
+            + 'static,
+    {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let job: Job = Box::new(move |controller| {
+            let _ = reply_tx.send(f(controller));
+        });
+        self.tx
+            .as_ref()
+            .ok_or(AsyncControllerError::Closed)?
+            .send(job)
+            .map_err(|_| AsyncControllerError::Closed)?;
+
+        reply_rx
+            .await
+            .map_err(|_| AsyncControllerError::Closed)?
+            .map_err(AsyncControllerError::Controller)
+    }
+
+    /// See [`ReachyMiniMotorController::reopen`].
+    pub async fn reopen(&self, timeout: Duration) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.reopen(timeout)).await
+    }
+
+    pub async fn check_missing_ids(&self) -> Result<Vec<u8>, AsyncControllerError> {
+        self.call(|c| c.check_missing_ids()).await
+    }
+
+    pub async fn read_all_voltages(&self) -> Result<[u16; 9], AsyncControllerError> {
+        self.call(|c| c.read_all_voltages()).await
+    }
+
+    pub async fn read_all_positions(&self) -> Result<[f64; 9], AsyncControllerError> {
+        self.call(|c| c.read_all_positions()).await
+    }
+
+    pub async fn set_all_goal_positions(
+        &self,
+        positions: [f64; 9],
+    ) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.set_all_goal_positions(positions))
+            .await
+    }
+
+    pub async fn set_antennas_positions(
+        &self,
+        positions: [f64; 2],
+    ) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.set_antennas_positions(positions))
+            .await
+    }
+
+    pub async fn set_stewart_platform_position(
+        &self,
+        position: [f64; 6],
+    ) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.set_stewart_platform_position(position))
+            .await
+    }
+
+    pub async fn set_body_rotation(&self, position: f64) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.set_body_rotation(position)).await
+    }
+
+    pub async fn is_torque_enabled(&self) -> Result<bool, AsyncControllerError> {
+        self.call(|c| c.is_torque_enabled()).await
+    }
+
+    pub async fn enable_torque(&self) -> Result<(), AsyncControllerError> {
+        self.call(|c| c.enable_torque()).await
+    }
+
+    pub async fn enable_torque_on_ids(&self, ids: Vec<u8>) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.enable_torque_on_ids(&ids)).await
+    }
+
+    pub async fn disable_torque(&self) -> Result<(), AsyncControllerError> {
+        self.call(|c| c.disable_torque()).await
+    }
+
+    pub async fn disable_torque_on_ids(&self, ids: Vec<u8>) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.disable_torque_on_ids(&ids)).await
+    }
+
+    pub async fn set_stewart_platform_goal_current(
+        &self,
+        current: [i16; 6],
+    ) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.set_stewart_platform_goal_current(current))
+            .await
+    }
+
+    pub async fn set_stewart_goal(
+        &self,
+        positions: [f64; 6],
+        currents: [i16; 6],
+    ) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.set_stewart_goal(positions, currents))
+            .await
+    }
+
+    pub async fn read_stewart_platform_current(&self) -> Result<[i16; 6], AsyncControllerError> {
+        self.call(|c| c.read_stewart_platform_current()).await
+    }
+
+    pub async fn set_stewart_platform_operating_mode(
+        &self,
+        mode: u8,
+    ) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.set_stewart_platform_operating_mode(mode))
+            .await
+    }
+
+    pub async fn set_antennas_operating_mode(&self, mode: u8) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.set_antennas_operating_mode(mode))
+            .await
+    }
+
+    pub async fn set_body_rotation_operating_mode(
+        &self,
+        mode: u8,
+    ) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.set_body_rotation_operating_mode(mode))
+            .await
+    }
+
+    pub async fn write_identity(&self, identity: Vec<u8>) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.write_identity(&identity)).await
+    }
+
+    pub async fn read_identity(&self) -> Result<Option<Vec<u8>>, AsyncControllerError> {
+        self.call(|c| c.read_identity()).await
+    }
+
+    pub async fn spin_body(&self, velocity: f64) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.spin_body(velocity)).await
+    }
+
+    pub async fn stop_body_spin(&self) -> Result<(), AsyncControllerError> {
+        self.call(|c| c.stop_body_spin()).await
+    }
+
+    pub async fn set_antennas_floppy(&self) -> Result<(), AsyncControllerError> {
+        self.call(|c| c.set_antennas_floppy()).await
+    }
+
+    pub async fn set_antennas_stiff(&self) -> Result<(), AsyncControllerError> {
+        self.call(|c| c.set_antennas_stiff()).await
+    }
+
+    pub async fn reboot(
+        &self,
+        on_error_status_only: bool,
+        reboot_timeout: Duration,
+    ) -> Result<(), AsyncControllerError> {
+        self.call(move |c| c.reboot(on_error_status_only, reboot_timeout))
+            .await
+    }
+}
+
+impl Drop for AsyncReachyMiniMotorController {
+    fn drop(&mut self) {
+        // Closing the job channel ends the worker once the pending jobs are done
+        self.tx.take();
+        if let Some(worker) = self.worker.take()
+            && worker.join().is_err()
+        {
+            log::error!("Motor controller worker panicked");
+        }
+    }
+}
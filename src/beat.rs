@@ -0,0 +1,92 @@
+//! Beats of the music the robot moves to.
+//!
+//! A [`BeatGrid`] is registered on the control loop (see
+//! [`crate::control_loop::ReachyMiniControlLoop::set_beat_grid`]), then trajectories
+//! played on beat have their keyframes quantized to whole beats and start on the
+//! next one. Beats are timestamped on the monotonic clock (see [`crate::clock`]),
+//! the one of the ALSA playback timestamps.
+
+use std::fmt;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+/// Periodic beats, from a reference beat.
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BeatGrid {
+    /// Monotonic time of a beat (s), any beat of the track can be used.
+    pub first_beat: f64,
+    /// Time between two beats (s).
+    pub period: f64,
+}
+
+/// Beat period or timestamp not forming a valid [`BeatGrid`].
+#[derive(Debug, Clone)]
+pub struct InvalidBeatGrid(pub String);
+
+impl std::error::Error for InvalidBeatGrid {}
+impl fmt::Display for InvalidBeatGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid beat grid: {}", self.0)
+    }
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl BeatGrid {
+    fn __repr__(&self) -> String {
+        format!(
+            "BeatGrid(first_beat={:.3}, period={:.3}, bpm={:.1})",
+            self.first_beat,
+            self.period,
+            self.bpm()
+        )
+    }
+}
+
+impl BeatGrid {
+    pub fn new(first_beat: f64, period: f64) -> Result<Self, InvalidBeatGrid> {
+        if !first_beat.is_finite() {
+            return Err(InvalidBeatGrid(format!(
+                "first beat {} is not finite",
+                first_beat
+            )));
+        }
+        if !(period.is_finite() && period > 0.0) {
+            return Err(InvalidBeatGrid(format!(
+                "period {} is not strictly positive",
+                period
+            )));
+        }
+        Ok(BeatGrid { first_beat, period })
+    }
+
+    /// Beats at `bpm` beats per minute.
+    pub fn from_bpm(first_beat: f64, bpm: f64) -> Result<Self, InvalidBeatGrid> {
+        if !(bpm.is_finite() && bpm > 0.0) {
+            return Err(InvalidBeatGrid(format!(
+                "tempo {} bpm is not strictly positive",
+                bpm
+            )));
+        }
+        Self::new(first_beat, 60.0 / bpm)
+    }
+
+    pub fn bpm(&self) -> f64 {
+        60.0 / self.period
+    }
+
+    /// First beat at or after `time` (monotonic, s).
+    pub fn next_beat(&self, time: f64) -> f64 {
+        self.first_beat + ((time - self.first_beat) / self.period).ceil() * self.period
+    }
+
+    /// Round a duration (s) to the nearest whole number of beats.
+    pub fn quantize(&self, duration: f64) -> f64 {
+        (duration / self.period).round() * self.period
+    }
+}
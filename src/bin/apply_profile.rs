@@ -0,0 +1,74 @@
+//! Restore the canonical Reachy Mini configuration of the motors (see
+//! [`reachy_mini_motor_controller::config_audit`]), e.g. after experimenting with the
+//! Dynamixel Wizard.
+//!
+//! The registers differing from the profile are listed and, once confirmed, written
+//! with the torque off.
+
+use std::io::{BufRead, Write};
+
+use clap::Parser;
+use reachy_mini_motor_controller::{
+    cli::PortArgs,
+    simulation::{Simulation, SimulationConfig},
+};
+
+/// Write the Reachy Mini register profile to all motors
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    port: PortArgs,
+
+    /// Only list the registers which would be written
+    #[clap(long)]
+    dry_run: bool,
+
+    /// Write without asking for confirmation
+    #[clap(short, long)]
+    yes: bool,
+
+    /// Drive simulated motors instead of the hardware
+    #[clap(long)]
+    simulated: bool,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let args = Args::parse();
+
+    let mut c = if args.simulated {
+        Simulation::new(SimulationConfig::default()).controller()
+    } else {
+        args.port.open()?
+    };
+
+    let deviations = c.apply_factory_profile(true)?;
+    if deviations.is_empty() {
+        println!("The motors already match the Reachy Mini profile");
+        return Ok(());
+    }
+    for deviation in &deviations {
+        println!("{}", deviation);
+    }
+    if args.dry_run {
+        return Ok(());
+    }
+
+    if !args.yes {
+        print!(
+            "Write {} registers, with the torque off? [y/N] ",
+            deviations.len()
+        );
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().lock().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            println!("Nothing written");
+            return Ok(());
+        }
+    }
+    let written = c.apply_factory_profile(false)?;
+    println!("{} registers written", written.len());
+    Ok(())
+}
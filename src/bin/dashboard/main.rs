@@ -0,0 +1,218 @@
+//! Local web dashboard: live joint plots, torque toggle and joint sliders.
+//!
+//! Only relies on the standard library so it can be run on a classroom laptop with
+//! nothing else installed: `cargo run --features dashboard --bin dashboard -- --port /dev/ttyACM0`
+//! then open <http://localhost:8080>. Telemetry is streamed with Server-Sent Events.
+//!
+//! Any client can watch, but only the one holding the control token can command the
+//! robot, see [`session`].
+//!
+//! With `--stdio`, no page is served: the robot is driven with JSON-RPC over stdin/stdout
+//! instead, see [`stdio`].
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+    thread::sleep,
+    time::Duration,
+};
+
+use clap::Parser;
+use reachy_mini_motor_controller::control_loop::{
+    FullBodyPosition, MotorCommand, MotorSet, ReachyMiniControlLoop,
+};
+
+mod session;
+mod stdio;
+
+use session::Session;
+
+const INDEX_HTML: &str = include_str!("index.html");
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Serial port to use
+    #[clap(short, long)]
+    port: String,
+
+    /// Address to serve the dashboard on
+    #[clap(short, long, default_value = "127.0.0.1:8080")]
+    address: String,
+
+    /// Frequency of the telemetry sent to the page
+    #[clap(short, long, default_value_t = 20.0)]
+    telemetry_frequency: f64,
+
+    /// Time without command or renewal after which the control token expires (s)
+    #[clap(long, default_value_t = 10.0)]
+    session_timeout: f64,
+
+    /// Speak newline-delimited JSON-RPC on stdin/stdout instead of serving the dashboard
+    #[clap(long)]
+    stdio: bool,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::Builder::from_env(env_logger::Env::default().filter_or("RUST_LOG", "info")).init();
+    let args = Args::parse();
+
+    let control_loop = Arc::new(ReachyMiniControlLoop::new(
+        args.port,
+        Duration::from_millis(10),
+        None,
+        5,
+        Duration::from_secs(30),
+        MotorSet::default(),
+    )?);
+    if args.stdio {
+        return stdio::serve(&control_loop);
+    }
+    let telemetry_period = Duration::from_secs_f64(1.0 / args.telemetry_frequency);
+    let session = Arc::new(Session::new(Duration::from_secs_f64(args.session_timeout)));
+
+    let listener = TcpListener::bind(&args.address)?;
+    log::info!("Dashboard available on http://{}", args.address);
+
+    for stream in listener.incoming().flatten() {
+        let control_loop = control_loop.clone();
+        let session = session.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = handle_connection(stream, &control_loop, &session, telemetry_period) {
+                log::debug!("Connection closed: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    control_loop: &ReachyMiniControlLoop,
+    session: &Session,
+    telemetry_period: Duration,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(stream.try_clone()?);
+    reader.read_line(&mut request_line)?;
+    // Skip the headers, all parameters are in the query string
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let token = query_value(query, "token");
+
+    match (method, path) {
+        ("GET", "/") => respond(&mut stream, "200 OK", "text/html", INDEX_HTML),
+        ("GET", "/events") => stream_telemetry(stream, control_loop, telemetry_period),
+        ("POST", "/session") => match session.acquire(token) {
+            Some(token) => respond(&mut stream, "200 OK", "text/plain", &token),
+            None => respond(
+                &mut stream,
+                "409 Conflict",
+                "text/plain",
+                "another client has the control",
+            ),
+        },
+        ("POST", "/session/release") => {
+            session.release(token);
+            respond(&mut stream, "200 OK", "text/plain", "ok")
+        }
+        ("POST", "/torque" | "/goal") if !session.check(token) => respond(
+            &mut stream,
+            "403 Forbidden",
+            "text/plain",
+            "acquire the control token first",
+        ),
+        ("POST", "/torque") => {
+            let command = match query_value(query, "on") {
+                Some("1") => MotorCommand::EnableTorque(),
+                _ => MotorCommand::DisableTorque(),
+            };
+            control_loop.push_command(command)?;
+            respond(&mut stream, "200 OK", "text/plain", "ok")
+        }
+        ("POST", "/goal") => match parse_goal(query) {
+            Some(positions) => {
+                control_loop.push_command(MotorCommand::SetAllGoalPositions { positions })?;
+                respond(&mut stream, "200 OK", "text/plain", "ok")
+            }
+            None => respond(&mut stream, "400 Bad Request", "text/plain", "bad goal"),
+        },
+        _ => respond(&mut stream, "404 Not Found", "text/plain", "not found"),
+    }
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
+
+/// Send the positions and torque state as Server-Sent Events until the page is closed.
+fn stream_telemetry(
+    mut stream: TcpStream,
+    control_loop: &ReachyMiniControlLoop,
+    period: Duration,
+) -> Result<(), Box<dyn std::error::Error>> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n"
+    )?;
+    loop {
+        let torque = control_loop.is_torque_enabled().unwrap_or(false);
+        let data = match control_loop.get_last_position() {
+            Ok(pos) => format!(
+                "{{\"body_yaw\":{},\"stewart\":{:?},\"antennas\":{:?},\"timestamp\":{},\"torque\":{}}}",
+                pos.body_yaw, pos.stewart, pos.antennas, pos.timestamp, torque
+            ),
+            Err(e) => format!("{{\"error\":{:?},\"torque\":{}}}", e.to_string(), torque),
+        };
+        write!(stream, "data: {}\n\n", data)?;
+        stream.flush()?;
+        sleep(period);
+    }
+}
+
+fn query_value<'a>(query: &'a str, key: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v)
+}
+
+/// Parse `?body_yaw=0.1&stewart=a,b,c,d,e,f&antennas=a,b` (commas may be url-encoded).
+fn parse_goal(query: &str) -> Option<FullBodyPosition> {
+    let list = |key| -> Option<Vec<f64>> {
+        query_value(query, key)?
+            .replace("%2C", ",")
+            .split(',')
+            .map(|v| v.parse().ok())
+            .collect()
+    };
+    let body_yaw = query_value(query, "body_yaw")?.parse().ok()?;
+    let stewart = list("stewart")?;
+    let antennas = list("antennas")?;
+    if stewart.len() != 6 || antennas.len() != 2 {
+        return None;
+    }
+    Some(FullBodyPosition::new(body_yaw, stewart, antennas))
+}
@@ -0,0 +1,91 @@
+//! Control token of the dashboard server, so a single client commands the robot at a
+//! time while the others can only watch.
+//!
+//! A client acquires the token with `POST /session`, then gives it as the `token` query
+//! parameter of its commands. It releases it with `POST /session/release?token=...`, or
+//! the token expires after the session timeout without any command or renewal
+//! (`POST /session?token=...`), so a closed page does not keep the robot.
+
+use std::{
+    hash::{BuildHasher, Hasher, RandomState},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+
+pub struct Session {
+    timeout: Duration,
+    /// Current token, with the last time its holder used it.
+    holder: Mutex<Option<(String, Instant)>>,
+}
+
+impl Session {
+    pub fn new(timeout: Duration) -> Self {
+        Session {
+            timeout,
+            holder: Mutex::new(None),
+        }
+    }
+
+    /// Acquire the token, or renew it if `token` is the current one. None while another
+    /// client holds it.
+    pub fn acquire(&self, token: Option<&str>) -> Option<String> {
+        let mut holder = self.holder.lock().unwrap();
+        let now = Instant::now();
+        match holder.as_mut() {
+            Some((current, last_use)) if now.duration_since(*last_use) < self.timeout => {
+                if token != Some(current.as_str()) {
+                    return None;
+                }
+                *last_use = now;
+                Some(current.clone())
+            }
+            _ => {
+                let token = new_token();
+                *holder = Some((token.clone(), now));
+                Some(token)
+            }
+        }
+    }
+
+    /// Release the token if `token` is the current one.
+    pub fn release(&self, token: Option<&str>) -> bool {
+        let mut holder = self.holder.lock().unwrap();
+        let held = holder
+            .as_ref()
+            .is_some_and(|(current, _)| token == Some(current.as_str()));
+        if held {
+            *holder = None;
+        }
+        held
+    }
+
+    /// Whether `token` is the current one, renewing it if so.
+    pub fn check(&self, token: Option<&str>) -> bool {
+        let mut holder = self.holder.lock().unwrap();
+        let now = Instant::now();
+        match holder.as_mut() {
+            Some((current, last_use))
+                if token == Some(current.as_str())
+                    && now.duration_since(*last_use) < self.timeout =>
+            {
+                *last_use = now;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Unpredictable token, from the random keys of the standard library hasher.
+fn new_token() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    let high = hasher.finish();
+    let low = RandomState::new().hash_one(high);
+    format!("{:016x}{:016x}", high, low)
+}
@@ -0,0 +1,243 @@
+//! JSON-RPC 2.0 over stdin/stdout, one request per line, to drive the robot from apps
+//! without bindings (Node.js, Electron, shell scripts...).
+//!
+//! Methods (positions in rad):
+//! - `get_state`: positions (with their age and freshness), torque and control mode.
+//! - `set_goal` `{"body_yaw": 0.1, "stewart": [..6], "antennas": [..2]}`: goal positions,
+//!   each part is optional.
+//! - `torque` `{"on": true}`: enable or disable the torque of all motors.
+//! - `set_mode` `{"part": "stewart" | "body_rotation" | "antennas", "mode": 3}`: operating
+//!   mode of a part.
+//! - `get_health`, `get_usage`: health report and wear counters.
+//! - `get_tracking` `{"since": 0.0}`: goal and position samples of the tracking stream
+//!   read after `since` (seconds since UNIX epoch), the tracking error of each joint
+//!   being `goal - position`.
+//!
+//! ```text
+//! --> {"jsonrpc": "2.0", "id": 1, "method": "torque", "params": {"on": true}}
+//! <-- {"jsonrpc":"2.0","id":1,"result":true}
+//! ```
+//! Requests without an `id` are notifications and get no response. Logs go to stderr.
+
+use std::io::{BufRead, Write};
+
+use reachy_mini_motor_controller::{
+    control_loop::{FullBodyPosition, MotorCommand, ReachyMiniControlLoop},
+    tracking::DEFAULT_TRACKING_STREAM_SIZE,
+};
+use serde_json::{Value, json};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// First code of the range left to the implementation, used for the motors errors.
+const MOTOR_ERROR: i64 = -32000;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn invalid_params(message: impl Into<String>) -> Self {
+        RpcError {
+            code: INVALID_PARAMS,
+            message: message.into(),
+        }
+    }
+
+    fn motor(e: impl std::fmt::Display) -> Self {
+        RpcError {
+            code: MOTOR_ERROR,
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Answer the requests read on stdin until it is closed.
+pub fn serve(control_loop: &ReachyMiniControlLoop) -> Result<(), Box<dyn std::error::Error>> {
+    control_loop.set_tracking_stream_size(DEFAULT_TRACKING_STREAM_SIZE);
+    let mut stdout = std::io::stdout().lock();
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_line(control_loop, &line) {
+            writeln!(stdout, "{}", response)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// The response to a request line, `None` for a notification.
+fn handle_line(control_loop: &ReachyMiniControlLoop, line: &str) -> Option<Value> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            return Some(error_response(
+                Value::Null,
+                RpcError {
+                    code: PARSE_ERROR,
+                    message: e.to_string(),
+                },
+            ));
+        }
+    };
+    let id = request.get("id").cloned();
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        return Some(error_response(
+            id.unwrap_or(Value::Null),
+            RpcError {
+                code: INVALID_REQUEST,
+                message: "Expected an object with a method".to_string(),
+            },
+        ));
+    };
+
+    let params = request.get("params").unwrap_or(&Value::Null);
+    let result = call(control_loop, method, params);
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err(e) => error_response(id, e),
+    })
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": error.code, "message": error.message},
+    })
+}
+
+fn call(
+    control_loop: &ReachyMiniControlLoop,
+    method: &str,
+    params: &Value,
+) -> Result<Value, RpcError> {
+    let push = |command| control_loop.push_command(command).map_err(RpcError::motor);
+
+    match method {
+        "get_state" => {
+            let position = control_loop.get_last_position().map_err(RpcError::motor)?;
+            Ok(json!({
+                "body_yaw": position.body_yaw,
+                "stewart": position.stewart,
+                "antennas": position.antennas,
+                "timestamp": position.timestamp,
+                "monotonic_timestamp": position.monotonic_timestamp,
+                "age": position.age,
+                "fresh": position.fresh,
+                "torque": control_loop.is_torque_enabled().map_err(RpcError::motor)?,
+                "control_mode": control_loop.get_control_mode().map_err(RpcError::motor)?,
+            }))
+        }
+        "set_goal" => {
+            let body_yaw = match params.get("body_yaw") {
+                Some(value) => Some(
+                    value
+                        .as_f64()
+                        .ok_or_else(|| RpcError::invalid_params("body_yaw must be a number"))?,
+                ),
+                None => None,
+            };
+            let stewart = numbers::<6>(params, "stewart")?;
+            let antennas = numbers::<2>(params, "antennas")?;
+
+            match (body_yaw, stewart, antennas) {
+                (None, None, None) => {
+                    return Err(RpcError::invalid_params(
+                        "Expected body_yaw, stewart or antennas",
+                    ));
+                }
+                // In a single command so the parts start moving at the same tick
+                (Some(body_yaw), Some(stewart), Some(antennas)) => {
+                    let mut joints = [0.0; 9];
+                    joints[0] = body_yaw;
+                    joints[1..7].copy_from_slice(&stewart);
+                    joints[7..].copy_from_slice(&antennas);
+                    push(MotorCommand::SetAllGoalPositions {
+                        positions: FullBodyPosition::from_joints(joints, 0.0),
+                    })?;
+                }
+                (body_yaw, stewart, antennas) => {
+                    if let Some(position) = body_yaw {
+                        push(MotorCommand::SetBodyRotation { position })?;
+                    }
+                    if let Some(position) = stewart {
+                        push(MotorCommand::SetStewartPlatformPosition { position })?;
+                    }
+                    if let Some(positions) = antennas {
+                        push(MotorCommand::SetAntennasPositions { positions })?;
+                    }
+                }
+            }
+            Ok(Value::Bool(true))
+        }
+        "torque" => {
+            let on = params
+                .get("on")
+                .and_then(Value::as_bool)
+                .ok_or_else(|| RpcError::invalid_params("on must be a boolean"))?;
+            push(if on {
+                MotorCommand::EnableTorque()
+            } else {
+                MotorCommand::DisableTorque()
+            })?;
+            Ok(Value::Bool(true))
+        }
+        "set_mode" => {
+            let mode = params
+                .get("mode")
+                .and_then(Value::as_u64)
+                .and_then(|mode| u8::try_from(mode).ok())
+                .ok_or_else(|| RpcError::invalid_params("mode must be an integer"))?;
+            let command = match params.get("part").and_then(Value::as_str) {
+                Some("stewart") => MotorCommand::SetStewartPlatformOperatingMode { mode },
+                Some("body_rotation") => MotorCommand::SetBodyRotationOperatingMode { mode },
+                Some("antennas") => MotorCommand::SetAntennasOperatingMode { mode },
+                _ => {
+                    return Err(RpcError::invalid_params(
+                        "part must be stewart, body_rotation or antennas",
+                    ));
+                }
+            };
+            push(command)?;
+            Ok(Value::Bool(true))
+        }
+        "get_health" => serde_json::to_value(control_loop.get_health()).map_err(RpcError::motor),
+        "get_usage" => {
+            serde_json::to_value(control_loop.get_usage_counters()).map_err(RpcError::motor)
+        }
+        "get_tracking" => {
+            let since = match params.get("since") {
+                Some(value) => value
+                    .as_f64()
+                    .ok_or_else(|| RpcError::invalid_params("since must be a number"))?,
+                None => 0.0,
+            };
+            serde_json::to_value(control_loop.get_tracking_since(since)).map_err(RpcError::motor)
+        }
+        _ => Err(RpcError {
+            code: METHOD_NOT_FOUND,
+            message: format!("Unknown method: {}", method),
+        }),
+    }
+}
+
+/// `params[key]` as an array of `N` numbers, `None` if missing.
+fn numbers<const N: usize>(params: &Value, key: &str) -> Result<Option<[f64; N]>, RpcError> {
+    let Some(value) = params.get(key) else {
+        return Ok(None);
+    };
+    value
+        .as_array()
+        .and_then(|values| values.iter().map(Value::as_f64).collect::<Option<Vec<_>>>())
+        .and_then(|values| values.try_into().ok())
+        .map(Some)
+        .ok_or_else(|| RpcError::invalid_params(format!("{} must be {} numbers", key, N)))
+}
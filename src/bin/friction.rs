@@ -0,0 +1,193 @@
+//! Friction identification.
+//!
+//! Moves each joint back and forth at several constant velocities while recording its
+//! current, fits its Coulomb and viscous friction and stores them in the calibration file
+//! (see [`reachy_mini_motor_controller::calibration`]).
+
+use std::{path::Path, thread::sleep, time::Duration};
+
+use clap::Parser;
+use reachy_mini_motor_controller::{
+    calibration::{Calibration, JointFriction},
+    cli::{PortArgs, joint_index},
+    control_loop::{FullBodyPosition, MotorCommand, ReachyMiniControlLoop},
+    position_bounds::JOINT_NAMES,
+    simulation::{Simulation, SimulationConfig},
+    smoothing::NUM_JOINTS,
+};
+
+/// Part of each pass, at both ends, left out while the joint accelerates.
+const TRANSIENT: f64 = 0.2;
+
+/// Sweep joints at several velocities and store their friction in the calibration file
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    port: PortArgs,
+
+    /// Joints to identify, one after the other (all by default)
+    #[clap(short, long, value_delimiter = ',')]
+    joints: Vec<String>,
+
+    /// Velocities of the sweeps (degrees/s)
+    #[clap(short, long, value_delimiter = ',', default_value = "10,30,60,120")]
+    velocities: Vec<f64>,
+
+    /// Half range of the sweeps around the start position (degrees)
+    #[clap(short, long, default_value_t = 15.0)]
+    range: f64,
+
+    /// Calibration file to store the friction in
+    #[clap(short, long, default_value = "calibration.txt")]
+    calibration: String,
+
+    /// Drive simulated motors instead of the hardware
+    #[clap(long)]
+    simulated: bool,
+}
+
+/// Move `joint` from its goal to `to` at `velocity` (rad/s), returns the (velocity, current)
+/// samples of the steady part of the motion.
+fn pass(
+    control_loop: &ReachyMiniControlLoop,
+    goals: &mut [f64; NUM_JOINTS],
+    joint: usize,
+    to: f64,
+    velocity: f64,
+    period: Duration,
+) -> Result<Vec<(f64, f64)>, Box<dyn std::error::Error>> {
+    let from = goals[joint];
+    let velocity = velocity.copysign(to - from);
+    let steps = ((to - from) / velocity / period.as_secs_f64())
+        .ceil()
+        .max(1.0) as usize;
+
+    let mut samples = Vec::new();
+    for step in 1..=steps {
+        let progress = step as f64 / steps as f64;
+        goals[joint] = from + (to - from) * progress;
+        control_loop.push_command(MotorCommand::SetAllGoalPositions {
+            positions: FullBodyPosition::from_joints(*goals, 0.0),
+        })?;
+        sleep(period);
+
+        if (TRANSIENT..=1.0 - TRANSIENT).contains(&progress) {
+            let current = control_loop.read_all_currents()?[joint];
+            samples.push((velocity, current as f64));
+        }
+    }
+    Ok(samples)
+}
+
+fn identify(
+    control_loop: &ReachyMiniControlLoop,
+    joint: usize,
+    velocities: &[f64],
+    range: f64,
+    period: Duration,
+) -> Result<Option<JointFriction>, Box<dyn std::error::Error>> {
+    let mut goals = control_loop.get_last_position()?.joints();
+    let center = goals[joint];
+    let (low, high) = (center - range, center + range);
+
+    let mut samples = Vec::new();
+    for &velocity in velocities {
+        pass(control_loop, &mut goals, joint, low, velocity, period)?;
+        samples.extend(pass(
+            control_loop,
+            &mut goals,
+            joint,
+            high,
+            velocity,
+            period,
+        )?);
+        samples.extend(pass(
+            control_loop,
+            &mut goals,
+            joint,
+            low,
+            velocity,
+            period,
+        )?);
+    }
+    pass(
+        control_loop,
+        &mut goals,
+        joint,
+        center,
+        velocities[0],
+        period,
+    )?;
+
+    Ok(JointFriction::fit(&samples))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let args = Args::parse();
+
+    let joints = if args.joints.is_empty() {
+        (0..NUM_JOINTS).collect()
+    } else {
+        args.joints
+            .iter()
+            .map(|joint| joint_index(joint))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    if args.velocities.len() < 2 || args.velocities.iter().any(|v| *v <= 0.0) {
+        return Err("At least two strictly positive velocities are needed".into());
+    }
+    let velocities: Vec<f64> = args.velocities.iter().map(|v| v.to_radians()).collect();
+    let path = Path::new(&args.calibration);
+    let mut calibration = Calibration::load_or_default(path)?;
+
+    let period = Duration::from_millis(10);
+    let control_loop = if args.simulated {
+        ReachyMiniControlLoop::with_controller(
+            Simulation::new(SimulationConfig::default()).controller(),
+            period,
+            None,
+            5,
+            Duration::from_secs(30),
+            args.port.motors()?,
+        )?
+    } else {
+        ReachyMiniControlLoop::new(
+            args.port.port()?,
+            period,
+            None,
+            5,
+            Duration::from_secs(30),
+            args.port.motors()?,
+        )?
+    };
+
+    control_loop.push_command(MotorCommand::EnableTorque())?;
+    let res = joints.iter().try_for_each(|&joint| {
+        let friction = identify(
+            &control_loop,
+            joint,
+            &velocities,
+            args.range.to_radians(),
+            period,
+        )?;
+        match friction {
+            Some(friction) => {
+                println!(
+                    "{}: coulomb {:.1} mA, viscous {:.2} mA/(rad/s)",
+                    JOINT_NAMES[joint], friction.coulomb, friction.viscous
+                );
+                calibration.friction[joint] = Some(friction);
+            }
+            None => println!("{}: not enough samples to fit", JOINT_NAMES[joint]),
+        }
+        Ok::<(), Box<dyn std::error::Error>>(())
+    });
+    control_loop.push_command(MotorCommand::DisableTorque())?;
+    res?;
+
+    calibration.save(path)?;
+    println!("Friction stored in {}", args.calibration);
+    Ok(())
+}
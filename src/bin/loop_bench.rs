@@ -3,7 +3,7 @@ use std::{f64::consts::PI, thread::sleep, time::Duration};
 // Create args struct
 use clap::Parser;
 use reachy_mini_motor_controller::control_loop::{
-    FullBodyPosition, MotorCommand, ReachyMiniControlLoop,
+    FullBodyPosition, MotorCommand, MotorSet, ReachyMiniControlLoop,
 };
 
 #[derive(Parser, Debug)]
@@ -30,6 +30,7 @@ fn main() {
         Some(Duration::from_secs(1)),
         5,
         Duration::from_secs(30),
+        MotorSet::default(),
     )
     .unwrap();
 
@@ -56,6 +57,7 @@ fn main() {
                     antennas: [target_pos; 2],
                     stewart: [target_pos; 6],
                     timestamp: 0.0,
+                    monotonic_timestamp: 0.0,
                 },
             })
             .unwrap();
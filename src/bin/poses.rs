@@ -0,0 +1,155 @@
+//! Named poses of the poses file (see [`reachy_mini_motor_controller::poses`]): list them,
+//! save the current posture, move to one or remove one, and fold the robot for transport.
+
+use std::{path::Path, thread::sleep, time::Duration};
+
+use clap::{Parser, Subcommand};
+use reachy_mini_motor_controller::{
+    cli::PortArgs,
+    control_loop::{MotorCommand, ReachyMiniControlLoop},
+    poses::{DEFAULT_POSES_FILE, PoseLibrary},
+    position_bounds::JOINT_NAMES,
+    simulation::{Simulation, SimulationConfig},
+};
+
+/// Save and recall named poses shared with the apps
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    port: PortArgs,
+
+    /// Poses file
+    #[clap(short, long, default_value = DEFAULT_POSES_FILE)]
+    file: String,
+
+    /// Drive simulated motors instead of the hardware
+    #[clap(long)]
+    simulated: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the poses and their joint positions (degrees)
+    List,
+    /// Save the current positions as a pose, e.g. after posing the robot by hand
+    Save { name: String },
+    /// Move to a pose, then turn the torque off
+    Goto {
+        name: String,
+
+        /// Duration of the move (s)
+        #[clap(short, long, default_value_t = 2.0)]
+        duration: f64,
+
+        /// Keep the torque on once the pose is reached
+        #[clap(long)]
+        keep_torque: bool,
+    },
+    /// Remove a pose
+    Remove { name: String },
+    /// Fold the robot to the transport pose and turn the torque off
+    Transport {
+        /// Duration of the move (s)
+        #[clap(short, long, default_value_t = 3.0)]
+        duration: f64,
+    },
+    /// Move back to the positions before the transport pose
+    WakeUp {
+        /// Duration of the move (s)
+        #[clap(short, long, default_value_t = 3.0)]
+        duration: f64,
+    },
+}
+
+fn open_loop(args: &Args) -> Result<ReachyMiniControlLoop, Box<dyn std::error::Error>> {
+    let period = Duration::from_millis(10);
+    let control_loop = if args.simulated {
+        ReachyMiniControlLoop::with_controller(
+            Simulation::new(SimulationConfig::default()).controller(),
+            period,
+            None,
+            5,
+            Duration::from_secs(30),
+            args.port.motors()?,
+        )?
+    } else {
+        ReachyMiniControlLoop::new(
+            args.port.port()?,
+            period,
+            None,
+            5,
+            Duration::from_secs(30),
+            args.port.motors()?,
+        )?
+    };
+    control_loop.set_poses_file(&args.file)?;
+    Ok(control_loop)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let args = Args::parse();
+    let path = Path::new(&args.file);
+
+    match &args.command {
+        Command::List => {
+            let poses = PoseLibrary::load_or_default(path)?;
+            println!("{:<20} {}", "pose", JOINT_NAMES.join(" "));
+            for name in poses.names() {
+                let positions: Vec<String> = poses
+                    .get(name)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|p| format!("{:.1}", p.to_degrees()))
+                    .collect();
+                println!("{:<20} {}", name, positions.join(" "));
+            }
+        }
+        Command::Save { name } => {
+            open_loop(&args)?.save_pose(name)?;
+            println!("Pose {} saved in {}", name, args.file);
+        }
+        Command::Goto {
+            name,
+            duration,
+            keep_torque,
+        } => {
+            let duration =
+                Duration::try_from_secs_f64(*duration).map_err(|_| "Invalid duration")?;
+            let control_loop = open_loop(&args)?;
+            control_loop.push_command(MotorCommand::EnableTorque())?;
+            let res = control_loop.goto_pose(name, duration);
+            if res.is_ok() {
+                sleep(duration + Duration::from_millis(500));
+            }
+            if res.is_err() || !keep_torque {
+                control_loop.push_command(MotorCommand::DisableTorque())?;
+            }
+            res?;
+        }
+        Command::Transport { duration } => {
+            let duration =
+                Duration::try_from_secs_f64(*duration).map_err(|_| "Invalid duration")?;
+            open_loop(&args)?.prepare_for_transport(duration, false)?;
+            println!("Ready for transport, the torque is off");
+        }
+        Command::WakeUp { duration } => {
+            let duration =
+                Duration::try_from_secs_f64(*duration).map_err(|_| "Invalid duration")?;
+            open_loop(&args)?.wake_up(duration)?;
+        }
+        Command::Remove { name } => {
+            let mut poses = PoseLibrary::load(path)?;
+            if poses.remove(name).is_none() {
+                return Err(format!("Unknown pose: {}", name).into());
+            }
+            poses.save(path)?;
+            println!("Pose {} removed from {}", name, args.file);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! Benchmark the position reads on the actual hardware (see
+//! [`reachy_mini_motor_controller::read_tuning`]) and report the best read strategy and
+//! return delay time for the control loop.
+//!
+//! The return delay times are only swept with the torque off, the best one is written
+//! to the motors.
+
+use clap::Parser;
+use reachy_mini_motor_controller::{
+    cli::PortArgs,
+    read_tuning::{DEFAULT_TUNING_SAMPLES, auto_tune_reads},
+    simulation::{Simulation, SimulationConfig},
+};
+
+/// Time the position reads with each read strategy and return delay time
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    port: PortArgs,
+
+    /// Return delay times to try, in units of 2 µs (comma separated)
+    #[clap(short, long, value_delimiter = ',', default_value = "0,1,5,25")]
+    return_delay_times: Vec<u8>,
+
+    /// Number of reads timed for each configuration
+    #[clap(short, long, default_value_t = DEFAULT_TUNING_SAMPLES)]
+    samples: usize,
+
+    /// Drive simulated motors instead of the hardware
+    #[clap(long)]
+    simulated: bool,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let args = Args::parse();
+
+    let mut c = if args.simulated {
+        Simulation::new(SimulationConfig::default()).controller()
+    } else {
+        args.port.open()?
+    };
+    if c.read_all_torque_enabled()?.contains(&true) {
+        println!("The torque is on, the return delay times are left unchanged");
+    }
+
+    let tuning = auto_tune_reads(&mut c, &args.return_delay_times, args.samples)?;
+    println!(
+        "{:<10} {:>12} {:>9} {:>10} {:>10} {:>10}",
+        "strategy", "return delay", "failures", "mean (ms)", "p99 (ms)", "max (ms)"
+    );
+    for benchmark in &tuning.benchmarks {
+        println!(
+            "{:<10} {:>12} {:>9} {:>10.3} {:>10.3} {:>10.3}",
+            benchmark.strategy.name(),
+            benchmark
+                .return_delay_time
+                .map_or("unchanged".to_string(), |delay| format!(
+                    "{} µs",
+                    2 * delay as u32
+                )),
+            benchmark.failures,
+            benchmark.mean * 1000.0,
+            benchmark.p99 * 1000.0,
+            benchmark.max * 1000.0,
+        );
+    }
+    println!(
+        "Best: {} strategy{}, set it with set_read_strategy(\"{}\")",
+        tuning.best.strategy,
+        tuning
+            .best
+            .return_delay_time
+            .map_or(String::new(), |delay| format!(
+                ", return delay time {} µs written to the motors",
+                2 * delay as u32
+            )),
+        tuning.best.strategy
+    );
+    Ok(())
+}
@@ -0,0 +1,302 @@
+//! Thermal soak test.
+//!
+//! Moves the joints along a sine for a fraction (duty cycle) of each cycle and holds them
+//! the rest of the time, for each duty cycle in turn, while logging the temperatures and
+//! currents of all motors. The phases run back to back so the heat carries over, as in a
+//! continuous operation. A report tells for each duty cycle whether the temperatures
+//! stabilized below the limit, i.e. whether apps can sustain it.
+
+use std::{
+    f64::consts::PI,
+    io::Write,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use reachy_mini_motor_controller::{
+    cli::{MotionArgs, PortArgs},
+    control_loop::{FullBodyPosition, MotorCommand, ReachyMiniControlLoop},
+    position_bounds::JOINT_NAMES,
+    simulation::{Simulation, SimulationConfig},
+    smoothing::NUM_JOINTS,
+};
+
+/// Temperature slope (°C/min) below which a motor is considered stabilized.
+const STABLE_SLOPE: f64 = 0.1;
+
+/// Run duty cycles for a long time and report the temperatures and currents of the motors
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    port: PortArgs,
+
+    #[command(flatten)]
+    motion: MotionArgs,
+
+    /// Fractions of each cycle spent moving, one phase each
+    #[clap(short, long, value_delimiter = ',', default_value = "0.25,0.5,0.75,1")]
+    duty_cycles: Vec<f64>,
+
+    /// Duration of each phase (min)
+    #[clap(long, default_value_t = 10.0)]
+    phase_duration: f64,
+
+    /// Duration of a cycle, moving then holding (s)
+    #[clap(long, default_value_t = 20.0)]
+    cycle_period: f64,
+
+    /// Period of the temperature and current logs (s)
+    #[clap(long, default_value_t = 1.0)]
+    sample_period: f64,
+
+    /// Temperature (°C) stopping the test
+    #[clap(long, default_value_t = 65)]
+    max_temperature: u8,
+
+    /// CSV file to log the samples to
+    #[clap(short, long, default_value = "soak.csv")]
+    output: String,
+
+    /// File to also write the report to
+    #[clap(long)]
+    report: Option<String>,
+
+    /// Drive simulated motors instead of the hardware
+    #[clap(long)]
+    simulated: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    /// Since the start of the phase (s).
+    time: f64,
+    temperatures: [u8; NUM_JOINTS],
+    currents: [i16; NUM_JOINTS],
+}
+
+/// Thermal behavior of a motor during a phase.
+#[derive(Debug, Clone, Copy)]
+struct JointSummary {
+    max_temperature: u8,
+    end_temperature: u8,
+    /// Over the last third of the phase (°C/min).
+    slope: f64,
+    mean_current: f64,
+    peak_current: i16,
+}
+
+fn summarize(samples: &[Sample], joint: usize) -> JointSummary {
+    let temperature = |s: &Sample| s.temperatures[joint];
+    let current = |s: &Sample| s.currents[joint].saturating_abs();
+
+    // Least squares slope of the end of the phase
+    let end_time = samples.last().map_or(0.0, |s| s.time);
+    let tail: Vec<(f64, f64)> = samples
+        .iter()
+        .filter(|s| s.time >= end_time * 2.0 / 3.0)
+        .map(|s| (s.time, temperature(s) as f64))
+        .collect();
+    let n = tail.len() as f64;
+    let mean_t = tail.iter().map(|(t, _)| t).sum::<f64>() / n;
+    let mean_y = tail.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = tail.iter().map(|(t, y)| (t - mean_t) * (y - mean_y)).sum();
+    let variance: f64 = tail.iter().map(|(t, _)| (t - mean_t).powi(2)).sum();
+    let slope = if variance > 0.0 {
+        covariance / variance * 60.0
+    } else {
+        0.0
+    };
+
+    JointSummary {
+        max_temperature: samples.iter().map(temperature).max().unwrap_or(0),
+        end_temperature: samples.last().map_or(0, temperature),
+        slope,
+        mean_current: samples.iter().map(|s| current(s) as f64).sum::<f64>() / samples.len() as f64,
+        peak_current: samples.iter().map(current).max().unwrap_or(0),
+    }
+}
+
+/// Report of a phase, `aborted` if the temperature limit was reached.
+fn report_phase(
+    duty_cycle: f64,
+    samples: &[Sample],
+    aborted: bool,
+    max_temperature: u8,
+) -> Vec<String> {
+    let summaries: Vec<JointSummary> = (0..NUM_JOINTS).map(|j| summarize(samples, j)).collect();
+    let verdict = if aborted {
+        format!("reached the {} °C limit", max_temperature)
+    } else if summaries.iter().all(|s| s.slope < STABLE_SLOPE) {
+        "stable".to_string()
+    } else {
+        "still heating up, run a longer phase".to_string()
+    };
+
+    let mut lines = vec![
+        format!(
+            "Duty cycle {:.0}% ({:.1} min): {}",
+            duty_cycle * 100.0,
+            samples.last().map_or(0.0, |s| s.time) / 60.0,
+            verdict
+        ),
+        format!(
+            "  {:<15} {:>8} {:>8} {:>12} {:>14} {:>14}",
+            "joint", "max °C", "end °C", "slope °C/min", "mean |I| mA", "peak |I| mA"
+        ),
+    ];
+    for (name, s) in JOINT_NAMES.iter().zip(&summaries) {
+        lines.push(format!(
+            "  {:<15} {:>8} {:>8} {:>12.2} {:>14.0} {:>14}",
+            name, s.max_temperature, s.end_temperature, s.slope, s.mean_current, s.peak_current
+        ));
+    }
+    lines
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let args = Args::parse();
+
+    let joints = args.motion.joint_mask()?;
+    if args.duty_cycles.iter().any(|d| !(0.0..=1.0).contains(d)) {
+        return Err("The duty cycles must be between 0 and 1".into());
+    }
+    let phase_duration = Duration::try_from_secs_f64(args.phase_duration * 60.0)
+        .map_err(|_| "Invalid phase duration")?;
+    let sample_period =
+        Duration::try_from_secs_f64(args.sample_period).map_err(|_| "Invalid sample period")?;
+    if args.cycle_period <= 0.0 {
+        return Err("The cycle period must be strictly positive".into());
+    }
+
+    let period = Duration::from_millis(10);
+    let control_loop = if args.simulated {
+        ReachyMiniControlLoop::with_controller(
+            Simulation::new(SimulationConfig::default()).controller(),
+            period,
+            None,
+            5,
+            Duration::from_secs(30),
+            args.port.motors()?,
+        )?
+    } else {
+        ReachyMiniControlLoop::new(
+            args.port.port()?,
+            period,
+            None,
+            5,
+            Duration::from_secs(30),
+            args.port.motors()?,
+        )?
+    };
+
+    let mut output = std::fs::File::create(&args.output)?;
+    let columns: Vec<String> = ["temperature", "current"]
+        .iter()
+        .flat_map(|quantity| {
+            JOINT_NAMES
+                .iter()
+                .map(move |j| format!("{}_{}", quantity, j))
+        })
+        .collect();
+    writeln!(output, "time_s,duty_cycle,moving,{}", columns.join(","))?;
+
+    let center = control_loop.get_last_position()?.joints();
+    control_loop.push_command(MotorCommand::EnableTorque())?;
+
+    let w = 2.0 * PI * args.motion.frequency;
+    let amplitude = args.motion.amplitude_rad();
+    let start = Instant::now();
+    // Only runs while moving, so the motion resumes where it stopped
+    let mut motion_time = 0.0;
+    let mut goals = center;
+    let mut report = Vec::new();
+
+    let res = (|| {
+        for &duty_cycle in &args.duty_cycles {
+            println!(
+                "Running the {:.0}% duty cycle for {} min",
+                duty_cycle * 100.0,
+                args.phase_duration
+            );
+            let phase_start = Instant::now();
+            let mut last_tick = phase_start;
+            let mut next_sample = phase_start;
+            let mut samples = Vec::new();
+            let mut aborted = false;
+
+            while phase_start.elapsed() < phase_duration {
+                let now = Instant::now();
+                let in_cycle = phase_start.elapsed().as_secs_f64() % args.cycle_period;
+                let moving = in_cycle < duty_cycle * args.cycle_period;
+                if moving {
+                    motion_time += (now - last_tick).as_secs_f64();
+                    for ((goal, center), _) in goals
+                        .iter_mut()
+                        .zip(center)
+                        .zip(joints)
+                        .filter(|(_, moves)| *moves)
+                    {
+                        *goal = center + amplitude * (w * motion_time).sin();
+                    }
+                    control_loop.push_command(MotorCommand::SetAllGoalPositions {
+                        positions: FullBodyPosition::from_joints(goals, 0.0),
+                    })?;
+                }
+                last_tick = now;
+
+                if now >= next_sample {
+                    next_sample += sample_period;
+                    let sample = Sample {
+                        time: (now - phase_start).as_secs_f64(),
+                        temperatures: control_loop.read_all_temperatures()?,
+                        currents: control_loop.read_all_currents()?,
+                    };
+                    let values: Vec<String> = sample
+                        .temperatures
+                        .iter()
+                        .map(u8::to_string)
+                        .chain(sample.currents.iter().map(i16::to_string))
+                        .collect();
+                    writeln!(
+                        output,
+                        "{:.3},{},{},{}",
+                        (now - start).as_secs_f64(),
+                        duty_cycle,
+                        moving as u8,
+                        values.join(",")
+                    )?;
+                    samples.push(sample);
+
+                    let hottest = sample.temperatures.iter().max().copied().unwrap_or(0);
+                    if hottest >= args.max_temperature {
+                        aborted = true;
+                        break;
+                    }
+                }
+                sleep(period);
+            }
+
+            if !samples.is_empty() {
+                let lines = report_phase(duty_cycle, &samples, aborted, args.max_temperature);
+                lines.iter().for_each(|line| println!("{}", line));
+                report.extend(lines);
+            }
+            if aborted {
+                break;
+            }
+        }
+        Ok::<(), Box<dyn std::error::Error>>(())
+    })();
+    control_loop.push_command(MotorCommand::DisableTorque())?;
+    res?;
+
+    println!("Samples written to {}", args.output);
+    if let Some(path) = &args.report {
+        std::fs::write(path, report.join("\n") + "\n")?;
+        println!("Report written to {}", path);
+    }
+    Ok(())
+}
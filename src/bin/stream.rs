@@ -0,0 +1,146 @@
+//! Goals streamed from stdin.
+//!
+//! Each line holds a timestamp (s) followed by the goals of the 9 joints, in joint order,
+//! separated by spaces or commas:
+//! ```text
+//! 0.00 0 0 0 0 0 0 0 0 0
+//! 0.02 0.01 0 0 0 0 0 0 0.1 -0.1
+//! ```
+//! so goals can be piped from any language or from recorded logs (e.g. CSV files, whose
+//! header line is skipped). Empty lines and lines starting with `#` are ignored.
+//!
+//! The goals are sent at their timestamp, counted from the first line, or as soon as they
+//! are read with `--live`. The joints first move to the first goal in `--ramp` seconds.
+
+use std::{
+    io::BufRead,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use reachy_mini_motor_controller::{
+    cli::PortArgs,
+    control_loop::{FullBodyPosition, MotorCommand, ReachyMiniControlLoop},
+    simulation::{Simulation, SimulationConfig},
+    trajectory::{Keyframe, Trajectory, parse_keyframe},
+};
+
+/// Send the goals read on stdin (timestamp and 9 joint goals per line) to the motors
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    port: PortArgs,
+
+    /// The goals are in degrees instead of radians
+    #[clap(long)]
+    degrees: bool,
+
+    /// Playback speed of the timestamps, e.g. 2 plays twice as fast
+    #[clap(short, long, default_value_t = 1.0)]
+    speed: f64,
+
+    /// Send each goal as soon as it is read, ignoring the timestamps
+    #[clap(long)]
+    live: bool,
+
+    /// Duration of the move to the first goal (s)
+    #[clap(long, default_value_t = 1.0)]
+    ramp: f64,
+
+    /// How long to hold the last goal before turning the torque off (s)
+    #[clap(long, default_value_t = 1.0)]
+    hold: f64,
+
+    /// Drive simulated motors instead of the hardware
+    #[clap(long)]
+    simulated: bool,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let args = Args::parse();
+
+    if args.speed.is_nan() || args.speed <= 0.0 {
+        return Err("The speed must be strictly positive".into());
+    }
+    let ramp = Duration::try_from_secs_f64(args.ramp).map_err(|_| "Invalid ramp duration")?;
+    let hold = Duration::try_from_secs_f64(args.hold).map_err(|_| "Invalid hold duration")?;
+
+    let period = Duration::from_millis(10);
+    let control_loop = if args.simulated {
+        ReachyMiniControlLoop::with_controller(
+            Simulation::new(SimulationConfig::default()).controller(),
+            period,
+            None,
+            5,
+            Duration::from_secs(30),
+            args.port.motors()?,
+        )?
+    } else {
+        ReachyMiniControlLoop::new(
+            args.port.port()?,
+            period,
+            None,
+            5,
+            Duration::from_secs(30),
+            args.port.motors()?,
+        )?
+    };
+
+    control_loop.push_command(MotorCommand::EnableTorque())?;
+    let mut sent = 0;
+    let res = (|| {
+        // Start instant and timestamp of the first goal
+        let mut start: Option<(Instant, f64)> = None;
+        for (index, line) in std::io::stdin().lock().lines().enumerate() {
+            let line = line?;
+            let (time, mut goals) = match parse_keyframe(&line) {
+                Ok(Some(keyframe)) => (keyframe.time, keyframe.positions),
+                Ok(None) => continue,
+                // CSV header
+                Err(_) if index == 0 => continue,
+                Err(e) => return Err(format!("Line {}: {}", index + 1, e).into()),
+            };
+            if args.degrees {
+                goals = goals.map(f64::to_radians);
+            }
+
+            match start {
+                None => {
+                    let current = control_loop.get_last_position()?.joints();
+                    if !ramp.is_zero() {
+                        let trajectory =
+                            Trajectory::new(vec![Keyframe::new(ramp.as_secs_f64(), goals)])?;
+                        control_loop.play_trajectory(trajectory.starting_from(current))?;
+                        sleep(ramp);
+                    }
+                    start = Some((Instant::now(), time));
+                }
+                Some((start, first_time)) if !args.live => {
+                    // Late goals, e.g. while the input catches up, are sent right away
+                    let delay = Duration::try_from_secs_f64((time - first_time) / args.speed)
+                        .ok()
+                        .and_then(|offset| (start + offset).checked_duration_since(Instant::now()));
+                    if let Some(delay) = delay {
+                        sleep(delay);
+                    }
+                }
+                Some(_) => {}
+            }
+
+            control_loop.push_command(MotorCommand::SetAllGoalPositions {
+                positions: FullBodyPosition::from_joints(goals, 0.0),
+            })?;
+            sent += 1;
+        }
+        sleep(hold);
+        Ok::<(), Box<dyn std::error::Error>>(())
+    })();
+    control_loop.push_command(MotorCommand::DisableTorque())?;
+    res?;
+
+    println!("{} goals sent", sent);
+    Ok(())
+}
@@ -0,0 +1,258 @@
+//! Frequency response measurement.
+//!
+//! Drives each selected joint along sines of increasing frequency (stepped sine sweep)
+//! around its current position, fits the measured response at each frequency and
+//! exports the gain and phase (Bode data) as CSV.
+
+use std::{
+    f64::consts::PI,
+    io::Write,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use reachy_mini_motor_controller::{
+    cli::{PortArgs, joint_index},
+    clock::wall_now,
+    control_loop::{FullBodyPosition, MotorCommand, ReachyMiniControlLoop},
+    position_bounds::JOINT_NAMES,
+    simulation::{Simulation, SimulationConfig},
+    smoothing::NUM_JOINTS,
+};
+
+/// Measure the frequency response of joints and export Bode data
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    port: PortArgs,
+
+    /// Joints to measure, one after the other
+    #[clap(
+        short,
+        long,
+        value_delimiter = ',',
+        default_value = "stewart_1,stewart_2,stewart_3,stewart_4,stewart_5,stewart_6"
+    )]
+    joints: Vec<String>,
+
+    /// Amplitude of the sines (degrees)
+    #[clap(short, long, default_value_t = 3.0)]
+    amplitude: f64,
+
+    /// Lowest frequency (Hz)
+    #[clap(long, default_value_t = 0.5)]
+    start_frequency: f64,
+
+    /// Highest frequency (Hz)
+    #[clap(long, default_value_t = 10.0)]
+    end_frequency: f64,
+
+    /// Number of frequencies, log-spaced between the lowest and the highest
+    #[clap(short, long, default_value_t = 12)]
+    frequencies: usize,
+
+    /// Number of measured periods at each frequency, after one period to settle
+    #[clap(short, long, default_value_t = 5)]
+    cycles: usize,
+
+    /// CSV file to write the Bode data to
+    #[clap(short, long, default_value = "bode.csv")]
+    output: String,
+
+    /// Drive simulated motors instead of the hardware
+    #[clap(long)]
+    simulated: bool,
+}
+
+/// Response of a joint at one frequency.
+#[derive(Debug, Clone, Copy)]
+struct FrequencyResponse {
+    frequency: f64,
+    /// Measured amplitude over commanded amplitude.
+    gain: f64,
+    /// Phase of the measure relative to the command (rad), negative for a lag.
+    phase: f64,
+}
+
+/// Least squares fit of `y = a sin(wt) + b cos(wt) + c` on `samples` (t, y), returns (a, b).
+fn fit_sine(samples: &[(f64, f64)], w: f64) -> Option<(f64, f64)> {
+    // Normal equations of the 3 parameters
+    let mut m = [[0.0; 3]; 3];
+    let mut v = [0.0; 3];
+    for (t, y) in samples {
+        let basis = [(w * t).sin(), (w * t).cos(), 1.0];
+        for (row, bi) in m.iter_mut().zip(basis) {
+            for (cell, bj) in row.iter_mut().zip(basis) {
+                *cell += bi * bj;
+            }
+        }
+        for (vi, bi) in v.iter_mut().zip(basis) {
+            *vi += bi * y;
+        }
+    }
+
+    // Cramer's rule
+    let det = |m: &[[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let d = det(&m);
+    if d.abs() < 1e-12 {
+        return None;
+    }
+    let solve = |column: usize| {
+        let mut mc = m;
+        for (row, vi) in mc.iter_mut().zip(v) {
+            row[column] = vi;
+        }
+        det(&mc) / d
+    };
+    Some((solve(0), solve(1)))
+}
+
+fn measure(
+    control_loop: &ReachyMiniControlLoop,
+    joint: usize,
+    center: [f64; NUM_JOINTS],
+    amplitude: f64,
+    frequency: f64,
+    cycles: usize,
+) -> Result<FrequencyResponse, Box<dyn std::error::Error>> {
+    const WRITE_PERIOD: Duration = Duration::from_millis(10);
+
+    let w = 2.0 * PI * frequency;
+    let period = 1.0 / frequency;
+    let duration = period * (cycles + 1) as f64;
+
+    let t0 = wall_now();
+    let start = Instant::now();
+    let mut goals = center;
+    while start.elapsed().as_secs_f64() < duration {
+        goals[joint] = center[joint] + amplitude * (w * start.elapsed().as_secs_f64()).sin();
+        control_loop.push_command(MotorCommand::SetAllGoalPositions {
+            positions: FullBodyPosition::from_joints(goals, 0.0),
+        })?;
+        sleep(WRITE_PERIOD);
+    }
+
+    // Skip the first period, while the response settles
+    let samples: Vec<(f64, f64)> = control_loop
+        .get_since(t0 + period)
+        .iter()
+        .map(|position| (position.timestamp - t0, position.joints()[joint]))
+        .filter(|(t, _)| *t <= duration)
+        .collect();
+    let (a, b) = fit_sine(&samples, w).ok_or("Not enough feedback to fit the response")?;
+
+    Ok(FrequencyResponse {
+        frequency,
+        gain: a.hypot(b) / amplitude,
+        phase: b.atan2(a),
+    })
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let args = Args::parse();
+
+    let joints = args
+        .joints
+        .iter()
+        .map(|joint| joint_index(joint))
+        .collect::<Result<Vec<_>, _>>()?;
+    if !(args.start_frequency > 0.0 && args.end_frequency >= args.start_frequency) {
+        return Err("The frequencies must be strictly positive and in increasing order".into());
+    }
+    let frequencies: Vec<f64> = (0..args.frequencies)
+        .map(|i| {
+            let ratio = args.end_frequency / args.start_frequency;
+            let x = i as f64 / (args.frequencies.max(2) - 1) as f64;
+            args.start_frequency * ratio.powf(x)
+        })
+        .collect();
+
+    let period = Duration::from_millis(10);
+    let control_loop = if args.simulated {
+        ReachyMiniControlLoop::with_controller(
+            Simulation::new(SimulationConfig::default()).controller(),
+            period,
+            None,
+            5,
+            Duration::from_secs(30),
+            args.port.motors()?,
+        )?
+    } else {
+        ReachyMiniControlLoop::new(
+            args.port.port()?,
+            period,
+            None,
+            5,
+            Duration::from_secs(30),
+            args.port.motors()?,
+        )?
+    };
+
+    // Keep the whole measure at the lowest frequency
+    let longest = (args.cycles + 1) as f64 / args.start_frequency;
+    control_loop.set_history_size((longest / period.as_secs_f64()).ceil() as usize + 100)?;
+
+    let center = control_loop.get_last_position()?.joints();
+    control_loop.push_command(MotorCommand::EnableTorque())?;
+    control_loop.push_command(MotorCommand::SetAllGoalPositions {
+        positions: FullBodyPosition::from_joints(center, 0.0),
+    })?;
+    sleep(Duration::from_secs(1));
+
+    let mut output = std::fs::File::create(&args.output)?;
+    writeln!(output, "joint,frequency_hz,gain,gain_db,phase_deg")?;
+    let amplitude = args.amplitude.to_radians();
+    let res = joints.iter().try_for_each(|&joint| {
+        println!("{}", JOINT_NAMES[joint]);
+        let mut previous_phase = 0.0;
+        for &frequency in &frequencies {
+            let mut response = measure(
+                &control_loop,
+                joint,
+                center,
+                amplitude,
+                frequency,
+                args.cycles,
+            )?;
+            // Unwrap, the lag only grows with the frequency
+            while response.phase - previous_phase > PI {
+                response.phase -= 2.0 * PI;
+            }
+            previous_phase = response.phase;
+            let gain_db = 20.0 * response.gain.log10();
+            println!(
+                "  {:7.3} Hz: gain {:6.2} dB, phase {:7.1} deg",
+                response.frequency,
+                gain_db,
+                response.phase.to_degrees()
+            );
+            writeln!(
+                output,
+                "{},{},{},{},{}",
+                JOINT_NAMES[joint],
+                response.frequency,
+                response.gain,
+                gain_db,
+                response.phase.to_degrees()
+            )?;
+        }
+        // Back to the center before the next joint
+        control_loop.push_command(MotorCommand::SetAllGoalPositions {
+            positions: FullBodyPosition::from_joints(center, 0.0),
+        })?;
+        sleep(Duration::from_millis(500));
+        Ok::<(), Box<dyn std::error::Error>>(())
+    });
+    control_loop.push_command(MotorCommand::DisableTorque())?;
+    res?;
+
+    println!("Bode data written to {}", args.output);
+    Ok(())
+}
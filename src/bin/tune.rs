@@ -0,0 +1,253 @@
+//! Step-response tuning assistant.
+//!
+//! Commands steps to a joint, measures the response from the control loop feedback
+//! and suggests position PID gains for its motor.
+
+use std::{thread::sleep, time::Duration};
+
+use clap::Parser;
+use reachy_mini_motor_controller::{
+    cli::{PortArgs, joint_index},
+    clock::wall_now,
+    control_loop::{FullBodyPosition, MotorCommand, ReachyMiniControlLoop},
+    position_bounds::JOINT_NAMES,
+    simulation::{Simulation, SimulationConfig},
+};
+
+/// Maximum gain accepted by the XL330.
+// https://emanual.robotis.com/docs/en/dxl/x/xl330-m288/#position-pid-gain
+const MAX_GAIN: u16 = 16383;
+/// Band around the target in which the joint is considered settled (fraction of the step).
+const SETTLING_BAND: f64 = 0.02;
+
+/// Command steps to a joint and suggest PID gains from its response
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    port: PortArgs,
+
+    /// Joint to tune, e.g. right_antenna
+    #[clap(short, long)]
+    joint: String,
+
+    /// Size of the steps (degrees)
+    #[clap(short, long, default_value_t = 10.0)]
+    step: f64,
+
+    /// Time given to the joint to settle after each step (s)
+    #[clap(short, long, default_value_t = 1.0)]
+    duration: f64,
+
+    /// Number of steps, alternately away from and back to the start position
+    #[clap(short, long, default_value_t = 4)]
+    count: usize,
+
+    /// Rise time (10% to 90% of the step) to aim for (s)
+    #[clap(short, long, default_value_t = 0.15)]
+    target_rise_time: f64,
+
+    /// Write the suggested gains to the motor
+    #[clap(long)]
+    apply: bool,
+
+    /// Drive simulated motors instead of the hardware
+    #[clap(long)]
+    simulated: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct StepResponse {
+    /// From 10% to 90% of the step (s), `None` if never reached.
+    rise_time: Option<f64>,
+    /// Beyond the target, as a fraction of the step.
+    overshoot: f64,
+    /// Time to stay within [`SETTLING_BAND`] (s), `None` if not settled at the end.
+    settling_time: Option<f64>,
+    /// Distance to the target at the end of the step (rad).
+    steady_state_error: f64,
+}
+
+/// Response to a step from `start` to `target`, `samples` being (time since the step, position).
+fn analyze(samples: &[(f64, f64)], start: f64, target: f64) -> StepResponse {
+    let step = target - start;
+    let progress = |position: f64| (position - start) / step;
+    let first_reaching = |fraction: f64| {
+        samples
+            .iter()
+            .find(|(_, position)| progress(*position) >= fraction)
+            .map(|(t, _)| *t)
+    };
+
+    let rise_time = first_reaching(0.1)
+        .zip(first_reaching(0.9))
+        .map(|(t10, t90)| t90 - t10);
+    let overshoot = samples
+        .iter()
+        .map(|(_, position)| progress(*position) - 1.0)
+        .fold(0.0, f64::max);
+    let settling_time = match samples
+        .iter()
+        .rposition(|(_, position)| (progress(*position) - 1.0).abs() > SETTLING_BAND)
+    {
+        None => samples.first().map(|(t, _)| *t),
+        Some(last_out) => samples.get(last_out + 1).map(|(t, _)| *t),
+    };
+
+    // Average over the last 10% of the samples
+    let tail = &samples[samples.len() - samples.len().div_ceil(10)..];
+    let end_position = tail.iter().map(|(_, position)| position).sum::<f64>() / tail.len() as f64;
+
+    StepResponse {
+        rise_time,
+        overshoot,
+        settling_time,
+        steady_state_error: (target - end_position).abs(),
+    }
+}
+
+/// Suggested (P, I, D) gains, with the reason of each change.
+fn suggest(
+    (p, i, d): (u16, u16, u16),
+    responses: &[StepResponse],
+    step: f64,
+    target_rise_time: f64,
+) -> ((u16, u16, u16), Vec<String>) {
+    let n = responses.len() as f64;
+    let overshoot = responses.iter().map(|r| r.overshoot).sum::<f64>() / n;
+    let steady_state_error = responses.iter().map(|r| r.steady_state_error).sum::<f64>() / n;
+    let slowest_rise = responses
+        .iter()
+        .map(|r| r.rise_time.unwrap_or(f64::INFINITY))
+        .fold(0.0, f64::max);
+    let scale = |gain: u16, factor: f64| ((gain as f64 * factor).round() as u16).min(MAX_GAIN);
+
+    let (mut p, mut i, mut d) = (p, i, d);
+    let mut reasons = Vec::new();
+    if overshoot > 0.1 {
+        p = scale(p, 0.8);
+        d = (d + (d / 4).max(20)).min(MAX_GAIN);
+        reasons.push(format!(
+            "overshoot of {:.0}%: lower P and raise D",
+            overshoot * 100.0
+        ));
+    } else if overshoot < 0.02 && slowest_rise > target_rise_time {
+        p = scale(p, 1.25).max(p.saturating_add(1).min(MAX_GAIN));
+        reasons.push(format!(
+            "rise time of {:.3} s above {:.3} s without overshoot: raise P",
+            slowest_rise, target_rise_time
+        ));
+    }
+    // Above 5% of the step
+    if steady_state_error > 0.05 * step.abs() {
+        i = if i == 0 { 50 } else { scale(i, 1.5) };
+        reasons.push(format!(
+            "steady state error of {:.2} deg: raise I",
+            steady_state_error.to_degrees()
+        ));
+    }
+    ((p, i, d), reasons)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let args = Args::parse();
+
+    let joint = joint_index(&args.joint)?;
+    let step_duration =
+        Duration::try_from_secs_f64(args.duration).map_err(|_| "Invalid step duration")?;
+
+    let period = Duration::from_millis(10);
+    let control_loop = if args.simulated {
+        ReachyMiniControlLoop::with_controller(
+            Simulation::new(SimulationConfig::default()).controller(),
+            period,
+            None,
+            5,
+            Duration::from_secs(30),
+            args.port.motors()?,
+        )?
+    } else {
+        ReachyMiniControlLoop::new(
+            args.port.port()?,
+            period,
+            None,
+            5,
+            Duration::from_secs(30),
+            args.port.motors()?,
+        )?
+    };
+    let id = control_loop.get_motor_name_id()[JOINT_NAMES[joint]];
+
+    // Keep the whole response of a step
+    control_loop.set_history_size((2.0 * args.duration / period.as_secs_f64()).ceil() as usize)?;
+    let gains = control_loop.async_read_pid_gains(id)?;
+    println!(
+        "Tuning {} (id {}), current gains P={} I={} D={}",
+        JOINT_NAMES[joint], id, gains.0, gains.1, gains.2
+    );
+
+    let mut goals = control_loop.get_last_position()?.joints();
+    let start = goals[joint];
+    control_loop.push_command(MotorCommand::EnableTorque())?;
+    control_loop.push_command(MotorCommand::SetAllGoalPositions {
+        positions: FullBodyPosition::from_joints(goals, 0.0),
+    })?;
+    sleep(step_duration);
+
+    let step = args.step.to_radians();
+    let mut responses = Vec::with_capacity(args.count);
+    for n in 0..args.count {
+        let from = goals[joint];
+        goals[joint] = if n % 2 == 0 { start + step } else { start };
+
+        let t0 = wall_now();
+        control_loop.push_command(MotorCommand::SetAllGoalPositions {
+            positions: FullBodyPosition::from_joints(goals, 0.0),
+        })?;
+        sleep(step_duration);
+
+        let samples: Vec<(f64, f64)> = control_loop
+            .get_since(t0)
+            .iter()
+            .map(|position| (position.timestamp - t0, position.joints()[joint]))
+            .collect();
+        if samples.is_empty() {
+            return Err("No feedback received during the step".into());
+        }
+        let response = analyze(&samples, from, goals[joint]);
+        println!(
+            "Step {}: rise time {}, overshoot {:.1}%, settling time {}, steady state error {:.2} deg",
+            n + 1,
+            format_time(response.rise_time),
+            response.overshoot * 100.0,
+            format_time(response.settling_time),
+            response.steady_state_error.to_degrees()
+        );
+        responses.push(response);
+    }
+    control_loop.push_command(MotorCommand::DisableTorque())?;
+
+    let (suggested, reasons) = suggest(gains, &responses, step, args.target_rise_time);
+    if reasons.is_empty() {
+        println!("The response is fine, keep the current gains");
+        return Ok(());
+    }
+    for reason in &reasons {
+        println!("- {}", reason);
+    }
+    println!(
+        "Suggested gains P={} I={} D={}",
+        suggested.0, suggested.1, suggested.2
+    );
+    if args.apply {
+        control_loop.async_write_pid_gains(id, suggested.0, suggested.1, suggested.2)?;
+        println!("Gains written, run again to check the new response");
+    }
+
+    Ok(())
+}
+
+fn format_time(time: Option<f64>) -> String {
+    time.map_or_else(|| "-".to_string(), |t| format!("{:.3} s", t))
+}
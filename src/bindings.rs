@@ -1,7 +1,40 @@
 use std::{collections::HashMap, sync::mpsc::channel, time::Duration};
 
-use crate::control_loop::{
-    ControlLoopStats, FullBodyPosition, MotorCommand, ReachyMiniControlLoop,
+use crate::{
+    arbitration::DEFAULT_SOURCE_TIMEOUT,
+    beat::BeatGrid,
+    clock::{ClockOffset, monotonic_now},
+    config_audit::{ConfigDeviation, ConfigProfile},
+    control_loop::{
+        CommandLatency, ControlLoopStats, DEFAULT_STATS_MAX_SAMPLES, FullBodyPosition,
+        LastPosition, MissingMotorsPolicy, MotorCommand, MotorError, MotorSet,
+        ReachyMiniControlLoop, StateSnapshot, StatsRetention,
+    },
+    diagnostics::Diagnostics,
+    dry_run::{DryRunLog, DryRunWrite},
+    duty_cycle::{DEFAULT_CONTINUOUS_CURRENT, DEFAULT_THERMAL_TIME_CONSTANT},
+    error_stats::{ErrorCounter, ErrorStats},
+    events::Event,
+    fault_journal::{DEFAULT_MAX_JOURNAL_FILES, DEFAULT_MAX_JOURNAL_SIZE, read_fault_journal},
+    firmware::{Feature, FirmwareInfo},
+    health::HealthReport,
+    kinematics::{GazeSplit, HeadPose, Kinematics, KinematicsError},
+    last_state::LastState,
+    position_bounds::{JOINT_NAMES, PositionBounds},
+    read_filter::DEFAULT_MAX_READ_VELOCITY,
+    read_schedule::ReadSchedule,
+    read_tuning::{
+        DEFAULT_RETURN_DELAY_TIMES, DEFAULT_TUNING_SAMPLES, ReadBenchmark, ReadStrategy, ReadTuning,
+    },
+    registry::{RobotInfo, RobotRegistry, discover_robots},
+    safe_mode::SafeModeLimits,
+    simulation::{JointDynamics, Simulation, SimulationConfig},
+    smoothing::{JointLimits, NUM_JOINTS},
+    state_buffer::StateBuffer,
+    touch::Touch,
+    tracking::{DEFAULT_TRACKING_STREAM_SIZE, TrackingSample},
+    trajectory::{Track, Trajectory, TrajectoryResult, TrajectoryStatus, VelocityFeedforward},
+    usage::UsageCounters,
 };
 
 use pyo3::{prelude::*, types::PyBytes};
@@ -10,7 +43,138 @@ use pyo3_stub_gen::{
     derive::{gen_stub_pyclass, gen_stub_pymethods},
 };
 
-use crate::ReachyMiniMotorController as Controller;
+use crate::{HomingConfig, MotorControllerError, ReachyMiniMotorController as Controller};
+
+/// Exceptions raised for the errors of the controller and of the control loop, so scripts
+/// can tell a bus glitch worth retrying from a problem to fix. They all derive from
+/// `RuntimeError`, which was raised for all of them before.
+mod exceptions {
+    use pyo3::exceptions::PyRuntimeError;
+
+    pyo3_stub_gen::create_exception!(
+        reachy_mini_motor_controller,
+        MotorControllerError,
+        PyRuntimeError,
+        "Failure of the motor controller."
+    );
+    pyo3_stub_gen::create_exception!(
+        reachy_mini_motor_controller,
+        SerialError,
+        MotorControllerError,
+        "The serial port is missing, lost or used by another process."
+    );
+    pyo3_stub_gen::create_exception!(
+        reachy_mini_motor_controller,
+        BusTimeoutError,
+        MotorControllerError,
+        "No answer from the motors in time, usually a transient bus glitch."
+    );
+    pyo3_stub_gen::create_exception!(
+        reachy_mini_motor_controller,
+        MotorNotRespondingError,
+        BusTimeoutError,
+        "A single motor addressed did not answer."
+    );
+    pyo3_stub_gen::create_exception!(
+        reachy_mini_motor_controller,
+        UnexpectedResponseError,
+        MotorControllerError,
+        "A corrupted or unexpected answer of the motors, usually a transient bus glitch."
+    );
+    pyo3_stub_gen::create_exception!(
+        reachy_mini_motor_controller,
+        OutOfBoundsError,
+        MotorControllerError,
+        "A value outside of the accepted range, e.g. a goal beyond the position bounds."
+    );
+    pyo3_stub_gen::create_exception!(
+        reachy_mini_motor_controller,
+        UnsupportedError,
+        MotorControllerError,
+        "An operation the motors do not support, e.g. with an outdated firmware."
+    );
+    pyo3_stub_gen::create_exception!(
+        reachy_mini_motor_controller,
+        WriteError,
+        MotorControllerError,
+        "A write not applied by the motors, e.g. an EEPROM value not read back as written."
+    );
+    pyo3_stub_gen::create_exception!(
+        reachy_mini_motor_controller,
+        RejectedError,
+        MotorControllerError,
+        "A command refused by the control loop, e.g. while the power is lost or the \
+         configuration locked."
+    );
+}
+
+/// Exception of the class matching `e`, with `message`.
+fn controller_error(e: &MotorControllerError, message: String) -> PyErr {
+    match e {
+        MotorControllerError::SerialIo(_)
+        | MotorControllerError::SerialPort(_)
+        | MotorControllerError::PortInUse(_) => exceptions::SerialError::new_err(message),
+        MotorControllerError::Timeout => exceptions::BusTimeoutError::new_err(message),
+        MotorControllerError::MotorNotResponding { .. } => {
+            exceptions::MotorNotRespondingError::new_err(message)
+        }
+        MotorControllerError::Checksum
+        | MotorControllerError::IncorrectId { .. }
+        | MotorControllerError::UnexpectedResponse(_)
+        | MotorControllerError::ImplausibleRead(_) => {
+            exceptions::UnexpectedResponseError::new_err(message)
+        }
+        MotorControllerError::OutOfBounds(_) | MotorControllerError::Kinematics(_) => {
+            exceptions::OutOfBoundsError::new_err(message)
+        }
+        MotorControllerError::Unsupported(_) => exceptions::UnsupportedError::new_err(message),
+        MotorControllerError::GroupWrite(_) | MotorControllerError::WriteVerification(_) => {
+            exceptions::WriteError::new_err(message)
+        }
+        MotorControllerError::Rejected(_) => exceptions::RejectedError::new_err(message),
+        MotorControllerError::Other(_) => exceptions::MotorControllerError::new_err(message),
+    }
+}
+
+impl From<MotorControllerError> for PyErr {
+    fn from(e: MotorControllerError) -> PyErr {
+        controller_error(&e, e.to_string())
+    }
+}
+
+impl From<MotorError> for PyErr {
+    fn from(e: MotorError) -> PyErr {
+        let message = e.to_string();
+        match e {
+            MotorError::ControllerError(e)
+            | MotorError::HomingError(e)
+            | MotorError::ReadTuningError(e) => controller_error(&e, message),
+            MotorError::PortNotFound(_)
+            | MotorError::CouldNotOpenPort(_)
+            | MotorError::PortInUse(..) => exceptions::SerialError::new_err(message),
+            MotorError::ConfigurationLocked() | MotorError::NoKinematicsError() => {
+                exceptions::RejectedError::new_err(message)
+            }
+            _ => pyo3::exceptions::PyRuntimeError::new_err(message),
+        }
+    }
+}
+
+/// Simulated motors moving at most at `max_velocity` (rad/s), with a uniform `noise` (rad)
+/// on the positions read.
+fn simulation(max_velocity: f64, noise: f64) -> PyResult<Simulation> {
+    if !(max_velocity.is_finite() && max_velocity > 0.0 && noise.is_finite() && noise >= 0.0) {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "Invalid simulated dynamics: max velocity {} rad/s, noise {} rad",
+            max_velocity, noise
+        )));
+    }
+    Ok(Simulation::new(SimulationConfig::uniform(JointDynamics {
+        max_velocity,
+        noise,
+        ..Default::default()
+    })))
+}
 
 #[gen_stub_pyclass]
 #[pyclass(frozen)]
@@ -26,24 +190,57 @@ impl ReachyMiniMotorController {
     ///
     /// # Arguments
     /// * `serialport` - Path to (Unix) or COM ID (Windows) of the serial port device.
+    /// * `simulated` - Drive simulated motors instead of the hardware, `serialport` is ignored
+    ///   then. They follow their goals like the real ones, e.g. to develop an app without a robot.
+    /// * `simulated_max_velocity` - Maximum velocity of the simulated joints (rad/s).
+    /// * `simulated_noise` - Amplitude of the uniform noise on the simulated positions read (rad).
     #[new]
-    fn new(serialport: String) -> PyResult<Self> {
-        let inner = Controller::new(&serialport)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+    #[pyo3(signature = (
+        serialport = None,
+        simulated = false,
+        simulated_max_velocity = JointDynamics::default().max_velocity,
+        simulated_noise = 0.0,
+    ))]
+    fn new(
+        serialport: Option<String>,
+        simulated: bool,
+        simulated_max_velocity: f64,
+        simulated_noise: f64,
+    ) -> PyResult<Self> {
+        let inner = match (simulated, serialport) {
+            (true, _) => simulation(simulated_max_velocity, simulated_noise)?.controller(),
+            (false, Some(serialport)) => Controller::new(&serialport).map_err(PyErr::from)?,
+            (false, None) => {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "A serial port is required, unless simulated=True",
+                ));
+            }
+        };
         Ok(ReachyMiniMotorController {
             inner: std::sync::Mutex::new(inner),
         })
     }
 
+    /// Close and open the serial port again, e.g. after a USB reset.
+    ///
+    /// # Arguments
+    /// * `timeout` - Maximum time to wait for the port to be back.
+    #[pyo3(signature = (timeout = Duration::from_secs(2)))]
+    fn reopen(&self, timeout: Duration) -> PyResult<()> {
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+
+        inner.reopen(timeout).map_err(PyErr::from)
+    }
+
     /// Is torque enabled on all motors
     fn is_torque_enabled(&self) -> PyResult<bool> {
         let mut inner = self.inner.lock().map_err(|_| {
             pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
         })?;
 
-        inner
-            .is_torque_enabled()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+        inner.is_torque_enabled().map_err(PyErr::from)
     }
 
     /// Enable torque on all motors.
@@ -52,9 +249,7 @@ impl ReachyMiniMotorController {
             pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
         })?;
 
-        inner
-            .enable_torque()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        inner.enable_torque().map_err(PyErr::from)?;
         Ok(())
     }
 
@@ -64,9 +259,7 @@ impl ReachyMiniMotorController {
             pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
         })?;
 
-        inner
-            .enable_torque_on_ids(&ids)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        inner.enable_torque_on_ids(&ids).map_err(PyErr::from)?;
         Ok(())
     }
 
@@ -76,9 +269,7 @@ impl ReachyMiniMotorController {
             pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
         })?;
 
-        inner
-            .disable_torque()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        inner.disable_torque().map_err(PyErr::from)?;
         Ok(())
     }
 
@@ -87,9 +278,7 @@ impl ReachyMiniMotorController {
         let mut inner = self.inner.lock().map_err(|_| {
             pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
         })?;
-        inner
-            .disable_torque_on_ids(&ids)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        inner.disable_torque_on_ids(&ids).map_err(PyErr::from)?;
         Ok(())
     }
 
@@ -99,9 +288,7 @@ impl ReachyMiniMotorController {
             pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
         })?;
 
-        inner
-            .read_all_positions()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+        inner.read_all_positions().map_err(PyErr::from)
     }
 
     /// Read the current for the Stewart platform motors.
@@ -110,9 +297,25 @@ impl ReachyMiniMotorController {
             pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
         })?;
 
-        inner
-            .read_stewart_platform_current()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+        inner.read_stewart_platform_current().map_err(PyErr::from)
+    }
+
+    /// Read the velocity (rad/s) of the antennas [right, left].
+    fn read_antennas_velocity(&self) -> PyResult<[f64; 2]> {
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+
+        inner.read_antennas_velocity().map_err(PyErr::from)
+    }
+
+    /// Read the current (mA) of the antennas [right, left], i.e. the load applied on them.
+    fn read_antennas_current(&self) -> PyResult<[i16; 2]> {
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+
+        inner.read_antennas_current().map_err(PyErr::from)
     }
 
     /// Read the operating mode for the Stewart platform motors.
@@ -123,7 +326,7 @@ impl ReachyMiniMotorController {
 
         inner
             .read_stewart_platform_operating_mode()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+            .map_err(PyErr::from)
     }
 
     /// Set goal positions for all motors (9 values).
@@ -137,7 +340,7 @@ impl ReachyMiniMotorController {
 
         inner
             .set_all_goal_positions(positions)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+            .map_err(PyErr::from)?;
         Ok(())
     }
 
@@ -152,7 +355,7 @@ impl ReachyMiniMotorController {
 
         inner
             .set_antennas_positions(positions)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+            .map_err(PyErr::from)?;
         Ok(())
     }
 
@@ -167,7 +370,7 @@ impl ReachyMiniMotorController {
 
         inner
             .set_stewart_platform_position(position)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+            .map_err(PyErr::from)?;
         Ok(())
     }
 
@@ -180,9 +383,7 @@ impl ReachyMiniMotorController {
             pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
         })?;
 
-        inner
-            .set_body_rotation(position)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        inner.set_body_rotation(position).map_err(PyErr::from)?;
         Ok(())
     }
 
@@ -197,7 +398,24 @@ impl ReachyMiniMotorController {
 
         inner
             .set_stewart_platform_goal_current(current)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+            .map_err(PyErr::from)?;
+        Ok(())
+    }
+
+    /// Set goal currents then goal positions for the Stewart platform motors,
+    /// for the current-based position mode.
+    ///
+    /// # Arguments
+    /// * `positions` - Array of 6 goal positions for Stewart platform motors.
+    /// * `currents` - Array of 6 goal currents for Stewart platform motors.
+    fn set_stewart_goal(&self, positions: [f64; 6], currents: [i16; 6]) -> PyResult<()> {
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+
+        inner
+            .set_stewart_goal(positions, currents)
+            .map_err(PyErr::from)?;
         Ok(())
     }
 
@@ -212,7 +430,7 @@ impl ReachyMiniMotorController {
 
         inner
             .set_stewart_platform_operating_mode(mode)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+            .map_err(PyErr::from)?;
         Ok(())
     }
 
@@ -227,7 +445,7 @@ impl ReachyMiniMotorController {
 
         inner
             .set_antennas_operating_mode(mode)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+            .map_err(PyErr::from)?;
         Ok(())
     }
 
@@ -242,7 +460,7 @@ impl ReachyMiniMotorController {
 
         inner
             .set_body_rotation_operating_mode(mode)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+            .map_err(PyErr::from)?;
         Ok(())
     }
 
@@ -255,9 +473,7 @@ impl ReachyMiniMotorController {
             pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
         })?;
 
-        inner
-            .enable_body_rotation(enable)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        inner.enable_body_rotation(enable).map_err(PyErr::from)?;
         Ok(())
     }
 
@@ -270,9 +486,7 @@ impl ReachyMiniMotorController {
             pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
         })?;
 
-        inner
-            .enable_antennas(enable)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        inner.enable_antennas(enable).map_err(PyErr::from)?;
         Ok(())
     }
 
@@ -285,12 +499,107 @@ impl ReachyMiniMotorController {
             pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
         })?;
 
-        inner
-            .enable_stewart_platform(enable)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        inner.enable_stewart_platform(enable).map_err(PyErr::from)?;
+        Ok(())
+    }
+
+    /// Spin the body continuously (switches the body rotation to velocity mode if needed).
+    ///
+    /// # Arguments
+    /// * `velocity` - Body rotation velocity in rad/s.
+    fn spin_body(&self, velocity: f64) -> PyResult<()> {
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+
+        inner.spin_body(velocity).map_err(PyErr::from)?;
+        Ok(())
+    }
+
+    /// Let the antennas dangle freely (torque off), the rest of the robot stays active.
+    fn set_antennas_floppy(&self) -> PyResult<()> {
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+
+        inner.set_antennas_floppy().map_err(PyErr::from)?;
+        Ok(())
+    }
+
+    /// Hold the antennas again where they currently are.
+    fn set_antennas_stiff(&self) -> PyResult<()> {
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+
+        inner.set_antennas_stiff().map_err(PyErr::from)?;
+        Ok(())
+    }
+
+    /// Stop spinning the body and switch it back to position control.
+    fn stop_body_spin(&self) -> PyResult<()> {
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+
+        inner.stop_body_spin().map_err(PyErr::from)?;
         Ok(())
     }
 
+    /// Home the body rotation on its mechanical stops and store the center as homing offset.
+    ///
+    /// Returns the new homing offset (rad).
+    ///
+    /// # Arguments
+    /// * `velocity` - Rotation velocity used to look for the stops (rad/s).
+    /// * `current_threshold` - Absolute current (mA) above which a stop is detected.
+    /// * `timeout` - Maximum duration to find each stop.
+    #[pyo3(signature = (velocity = 0.3, current_threshold = 300, timeout = Duration::from_secs(20)))]
+    fn home_body_rotation(
+        &self,
+        velocity: f64,
+        current_threshold: i16,
+        timeout: Duration,
+    ) -> PyResult<f64> {
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+
+        inner
+            .home_body_rotation(HomingConfig {
+                velocity,
+                current_threshold,
+                timeout,
+            })
+            .map_err(PyErr::from)
+    }
+
+    /// Store a robot identity (e.g. its serial number) in the motors.
+    ///
+    /// The torque must be disabled on all motors. An empty identity clears it.
+    ///
+    /// # Arguments
+    /// * `identity` - At most 8 bytes once encoded in UTF-8.
+    fn write_identity(&self, identity: &str) -> PyResult<()> {
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+
+        inner
+            .write_identity(identity.as_bytes())
+            .map_err(PyErr::from)
+    }
+
+    /// Read the robot identity stored in the motors, None if there is none.
+    fn read_identity(&self) -> PyResult<Option<String>> {
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+
+        let identity = inner.read_identity().map_err(PyErr::from)?;
+        Ok(identity.map(|identity| String::from_utf8_lossy(&identity).into_owned()))
+    }
+
     /// Write raw packet data to the serial port.
     ///
     /// # Arguments
@@ -305,12 +614,74 @@ impl ReachyMiniMotorController {
             .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
         Ok(())
     }
+
+    /// Keep the bus silent before and after each raw packet, e.g. between protocol v1
+    /// packets sent raw and the protocol v2 traffic of the XL330s on marginal wiring.
+    ///
+    /// # Arguments
+    /// * `gap` - Duration of the silence (s), 0 to disable.
+    fn set_raw_packet_gap(&self, gap: f64) -> PyResult<()> {
+        let gap = Duration::try_from_secs_f64(gap)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+        inner.set_raw_packet_gap(gap);
+        Ok(())
+    }
+
+    /// Compare the configuration registers of the motors to the Reachy Mini profile.
+    fn audit_configuration(&self) -> PyResult<Vec<ConfigDeviation>> {
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+
+        inner
+            .audit_configuration(&ConfigProfile::default())
+            .map_err(PyErr::from)
+    }
+
+    /// Write the canonical Reachy Mini register profile to all motors, with the torque off
+    /// on the motors written, and return the registers which differed.
+    ///
+    /// # Arguments
+    /// * `dry_run` - Only return the registers which would be written.
+    fn apply_factory_profile(&self, dry_run: bool) -> PyResult<Vec<ConfigDeviation>> {
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+
+        inner.apply_factory_profile(dry_run).map_err(PyErr::from)
+    }
+
+    /// Record the writes instead of sending them to the motors, the reads still go to the
+    /// bus. The write methods still validate their arguments.
+    ///
+    /// # Arguments
+    /// * `enable` - Enable or disable the dry-run mode, disabling it drops the recorded writes.
+    fn set_dry_run(&self, enable: bool) -> PyResult<()> {
+        let mut inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+        inner.set_dry_run(enable.then(DryRunLog::default));
+        Ok(())
+    }
+
+    /// Get and clear the writes recorded in dry-run mode, oldest first.
+    fn take_dry_run_writes(&self) -> PyResult<Vec<DryRunWrite>> {
+        let inner = self.inner.lock().map_err(|_| {
+            pyo3::exceptions::PyRuntimeError::new_err("Failed to lock motor controller")
+        })?;
+        Ok(inner.dry_run().map(DryRunLog::take).unwrap_or_default())
+    }
 }
 
 #[gen_stub_pyclass]
 #[pyclass]
 struct ReachyMiniPyControlLoop {
     inner: std::sync::Arc<ReachyMiniControlLoop>,
+    /// Writes of the controller with `dry_run=True`.
+    dry_run: Option<DryRunLog>,
 }
 
 #[gen_stub_pymethods]
@@ -324,97 +695,353 @@ impl ReachyMiniPyControlLoop {
     /// * `allowed_retries` - Number of allowed retries for reading positions.
     /// * `init_timeout` - Timeout for initial position read.
     /// * `stats_pub_period` - Optional period for publishing stats.
+    /// * `simulated` - Drive simulated motors instead of the hardware, `serialport` is ignored then.
+    /// * `stats_max_samples` - Number of ticks and commands kept in the stats, None for no limit.
+    /// * `stats_max_age` - Drop the ticks and commands older than this from the stats.
+    /// * `missing_motors` - What to do if some motors do not answer: "fail", "continue" with the
+    ///   other motors, or "retry" until `missing_motors_timeout` then fail.
+    /// * `missing_motors_timeout` - How long to retry with `missing_motors="retry"`.
+    /// * `disabled_joints` - Names of the joints left out, e.g. the antennas removed on a
+    ///   custom build: they are neither read nor written and their goals are not checked.
+    /// * `dry_run` - Record the writes instead of sending them to the motors, see
+    ///   `take_dry_run_writes`. The positions are still read from the motors.
+    /// * `simulated_max_velocity` - Maximum velocity of the simulated joints (rad/s).
+    /// * `simulated_noise` - Amplitude of the uniform noise on the simulated positions read (rad).
     #[new]
     #[pyo3(signature = (
-        serialport,
-        read_position_loop_period,
+        serialport = None,
+        read_position_loop_period = Duration::from_millis(10),
         allowed_retries = 5,
         stats_pub_period = None,
         voltage_rampup_timeout = Duration::from_secs(30),
+        simulated = false,
+        stats_max_samples = Some(DEFAULT_STATS_MAX_SAMPLES),
+        stats_max_age = None,
+        missing_motors = "fail",
+        missing_motors_timeout = Duration::from_secs(10),
+        disabled_joints = Vec::new(),
+        dry_run = false,
+        simulated_max_velocity = JointDynamics::default().max_velocity,
+        simulated_noise = 0.0,
     ))]
+    #[allow(clippy::too_many_arguments)]
     fn new(
-        serialport: String,
+        serialport: Option<String>,
         read_position_loop_period: Duration,
         allowed_retries: u64,
         stats_pub_period: Option<Duration>,
         voltage_rampup_timeout: Duration,
+        simulated: bool,
+        stats_max_samples: Option<usize>,
+        stats_max_age: Option<Duration>,
+        missing_motors: &str,
+        missing_motors_timeout: Duration,
+        disabled_joints: Vec<String>,
+        dry_run: bool,
+        simulated_max_velocity: f64,
+        simulated_noise: f64,
     ) -> PyResult<Self> {
-        let control_loop = ReachyMiniControlLoop::new(
-            serialport,
-            read_position_loop_period,
-            stats_pub_period,
-            allowed_retries,
-            voltage_rampup_timeout,
-        )
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        let missing_motors = match missing_motors {
+            "fail" => MissingMotorsPolicy::Fail,
+            "continue" => MissingMotorsPolicy::Continue,
+            "retry" => MissingMotorsPolicy::Retry(missing_motors_timeout),
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Unknown missing motors policy {:?}, expected \"fail\", \"continue\" or \"retry\"",
+                    missing_motors
+                )));
+            }
+        };
+        let mut motors = MotorSet {
+            missing_motors,
+            ..Default::default()
+        };
+        for name in &disabled_joints {
+            let joint = JOINT_NAMES.iter().position(|n| n == name).ok_or_else(|| {
+                pyo3::exceptions::PyValueError::new_err(format!("Unknown joint {:?}", name))
+            })?;
+            motors.disabled_joints[joint] = true;
+        }
+        let dry_run_log = dry_run.then(DryRunLog::default);
+        let dry_run_controller = |mut c: Controller| {
+            c.set_dry_run(dry_run_log.clone());
+            c
+        };
+        let control_loop = match (simulated, serialport) {
+            (true, _) => ReachyMiniControlLoop::with_controller(
+                dry_run_controller(
+                    simulation(simulated_max_velocity, simulated_noise)?.controller(),
+                ),
+                read_position_loop_period,
+                stats_pub_period,
+                allowed_retries,
+                voltage_rampup_timeout,
+                motors,
+            ),
+            // The controller must be in dry-run mode before the loop initializes the motors
+            (false, Some(serialport)) if dry_run => ReachyMiniControlLoop::with_controller(
+                Controller::new(&serialport)
+                    .map(dry_run_controller)
+                    .map_err(PyErr::from)?,
+                read_position_loop_period,
+                stats_pub_period,
+                allowed_retries,
+                voltage_rampup_timeout,
+                motors,
+            ),
+            (false, Some(serialport)) => ReachyMiniControlLoop::new(
+                serialport,
+                read_position_loop_period,
+                stats_pub_period,
+                allowed_retries,
+                voltage_rampup_timeout,
+                motors,
+            ),
+            (false, None) => {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "A serial port is required, unless simulated=True",
+                ));
+            }
+        }
+        .map_err(PyErr::from)?;
+        control_loop
+            .set_stats_retention(StatsRetention {
+                max_samples: stats_max_samples,
+                max_age: stats_max_age,
+            })
+            .map_err(PyErr::from)?;
         Ok(ReachyMiniPyControlLoop {
             inner: std::sync::Arc::new(control_loop),
+            dry_run: dry_run_log,
         })
     }
 
+    /// Get and clear the writes recorded with `dry_run=True`, oldest first.
+    fn take_dry_run_writes(&self) -> Vec<DryRunWrite> {
+        self.dry_run
+            .as_ref()
+            .map(DryRunLog::take)
+            .unwrap_or_default()
+    }
+
     /// Close the control loop and release resources.
-    fn close(&self) -> PyResult<()> {
-        self.inner.close();
+    fn close(&self, py: Python) -> PyResult<()> {
+        py.detach(|| self.inner.close());
         Ok(())
     }
 
+    /// Get the names of the motors left out since startup, with `missing_motors="continue"`.
+    fn get_missing_motors(&self) -> Vec<String> {
+        self.inner.get_missing_motors()
+    }
+
+    /// Get the names of the joints disabled at startup with `disabled_joints`.
+    fn get_disabled_joints(&self) -> Vec<String> {
+        self.inner.get_disabled_joints()
+    }
+
+    /// Get the registers which differed from the Reachy Mini profile at startup, e.g.
+    /// after the motors were reconfigured by another tool.
+    fn get_config_deviations(&self) -> Vec<ConfigDeviation> {
+        self.inner.get_config_deviations()
+    }
+
     /// Get the id/name motors used in this controller.
     fn get_motor_name_id(&self) -> HashMap<String, u8> {
         self.inner.get_motor_name_id()
     }
 
-    /// Get the last successfully read motor positions.
-    fn get_last_position(&self) -> PyResult<FullBodyPosition> {
-        self.inner
-            .get_last_position()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    /// Get the firmware info of each motor (by name), as detected at init.
+    fn get_firmware_info(&self) -> HashMap<String, FirmwareInfo> {
+        self.inner.get_firmware_info()
+    }
+
+    /// Check if all motors run a firmware supporting the given feature.
+    ///
+    /// # Arguments
+    /// * `feature` - Feature name (e.g. "fast_sync_read", "bus_watchdog").
+    fn supports_feature(&self, feature: &str) -> PyResult<bool> {
+        let feature = Feature::from_name(feature).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!("Unknown feature: {}", feature))
+        })?;
+        Ok(self.inner.supports(feature))
+    }
+
+    /// Get the latest measured offset between the wall clock and the monotonic clock.
+    ///
+    /// Positions carry both a wall clock `timestamp` and a `monotonic_timestamp`
+    /// (`CLOCK_MONOTONIC` on Linux), with `wall = monotonic + offset`.
+    fn get_clock_offset(&self) -> ClockOffset {
+        self.inner.get_clock_offset()
+    }
+
+    /// Set how many of the last read positions are kept (0 disables the history).
+    ///
+    /// # Arguments
+    /// * `size` - Number of positions kept.
+    fn set_history_size(&self, size: usize) -> PyResult<()> {
+        self.inner.set_history_size(size).map_err(PyErr::from)
+    }
+
+    /// Get the last `n` read positions (or less if not available yet), oldest first.
+    ///
+    /// # Arguments
+    /// * `n` - Number of positions.
+    fn get_history(&self, n: usize) -> Vec<FullBodyPosition> {
+        self.inner.get_history(n)
+    }
+
+    /// Get the read positions kept in the history after a timestamp, oldest first.
+    ///
+    /// # Arguments
+    /// * `timestamp` - Seconds since UNIX epoch.
+    fn get_since(&self, timestamp: f64) -> Vec<FullBodyPosition> {
+        self.inner.get_since(timestamp)
+    }
+
+    /// Publish the last goal written to each joint with the position read at each tick,
+    /// while the torque is on, to monitor the tracking error live.
+    ///
+    /// # Arguments
+    /// * `size` - Number of samples kept, 0 to stop publishing them.
+    #[pyo3(signature = (size = DEFAULT_TRACKING_STREAM_SIZE))]
+    fn set_tracking_stream_size(&self, size: usize) {
+        self.inner.set_tracking_stream_size(size)
+    }
+
+    /// Get the goal and position samples of the tracking stream after a timestamp,
+    /// oldest first.
+    ///
+    /// # Arguments
+    /// * `timestamp` - Seconds since UNIX epoch.
+    #[pyo3(signature = (timestamp = 0.0))]
+    fn get_tracking_since(&self, timestamp: f64) -> Vec<TrackingSample> {
+        self.inner.get_tracking_since(timestamp)
+    }
+
+    /// Get the last successfully read motor positions as a read-only buffer of floats,
+    /// updated in place by the control loop: `numpy.asarray(buffer)` is a view on it.
+    ///
+    /// The floats are a sequence number (odd while updated), the wall clock and monotonic
+    /// timestamps, then the joint positions. `read()` gives a consistent copy.
+    fn get_state_buffer(&self) -> StateBuffer {
+        self.inner.get_state_buffer()
+    }
+
+    /// Get the last successfully read motor positions, with their `age` (s) and whether
+    /// they are `fresh` (younger than the maximum age).
+    fn get_last_position(&self, py: Python) -> PyResult<Py<LastPosition>> {
+        let last = self.inner.get_last_position().map_err(PyErr::from)?;
+        Py::new(
+            py,
+            PyClassInitializer::from(last.position).add_subclass(last),
+        )
+    }
+
+    /// Get the last successfully read motor positions, even if the reads are failing
+    /// since, with the `error` of the last read.
+    fn get_last_known_position(&self, py: Python) -> PyResult<Py<LastPosition>> {
+        let last = self.inner.get_last_known_position();
+        Py::new(
+            py,
+            PyClassInitializer::from(last.position).add_subclass(last),
+        )
+    }
+
+    /// Get the error of the last read, None if it succeeded.
+    fn get_read_error(&self) -> Option<String> {
+        self.inner.get_read_error().map(|e| e.to_string())
+    }
+
+    /// Set the age above which the last read position is not fresh anymore.
+    fn set_max_position_age(&self, max_age: Duration) {
+        self.inner.set_max_position_age(max_age);
+    }
+
+    /// Get the age above which the last read position is not fresh anymore.
+    fn get_max_position_age(&self) -> Duration {
+        self.inner.get_max_position_age()
     }
 
     /// Set goal positions for all motors (9 values).
     ///
     /// # Arguments
     /// * `positions` - Array of 9 goal positions (body_yaw, stewart, antennas).
-    fn set_all_goal_positions(&self, positions: FullBodyPosition) -> PyResult<()> {
-        self.inner
-            .push_command(MotorCommand::SetAllGoalPositions { positions })
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    /// * `deadline` - Optional deadline (seconds since UNIX epoch) after which the goal is dropped.
+    /// * `source` - Optional name of the command source, for arbitration.
+    #[pyo3(signature = (positions, deadline = None, source = None))]
+    fn set_all_goal_positions(
+        &self,
+        positions: FullBodyPosition,
+        deadline: Option<f64>,
+        source: Option<String>,
+    ) -> PyResult<()> {
+        self.push_goal(
+            MotorCommand::SetAllGoalPositions { positions },
+            deadline,
+            source,
+        )
     }
 
     /// Set goal positions for the Stewart platform (6 values).
     ///
     /// # Arguments
     /// * `position` - Array of 6 goal positions for Stewart platform.
-    fn set_stewart_platform_position(&self, position: [f64; 6]) -> PyResult<()> {
-        self.inner
-            .push_command(MotorCommand::SetStewartPlatformPosition { position })
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    /// * `deadline` - Optional deadline (seconds since UNIX epoch) after which the goal is dropped.
+    /// * `source` - Optional name of the command source, for arbitration.
+    #[pyo3(signature = (position, deadline = None, source = None))]
+    fn set_stewart_platform_position(
+        &self,
+        position: [f64; 6],
+        deadline: Option<f64>,
+        source: Option<String>,
+    ) -> PyResult<()> {
+        self.push_goal(
+            MotorCommand::SetStewartPlatformPosition { position },
+            deadline,
+            source,
+        )
     }
 
     /// Set goal position for the body rotation motor.
     ///
     /// # Arguments
     /// * `position` - Goal position for body rotation motor.
-    fn set_body_rotation(&self, position: f64) -> PyResult<()> {
-        self.inner
-            .push_command(MotorCommand::SetBodyRotation { position })
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    /// * `deadline` - Optional deadline (seconds since UNIX epoch) after which the goal is dropped.
+    /// * `source` - Optional name of the command source, for arbitration.
+    #[pyo3(signature = (position, deadline = None, source = None))]
+    fn set_body_rotation(
+        &self,
+        position: f64,
+        deadline: Option<f64>,
+        source: Option<String>,
+    ) -> PyResult<()> {
+        self.push_goal(MotorCommand::SetBodyRotation { position }, deadline, source)
     }
 
     /// Set goal positions for the antennas (2 values).
     ///
     /// # Arguments
     /// * `positions` - Array of 2 goal positions for antennas.
-    fn set_antennas_positions(&self, positions: [f64; 2]) -> PyResult<()> {
-        self.inner
-            .push_command(MotorCommand::SetAntennasPositions { positions })
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    /// * `deadline` - Optional deadline (seconds since UNIX epoch) after which the goal is dropped.
+    /// * `source` - Optional name of the command source, for arbitration.
+    #[pyo3(signature = (positions, deadline = None, source = None))]
+    fn set_antennas_positions(
+        &self,
+        positions: [f64; 2],
+        deadline: Option<f64>,
+        source: Option<String>,
+    ) -> PyResult<()> {
+        self.push_goal(
+            MotorCommand::SetAntennasPositions { positions },
+            deadline,
+            source,
+        )
     }
 
     /// Check torque enabled status.
     fn is_torque_enabled(&self) -> PyResult<bool> {
-        self.inner
-            .is_torque_enabled()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+        self.inner.is_torque_enabled().map_err(PyErr::from)
     }
 
     /// Enable torque on all motors.
@@ -455,11 +1082,35 @@ impl ReachyMiniPyControlLoop {
             .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
     }
 
+    /// Set goal currents then goal positions for the Stewart platform motors,
+    /// for the current-based position mode.
+    ///
+    /// # Arguments
+    /// * `positions` - Array of 6 goal positions for Stewart platform motors.
+    /// * `currents` - Array of 6 goal currents for Stewart platform motors.
+    /// * `deadline` - Optional deadline (seconds since UNIX epoch) after which the goal is dropped.
+    /// * `source` - Optional name of the command source, for arbitration.
+    #[pyo3(signature = (positions, currents, deadline = None, source = None))]
+    fn set_stewart_goal(
+        &self,
+        positions: [f64; 6],
+        currents: [i16; 6],
+        deadline: Option<f64>,
+        source: Option<String>,
+    ) -> PyResult<()> {
+        self.push_goal(
+            MotorCommand::SetStewartGoal {
+                positions,
+                currents,
+            },
+            deadline,
+            source,
+        )
+    }
+
     /// Check stewart platform operating mode
     fn get_stewart_platform_operating_mode(&self) -> PyResult<u8> {
-        self.inner
-            .get_control_mode()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+        self.inner.get_control_mode().map_err(PyErr::from)
     }
 
     /// Set operating mode for all Stewart platform motors.
@@ -468,8 +1119,8 @@ impl ReachyMiniPyControlLoop {
     /// * `mode` - Operating mode value for Stewart platform motors.
     fn set_stewart_platform_operating_mode(&self, mode: u8) -> PyResult<()> {
         self.inner
-            .push_command(MotorCommand::SetStewartPlatformOperatingMode { mode })
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+            .push_configuration(MotorCommand::SetStewartPlatformOperatingMode { mode })
+            .map_err(PyErr::from)
     }
 
     /// Set operating mode for both antennas.
@@ -478,8 +1129,8 @@ impl ReachyMiniPyControlLoop {
     /// * `mode` - Operating mode value for antennas.
     fn set_antennas_operating_mode(&self, mode: u8) -> PyResult<()> {
         self.inner
-            .push_command(MotorCommand::SetAntennasOperatingMode { mode })
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+            .push_configuration(MotorCommand::SetAntennasOperatingMode { mode })
+            .map_err(PyErr::from)
     }
 
     /// Set operating mode for the body rotation motor.
@@ -488,8 +1139,8 @@ impl ReachyMiniPyControlLoop {
     /// * `mode` - Operating mode value for body rotation motor.
     fn set_body_rotation_operating_mode(&self, mode: u8) -> PyResult<()> {
         self.inner
-            .push_command(MotorCommand::SetBodyRotationOperatingMode { mode })
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+            .push_configuration(MotorCommand::SetBodyRotationOperatingMode { mode })
+            .map_err(PyErr::from)
     }
 
     /// Enable or disable the Stewart platform motors.
@@ -522,11 +1173,1062 @@ impl ReachyMiniPyControlLoop {
             .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
     }
 
+    /// Spin the body continuously (switches the body rotation to velocity mode if needed).
+    ///
+    /// # Arguments
+    /// * `velocity` - Body rotation velocity in rad/s.
+    fn spin_body(&self, velocity: f64) -> PyResult<()> {
+        self.inner.spin_body(velocity).map_err(PyErr::from)
+    }
+
+    /// Let the antennas dangle freely (torque off), the rest of the robot stays active.
+    fn set_antennas_floppy(&self) -> PyResult<()> {
+        self.inner.set_antennas_floppy().map_err(PyErr::from)
+    }
+
+    /// Hold the antennas again where they currently are.
+    fn set_antennas_stiff(&self) -> PyResult<()> {
+        self.inner.set_antennas_stiff().map_err(PyErr::from)
+    }
+
+    /// Stop spinning the body and switch it back to position control.
+    fn stop_body_spin(&self) -> PyResult<()> {
+        self.inner.stop_body_spin().map_err(PyErr::from)
+    }
+
+    /// Get the body yaw (rad) integrated from encoder deltas (does not wrap around).
+    fn get_integrated_body_yaw(&self) -> f64 {
+        self.inner.get_integrated_body_yaw()
+    }
+
+    /// Home the body rotation on its mechanical stops and store the center as homing offset.
+    ///
+    /// Blocks until the routine is over. Returns the new homing offset (rad).
+    ///
+    /// # Arguments
+    /// * `velocity` - Rotation velocity used to look for the stops (rad/s).
+    /// * `current_threshold` - Absolute current (mA) above which a stop is detected.
+    /// * `timeout` - Maximum duration to find each stop.
+    #[pyo3(signature = (velocity = 0.3, current_threshold = 300, timeout = Duration::from_secs(20)))]
+    fn home_body_rotation(
+        &self,
+        py: Python,
+        velocity: f64,
+        current_threshold: i16,
+        timeout: Duration,
+    ) -> PyResult<f64> {
+        let config = HomingConfig {
+            velocity,
+            current_threshold,
+            timeout,
+        };
+        py.detach(|| self.inner.home_body_rotation(config))
+            .map_err(PyErr::from)
+    }
+
+    /// Read the velocity (rad/s) of the antennas [right, left], e.g. to detect a flick.
+    fn read_antennas_velocity(&self, py: Python) -> PyResult<[f64; 2]> {
+        py.detach(|| self.inner.read_antennas_velocity())
+            .map_err(PyErr::from)
+    }
+
+    /// Read the current (mA) of the antennas [right, left], i.e. the load applied on them.
+    fn read_antennas_current(&self, py: Python) -> PyResult<[i16; 2]> {
+        py.detach(|| self.inner.read_antennas_current())
+            .map_err(PyErr::from)
+    }
+
+    /// Read the temperature (°C) of all motors, in joint order.
+    fn read_all_temperatures(&self, py: Python) -> PyResult<[u8; 9]> {
+        py.detach(|| self.inner.read_all_temperatures())
+            .map_err(PyErr::from)
+    }
+
+    /// Read the current (mA) of all motors, in joint order.
+    fn read_all_currents(&self, py: Python) -> PyResult<[i16; 9]> {
+        py.detach(|| self.inner.read_all_currents())
+            .map_err(PyErr::from)
+    }
+
+    /// Play a trajectory, interpolated between its keyframes at each tick of the loop.
+    ///
+    /// It starts from the current goals and is stopped by any new goal.
+    ///
+    /// # Arguments
+    /// * `keyframes` - A Trajectory, or a list of (time in seconds, 9 joint positions) by
+    ///   increasing time, optionally followed by an easing (see Trajectory).
+    /// * `on_beat` - Quantize the keyframes to whole beats and start on the next beat, see set_beat_grid().
+    #[pyo3(signature = (keyframes, on_beat = false))]
+    fn play_trajectory(
+        &self,
+        #[gen_stub(override_type(
+            type_repr = "Trajectory | typing.Sequence[tuple[float, typing.Sequence[float]] | tuple[float, typing.Sequence[float], str]]",
+            imports = ("typing",)
+        ))]
+        keyframes: &Bound<'_, PyAny>,
+        on_beat: bool,
+    ) -> PyResult<()> {
+        let trajectory = match keyframes.extract::<Trajectory>() {
+            Ok(trajectory) => trajectory,
+            Err(_) => Trajectory::from_py_keyframes(keyframes.extract()?)?,
+        };
+        self.inner
+            .validate_goal(&MotorCommand::PlayTrajectory {
+                trajectory: trajectory.clone(),
+                start: None,
+            })
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let res = match on_beat {
+            true => self.inner.play_trajectory_on_beat(trajectory),
+            false => self.inner.play_trajectory(trajectory),
+        };
+        res.map_err(PyErr::from)
+    }
+
+    /// Register the beats of the music being played, for the trajectories played on beat.
+    ///
+    /// # Arguments
+    /// * `bpm` - Tempo (beats per minute).
+    /// * `first_beat` - Monotonic time (s) of a beat, e.g. from the audio playback timestamps (now by default).
+    #[pyo3(signature = (bpm, first_beat = None))]
+    fn set_beat_grid(&self, bpm: f64, first_beat: Option<f64>) -> PyResult<()> {
+        let grid = BeatGrid::from_bpm(first_beat.unwrap_or_else(monotonic_now), bpm)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        self.inner.set_beat_grid(Some(grid));
+        Ok(())
+    }
+
+    /// Forget the beats, trajectories played on beat then start right away.
+    fn clear_beat_grid(&self) {
+        self.inner.set_beat_grid(None)
+    }
+
+    /// Get the registered beats, if any.
+    fn get_beat_grid(&self) -> Option<BeatGrid> {
+        self.inner.get_beat_grid()
+    }
+
+    /// Set the kinematics used by the head pose commands.
+    ///
+    /// Its methods are called from the control loop thread (holding the GIL), at the
+    /// loop period while a head twist is followed, so they should return quickly.
+    ///
+    /// # Arguments
+    /// * `kinematics` - Object with `inverse(pose: HeadPose) -> 6 stewart joints` and `forward(joints) -> HeadPose`.
+    fn set_kinematics(&self, kinematics: Py<PyAny>) -> PyResult<()> {
+        self.inner
+            .set_kinematics(PyKinematics(kinematics))
+            .map_err(PyErr::from)
+    }
+
+    /// Move the head at a velocity expressed in the head frame, e.g. from a joystick.
+    ///
+    /// The head stops if no new twist is received within 0.5 s, or on any new goal.
+    ///
+    /// # Arguments
+    /// * `linear` - Linear velocity (m/s) along the head x, y, z axes.
+    /// * `angular` - Angular velocity (rad/s) around the head x, y, z axes.
+    fn set_head_twist(&self, linear: [f64; 3], angular: [f64; 3]) -> PyResult<()> {
+        if linear.iter().chain(&angular).any(|v| !v.is_finite()) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "The head twist must be finite",
+            ));
+        }
+        self.inner
+            .set_head_twist(linear, angular)
+            .map_err(PyErr::from)
+    }
+
+    /// Set how the gaze yaw is shared between the body rotation and the head yaw.
+    fn set_gaze_split(&self, split: GazeSplit) -> PyResult<()> {
+        self.inner.set_gaze_split(split).map_err(PyErr::from)
+    }
+
+    fn get_gaze_split(&self) -> GazeSplit {
+        self.inner.get_gaze_split()
+    }
+
+    /// Look at a yaw by turning both the body and the head, keeping the rest of the head pose.
+    ///
+    /// Requires the kinematics, see set_kinematics().
+    ///
+    /// # Arguments
+    /// * `yaw` - Gaze yaw (rad) relative to the base.
+    fn set_gaze_yaw(&self, yaw: f64) -> PyResult<()> {
+        if !yaw.is_finite() {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "The gaze yaw must be finite",
+            ));
+        }
+        self.inner.set_gaze_yaw(yaw).map_err(PyErr::from)
+    }
+
+    /// Use another file for the named poses (poses.txt by default).
+    ///
+    /// # Arguments
+    /// * `path` - Path of the poses file, created by save_pose() if it does not exist.
+    fn set_poses_file(&self, path: std::path::PathBuf) -> PyResult<()> {
+        self.inner.set_poses_file(path).map_err(PyErr::from)
+    }
+
+    fn get_poses_file(&self) -> std::path::PathBuf {
+        self.inner.get_poses_file()
+    }
+
+    /// Save the measured positions as a named pose in the poses file.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the pose (single word), replaced if it already exists.
+    fn save_pose(&self, name: &str) -> PyResult<()> {
+        self.inner.save_pose(name).map_err(PyErr::from)
+    }
+
+    /// Move to a named pose of the poses file, played as a trajectory.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the pose.
+    /// * `duration` - Duration of the move (seconds).
+    #[pyo3(signature = (name, duration = 1.0))]
+    fn goto_pose(&self, name: &str, duration: f64) -> PyResult<()> {
+        let duration = Duration::try_from_secs_f64(duration)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        self.inner.goto_pose(name, duration).map_err(PyErr::from)
+    }
+
+    /// Fold the robot for transport: move to the "transport" pose of the poses file, then
+    /// turn the torque off. Blocks until folded.
+    ///
+    /// The positions before are saved as the "before_transport" pose, for wake_up().
+    ///
+    /// # Arguments
+    /// * `duration` - Duration of the move (seconds).
+    /// * `lock_bounds` - Narrow the position bounds around the transport pose until wake_up().
+    #[pyo3(signature = (duration = 3.0, lock_bounds = false))]
+    fn prepare_for_transport(
+        &self,
+        py: Python<'_>,
+        duration: f64,
+        lock_bounds: bool,
+    ) -> PyResult<()> {
+        let duration = Duration::try_from_secs_f64(duration)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        py.detach(|| self.inner.prepare_for_transport(duration, lock_bounds))
+            .map_err(PyErr::from)
+    }
+
+    /// Unfold the robot after prepare_for_transport(): restore the position bounds and move
+    /// back to the positions before it. Blocks until unfolded.
+    ///
+    /// # Arguments
+    /// * `duration` - Duration of the move (seconds).
+    #[pyo3(signature = (duration = 3.0))]
+    fn wake_up(&self, py: Python<'_>, duration: f64) -> PyResult<()> {
+        let duration = Duration::try_from_secs_f64(duration)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        py.detach(|| self.inner.wake_up(duration))
+            .map_err(PyErr::from)
+    }
+
+    /// Names of the poses of the poses file, sorted.
+    fn list_poses(&self) -> PyResult<Vec<String>> {
+        self.inner.list_poses().map_err(PyErr::from)
+    }
+
+    /// Move the head to a pose, and the body to a yaw in the same tick if given.
+    ///
+    /// Requires the kinematics, see set_kinematics().
+    ///
+    /// # Arguments
+    /// * `pose` - Head pose relative to the home pose.
+    /// * `body_yaw` - Body rotation (rad), None to keep it.
+    #[pyo3(signature = (pose, body_yaw = None))]
+    fn set_head_pose(&self, pose: HeadPose, body_yaw: Option<f64>) -> PyResult<()> {
+        self.inner
+            .set_head_pose(pose, body_yaw)
+            .map_err(PyErr::from)
+    }
+
+    /// Hold the current goals of the trajectory until resume_trajectory().
+    fn pause_trajectory(&self) -> PyResult<()> {
+        self.inner.pause_trajectory().map_err(PyErr::from)
+    }
+
+    fn resume_trajectory(&self) -> PyResult<()> {
+        self.inner.resume_trajectory().map_err(PyErr::from)
+    }
+
+    /// Re-time the trajectory being played from now on, e.g. to follow the audio playback rate.
+    ///
+    /// # Arguments
+    /// * `speed` - Speed factor, 1 is real time and 0 holds the current goals.
+    fn set_trajectory_speed(&self, speed: f64) -> PyResult<()> {
+        if !(speed.is_finite() && speed >= 0.0) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "The speed must be a positive number",
+            ));
+        }
+        self.inner.set_trajectory_speed(speed).map_err(PyErr::from)
+    }
+
+    /// Stop the trajectory, the motors stay at its current goals.
+    fn stop_trajectory(&self) -> PyResult<()> {
+        self.inner.stop_trajectory().map_err(PyErr::from)
+    }
+
+    /// Get the playback state of the trajectory, or None when none is playing.
+    fn get_trajectory_status(&self) -> Option<TrajectoryStatus> {
+        self.inner.get_trajectory_status()
+    }
+
+    /// Get the result of the last trajectory that ended, completed or not, with its tracking errors.
+    fn get_trajectory_result(&self) -> Option<TrajectoryResult> {
+        self.inner.get_trajectory_result()
+    }
+
+    /// Play a trajectory on the joints of a track only, over the trajectory and the other tracks.
+    ///
+    /// It starts from the current goals of the track joints, replaces the trajectory of the same
+    /// track and is stopped by a new goal of its joints. The other joints of the keyframes are
+    /// ignored.
+    ///
+    /// # Arguments
+    /// * `track` - "body", "head" (stewart platform) or "antennas".
+    /// * `keyframes` - A Trajectory, or a list of keyframes (see play_trajectory()).
+    fn play_track(
+        &self,
+        track: &str,
+        #[gen_stub(override_type(
+            type_repr = "Trajectory | typing.Sequence[tuple[float, typing.Sequence[float]] | tuple[float, typing.Sequence[float], str]]",
+            imports = ("typing",)
+        ))]
+        keyframes: &Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        let track = py_track(track)?;
+        let trajectory = match keyframes.extract::<Trajectory>() {
+            Ok(trajectory) => trajectory,
+            Err(_) => Trajectory::from_py_keyframes(keyframes.extract()?)?,
+        };
+        self.inner
+            .validate_goal(&MotorCommand::PlayTrack {
+                track,
+                trajectory: trajectory.clone(),
+                start: None,
+            })
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        self.inner
+            .play_track(track, trajectory)
+            .map_err(PyErr::from)
+    }
+
+    /// Stop the trajectory of a track ("body", "head" or "antennas"), its joints stay at their
+    /// current goals.
+    fn stop_track(&self, track: &str) -> PyResult<()> {
+        self.inner.stop_track(py_track(track)?).map_err(PyErr::from)
+    }
+
+    /// Get the playback state of the trajectory of a track, or None when none is playing.
+    fn get_track_status(&self, track: &str) -> PyResult<Option<TrajectoryStatus>> {
+        Ok(self.inner.get_track_status(py_track(track)?))
+    }
+
+    /// Shape the goal positions so each joint respects the given limits (S-curve).
+    ///
+    /// # Arguments
+    /// * `limits` - One JointLimits per joint: body rotation, stewart platform (x6), right and left antennas.
+    fn enable_goal_smoothing(&self, limits: Vec<JointLimits>) -> PyResult<()> {
+        let limits: [JointLimits; NUM_JOINTS] = limits.try_into().map_err(|_| {
+            pyo3::exceptions::PyValueError::new_err(format!("Expected {} joint limits", NUM_JOINTS))
+        })?;
+        self.inner
+            .enable_goal_smoothing(limits)
+            .map_err(PyErr::from)
+    }
+
+    /// Send the goal positions directly again (no shaping).
+    fn disable_goal_smoothing(&self) -> PyResult<()> {
+        self.inner.disable_goal_smoothing().map_err(PyErr::from)
+    }
+
+    /// Set the bounds of the goal position of each joint, goals outside of them are rejected.
+    ///
+    /// # Arguments
+    /// * `bounds` - One PositionBounds per joint: body rotation, stewart platform (x6), right and left antennas.
+    fn set_position_bounds(&self, bounds: Vec<PositionBounds>) -> PyResult<()> {
+        let bounds: [PositionBounds; NUM_JOINTS] = bounds.try_into().map_err(|_| {
+            pyo3::exceptions::PyValueError::new_err(format!(
+                "Expected {} position bounds",
+                NUM_JOINTS
+            ))
+        })?;
+        self.inner.set_position_bounds(bounds).map_err(PyErr::from)
+    }
+
+    /// Get the bounds of the goal position of each joint.
+    fn get_position_bounds(&self) -> Vec<PositionBounds> {
+        self.inner.get_position_bounds().to_vec()
+    }
+
+    /// Clamp the out of bounds goals to the nearest legal value instead of rejecting them.
+    ///
+    /// Clamped goals are counted and logged as warnings. Non-finite goals are still rejected.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether to clamp the goals.
+    fn set_goal_clamping(&self, enabled: bool) -> PyResult<()> {
+        self.inner.set_goal_clamping(enabled).map_err(PyErr::from)
+    }
+
+    /// Get the number of goals clamped to the position bounds so far.
+    fn get_clamped_goals(&self) -> u64 {
+        self.inner.get_clamped_goals()
+    }
+
+    /// Lock the configuration while still accepting the motion commands.
+    ///
+    /// The operating modes, limits (position bounds, goal clamping, safe mode, current
+    /// budget...) and raw register writes are then rejected, so untrusted code can be
+    /// given the control loop, e.g. in a kiosk deployment.
+    ///
+    /// # Arguments
+    /// * `key` - Key to give to `unlock_configuration`.
+    fn lock_configuration(&self, key: &str) -> PyResult<()> {
+        self.inner.lock_configuration(key).map_err(PyErr::from)
+    }
+
+    /// Unlock the configuration locked with `lock_configuration`.
+    ///
+    /// # Arguments
+    /// * `key` - Key given to `lock_configuration`.
+    fn unlock_configuration(&self, key: &str) -> PyResult<()> {
+        self.inner.unlock_configuration(key).map_err(PyErr::from)
+    }
+
+    /// Get whether the configuration is locked.
+    fn is_configuration_locked(&self) -> bool {
+        self.inner.is_configuration_locked()
+    }
+
+    /// Enable or disable the safe mode, for demos with the public and classroom use.
+    ///
+    /// While enabled, the velocity, the stewart platform currents and the workspace are
+    /// limited on every command path, to the limits set with `set_safe_mode_limits`.
+    /// Goals outside of the safe workspace are rejected (or clamped with goal clamping).
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether to enforce the safe mode limits.
+    fn safe_mode(&self, enabled: bool) -> PyResult<()> {
+        self.inner.safe_mode(enabled).map_err(PyErr::from)
+    }
+
+    /// Get whether the safe mode is enabled.
+    fn is_safe_mode(&self) -> bool {
+        self.inner.is_safe_mode()
+    }
+
+    /// Set the limits of the safe mode, applied right away if it is enabled.
+    ///
+    /// # Arguments
+    /// * `limits` - Strictly positive limits, with a workspace scale of at most 1.
+    fn set_safe_mode_limits(&self, limits: SafeModeLimits) -> PyResult<()> {
+        limits
+            .validate()
+            .map_err(pyo3::exceptions::PyValueError::new_err)?;
+        self.inner.set_safe_mode_limits(limits).map_err(PyErr::from)
+    }
+
+    /// Get the limits of the safe mode.
+    fn get_safe_mode_limits(&self) -> SafeModeLimits {
+        self.inner.get_safe_mode_limits()
+    }
+
+    /// Drop the implausible position reads (non-finite values or impossible velocities).
+    ///
+    /// The last plausible position keeps being published instead, rejections are
+    /// counted as "implausible" in `get_error_stats()`.
+    ///
+    /// # Arguments
+    /// * `max_velocity` - Maximum plausible joint velocity (rad/s).
+    #[pyo3(signature = (max_velocity = DEFAULT_MAX_READ_VELOCITY))]
+    fn enable_read_filter(&self, max_velocity: f64) -> PyResult<()> {
+        self.inner
+            .enable_read_filter(max_velocity)
+            .map_err(PyErr::from)
+    }
+
+    /// Publish all position reads again.
+    fn disable_read_filter(&self) -> PyResult<()> {
+        self.inner.disable_read_filter().map_err(PyErr::from)
+    }
+
+    /// Write the profile velocity of the joints from the velocity of the trajectory and the
+    /// tracks being played, so the motors move at the speed of the trajectory between two
+    /// goals, improving the tracking of fast trajectories. Reset to no limit once nothing plays.
+    ///
+    /// # Arguments
+    /// * `gain` - Profile velocity over trajectory velocity, above 1 to let the motors catch up.
+    /// * `min_velocity` - Lowest profile velocity (rad/s), so slow parts do not freeze a lagging joint.
+    #[pyo3(signature = (gain = 1.2, min_velocity = 0.5))]
+    fn enable_velocity_feedforward(&self, gain: f64, min_velocity: f64) -> PyResult<()> {
+        if !(gain.is_finite() && gain > 0.0 && min_velocity.is_finite() && min_velocity > 0.0) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "gain and min_velocity must be strictly positive",
+            ));
+        }
+        self.inner
+            .enable_velocity_feedforward(VelocityFeedforward { gain, min_velocity })
+            .map_err(PyErr::from)
+    }
+
+    /// Stop writing the profile velocities from the trajectories.
+    fn disable_velocity_feedforward(&self) -> PyResult<()> {
+        self.inner
+            .disable_velocity_feedforward()
+            .map_err(PyErr::from)
+    }
+
+    /// Filter the antenna goals with a second-order low-pass filter.
+    ///
+    /// Smooths noisy antenna targets, such as the ones driven from audio or emotions,
+    /// without delaying the head or the body.
+    ///
+    /// # Arguments
+    /// * `natural_frequency` - Natural frequency of the filter (Hz), strictly positive.
+    /// * `damping` - Damping ratio, strictly positive; 1 or above for no overshoot.
+    #[pyo3(signature = (natural_frequency = 3.0, damping = 0.8))]
+    fn enable_antenna_smoothing(&self, natural_frequency: f64, damping: f64) -> PyResult<()> {
+        if !(natural_frequency.is_finite()
+            && natural_frequency > 0.0
+            && damping.is_finite()
+            && damping > 0.0)
+        {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "natural_frequency and damping must be strictly positive",
+            ));
+        }
+        self.inner
+            .enable_antenna_smoothing(natural_frequency, damping)
+            .map_err(PyErr::from)
+    }
+
+    /// Stop filtering the antenna goals.
+    fn disable_antenna_smoothing(&self) -> PyResult<()> {
+        self.inner.disable_antenna_smoothing().map_err(PyErr::from)
+    }
+
+    /// Limit the total current of the stewart platform motors.
+    ///
+    /// While the sum of their currents exceeds it, their goals are pulled back toward the
+    /// measured positions so aggressive motions slow down instead of browning out the supply.
+    ///
+    /// # Arguments
+    /// * `max_total_current` - Maximum total current (mA), strictly positive.
+    fn enable_current_budget(&self, max_total_current: f64) -> PyResult<()> {
+        if !(max_total_current.is_finite() && max_total_current > 0.0) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "max_total_current must be strictly positive",
+            ));
+        }
+        self.inner
+            .enable_current_budget(max_total_current)
+            .map_err(PyErr::from)
+    }
+
+    /// Stop limiting the total current of the stewart platform.
+    fn disable_current_budget(&self) -> PyResult<()> {
+        self.inner.disable_current_budget().map_err(PyErr::from)
+    }
+
+    /// Get the scale applied to the stewart platform position errors by the current
+    /// budget, 1 when it is not limiting.
+    fn get_current_budget_scale(&self) -> f64 {
+        self.inner.get_current_budget_scale()
+    }
+
+    /// Track the heating of each motor from its current (i²t).
+    ///
+    /// A "duty_cycle" event is recorded when the RMS current of a motor approaches its
+    /// continuous rating, and the goal current of the overloaded stewart platform motors
+    /// is lowered to it until they cool down.
+    ///
+    /// # Arguments
+    /// * `continuous_current` - Continuous current rating of the motors (mA).
+    /// * `time_constant` - Thermal time constant of the motors (s).
+    #[pyo3(signature = (
+        continuous_current = DEFAULT_CONTINUOUS_CURRENT,
+        time_constant = DEFAULT_THERMAL_TIME_CONSTANT,
+    ))]
+    fn enable_duty_cycle_limiter(
+        &self,
+        continuous_current: f64,
+        time_constant: f64,
+    ) -> PyResult<()> {
+        let positive = |value: f64| value.is_finite() && value > 0.0;
+        if !positive(continuous_current) || !positive(time_constant) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "continuous_current and time_constant must be strictly positive",
+            ));
+        }
+        self.inner
+            .enable_duty_cycle_limiter(continuous_current, time_constant)
+            .map_err(PyErr::from)
+    }
+
+    /// Stop tracking the heating of the motors, restoring the lowered goal currents.
+    fn disable_duty_cycle_limiter(&self) -> PyResult<()> {
+        self.inner.disable_duty_cycle_limiter().map_err(PyErr::from)
+    }
+
+    /// Get the RMS current of each joint over its continuous rating (0 while the
+    /// duty-cycle limiter is disabled).
+    fn get_duty_cycle_loads(&self) -> [f64; NUM_JOINTS] {
+        self.inner.get_duty_cycle_loads()
+    }
+
+    /// Get the last temperatures, voltages, hardware errors and currents polled in the
+    /// background.
+    fn get_diagnostics(&self) -> Diagnostics {
+        self.inner.get_diagnostics()
+    }
+
+    /// Set how often the temperature, voltage, hardware error and current registers are
+    /// polled.
+    fn set_diagnostics_period(&self, period: Duration) -> PyResult<()> {
+        self.inner
+            .set_diagnostics_period(period)
+            .map_err(PyErr::from)
+    }
+
+    fn get_diagnostics_period(&self) -> Duration {
+        self.inner.get_diagnostics_period()
+    }
+
+    /// Get the number of ticks which overran the loop period, by main cause: "read",
+    /// "command:<name>", "callbacks" or "other".
+    fn get_overrun_counts(&self) -> HashMap<String, u64> {
+        self.inner.get_overrun_counts()
+    }
+
+    /// Set the tolerance (rad) of each joint used to decide when the goal is reached.
+    ///
+    /// # Arguments
+    /// * `tolerance` - Tolerance of the body rotation, stewart platform (x6), right and left antennas.
+    fn set_goal_tolerance(&self, tolerance: [f64; NUM_JOINTS]) -> PyResult<()> {
+        self.inner
+            .set_goal_tolerance(tolerance)
+            .map_err(PyErr::from)
+    }
+
+    /// Shut down the robot when the process receives SIGINT (Ctrl-C) or SIGTERM.
+    ///
+    /// The motors are ramped down, the torque disabled and the control loop stopped, the
+    /// process is not exited. The previous handlers are chained, not replaced: Python's
+    /// still runs, so a KeyboardInterrupt is still raised while the torque is disabled.
+    /// Other signals are up to the application, see get_shutdown_signal().
+    ///
+    /// # Arguments
+    /// * `ramp_duration` - Duration of the ramp down before disabling the torque.
+    #[pyo3(signature = (ramp_duration = Duration::from_secs(1)))]
+    fn enable_signal_shutdown(&self, ramp_duration: Duration) -> PyResult<()> {
+        self.inner
+            .enable_signal_shutdown(ramp_duration)
+            .map_err(PyErr::from)
+    }
+
+    /// Get the process exit code matching the signal the control loop was shut down on
+    /// (130 for SIGINT, 143 for SIGTERM), None until then.
+    fn get_shutdown_signal(&self) -> Option<i32> {
+        self.inner.get_shutdown_signal()
+    }
+
+    /// Register (or update) a command source used to arbitrate goals.
+    ///
+    /// While a source is active (it sent a command less than `timeout` ago), goals from
+    /// sources with a lower priority are dropped. `safety`, `teleop` and `animation`
+    /// are registered by default.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the source.
+    /// * `priority` - Higher priorities preempt lower ones.
+    /// * `timeout` - How long the source stays active after each command.
+    #[pyo3(signature = (name, priority, timeout = DEFAULT_SOURCE_TIMEOUT))]
+    fn register_source(&self, name: &str, priority: i32, timeout: Duration) {
+        self.inner.register_source(name, priority, timeout);
+    }
+
+    /// Let lower priority sources take the control back immediately.
+    fn release_source(&self, name: &str) {
+        self.inner.release_source(name);
+    }
+
+    /// Call `provider` at `rate` Hz to get the next goal positions, instead of running a
+    /// timing loop in Python.
+    ///
+    /// The provider runs on a dedicated thread, not on the control loop, and replaces the
+    /// one followed so far. Its goals are dropped when they come more than `timeout` after
+    /// the call, so the motors hold the last goal while it is slow. Following stops when
+    /// it raises.
+    ///
+    /// # Arguments
+    /// * `provider` - Callable returning a FullBodyPosition, or None to keep the current goal.
+    /// * `rate` - Calls per second.
+    /// * `timeout` - Maximum age of a goal when it reaches the control loop.
+    /// * `source` - Optional name of the command source, for arbitration.
+    #[pyo3(signature = (provider, rate = 50.0, timeout = Duration::from_millis(50), source = None))]
+    fn follow_target(
+        &self,
+        provider: Py<PyAny>,
+        rate: f64,
+        timeout: Duration,
+        source: Option<String>,
+    ) -> PyResult<()> {
+        let period = Duration::try_from_secs_f64(1.0 / rate).map_err(|_| {
+            pyo3::exceptions::PyValueError::new_err("The rate must be strictly positive")
+        })?;
+        let provider = move || {
+            Python::attach(|py| {
+                let goal = provider.call0(py).map_err(|e| e.to_string())?;
+                if goal.is_none(py) {
+                    return Ok(None);
+                }
+                goal.extract::<FullBodyPosition>(py)
+                    .map(Some)
+                    .map_err(|e| e.to_string())
+            })
+        };
+        self.inner
+            .follow_target(provider, period, timeout, source.as_deref());
+        Ok(())
+    }
+
+    /// Stop calling the provider given to follow_target, the motors hold the last goal.
+    fn stop_following(&self) {
+        self.inner.stop_following();
+    }
+
+    /// Whether a provider given to follow_target is still followed.
+    fn is_following(&self) -> bool {
+        self.inner.is_following()
+    }
+
+    /// Get the name of the command source currently in control, if any.
+    fn get_active_source(&self) -> Option<String> {
+        self.inner.get_active_source()
+    }
+
+    /// Get the number of commands dropped because their deadline had passed.
+    fn get_stale_commands_dropped(&self) -> u64 {
+        self.inner.get_stale_commands_dropped()
+    }
+
+    /// Check if all joints reached the latest goal (within tolerance).
+    fn is_goal_reached(&self) -> bool {
+        self.inner.is_goal_reached()
+    }
+
+    /// Register a callback called with the measured FullBodyPosition each time a goal is reached.
+    ///
+    /// The callback runs on a dedicated thread, not on the control loop.
+    ///
+    /// # Arguments
+    /// * `callback` - Callable taking a FullBodyPosition.
+    fn on_goal_reached(&self, callback: Py<PyAny>) {
+        let (tx, rx) = channel::<FullBodyPosition>();
+        std::thread::spawn(move || {
+            for position in rx {
+                Python::attach(|py| {
+                    if let Err(e) = callback.call1(py, (position,)) {
+                        log::warn!("Goal reached callback failed: {}", e);
+                    }
+                });
+            }
+        });
+        self.inner.on_goal_reached(move |position| {
+            let _ = tx.send(*position);
+        });
+    }
+
+    /// Register a callback called with True when the USB device is plugged back in and
+    /// False when it is unplugged.
+    ///
+    /// The callback runs on a dedicated thread, not on the control loop. It is never
+    /// called for simulated motors.
+    ///
+    /// # Arguments
+    /// * `callback` - Callable taking a bool.
+    fn on_hotplug(&self, callback: Py<PyAny>) {
+        let (tx, rx) = channel::<bool>();
+        std::thread::spawn(move || {
+            for connected in rx {
+                Python::attach(|py| {
+                    if let Err(e) = callback.call1(py, (connected,)) {
+                        log::warn!("Hotplug callback failed: {}", e);
+                    }
+                });
+            }
+        });
+        self.inner.on_hotplug(move |connected| {
+            let _ = tx.send(connected);
+        });
+    }
+
+    /// Register a callback called with a Touch each time the stewart platform or an antenna is touched.
+    ///
+    /// The motor currents are read at each tick once a callback is registered.
+    /// The callback runs on a dedicated thread, not on the control loop.
+    ///
+    /// # Arguments
+    /// * `callback` - Callable taking a Touch.
+    /// * `sensitivity` - From 0 (only strong pushes) to 1 (light touches, more false positives).
+    #[pyo3(signature = (callback, sensitivity = 0.5))]
+    fn on_touch(&self, callback: Py<PyAny>, sensitivity: f64) -> PyResult<()> {
+        if !(0.0..=1.0).contains(&sensitivity) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "The sensitivity must be between 0 and 1",
+            ));
+        }
+        let (tx, rx) = channel::<Touch>();
+        std::thread::spawn(move || {
+            for touch in rx {
+                Python::attach(|py| {
+                    if let Err(e) = callback.call1(py, (touch,)) {
+                        log::warn!("Touch callback failed: {}", e);
+                    }
+                });
+            }
+        });
+        self.inner.on_touch(
+            move |touch| {
+                let _ = tx.send(touch.clone());
+            },
+            sensitivity,
+        );
+        Ok(())
+    }
+
+    /// Check whether all the motors disappeared at once (e.g. power brownout).
+    ///
+    /// Motion and torque commands are rejected until the motors come back and
+    /// their configuration has been verified.
+    fn is_power_lost(&self) -> bool {
+        self.inner.is_power_lost()
+    }
+
+    /// Stop writing goals when no position could be read for `timeout` seconds.
+    ///
+    /// Motion and torque commands are then rejected, and the torque is disabled as
+    /// soon as the reads succeed again. Enable the torque again to resume.
+    ///
+    /// # Arguments
+    /// * `timeout` - Time without feedback (seconds) before stopping the writes.
+    fn enable_stale_feedback_shutdown(&self, timeout: f64) -> PyResult<()> {
+        self.inner
+            .enable_stale_feedback_shutdown(Duration::from_secs_f64(timeout))
+            .map_err(PyErr::from)
+    }
+
+    /// Keep writing goals whatever the feedback.
+    fn disable_stale_feedback_shutdown(&self) -> PyResult<()> {
+        self.inner
+            .disable_stale_feedback_shutdown()
+            .map_err(PyErr::from)
+    }
+
+    /// Ramp the stewart platform goal current up when its torque is enabled (on by default).
+    ///
+    /// The platform then tensions gently instead of snapping taut. The ramp starts
+    /// from a tenth of the configured goal current.
+    ///
+    /// # Arguments
+    /// * `duration` - Duration of the ramp (seconds).
+    #[pyo3(signature = (duration = 0.5))]
+    fn enable_soft_start(&self, duration: f64) -> PyResult<()> {
+        let duration = Duration::try_from_secs_f64(duration)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        self.inner.enable_soft_start(duration).map_err(PyErr::from)
+    }
+
+    /// Apply the goal current right away when the torque is enabled.
+    fn disable_soft_start(&self) -> PyResult<()> {
+        self.inner.disable_soft_start().map_err(PyErr::from)
+    }
+
+    /// Check whether the goal writes are stopped because of stale feedback.
+    fn is_feedback_stale(&self) -> bool {
+        self.inner.is_feedback_stale()
+    }
+
+    /// Get the wear counters: torque-on time, joint travel and overload events.
+    fn get_usage_counters(&self) -> UsageCounters {
+        self.inner.get_usage_counters()
+    }
+
+    /// Persist the wear counters to a file, adding the ones it already holds.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the usage file, created if it does not exist.
+    fn set_usage_file(&self, py: Python<'_>, path: std::path::PathBuf) -> PyResult<()> {
+        py.detach(|| self.inner.set_usage_file(path))
+            .map_err(PyErr::from)
+    }
+
+    /// Write the last known state (positions, torque, operating mode, safe mode) to a
+    /// file every second and when the loop stops, replacing it atomically.
+    ///
+    /// Returns the state the file held, e.g. the one before a crash: compare its
+    /// positions to the current ones (`LastState.is_pose_unchanged`) before re-enabling
+    /// the torque.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the last state file, created if it does not exist.
+    fn set_last_state_file(
+        &self,
+        py: Python<'_>,
+        path: std::path::PathBuf,
+    ) -> PyResult<Option<LastState>> {
+        py.detach(|| self.inner.set_last_state_file(path))
+            .map_err(PyErr::from)
+    }
+
+    /// Stop writing the last state file.
+    fn disable_last_state_file(&self, py: Python<'_>) -> PyResult<()> {
+        py.detach(|| self.inner.disable_last_state_file())
+            .map_err(PyErr::from)
+    }
+
+    /// Get the errors met by the control loop so far.
+    ///
+    /// Counts are given in total, by error kind and by motor name, with the
+    /// timestamp of their last occurrence.
+    fn get_error_stats(&self) -> ErrorStats {
+        self.inner.get_error_stats()
+    }
+
+    /// Get the journal events recorded after `since` (seconds since UNIX epoch), oldest first.
+    ///
+    /// Kinds are "torque", "mode", "fault", "reconnect", "watchdog" and "hotplug".
+    ///
+    /// # Arguments
+    /// * `since` - Only the events after this timestamp are returned (all by default).
+    #[pyo3(signature = (since = 0.0))]
+    fn get_events(&self, since: f64) -> Vec<Event> {
+        self.inner.get_events(since)
+    }
+
+    /// Append the faults (hardware errors, power losses, watchdog trips, USB changes,
+    /// current and thermal warnings) to a journal file, rotated once too large.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the journal, created if it does not exist.
+    /// * `max_size` - Size (bytes) above which the journal is rotated.
+    /// * `max_files` - Number of rotated files kept (`path.1`, `path.2`...).
+    #[pyo3(signature = (path, max_size = DEFAULT_MAX_JOURNAL_SIZE, max_files = DEFAULT_MAX_JOURNAL_FILES))]
+    fn set_fault_journal(
+        &self,
+        path: std::path::PathBuf,
+        max_size: u64,
+        max_files: usize,
+    ) -> PyResult<()> {
+        self.inner
+            .set_fault_journal(path, max_size, max_files)
+            .map_err(PyErr::from)
+    }
+
+    /// Stop journaling the faults to disk.
+    fn disable_fault_journal(&self) -> PyResult<()> {
+        self.inner.disable_fault_journal().map_err(PyErr::from)
+    }
+
+    /// Read the faults of a journal and of its rotated files, oldest first.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the journal given to `set_fault_journal`.
+    #[staticmethod]
+    fn read_fault_journal(path: std::path::PathBuf) -> PyResult<Vec<Event>> {
+        read_fault_journal(path).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))
+    }
+
+    /// Get the health report: feedback, power, USB and torque state, errors and last events.
+    fn get_health(&self) -> HealthReport {
+        self.inner.get_health()
+    }
+
+    /// Set how much of the stats is kept, applied at their next publication.
+    ///
+    /// # Arguments
+    /// * `max_samples` - Number of ticks and commands kept, None for no limit.
+    /// * `max_age` - Drop the ticks and commands older than this, None for no limit.
+    #[pyo3(signature = (max_samples = Some(DEFAULT_STATS_MAX_SAMPLES), max_age = None))]
+    fn set_stats_retention(
+        &self,
+        max_samples: Option<usize>,
+        max_age: Option<Duration>,
+    ) -> PyResult<()> {
+        self.inner
+            .set_stats_retention(StatsRetention {
+                max_samples,
+                max_age,
+            })
+            .map_err(PyErr::from)
+    }
+
+    /// Read each group of joints at its own period (zero to read it at every tick), e.g.
+    /// the stewart platform at every tick and the body and antennas every 50 ms.
+    fn set_read_schedule(&self, schedule: ReadSchedule) -> PyResult<()> {
+        self.inner.set_read_schedule(schedule).map_err(PyErr::from)
+    }
+
+    fn get_read_schedule(&self) -> ReadSchedule {
+        self.inner.get_read_schedule()
+    }
+
+    /// Read the positions with a strategy from now on, e.g. the best one found by
+    /// `auto_tune_reads` at a previous run.
+    ///
+    /// # Arguments
+    /// * `strategy` - "combined" (a sync read of all the motors), "split" (a sync read
+    ///   per group of joints) or "fast_sync" (a fast sync read of all the motors).
+    fn set_read_strategy(&self, strategy: &str, py: Python) -> PyResult<()> {
+        let strategy = ReadStrategy::from_name(strategy).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!("Unknown read strategy: {}", strategy))
+        })?;
+        py.detach(|| self.inner.set_read_strategy(strategy))
+            .map_err(PyErr::from)
+    }
+
+    /// Time the reads of the positions with each strategy and return delay time, and
+    /// apply the best configuration for the control loop: the fewest failed reads, then
+    /// the lowest 99th percentile read time.
+    ///
+    /// This blocks until the benchmark is over, the loop does not run meanwhile. The
+    /// return delay times are only swept while the torque is off, and the one applied
+    /// stays in the motors, while the read strategy has to be set again at each start.
+    ///
+    /// # Arguments
+    /// * `return_delay_times` - Return delay times to try (units of 2 µs).
+    /// * `samples` - Number of reads timed for each configuration.
+    #[pyo3(signature = (
+        return_delay_times = DEFAULT_RETURN_DELAY_TIMES.to_vec(),
+        samples = DEFAULT_TUNING_SAMPLES
+    ))]
+    fn auto_tune_reads(
+        &self,
+        py: Python<'_>,
+        return_delay_times: Vec<u8>,
+        samples: usize,
+    ) -> PyResult<ReadTuning> {
+        py.detach(|| self.inner.auto_tune_reads(&return_delay_times, samples))
+            .map_err(PyErr::from)
+    }
+
+    /// Get the position, velocity, current and torque state of the motors, all from the
+    /// same loop tick, with the health report and the stats, in a single call.
+    fn get_snapshot(&self) -> StateSnapshot {
+        self.inner.get_snapshot()
+    }
+
     /// Get the latest control loop statistics, if available.
     fn get_stats(&self) -> PyResult<Option<ControlLoopStats>> {
-        self.inner
-            .get_stats()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+        self.inner.get_stats().map_err(PyErr::from)
     }
 
     /// Perform an asynchronous raw read of motor bytes.
@@ -540,7 +2242,7 @@ impl ReachyMiniPyControlLoop {
             .map_err(|e| {
                 pyo3::exceptions::PyRuntimeError::new_err(format!(
                     "Failed to read raw bytes: {}",
-                    e.to_string()
+                    e
                 ))
             })
     }
@@ -556,7 +2258,7 @@ impl ReachyMiniPyControlLoop {
             .map_err(|e| {
                 pyo3::exceptions::PyRuntimeError::new_err(format!(
                     "Failed to write raw bytes: {}",
-                    e.to_string()
+                    e
                 ))
             })
     }
@@ -566,10 +2268,7 @@ impl ReachyMiniPyControlLoop {
     /// * `id` - Motor ID to read from.
     fn async_read_pid_gains(&self, id: u8) -> PyResult<(u16, u16, u16)> {
         self.inner.async_read_pid_gains(id).map_err(|e| {
-            pyo3::exceptions::PyRuntimeError::new_err(format!(
-                "Failed to read pid gains: {}",
-                e.to_string()
-            ))
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to read pid gains: {}", e))
         })
     }
 
@@ -581,22 +2280,30 @@ impl ReachyMiniPyControlLoop {
     /// * `d` - Derivative gain.
     fn async_write_pid_gains(&self, id: u8, p: u16, i: u16, d: u16) -> PyResult<()> {
         self.inner.async_write_pid_gains(id, p, i, d).map_err(|e| {
-            pyo3::exceptions::PyRuntimeError::new_err(format!(
-                "Failed to write pid gains: {}",
-                e.to_string()
-            ))
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to write pid gains: {}", e))
         })
     }
 
+    /// Keep the bus silent before and after each raw packet, e.g. between protocol v1
+    /// packets sent raw and the protocol v2 traffic of the XL330s on marginal wiring.
+    ///
+    /// # Arguments
+    /// * `gap` - Duration of the silence (s), 0 to disable.
+    fn set_raw_packet_gap(&self, gap: f64) -> PyResult<()> {
+        let gap = Duration::try_from_secs_f64(gap)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        self.inner.set_raw_packet_gap(gap).map_err(PyErr::from)
+    }
+
     fn write_raw_packet(&self, data: Py<PyBytes>, py: Python) -> PyResult<Vec<u8>> {
         let bytes = data.as_bytes(py);
         let (tx, rx) = channel();
         self.inner
-            .push_command(MotorCommand::WriteRawPacket {
+            .push_configuration(MotorCommand::WriteRawPacket {
                 packet: bytes.to_vec(),
                 tx,
             })
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+            .map_err(PyErr::from)?;
         let first_packet = rx.recv().map_err(|e| {
             pyo3::exceptions::PyRuntimeError::new_err(format!(
                 "Failed to receive raw packet response: {}",
@@ -607,14 +2314,214 @@ impl ReachyMiniPyControlLoop {
     }
 }
 
+fn py_track(name: &str) -> PyResult<Track> {
+    Track::from_name(name)
+        .ok_or_else(|| pyo3::exceptions::PyValueError::new_err(format!("Unknown track: {}", name)))
+}
+
+/// Kinematics implemented by a Python object.
+pub(crate) struct PyKinematics(pub(crate) Py<PyAny>);
+
+impl Kinematics for PyKinematics {
+    fn inverse(&self, pose: &HeadPose) -> Result<[f64; 6], KinematicsError> {
+        Python::attach(|py| {
+            self.0
+                .call_method1(py, "inverse", (*pose,))
+                .and_then(|joints| joints.extract(py))
+                .map_err(|e| KinematicsError(e.to_string()))
+        })
+    }
+
+    fn forward(&self, joints: &[f64; 6]) -> Result<HeadPose, KinematicsError> {
+        Python::attach(|py| {
+            self.0
+                .call_method1(py, "forward", (*joints,))
+                .and_then(|pose| pose.extract(py))
+                .map_err(|e| KinematicsError(e.to_string()))
+        })
+    }
+}
+
+impl ReachyMiniPyControlLoop {
+    fn push_goal(
+        &self,
+        command: MotorCommand,
+        deadline: Option<f64>,
+        source: Option<String>,
+    ) -> PyResult<()> {
+        self.inner
+            .validate_goal(&command)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+
+        // The deadline is checked first, so stale goals do not keep their source active
+        let res = match source {
+            Some(source) => self.inner.push_command_with_deadline(
+                MotorCommand::FromSource {
+                    source,
+                    command: Box::new(command),
+                },
+                deadline,
+            ),
+            None => self.inner.push_command_with_deadline(command, deadline),
+        };
+        res.map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    }
+}
+
+#[gen_stub_pyclass]
+#[pyclass]
+struct ReachyMiniPyRobotRegistry {
+    inner: RobotRegistry,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl ReachyMiniPyRobotRegistry {
+    /// Discover the robots plugged in and start a control loop for each of them.
+    ///
+    /// # Arguments
+    /// * `names` - Names of the robots by identity or USB serial number, see `discover_robots`.
+    /// * `read_position_loop_period` - Period between control loop updates.
+    /// * `allowed_retries` - Number of allowed retries for reading positions.
+    /// * `stats_pub_period` - Optional period for publishing stats.
+    /// * `voltage_rampup_timeout` - Maximum time to wait for the motors to be powered.
+    #[new]
+    #[pyo3(signature = (
+        names = None,
+        read_position_loop_period = Duration::from_millis(10),
+        allowed_retries = 5,
+        stats_pub_period = None,
+        voltage_rampup_timeout = Duration::from_secs(30),
+    ))]
+    fn new(
+        names: Option<HashMap<String, String>>,
+        read_position_loop_period: Duration,
+        allowed_retries: u64,
+        stats_pub_period: Option<Duration>,
+        voltage_rampup_timeout: Duration,
+    ) -> PyResult<Self> {
+        let robots = discover_robots(&names.unwrap_or_default());
+        let inner = RobotRegistry::open(
+            robots,
+            read_position_loop_period,
+            stats_pub_period,
+            allowed_retries,
+            voltage_rampup_timeout,
+            MotorSet::default(),
+        )
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        Ok(ReachyMiniPyRobotRegistry { inner })
+    }
+
+    /// List the robots plugged in, without opening them.
+    ///
+    /// # Arguments
+    /// * `names` - Names of the robots by identity or USB serial number, see `discover_robots`.
+    #[staticmethod]
+    #[pyo3(signature = (names = None))]
+    fn discover(names: Option<HashMap<String, String>>) -> Vec<RobotInfo> {
+        discover_robots(&names.unwrap_or_default())
+    }
+
+    /// Get the names of the robots, sorted.
+    fn names(&self) -> Vec<String> {
+        self.inner.names()
+    }
+
+    /// Get the name, port and serial number of each robot.
+    fn robots(&self) -> Vec<RobotInfo> {
+        self.inner.robots()
+    }
+
+    /// Get the control loop of a robot.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the robot.
+    fn get(&self, name: &str) -> PyResult<ReachyMiniPyControlLoop> {
+        let inner = self.inner.get(name).ok_or_else(|| {
+            pyo3::exceptions::PyKeyError::new_err(format!("No robot named {}", name))
+        })?;
+        Ok(ReachyMiniPyControlLoop {
+            inner,
+            dry_run: None,
+        })
+    }
+
+    /// Close the control loops of all the robots.
+    fn close(&self, py: Python) {
+        py.detach(|| self.inner.close());
+    }
+}
+
 #[pyo3::pymodule]
 fn reachy_mini_motor_controller(m: &Bound<'_, PyModule>) -> PyResult<()> {
     pyo3_log::init();
 
     m.add_class::<ReachyMiniMotorController>()?;
     m.add_class::<ReachyMiniPyControlLoop>()?;
+    m.add_class::<ReachyMiniPyRobotRegistry>()?;
+    m.add_class::<RobotInfo>()?;
     m.add_class::<FullBodyPosition>()?;
+    m.add_class::<LastPosition>()?;
+    m.add_class::<StateBuffer>()?;
     m.add_class::<ControlLoopStats>()?;
+    m.add_class::<StateSnapshot>()?;
+    m.add_class::<CommandLatency>()?;
+    m.add_class::<FirmwareInfo>()?;
+    m.add_class::<ErrorStats>()?;
+    m.add_class::<ErrorCounter>()?;
+    m.add_class::<Event>()?;
+    m.add_class::<HealthReport>()?;
+    m.add_class::<Touch>()?;
+    m.add_class::<TrackingSample>()?;
+    m.add_class::<Trajectory>()?;
+    m.add_class::<TrajectoryStatus>()?;
+    m.add_class::<TrajectoryResult>()?;
+    m.add_class::<BeatGrid>()?;
+    m.add_class::<HeadPose>()?;
+    m.add_class::<GazeSplit>()?;
+    m.add_class::<UsageCounters>()?;
+    m.add_class::<LastState>()?;
+    m.add_class::<Diagnostics>()?;
+    m.add_class::<ConfigDeviation>()?;
+    m.add_class::<DryRunWrite>()?;
+    m.add_class::<ClockOffset>()?;
+    m.add_class::<JointLimits>()?;
+    m.add_class::<PositionBounds>()?;
+    m.add_class::<SafeModeLimits>()?;
+    m.add_class::<ReadSchedule>()?;
+    m.add_class::<ReadBenchmark>()?;
+    m.add_class::<ReadTuning>()?;
+    m.add(
+        "MotorControllerError",
+        m.py().get_type::<exceptions::MotorControllerError>(),
+    )?;
+    m.add("SerialError", m.py().get_type::<exceptions::SerialError>())?;
+    m.add(
+        "BusTimeoutError",
+        m.py().get_type::<exceptions::BusTimeoutError>(),
+    )?;
+    m.add(
+        "MotorNotRespondingError",
+        m.py().get_type::<exceptions::MotorNotRespondingError>(),
+    )?;
+    m.add(
+        "UnexpectedResponseError",
+        m.py().get_type::<exceptions::UnexpectedResponseError>(),
+    )?;
+    m.add(
+        "OutOfBoundsError",
+        m.py().get_type::<exceptions::OutOfBoundsError>(),
+    )?;
+    m.add(
+        "UnsupportedError",
+        m.py().get_type::<exceptions::UnsupportedError>(),
+    )?;
+    m.add("WriteError", m.py().get_type::<exceptions::WriteError>())?;
+    m.add(
+        "RejectedError",
+        m.py().get_type::<exceptions::RejectedError>(),
+    )?;
 
     Ok(())
 }
@@ -0,0 +1,166 @@
+//! Calibration of the joints, identified on the robot and loaded by the modules using it.
+//!
+//! For now it holds the friction of each joint, identified with the `friction` tool:
+//! the current needed to move a joint at velocity `v` is modeled as
+//! `coulomb * sign(v) + viscous * v`.
+//!
+//! The file is a small text file, one parameter set per line:
+//! ```text
+//! friction body_rotation 18.2 4.1
+//! friction right_antenna 6.5 1.2
+//! ```
+//! Lines it does not know (e.g. from a newer version) are kept as is when saving.
+
+use std::{io, path::Path};
+
+use crate::{position_bounds::JOINT_NAMES, smoothing::NUM_JOINTS};
+
+/// Friction of a joint, as the current (mA) it takes to overcome it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JointFriction {
+    /// Current needed to start moving (mA).
+    pub coulomb: f64,
+    /// Current added per unit of velocity (mA per rad/s).
+    pub viscous: f64,
+}
+
+impl JointFriction {
+    /// Current (mA) compensating the friction at `velocity` (rad/s).
+    pub fn current(&self, velocity: f64) -> f64 {
+        if velocity == 0.0 {
+            return 0.0;
+        }
+        self.coulomb * velocity.signum() + self.viscous * velocity
+    }
+
+    /// Least squares fit on (velocity (rad/s), current (mA)) samples.
+    ///
+    /// A constant current, e.g. holding the head against gravity, is fitted along and left
+    /// out. Samples at rest are ignored, and `None` is returned unless both directions
+    /// were covered at two velocities at least.
+    pub fn fit(samples: &[(f64, f64)]) -> Option<Self> {
+        let moving: Vec<&(f64, f64)> = samples.iter().filter(|(v, _)| *v != 0.0).collect();
+        let mut velocities: Vec<f64> = moving.iter().map(|(v, _)| v.abs()).collect();
+        velocities.sort_by(f64::total_cmp);
+        velocities.dedup();
+        if velocities.len() < 2
+            || !moving.iter().any(|(v, _)| *v > 0.0)
+            || !moving.iter().any(|(v, _)| *v < 0.0)
+        {
+            return None;
+        }
+
+        // Normal equations of current = coulomb * sign(v) + viscous * v + offset
+        let mut m = [[0.0; 3]; 3];
+        let mut b = [0.0; 3];
+        for (v, current) in moving {
+            let basis = [v.signum(), *v, 1.0];
+            for (row, bi) in m.iter_mut().zip(basis) {
+                for (cell, bj) in row.iter_mut().zip(basis) {
+                    *cell += bi * bj;
+                }
+            }
+            for (value, bi) in b.iter_mut().zip(basis) {
+                *value += bi * current;
+            }
+        }
+        let [coulomb, viscous, _offset] = solve3(m, b)?;
+        Some(Self { coulomb, viscous })
+    }
+}
+
+/// Solve `m x = b` with Cramer's rule.
+fn solve3(m: [[f64; 3]; 3], b: [f64; 3]) -> Option<[f64; 3]> {
+    let det = |m: &[[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let d = det(&m);
+    if d.abs() < 1e-12 {
+        return None;
+    }
+    let mut x = [0.0; 3];
+    for (column, xi) in x.iter_mut().enumerate() {
+        let mut mc = m;
+        for (row, bi) in mc.iter_mut().zip(b) {
+            row[column] = bi;
+        }
+        *xi = det(&mc) / d;
+    }
+    Some(x)
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Calibration {
+    /// Friction of each joint, in joint order, `None` if not identified.
+    pub friction: [Option<JointFriction>; NUM_JOINTS],
+    /// Lines of the file not handled by this version, written back as is.
+    unknown_lines: Vec<String>,
+}
+
+impl Calibration {
+    /// Read a calibration saved with [`Calibration::save`].
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid calibration file line: {:?}", line),
+            )
+        };
+
+        let mut calibration = Calibration::default();
+        for line in std::fs::read_to_string(path)?.lines() {
+            let mut values = line.split_whitespace();
+            match values.next() {
+                Some("friction") => {
+                    let joint = values
+                        .next()
+                        .and_then(|name| JOINT_NAMES.iter().position(|j| *j == name))
+                        .ok_or_else(|| invalid(line))?;
+                    let mut parameter = || {
+                        values
+                            .next()
+                            .and_then(|v| v.parse().ok())
+                            .ok_or_else(|| invalid(line))
+                    };
+                    calibration.friction[joint] = Some(JointFriction {
+                        coulomb: parameter()?,
+                        viscous: parameter()?,
+                    });
+                }
+                None => {}
+                _ => calibration.unknown_lines.push(line.to_string()),
+            }
+        }
+        Ok(calibration)
+    }
+
+    /// Like [`Calibration::load`], but an empty calibration if the file does not exist yet.
+    pub fn load_or_default(path: &Path) -> io::Result<Self> {
+        match Self::load(path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            res => res,
+        }
+    }
+
+    /// Write the calibration, replacing the file atomically.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut content = String::new();
+        for (name, friction) in JOINT_NAMES.iter().zip(&self.friction) {
+            if let Some(friction) = friction {
+                content += &format!(
+                    "friction {} {} {}\n",
+                    name, friction.coulomb, friction.viscous
+                );
+            }
+        }
+        for line in &self.unknown_lines {
+            content += line;
+            content += "\n";
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, content)?;
+        std::fs::rename(tmp, path)
+    }
+}
@@ -0,0 +1,130 @@
+//! Command line arguments shared by the examples and the tools.
+//!
+//! Flatten them in a clap parser:
+//!
+//! ```no_run
+//! use clap::Parser;
+//! use reachy_mini_motor_controller::cli::{MotionArgs, PortArgs};
+//!
+//! #[derive(Parser)]
+//! struct Args {
+//!     #[command(flatten)]
+//!     port: PortArgs,
+//!     #[command(flatten)]
+//!     motion: MotionArgs,
+//! }
+//!
+//! let args = Args::parse();
+//! let controller = args.port.open()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{
+    DEFAULT_BAUD_RATE, ReachyMiniMotorController,
+    control_loop::{MissingMotorsPolicy, MotorSet},
+    position_bounds::JOINT_NAMES,
+    registry::discover_robots,
+    smoothing::NUM_JOINTS,
+};
+
+/// Serial port of the motors.
+#[derive(clap::Args, Debug, Clone)]
+pub struct PortArgs {
+    /// Serial port of the motors, the first robot found by default
+    #[clap(short, long)]
+    pub port: Option<String>,
+
+    /// Baud rate of the motors bus
+    #[clap(short, long, default_value_t = DEFAULT_BAUD_RATE)]
+    pub baud: u32,
+
+    /// Drive the other motors if some do not answer, e.g. a dead antenna
+    #[clap(long)]
+    pub allow_missing_motors: bool,
+
+    /// Joints left out, e.g. the antennas removed on a custom build (comma separated names)
+    #[clap(long, value_delimiter = ',')]
+    pub disable_joints: Vec<String>,
+}
+
+impl PortArgs {
+    /// The given port, else the one of the first robot found.
+    pub fn port(&self) -> Result<String, Box<dyn std::error::Error>> {
+        if let Some(port) = &self.port {
+            return Ok(port.clone());
+        }
+        discover_robots(&HashMap::new())
+            .into_iter()
+            .next()
+            .map(|robot| robot.port)
+            .ok_or_else(|| "No robot found, give its serial port with --port".into())
+    }
+
+    pub fn open(&self) -> Result<ReachyMiniMotorController, Box<dyn std::error::Error>> {
+        Ok(ReachyMiniMotorController::with_baud_rate(
+            &self.port()?,
+            self.baud,
+        )?)
+    }
+
+    /// Motors driven by the control loop: the disabled joints and the policy for the
+    /// motors which do not answer at startup.
+    pub fn motors(&self) -> Result<MotorSet, String> {
+        let mut motors = MotorSet {
+            missing_motors: match self.allow_missing_motors {
+                true => MissingMotorsPolicy::Continue,
+                false => MissingMotorsPolicy::Fail,
+            },
+            ..Default::default()
+        };
+        for name in &self.disable_joints {
+            motors.disabled_joints[joint_index(name)?] = true;
+        }
+        Ok(motors)
+    }
+}
+
+/// Periodic motion of some joints.
+#[derive(clap::Args, Debug, Clone)]
+pub struct MotionArgs {
+    /// Frequency of the motion (Hz)
+    #[clap(short, long, default_value_t = 0.25)]
+    pub frequency: f64,
+
+    /// Amplitude of the motion (degrees)
+    #[clap(short, long, default_value_t = 30.0)]
+    pub amplitude: f64,
+
+    /// Joints to move, e.g. body_rotation,right_antenna (all by default)
+    #[clap(short, long, value_delimiter = ',')]
+    pub joints: Vec<String>,
+}
+
+impl MotionArgs {
+    /// Whether each joint moves, in joint order.
+    pub fn joint_mask(&self) -> Result<[bool; NUM_JOINTS], String> {
+        if self.joints.is_empty() {
+            return Ok([true; NUM_JOINTS]);
+        }
+        let mut mask = [false; NUM_JOINTS];
+        for joint in &self.joints {
+            mask[joint_index(joint)?] = true;
+        }
+        Ok(mask)
+    }
+
+    /// Amplitude of the motion (rad).
+    pub fn amplitude_rad(&self) -> f64 {
+        self.amplitude.to_radians()
+    }
+}
+
+/// Index of the joint named `name` (see [`JOINT_NAMES`]), in joint order.
+pub fn joint_index(name: &str) -> Result<usize, String> {
+    JOINT_NAMES
+        .iter()
+        .position(|joint| *joint == name)
+        .ok_or_else(|| format!("Unknown joint {}, expected one of {:?}", name, JOINT_NAMES))
+}
@@ -0,0 +1,273 @@
+//! Monotonic and wall clock timestamps.
+//!
+//! Positions are timestamped with both the wall clock (seconds since the UNIX
+//! epoch) and the system monotonic clock. On Linux the latter is
+//! `CLOCK_MONOTONIC`, the clock used by V4L2 cameras and ALSA, so motor data can be
+//! aligned with streams captured by other processes even if the wall clock jumps
+//! (e.g. NTP correction). The control loop regularly measures the offset between
+//! both clocks, see [`crate::control_loop::ReachyMiniControlLoop::get_clock_offset`].
+//!
+//! The control loop itself reads the time and waits for its ticks through a
+//! [`LoopClock`]. [`SystemClock`] is the real one, [`ManualClock`] lets tests drive
+//! the ticks one by one without sleeping, e.g. with a non realtime
+//! [`crate::simulation::Simulation`].
+
+use std::time::Duration;
+#[cfg(feature = "serial")]
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Condvar, Mutex},
+    time::Instant,
+};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+/// How often the control loop measures the clock offset again.
+pub const CLOCK_SYNC_PERIOD: Duration = Duration::from_secs(10);
+
+/// Wall clock time (seconds since the UNIX epoch).
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn wall_now() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or(Duration::from_secs(0))
+        .as_secs_f64()
+}
+
+/// Monotonic clock time (s), `CLOCK_MONOTONIC` on unix.
+#[cfg(unix)]
+pub fn monotonic_now() -> f64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: ts is a valid timespec and CLOCK_MONOTONIC is always available
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as f64 + ts.tv_nsec as f64 * 1e-9
+}
+
+/// Monotonic clock time (s), counted from the first call in this process.
+#[cfg(not(any(unix, all(target_arch = "wasm32", target_os = "unknown"))))]
+pub fn monotonic_now() -> f64 {
+    static START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+    START
+        .get_or_init(std::time::Instant::now)
+        .elapsed()
+        .as_secs_f64()
+}
+
+/// There is no clock without a JavaScript host on `wasm32-unknown-unknown` (the std
+/// one panics), the positions built there have a 0 timestamp.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub fn wall_now() -> f64 {
+    0.0
+}
+
+/// See [`wall_now`].
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub fn monotonic_now() -> f64 {
+    0.0
+}
+
+/// Offset between the wall clock and the monotonic clock.
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, Copy)]
+pub struct ClockOffset {
+    /// `wall = monotonic + offset` (s).
+    pub offset: f64,
+    /// Half the time taken by the measurement, bounds the error on the offset (s).
+    pub uncertainty: f64,
+    /// Monotonic time of the measurement (s).
+    pub measured_at: f64,
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl ClockOffset {
+    fn __repr__(&self) -> String {
+        format!(
+            "ClockOffset(offset={:.6}, uncertainty={:.2e}, measured_at={:.3})",
+            self.offset, self.uncertainty, self.measured_at
+        )
+    }
+}
+
+impl ClockOffset {
+    /// Measure the offset, reading the monotonic clock between two wall clock reads.
+    pub fn measure() -> Self {
+        let wall_before = wall_now();
+        let monotonic = monotonic_now();
+        let wall_after = wall_now();
+        ClockOffset {
+            offset: (wall_before + wall_after) / 2.0 - monotonic,
+            uncertainty: (wall_after - wall_before) / 2.0,
+            measured_at: monotonic,
+        }
+    }
+
+    pub fn to_wall(&self, monotonic: f64) -> f64 {
+        monotonic + self.offset
+    }
+
+    pub fn to_monotonic(&self, wall: f64) -> f64 {
+        wall - self.offset
+    }
+}
+
+/// Time source of the control loop.
+#[cfg(feature = "serial")]
+pub trait LoopClock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Wait for the next control loop tick, `period` after the previous one.
+    ///
+    /// The returned future may be dropped before completion (the loop handles a
+    /// command meanwhile), the tick must not be lost then.
+    fn next_tick(&self, period: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + '_>>;
+
+    /// Called by the loop once it has handled a tick.
+    fn tick_handled(&self) {}
+}
+
+/// Wall clock ticks, late ticks are fired right away to catch up.
+#[cfg(feature = "serial")]
+#[derive(Debug, Default)]
+pub struct SystemClock {
+    next_tick: Mutex<Option<Instant>>,
+}
+
+#[cfg(feature = "serial")]
+impl LoopClock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn next_tick(&self, period: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(async move {
+            let deadline = lock(&self.next_tick).unwrap_or_else(Instant::now);
+            tokio::time::sleep_until(deadline.into()).await;
+            *lock(&self.next_tick) = Some(deadline + period);
+        })
+    }
+}
+
+/// How long [`ManualClock::step`] waits for the loop to handle its tick.
+#[cfg(feature = "serial")]
+const MANUAL_STEP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Clock only moving when told to, with the ticks released one by one.
+///
+/// ```no_run
+/// use std::{sync::Arc, time::Duration};
+/// use reachy_mini_motor_controller::{clock::ManualClock, control_loop::ReachyMiniControlLoop};
+/// use reachy_mini_motor_controller::simulation::{Simulation, SimulationConfig};
+///
+/// let sim = Simulation::new(SimulationConfig { realtime: false, ..Default::default() });
+/// let clock = Arc::new(ManualClock::new());
+/// let period = Duration::from_millis(10);
+/// let control_loop = ReachyMiniControlLoop::with_clock(
+///     sim.controller(), clock.clone(), period, None, 5, Duration::from_secs(1),
+///     Default::default(),
+/// ).unwrap();
+///
+/// // Let the loop read the positions after 10 ms of simulated motion
+/// sim.advance(period);
+/// assert!(clock.step(period));
+/// ```
+#[cfg(feature = "serial")]
+#[derive(Debug)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+    pending_ticks: Mutex<u64>,
+    tick: tokio::sync::Notify,
+    handled_ticks: Mutex<u64>,
+    handled: Condvar,
+}
+
+#[cfg(feature = "serial")]
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "serial")]
+impl ManualClock {
+    pub fn new() -> Self {
+        ManualClock {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+            pending_ticks: Mutex::new(0),
+            tick: tokio::sync::Notify::new(),
+            handled_ticks: Mutex::new(0),
+            handled: Condvar::new(),
+        }
+    }
+
+    /// Move the time forward without releasing a tick.
+    pub fn advance(&self, dt: Duration) {
+        *lock(&self.elapsed) += dt;
+    }
+
+    /// Move the time forward and release one tick, then wait for the loop to handle it.
+    ///
+    /// Returns `false` if the tick was not handled within a second (e.g. the loop is stopped).
+    pub fn step(&self, dt: Duration) -> bool {
+        self.advance(dt);
+        let before = *lock(&self.handled_ticks);
+        *lock(&self.pending_ticks) += 1;
+        self.tick.notify_one();
+
+        let handled = self
+            .handled
+            .wait_timeout_while(lock(&self.handled_ticks), MANUAL_STEP_TIMEOUT, |n| {
+                *n == before
+            })
+            .unwrap_or_else(|e| e.into_inner());
+        !handled.1.timed_out()
+    }
+
+    /// Time elapsed since the creation of the clock.
+    pub fn elapsed(&self) -> Duration {
+        *lock(&self.elapsed)
+    }
+}
+
+#[cfg(feature = "serial")]
+impl LoopClock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn next_tick(&self, _period: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(async move {
+            loop {
+                let notified = self.tick.notified();
+                {
+                    let mut pending = lock(&self.pending_ticks);
+                    if *pending > 0 {
+                        *pending -= 1;
+                        return;
+                    }
+                }
+                notified.await;
+            }
+        })
+    }
+
+    fn tick_handled(&self) {
+        *lock(&self.handled_ticks) += 1;
+        self.handled.notify_all();
+    }
+}
+
+#[cfg(feature = "serial")]
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
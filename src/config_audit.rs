@@ -0,0 +1,180 @@
+//! Audit of the motor configuration against the Reachy Mini profile.
+//!
+//! The EEPROM registers of the motors (operating mode, position limits, return delay,
+//! baud rate) are set once when the robot is assembled, but third-party tools such as
+//! the Dynamixel Wizard can change them and leave the robot misbehaving in obscure
+//! ways. The control loop reads them at init and reports the deviations from a
+//! [`ConfigProfile`], see
+//! [`crate::control_loop::ReachyMiniControlLoop::get_config_deviations`].
+//!
+//! [`crate::ReachyMiniMotorController::apply_factory_profile`] (or the `apply_profile`
+//! tool) writes the profile back to restore a robot to a known-good configuration.
+
+use std::fmt;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::{DEFAULT_BAUD_RATE, position_bounds::JOINT_NAMES, smoothing::NUM_JOINTS};
+
+/// First register read by the audit.
+pub(crate) const AUDIT_START_ADDRESS: u8 = 8;
+/// Number of registers read by the audit, up to the end of the position limits.
+pub(crate) const AUDIT_LENGTH: u8 = 56 - AUDIT_START_ADDRESS;
+
+const BAUD_RATE_ADDRESS: u8 = 8;
+const RETURN_DELAY_TIME_ADDRESS: u8 = 9;
+const OPERATING_MODE_ADDRESS: u8 = 11;
+const MAX_POSITION_LIMIT_ADDRESS: u8 = 48;
+const MIN_POSITION_LIMIT_ADDRESS: u8 = 52;
+
+/// Baud rates (bps) of the values of the baud rate register.
+const BAUD_RATES: [u32; 8] = [
+    9_600, 57_600, 115_200, 1_000_000, 2_000_000, 3_000_000, 4_000_000, 4_500_000,
+];
+
+/// Position control mode of the operating mode register.
+const POSITION_CONTROL_MODE: u8 = 3;
+/// Full range of the position limits (encoder ticks).
+const FULL_POSITION_RANGE: (u32, u32) = (0, 4095);
+
+/// Expected configuration of the motors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigProfile {
+    /// Baud rate (bps) of the bus.
+    pub baud_rate: u32,
+    /// Return delay time register, in units of 2 µs.
+    pub return_delay_time: u8,
+    /// Operating mode of each joint at startup, in joint order.
+    pub operating_modes: [u8; NUM_JOINTS],
+    /// Minimum and maximum position limits (encoder ticks) of each joint, in joint order.
+    pub position_limits: [(u32, u32); NUM_JOINTS],
+}
+
+impl Default for ConfigProfile {
+    /// Reachy Mini: 1 Mbps, no return delay, all joints in position control without
+    /// position limits (the goals are bounded by the control loop instead, see
+    /// [`crate::position_bounds`]).
+    fn default() -> Self {
+        ConfigProfile {
+            baud_rate: DEFAULT_BAUD_RATE,
+            return_delay_time: 0,
+            operating_modes: [POSITION_CONTROL_MODE; NUM_JOINTS],
+            position_limits: [FULL_POSITION_RANGE; NUM_JOINTS],
+        }
+    }
+}
+
+/// A register differing from the [`ConfigProfile`].
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConfigDeviation {
+    /// Name of the joint, see [`JOINT_NAMES`].
+    pub joint: String,
+    /// Name of the register, e.g. "return_delay_time".
+    pub register: String,
+    pub expected: u32,
+    pub actual: u32,
+}
+
+impl fmt::Display for ConfigDeviation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} is {}, expected {}",
+            self.register, self.joint, self.actual, self.expected
+        )
+    }
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl ConfigDeviation {
+    fn __repr__(&self) -> String {
+        format!(
+            "ConfigDeviation(joint={:?}, register={:?}, expected={}, actual={})",
+            self.joint, self.register, self.expected, self.actual
+        )
+    }
+}
+
+/// Address and bytes to write to restore the register of `deviation` to its expected
+/// value, `None` for the baud rate which cannot be changed on the bus in use.
+pub(crate) fn restore_write(deviation: &ConfigDeviation) -> Option<(u8, Vec<u8>)> {
+    let (address, length) = match deviation.register.as_str() {
+        "return_delay_time" => (RETURN_DELAY_TIME_ADDRESS, 1),
+        "operating_mode" => (OPERATING_MODE_ADDRESS, 1),
+        "min_position_limit" => (MIN_POSITION_LIMIT_ADDRESS, 4),
+        "max_position_limit" => (MAX_POSITION_LIMIT_ADDRESS, 4),
+        _ => return None,
+    };
+    Some((address, deviation.expected.to_le_bytes()[..length].to_vec()))
+}
+
+/// Compare the registers of `joint`, read from [`AUDIT_START_ADDRESS`], to the profile.
+pub(crate) fn audit_registers(
+    joint: usize,
+    registers: &[u8],
+    profile: &ConfigProfile,
+) -> Result<Vec<ConfigDeviation>, String> {
+    if registers.len() != AUDIT_LENGTH as usize {
+        return Err(format!(
+            "Invalid configuration registers length: expected {} bytes, got {}",
+            AUDIT_LENGTH,
+            registers.len()
+        ));
+    }
+    let register = |address: u8, length: usize| {
+        let start = (address - AUDIT_START_ADDRESS) as usize;
+        registers[start..start + length]
+            .iter()
+            .rev()
+            .fold(0u32, |value, &byte| value << 8 | byte as u32)
+    };
+    let baud_rate = register(BAUD_RATE_ADDRESS, 1);
+    let (min_position, max_position) = profile.position_limits[joint];
+    let checks = [
+        (
+            "baud_rate",
+            profile.baud_rate,
+            BAUD_RATES
+                .get(baud_rate as usize)
+                .copied()
+                .unwrap_or(baud_rate),
+        ),
+        (
+            "return_delay_time",
+            profile.return_delay_time as u32,
+            register(RETURN_DELAY_TIME_ADDRESS, 1),
+        ),
+        (
+            "operating_mode",
+            profile.operating_modes[joint] as u32,
+            register(OPERATING_MODE_ADDRESS, 1),
+        ),
+        (
+            "min_position_limit",
+            min_position,
+            register(MIN_POSITION_LIMIT_ADDRESS, 4),
+        ),
+        (
+            "max_position_limit",
+            max_position,
+            register(MAX_POSITION_LIMIT_ADDRESS, 4),
+        ),
+    ];
+    Ok(checks
+        .into_iter()
+        .filter(|(_, expected, actual)| expected != actual)
+        .map(|(register, expected, actual)| ConfigDeviation {
+            joint: JOINT_NAMES[joint].to_string(),
+            register: register.to_string(),
+            expected,
+            actual,
+        })
+        .collect())
+}
@@ -0,0 +1,61 @@
+//! Custom control laws run by the control loop at each tick.
+//!
+//! A [`ControlLaw`] installed with
+//! [`crate::control_loop::ReachyMiniControlLoop::set_control_law`] gets the measured
+//! positions and the target of the joints, i.e. the goals the loop would write
+//! otherwise (from the goal commands, trajectories, smoothing...), and decides what is
+//! actually written to the motors:
+//!
+//! ```no_run
+//! use reachy_mini_motor_controller::control_law::{ControlInput, ControlLaw, ControlOutput};
+//!
+//! /// Feed-forward of the tracking error, to reduce the lag of the motors.
+//! struct LeadCompensation {
+//!     gain: f64,
+//! }
+//!
+//! impl ControlLaw for LeadCompensation {
+//!     fn update(&mut self, input: &ControlInput) -> ControlOutput {
+//!         let mut goals = input.target;
+//!         for (goal, position) in goals.iter_mut().zip(input.positions) {
+//!             *goal += self.gain * (*goal - position);
+//!         }
+//!         ControlOutput {
+//!             goals: Some(goals),
+//!             stewart_currents: None,
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::smoothing::NUM_JOINTS;
+
+/// State of the joints given to a [`ControlLaw`].
+#[derive(Debug, Clone, Copy)]
+pub struct ControlInput {
+    /// Time since the previous update (s), the loop period for the first one.
+    pub dt: f64,
+    /// Measured positions (rad), in joint order.
+    pub positions: [f64; NUM_JOINTS],
+    /// Goals the joints should reach (rad), in joint order.
+    pub target: [f64; NUM_JOINTS],
+}
+
+/// What a [`ControlLaw`] writes to the motors.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ControlOutput {
+    /// Goal positions (rad), in joint order, `None` to leave the motors goals as they are.
+    pub goals: Option<[f64; NUM_JOINTS]>,
+    /// Goal currents (mA) of the stewart platform motors, e.g. in current-based position
+    /// control, `None` to leave them as they are.
+    pub stewart_currents: Option<[i16; 6]>,
+}
+
+/// Control law run by the control loop, after each position read.
+///
+/// It runs on the control loop thread, so it must be fast: a slow update delays the
+/// whole loop. It is not updated while the torque is off or the goal writes are
+/// stopped (power loss, stale feedback).
+pub trait ControlLaw: Send {
+    fn update(&mut self, input: &ControlInput) -> ControlOutput;
+}
@@ -1,41 +1,93 @@
-use log::info;
+use log::{info, warn};
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+#[cfg(feature = "python")]
 use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     fmt::Debug,
-    sync::{Arc, Mutex},
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicU64, Ordering},
+    },
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
-use tokio::{
-    sync::mpsc::{self, Receiver, Sender},
-    time,
+use tokio::sync::mpsc::{self, Receiver, Sender};
+
+use crate::{
+    HomingConfig, MotorControllerError, ReachyMiniMotorController,
+    arbitration::Arbiter,
+    beat::BeatGrid,
+    clock::{CLOCK_SYNC_PERIOD, ClockOffset, LoopClock, SystemClock, monotonic_now, wall_now},
+    config_audit::{ConfigDeviation, ConfigProfile},
+    control_law::{ControlInput, ControlLaw},
+    current_budget::CurrentBudget,
+    diagnostics::{
+        DEFAULT_DIAGNOSTICS_PERIOD, Diagnostics, DiagnosticsPoller, DiagnosticsRegister,
+    },
+    duty_cycle::{DUTY_CYCLE_UPDATE_PERIOD, DutyCycleLevel, DutyCycleLimiter},
+    error_stats::ErrorStats,
+    events::{Event, EventLog},
+    fault_journal::FaultJournal,
+    firmware::{Feature, FirmwareInfo},
+    follow::{TargetFollower, TargetProvider},
+    health::{HEALTH_REPORT_EVENTS, HealthReport},
+    hotplug::{self, HotplugMonitor},
+    kinematics::{GazeSplit, HeadPose, HeadTwist, HeadTwistFollower, Kinematics},
+    last_state::{LastState, LastStateWriter},
+    log_throttle::{DEFAULT_LOG_PERIOD, LogThrottle},
+    port_lock::PortInUseError,
+    poses::{BEFORE_TRANSPORT_POSE, DEFAULT_POSES_FILE, PoseLibrary, TRANSPORT_POSE},
+    position_bounds::{
+        DEFAULT_POSITION_BOUNDS, JOINT_NAMES, PositionBounds, PositionOutOfBounds, clamp_positions,
+        validate_positions,
+    },
+    read_filter::ReadFilter,
+    read_schedule::{ReadSchedule, ReadScheduler},
+    read_tuning::{ReadStrategy, ReadTuning, auto_tune_reads},
+    safe_mode::SafeModeLimits,
+    smoothing::{AntennaSmoother, GoalSmoother, JointLimits, NUM_JOINTS},
+    state_buffer::StateBuffer,
+    touch::{Touch, TouchDetector, TouchListener},
+    tracking::{TrackingSample, TrackingStream},
+    trajectory::{
+        Keyframe, Track, Trajectory, TrajectoryPlayer, TrajectoryResult, TrajectoryStatus,
+        VelocityFeedforward,
+    },
+    usage::{UsageCounters, UsageTracker},
 };
 
-use crate::ReachyMiniMotorController;
+pub use crate::position::FullBodyPosition;
 
-#[gen_stub_pyclass]
-#[pyclass]
-#[derive(Debug, Clone, Copy)]
-pub struct FullBodyPosition {
-    #[pyo3(get)]
-    pub body_yaw: f64,
-    #[pyo3(get)]
-    pub stewart: [f64; 6],
-    #[pyo3(get)]
-    pub antennas: [f64; 2],
-    #[pyo3(get)]
-    pub timestamp: f64, // seconds since UNIX epoch
+/// Publish the USB device changes of `serialport`, to the events and the hotplug callbacks.
+fn watch_usb_device(serialport: &str, shared: &SharedState) -> HotplugMonitor {
+    *lock_or_recover(&shared.usb_connected, "usb_connected") =
+        Some(hotplug::is_present(serialport));
+
+    let shared = shared.clone();
+    HotplugMonitor::start(serialport, move |connected| {
+        *lock_or_recover(&shared.usb_connected, "usb_connected") = Some(connected);
+        let change = if connected {
+            "connected"
+        } else {
+            "disconnected"
+        };
+        lock_or_recover(&shared.events, "events")
+            .record("hotplug", format!("USB device {}", change));
+        for callback in lock_or_recover(&shared.hotplug_callbacks, "hotplug_callbacks").iter() {
+            callback(connected);
+        }
+    })
 }
 
 /// Execute an operation with automatic retry on transient failures
 ///
 /// Handles brief USB interruptions with fast retries
 /// Fallback to control loop's slower retry mechanism for persistent issues
-fn with_retry<T, F>(mut op: F, attempts: u64) -> Result<T, Box<dyn std::error::Error>>
+fn with_retry<T, F>(mut op: F, attempts: u64) -> Result<T, MotorControllerError>
 where
-    F: FnMut() -> Result<T, Box<dyn std::error::Error>>,
+    F: FnMut() -> Result<T, MotorControllerError>,
 {
     const RETRY_DELAY_MS: u64 = 20;
 
@@ -49,17 +101,7 @@ where
             }
             Err(e) if attempt < attempts - 1 => {
                 // Only retry on transient errors
-                let is_transient = e
-                    .downcast_ref::<std::io::Error>()
-                    .map(|io_err| {
-                        matches!(
-                            io_err.kind(),
-                            std::io::ErrorKind::TimedOut | std::io::ErrorKind::Interrupted
-                        )
-                    })
-                    .unwrap_or(false);
-
-                if is_transient {
+                if e.is_transient() {
                     std::thread::sleep(Duration::from_millis(RETRY_DELAY_MS));
                 } else {
                     // Non-transient error, fail immediately
@@ -72,45 +114,473 @@ where
     unreachable!()
 }
 
-#[gen_stub_pymethods]
-#[pymethods]
-impl FullBodyPosition {
-    #[new]
-    pub fn new(body_yaw: f64, stewart: Vec<f64>, antennas: Vec<f64>) -> Self {
-        if stewart.len() != 6 || antennas.len() != 2 {
-            panic!("Stewart platform must have 6 positions and antennas must have 2 positions.");
-        }
-        FullBodyPosition {
-            body_yaw,
-            stewart: [
-                stewart[0], stewart[1], stewart[2], stewart[3], stewart[4], stewart[5],
-            ],
-            antennas: [antennas[0], antennas[1]],
-            timestamp: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or(Duration::from_secs(0))
-                .as_secs_f64(),
-        }
-    }
+pub struct ReachyMiniControlLoop {
+    loop_handle: Arc<Mutex<Option<std::thread::JoinHandle<()>>>>,
+    stop_signal: Arc<StopSignal>,
+    tx: Sender<QueuedCommand>,
+    next_command_id: Arc<AtomicU64>,
+    shared: SharedState,
+    rx_raw_bytes: Arc<Mutex<Receiver<Vec<u8>>>>,
+    motor_name_id: HashMap<String, u8>,
+    firmware_info: HashMap<u8, FirmwareInfo>,
+    /// Motors which did not answer at startup, see [`MissingMotorsPolicy::Continue`].
+    missing_motors: Vec<String>,
+    /// Joints disabled at startup, see [`MotorSet::disabled_joints`].
+    disabled_joints: Vec<String>,
+    config_deviations: Vec<ConfigDeviation>,
+    /// Watches the USB device, only when opened from a serial port name.
+    _hotplug: Option<HotplugMonitor>,
+    target_follower: Mutex<Option<TargetFollower>>,
+    poses_file: Mutex<std::path::PathBuf>,
+    /// Bounds replaced by the transport lock, restored by [`Self::wake_up`].
+    bounds_before_transport: Mutex<Option<[PositionBounds; NUM_JOINTS]>>,
+}
 
-    fn __repr__(&self) -> pyo3::PyResult<String> {
-        Ok(format!(
-            "FullBodyPosition(body_yaw={:.3}, stewart={:?}, antennas={:?}, timestamp={:.3})",
-            self.body_yaw, self.stewart, self.antennas, self.timestamp
-        ))
-    }
+/// Asks the control loop thread to stop.
+#[derive(Default)]
+struct StopSignal {
+    stop: Mutex<bool>,
+    /// Wakes up the loop without waiting for the next read tick.
+    notify: tokio::sync::Notify,
 }
 
-pub struct ReachyMiniControlLoop {
-    loop_handle: Arc<Mutex<Option<std::thread::JoinHandle<()>>>>,
-    stop_signal: Arc<Mutex<bool>>,
-    tx: Sender<MotorCommand>,
-    last_position: Arc<Mutex<Result<FullBodyPosition, MotorError>>>,
+/// Last good position and the error of the last read, if it failed.
+///
+/// A failed read does not erase the position, the safety logic may still need it.
+struct PositionState {
+    position: FullBodyPosition,
+    error: Option<MotorError>,
+}
+
+/// State published by the control loop thread and read by the [`ReachyMiniControlLoop`] getters.
+#[derive(Clone)]
+struct SharedState {
+    last_position: Arc<Mutex<PositionState>>,
     last_torque: Arc<Mutex<Result<bool, MotorError>>>,
     last_control_mode: Arc<Mutex<Result<u8, MotorError>>>,
     last_stats: Option<(Duration, Arc<Mutex<ControlLoopStats>>)>,
-    rx_raw_bytes: Arc<Mutex<Receiver<Vec<u8>>>>,
-    motor_name_id: HashMap<String, u8>,
+    body_yaw: Arc<Mutex<BodyYawTracker>>,
+    goal_reached: Arc<Mutex<bool>>,
+    goal_reached_callbacks: Arc<Mutex<Vec<GoalReachedCallback>>>,
+    stale_commands: Arc<Mutex<u64>>,
+    arbiter: Arc<Mutex<Arbiter>>,
+    error_stats: Arc<Mutex<ErrorStats>>,
+    usage: Arc<Mutex<UsageCounters>>,
+    power_lost: Arc<Mutex<bool>>,
+    history: Arc<Mutex<History>>,
+    clock_offset: Arc<Mutex<ClockOffset>>,
+    feedback_stale: Arc<Mutex<bool>>,
+    /// Exit code of the shutdown signal the loop stopped on, if any.
+    shutdown_signal: Arc<Mutex<Option<i32>>>,
+    position_bounds: Arc<Mutex<[PositionBounds; NUM_JOINTS]>>,
+    /// Joints neither read nor written: disabled or missing, see [`MotorSet`].
+    disabled_joints: [bool; NUM_JOINTS],
+    goal_clamping: Arc<Mutex<bool>>,
+    clamped_goals: Arc<Mutex<u64>>,
+    safe_mode: Arc<Mutex<SafeMode>>,
+    /// Key to unlock the configuration, if locked.
+    configuration_lock: Arc<Mutex<Option<String>>>,
+    clock: Arc<dyn LoopClock>,
+    events: Arc<Mutex<EventLog>>,
+    touch_listeners: Arc<Mutex<Vec<TouchListener>>>,
+    trajectory: Arc<Mutex<Option<TrajectoryStatus>>>,
+    trajectory_result: Arc<Mutex<Option<TrajectoryResult>>>,
+    tracks: Arc<Mutex<[Option<TrajectoryStatus>; Track::ALL.len()]>>,
+    current_budget_scale: Arc<Mutex<f64>>,
+    duty_cycle_loads: Arc<Mutex<[f64; NUM_JOINTS]>>,
+    overruns: Arc<Mutex<HashMap<String, u64>>>,
+    beat_grid: Arc<Mutex<Option<BeatGrid>>>,
+    kinematics: Arc<Mutex<Option<Box<dyn Kinematics>>>>,
+    control_law: Arc<Mutex<Option<Box<dyn ControlLaw>>>>,
+    gaze_split: Arc<Mutex<GazeSplit>>,
+    usb_connected: Arc<Mutex<Option<bool>>>,
+    hotplug_callbacks: Arc<Mutex<Vec<HotplugCallback>>>,
+    stats_retention: Arc<Mutex<StatsRetention>>,
+    max_position_age: Arc<Mutex<Duration>>,
+    read_schedule: Arc<Mutex<ReadSchedule>>,
+    diagnostics: Arc<Mutex<Diagnostics>>,
+    diagnostics_period: Arc<Mutex<Duration>>,
+    tracking: Arc<Mutex<TrackingStream>>,
+    /// The last position again, updated in place, see [`crate::state_buffer`].
+    state_buffer: StateBuffer,
+    tick_state: Arc<Mutex<TickState>>,
+}
+
+/// State of the motors at the end of the last tick, published in one piece for
+/// [`ReachyMiniControlLoop::get_snapshot`].
+#[derive(Clone, Copy)]
+struct TickState {
+    position: FullBodyPosition,
+    velocity: [f64; NUM_JOINTS],
+    current: [i16; NUM_JOINTS],
+    torque_enabled: Option<bool>,
+}
+
+/// Default number of positions kept in the history (10 s at 100 Hz).
+pub const DEFAULT_HISTORY_SIZE: usize = 1000;
+
+/// Age above which the last read position is not fresh anymore, until another one is set.
+pub const DEFAULT_MAX_POSITION_AGE: Duration = Duration::from_millis(100);
+
+/// Default number of ticks and commands kept in the stats (about 10 min at 100 Hz).
+pub const DEFAULT_STATS_MAX_SAMPLES: usize = 60_000;
+
+/// How much of the stats is kept: the oldest ticks and commands are dropped beyond
+/// `max_samples` of each, or once older than `max_age`.
+///
+/// Unbounded stats slowly leak memory in long running daemons, the default keeps the
+/// last [`DEFAULT_STATS_MAX_SAMPLES`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatsRetention {
+    pub max_samples: Option<usize>,
+    pub max_age: Option<Duration>,
+}
+
+impl Default for StatsRetention {
+    fn default() -> Self {
+        StatsRetention {
+            max_samples: Some(DEFAULT_STATS_MAX_SAMPLES),
+            max_age: None,
+        }
+    }
+}
+
+/// Ring buffer of the last published positions, oldest first.
+struct History {
+    capacity: usize,
+    positions: VecDeque<FullBodyPosition>,
+}
+
+impl History {
+    fn push(&mut self, position: FullBodyPosition) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.positions.len() == self.capacity {
+            self.positions.pop_front();
+        }
+        self.positions.push_back(position);
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        let excess = self.positions.len().saturating_sub(capacity);
+        self.positions.drain(..excess);
+        self.capacity = capacity;
+    }
+}
+
+/// Called from the control loop thread with the measured positions when the goal is reached.
+pub type GoalReachedCallback = Box<dyn Fn(&FullBodyPosition) + Send>;
+
+/// Called from the hotplug monitor thread with `true` when the USB device is plugged
+/// in and `false` when it is unplugged.
+pub type HotplugCallback = Box<dyn Fn(bool) + Send>;
+
+/// Default tolerance (rad) for a joint to be considered on its goal.
+pub const DEFAULT_GOAL_TOLERANCE: f64 = 0.02;
+
+/// Integrates the body yaw from successive encoder readings so it does not wrap around.
+#[derive(Debug, Clone, Copy)]
+struct BodyYawTracker {
+    last_reading: f64,
+    integrated: f64,
+}
+
+impl BodyYawTracker {
+    fn new(reading: f64) -> Self {
+        Self {
+            last_reading: reading,
+            integrated: reading,
+        }
+    }
+
+    fn update(&mut self, reading: f64) {
+        self.integrated += wrap_angle(reading - self.last_reading);
+        self.last_reading = reading;
+    }
+}
+
+/// State owned by the control loop thread, updated by the commands and at each read.
+struct LoopState {
+    smoother: Option<GoalSmoother>,
+    antenna_smoother: Option<AntennaSmoother>,
+    /// Last step of the antenna smoother.
+    antenna_last_step: std::time::Instant,
+    goal: GoalTracker,
+    /// Ramp down duration, if the loop should shut down the robot on SIGINT/SIGTERM.
+    signal_shutdown: Option<Duration>,
+    usage: UsageTracker,
+    last_state: LastStateWriter,
+    power: PowerMonitor,
+    read_filter: Option<ReadFilter>,
+    stale_feedback: Option<StaleFeedbackPolicy>,
+    /// Last warning about clamped goals, and the goals clamped since.
+    clamp_warning: Option<(std::time::Instant, u64)>,
+    torque: TorqueReconciler,
+    /// Last polled hardware error status of each joint, to journal the new faults only.
+    hardware_errors: [u8; NUM_JOINTS],
+    touch: TouchDetector,
+    trajectory: Option<TrajectoryPlayer>,
+    /// Players of the tracks, by [`Track::index`].
+    tracks: [Option<TrajectoryPlayer>; Track::ALL.len()],
+    velocity_feedforward: Option<VelocityFeedforward>,
+    /// Whether profile velocities were written since the last reset to no limit.
+    profile_velocities_written: bool,
+    twist: Option<HeadTwistFollower>,
+    /// Duration of the goal current ramp of the stewart platform, if enabled.
+    soft_start: Option<Duration>,
+    current_ramp: Option<CurrentRamp>,
+    /// Goals handed to the control law instead of being written, see [`drive_joints`].
+    law_target: Option<[f64; NUM_JOINTS]>,
+    law_last_update: Option<std::time::Instant>,
+    current_budget: Option<CurrentBudget>,
+    /// Total current enabled with [`ReachyMiniControlLoop::enable_current_budget`].
+    current_budget_limit: Option<f64>,
+    /// Limits of the safe mode, if enabled.
+    safe_mode: Option<SafeModeLimits>,
+    duty_cycle: Option<DutyCycleLimiter>,
+    overrun: OverrunTracker,
+    read_scheduler: ReadScheduler,
+    diagnostics: DiagnosticsPoller,
+    /// Last goal written to each joint, published with the positions read, see
+    /// [`crate::tracking`].
+    commanded: Option<[f64; NUM_JOINTS]>,
+    /// Velocity of each joint between the last two position reads (rad/s).
+    velocity: [f64; NUM_JOINTS],
+}
+
+/// How often the torque and operating mode registers are checked against the intended state.
+const TORQUE_RECONCILE_PERIOD: Duration = Duration::from_secs(1);
+
+/// Torque state the loop wants, applied again if a motor lost it (e.g. rebooted after an error).
+struct TorqueReconciler {
+    /// Intended torque of each joint, taken from the motors when unknown.
+    intended: Option<[bool; NUM_JOINTS]>,
+    last_check: std::time::Instant,
+}
+
+/// How a command changes the torque of the joints.
+enum TorqueChange {
+    Joints(Vec<usize>, bool),
+    /// The resulting torque is only known by reading it back.
+    Unknown,
+}
+
+impl TorqueReconciler {
+    fn check_due(&mut self, now: std::time::Instant) -> bool {
+        if now.duration_since(self.last_check) < TORQUE_RECONCILE_PERIOD {
+            return false;
+        }
+        self.last_check = now;
+        true
+    }
+
+    /// A failed command may have changed the torque of some joints only.
+    fn command_handled(&mut self, change: Option<TorqueChange>, success: bool) {
+        match (change, success) {
+            (None, _) => {}
+            (Some(TorqueChange::Joints(joints, enable)), true) => {
+                if let Some(intended) = self.intended.as_mut() {
+                    for joint in joints {
+                        intended[joint] = enable;
+                    }
+                }
+            }
+            _ => self.intended = None,
+        }
+    }
+}
+
+/// Default duration of the stewart platform goal current ramp when its torque is enabled.
+pub const DEFAULT_SOFT_START_DURATION: Duration = Duration::from_millis(500);
+/// Share of the goal current the ramp starts from.
+const SOFT_START_INITIAL_RATIO: f64 = 0.1;
+
+/// Stewart platform goal current raised progressively after its torque is enabled.
+struct CurrentRamp {
+    /// Goal current at the end of the ramp (mA), as configured before it.
+    target: [i16; 6],
+    start: std::time::Instant,
+    duration: Duration,
+}
+
+impl CurrentRamp {
+    /// Goal currents at `now`, and whether the ramp is over.
+    fn currents(&self, now: std::time::Instant) -> ([i16; 6], bool) {
+        let elapsed = now.saturating_duration_since(self.start);
+        let progress = match self.duration.is_zero() {
+            true => 1.0,
+            false => (elapsed.as_secs_f64() / self.duration.as_secs_f64()).min(1.0),
+        };
+        let ratio = SOFT_START_INITIAL_RATIO + (1.0 - SOFT_START_INITIAL_RATIO) * progress;
+        let currents = self
+            .target
+            .map(|current| (current as f64 * ratio).round() as i16);
+        (currents, progress >= 1.0)
+    }
+}
+
+/// Where the time of the last tick went, to tell what caused an overrun of the period.
+#[derive(Default)]
+struct OverrunTracker {
+    /// Commands handled since the previous tick (s).
+    commands: f64,
+    /// Slowest of these commands, and its duration (s).
+    slowest_command: Option<(String, f64)>,
+    read: f64,
+    callbacks: f64,
+}
+
+impl OverrunTracker {
+    fn command(&mut self, name: String, duration: f64) {
+        self.commands += duration;
+        if self
+            .slowest_command
+            .as_ref()
+            .is_none_or(|(_, slowest)| duration > *slowest)
+        {
+            self.slowest_command = Some((name, duration));
+        }
+    }
+
+    /// End a tick which took `tick` (s), returning the main cause and a description of
+    /// the overrun if the period was exceeded.
+    fn end_tick(&mut self, tick: f64, period: f64) -> Option<(String, String)> {
+        let tracker = std::mem::take(self);
+        let total = tick + tracker.commands;
+        if total <= period {
+            return None;
+        }
+
+        let mut causes = vec![
+            ("read".to_string(), tracker.read),
+            ("callbacks".to_string(), tracker.callbacks),
+            ("other".to_string(), tick - tracker.read - tracker.callbacks),
+        ];
+        if let Some((name, duration)) = tracker.slowest_command {
+            causes.push((format!("command:{}", name), duration));
+        }
+        let (cause, duration) = causes
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap_or_default();
+        let detail = format!(
+            "{:.1} ms for a {:.1} ms period, {} took {:.1} ms (read {:.1} ms, commands {:.1} ms, callbacks {:.1} ms)",
+            total * 1000.0,
+            period * 1000.0,
+            cause,
+            duration * 1000.0,
+            tracker.read * 1000.0,
+            tracker.commands * 1000.0,
+            tracker.callbacks * 1000.0,
+        );
+        Some((cause, detail))
+    }
+}
+
+/// Stop writing goals when the reads keep failing, and disable the torque once they are back.
+struct StaleFeedbackPolicy {
+    timeout: Duration,
+    last_feedback: std::time::Instant,
+}
+
+/// Half width (rad) of the position bounds locked around the transport pose.
+const TRANSPORT_LOCK_MARGIN: f64 = 0.1;
+/// Time given to the joints to settle at the end of the transport moves.
+const TRANSPORT_SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// Consecutive failed reads before checking whether all the motors are gone.
+const POWER_LOSS_READ_FAILURES: u32 = 3;
+/// Minimum time between two checks of the missing motors.
+const POWER_LOSS_CHECK_PERIOD: Duration = Duration::from_secs(1);
+
+/// Tells a power loss (all motors gone at once) from a single motor timing out.
+struct PowerMonitor {
+    consecutive_failures: u32,
+    last_check: Option<std::time::Instant>,
+}
+
+impl PowerMonitor {
+    /// Whether enough reads failed in a row to look for the missing motors now.
+    fn read_failed(&mut self, now: std::time::Instant) -> bool {
+        self.consecutive_failures += 1;
+        if self.consecutive_failures < POWER_LOSS_READ_FAILURES
+            || self
+                .last_check
+                .is_some_and(|t| now.duration_since(t) < POWER_LOSS_CHECK_PERIOD)
+        {
+            return false;
+        }
+        self.last_check = Some(now);
+        true
+    }
+
+    fn read_succeeded(&mut self) {
+        self.consecutive_failures = 0;
+    }
+}
+
+/// Latest goal of each joint, compared to the measured positions with a per-joint tolerance.
+struct GoalTracker {
+    goal: Option<[f64; NUM_JOINTS]>,
+    tolerance: [f64; NUM_JOINTS],
+}
+
+impl GoalTracker {
+    /// Update the goal of some joints, the others keep their goal (or current position if none).
+    fn set_joints(&mut self, first: usize, targets: &[f64], current: [f64; NUM_JOINTS]) {
+        let mut goal = self.goal.unwrap_or(current);
+        goal[first..first + targets.len()].copy_from_slice(targets);
+        self.goal = Some(goal);
+    }
+
+    fn is_reached(&self, positions: &[f64; NUM_JOINTS]) -> bool {
+        self.goal.is_some_and(|goal| {
+            goal.iter()
+                .zip(positions.iter())
+                .zip(self.tolerance.iter())
+                .all(|((goal, position), tolerance)| (goal - position).abs() <= *tolerance)
+        })
+    }
+}
+
+/// Safe mode setting, see [`crate::safe_mode`].
+#[derive(Debug, Clone, Copy, Default)]
+struct SafeMode {
+    enabled: bool,
+    limits: SafeModeLimits,
+}
+
+impl SafeMode {
+    /// Limits to enforce, if enabled.
+    fn active(&self) -> Option<SafeModeLimits> {
+        self.enabled.then_some(self.limits)
+    }
+}
+
+/// Bounds the goals are checked against: the position bounds, shrunk to the workspace of
+/// the safe mode if enabled, without any for the disabled joints since their goals are
+/// not written.
+fn goal_bounds(shared: &SharedState) -> [PositionBounds; NUM_JOINTS] {
+    let mut bounds = *lock_or_recover(&shared.position_bounds, "position_bounds");
+    if let Some(limits) = lock_or_recover(&shared.safe_mode, "safe_mode").active() {
+        bounds = limits.workspace(&bounds);
+    }
+    std::array::from_fn(|joint| match shared.disabled_joints[joint] {
+        true => PositionBounds::new(f64::NEG_INFINITY, f64::INFINITY),
+        false => bounds[joint],
+    })
+}
+
+/// Lock a shared state mutex, recovering the data if a panic poisoned it.
+fn lock_or_recover<'a, T>(mutex: &'a Mutex<T>, name: &str) -> MutexGuard<'a, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            log::error!("{} mutex was poisoned", name);
+            poisoned.into_inner()
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -138,6 +608,11 @@ pub enum MotorCommand {
     SetStewartPlatformGoalCurrent {
         current: [i16; 6],
     },
+    /// Goal currents written right before the goal positions.
+    SetStewartGoal {
+        positions: [f64; 6],
+        currents: [i16; 6],
+    },
     SetStewartPlatformOperatingMode {
         mode: u8,
     },
@@ -171,39 +646,421 @@ pub enum MotorCommand {
         packet: Vec<u8>,
         tx: std::sync::mpsc::Sender<Vec<u8>>,
     },
+    SetRawPacketGap {
+        gap: Duration,
+    },
+    SpinBody {
+        velocity: f64,
+    },
+    StopBodySpin(),
+    SetAntennasFloppy(),
+    SetAntennasStiff(),
+    HomeBodyRotation {
+        config: HomingConfig,
+        tx: std::sync::mpsc::Sender<Result<f64, MotorControllerError>>,
+    },
+    ReadAntennasVelocity {
+        tx: std::sync::mpsc::Sender<Result<[f64; 2], MotorControllerError>>,
+    },
+    ReadAntennasCurrent {
+        tx: std::sync::mpsc::Sender<Result<[i16; 2], MotorControllerError>>,
+    },
+    ReadAllTemperatures {
+        tx: std::sync::mpsc::Sender<Result<[u8; NUM_JOINTS], MotorControllerError>>,
+    },
+    ReadAllCurrents {
+        tx: std::sync::mpsc::Sender<Result<[i16; NUM_JOINTS], MotorControllerError>>,
+    },
+    SetGoalSmoothing {
+        limits: Option<Box<[JointLimits; NUM_JOINTS]>>,
+    },
+    SetGoalTolerance {
+        tolerance: [f64; NUM_JOINTS],
+    },
+    /// Execute the command only if it is handled before the deadline (seconds since UNIX epoch).
+    WithDeadline {
+        deadline: f64,
+        command: Box<MotorCommand>,
+    },
+    /// Execute the command only if the source is not preempted by a higher priority one.
+    FromSource {
+        source: String,
+        command: Box<MotorCommand>,
+    },
+    EnableSignalShutdown {
+        ramp_duration: Duration,
+    },
+    SetUsageFile {
+        path: std::path::PathBuf,
+        tx: std::sync::mpsc::Sender<Result<(), String>>,
+    },
+    SetLastStateFile {
+        path: Option<std::path::PathBuf>,
+        /// Receives the state the file held.
+        tx: std::sync::mpsc::Sender<Result<Option<LastState>, String>>,
+    },
+    SetReadFilter {
+        max_velocity: Option<f64>,
+    },
+    SetCurrentBudget {
+        max_total_current: Option<f64>,
+    },
+    SetDutyCycleLimiter {
+        /// Continuous current rating (mA) and thermal time constant (s).
+        rating: Option<(f64, f64)>,
+    },
+    SetStaleFeedbackTimeout {
+        timeout: Option<Duration>,
+    },
+    SetSoftStart {
+        duration: Option<Duration>,
+    },
+    SetVelocityFeedforward {
+        feedforward: Option<VelocityFeedforward>,
+    },
+    SetAntennaSmoothing {
+        /// Natural frequency (Hz) and damping ratio.
+        parameters: Option<(f64, f64)>,
+    },
+    SetSafeMode {
+        limits: Option<SafeModeLimits>,
+    },
+    SetReadStrategy {
+        strategy: ReadStrategy,
+        tx: std::sync::mpsc::Sender<Result<(), MotorControllerError>>,
+    },
+    AutoTuneReads {
+        /// Return delay times (units of 2 µs) to try.
+        return_delay_times: Vec<u8>,
+        samples: usize,
+        tx: std::sync::mpsc::Sender<Result<ReadTuning, MotorControllerError>>,
+    },
+    /// Sample the trajectory at each tick of the loop, from its start.
+    PlayTrajectory {
+        trajectory: Trajectory,
+        /// Monotonic time (s) to start at, right away if `None`.
+        start: Option<f64>,
+    },
+    PauseTrajectory(),
+    ResumeTrajectory(),
+    SetTrajectorySpeed {
+        speed: f64,
+    },
+    StopTrajectory(),
+    /// Sample the joints of `track` from the trajectory at each tick of the loop, over
+    /// the trajectory and the goals of the other joints.
+    PlayTrack {
+        track: Track,
+        trajectory: Trajectory,
+        /// Monotonic time (s) to start at, right away if `None`.
+        start: Option<f64>,
+    },
+    StopTrack {
+        track: Track,
+    },
+    /// Move the head at this velocity until the next twist, see [`crate::kinematics`].
+    SetHeadTwist {
+        twist: HeadTwist,
+    },
+    /// Turn the body and the head to look at `yaw` (rad), see [`GazeSplit`].
+    SetGazeYaw {
+        yaw: f64,
+    },
+    /// Move the head to `pose` through the kinematics, and the body to `body_yaw` (rad).
+    SetHeadPose {
+        pose: HeadPose,
+        body_yaw: Option<f64>,
+    },
+}
+
+impl MotorCommand {
+    /// First joint and goal positions set by the command, if it sets goals.
+    ///
+    /// Wrapped commands (deadline, source) are not looked into.
+    pub fn goal_joints(&self) -> Option<(usize, Vec<f64>)> {
+        use MotorCommand::*;
+
+        match self {
+            SetAllGoalPositions { positions } => Some((0, positions.joints().to_vec())),
+            SetStewartPlatformPosition { position } => Some((1, position.to_vec())),
+            SetStewartGoal { positions, .. } => Some((1, positions.to_vec())),
+            SetBodyRotation { position } => Some((0, vec![*position])),
+            SetAntennasPositions { positions } => Some((7, positions.to_vec())),
+            _ => None,
+        }
+    }
+
+    /// Replace the goal positions set by the command, see [`Self::goal_joints`].
+    fn set_goal_joints(&mut self, targets: &[f64]) {
+        use MotorCommand::*;
+
+        match self {
+            SetAllGoalPositions { positions } => {
+                positions.body_yaw = targets[0];
+                positions.stewart.copy_from_slice(&targets[1..7]);
+                positions.antennas.copy_from_slice(&targets[7..9]);
+            }
+            SetStewartPlatformPosition { position } => position.copy_from_slice(targets),
+            SetStewartGoal { positions, .. } => positions.copy_from_slice(targets),
+            SetBodyRotation { position } => *position = targets[0],
+            SetAntennasPositions { positions } => positions.copy_from_slice(targets),
+            _ => {}
+        }
+    }
+
+    /// Whether the command moves the robot or enables the torque.
+    pub fn is_motion(&self) -> bool {
+        use MotorCommand::*;
+
+        match self {
+            SetAllGoalPositions { .. }
+            | SetStewartPlatformPosition { .. }
+            | SetBodyRotation { .. }
+            | SetAntennasPositions { .. }
+            | SetStewartGoal { .. }
+            | EnableTorque()
+            | EnableTorqueOnIds { .. }
+            | EnableStewartPlatform { enable: true }
+            | EnableBodyRotation { enable: true }
+            | EnableAntennas { enable: true }
+            | SetAntennasStiff()
+            | SpinBody { .. }
+            | HomeBodyRotation { .. }
+            | PlayTrajectory { .. }
+            | PlayTrack { .. }
+            | SetHeadTwist { .. }
+            | SetGazeYaw { .. }
+            | SetHeadPose { .. } => true,
+            WithDeadline { command, .. } | FromSource { command, .. } => command.is_motion(),
+            _ => false,
+        }
+    }
+
+    /// Whether the command changes the configuration (operating modes, limits, raw
+    /// register writes, homing...), rejected while it is locked, see
+    /// [`ReachyMiniControlLoop::lock_configuration`].
+    pub fn is_configuration(&self) -> bool {
+        use MotorCommand::*;
+
+        match self {
+            SetStewartPlatformOperatingMode { .. }
+            | SetAntennasOperatingMode { .. }
+            | SetBodyRotationOperatingMode { .. }
+            | WriteRawBytes { .. }
+            | WriteRawPacket { .. }
+            | SetRawPacketGap { .. }
+            | SetGoalSmoothing { .. }
+            | EnableSignalShutdown { .. }
+            | SetUsageFile { .. }
+            | SetLastStateFile { .. }
+            | SetReadFilter { .. }
+            | SetCurrentBudget { .. }
+            | SetDutyCycleLimiter { .. }
+            | SetStaleFeedbackTimeout { .. }
+            | SetSoftStart { .. }
+            | SetSafeMode { .. }
+            | SetReadStrategy { .. }
+            | AutoTuneReads { .. }
+            | HomeBodyRotation { .. }
+            | SetVelocityFeedforward { .. }
+            | SetAntennaSmoothing { .. }
+            | SetGoalTolerance { .. } => true,
+            WithDeadline { command, .. } | FromSource { command, .. } => command.is_configuration(),
+            _ => false,
+        }
+    }
+
+    /// Whether the command is out of reach of the safe mode limits (raw register writes,
+    /// homing into the end-stops), so rejected while it is enabled.
+    pub fn bypasses_safe_mode(&self) -> bool {
+        use MotorCommand::*;
+
+        match self {
+            WriteRawBytes { .. } | WriteRawPacket { .. } | HomeBodyRotation { .. } => true,
+            WithDeadline { command, .. } | FromSource { command, .. } => {
+                command.bypasses_safe_mode()
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A command in the queue of the control loop.
+struct QueuedCommand {
+    id: u64,
+    pushed_at: std::time::Instant,
+    command: MotorCommand,
+}
+
+/// Time spent by a command between its push and the end of its bus write.
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CommandLatency {
+    /// Id returned by [`ReachyMiniControlLoop::push_command_tracked`].
+    pub id: u64,
+    /// Time waited in the command queue (s).
+    pub queue_wait: f64,
+    /// Time taken to handle the command, mostly writing on the bus (s).
+    pub write: f64,
+    /// When the command was handled, on the monotonic clock (s), see [`crate::clock`].
+    pub timestamp: f64,
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl CommandLatency {
+    fn __repr__(&self) -> String {
+        format!(
+            "CommandLatency(id={}, queue_wait={:.2}ms, write={:.2}ms, timestamp={:.3})",
+            self.id,
+            self.queue_wait * 1000.0,
+            self.write * 1000.0,
+            self.timestamp
+        )
+    }
+}
+
+impl CommandLatency {
+    /// End to end latency (s).
+    pub fn total(&self) -> f64 {
+        self.queue_wait + self.write
+    }
 }
 
-#[gen_stub_pyclass]
-#[pyclass]
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ControlLoopStats {
-    #[pyo3(get)]
     pub period: Vec<f64>,
-    #[pyo3(get)]
     pub read_dt: Vec<f64>,
-    #[pyo3(get)]
+    /// Start of each tick of `read_dt`, on the monotonic clock (s), see [`crate::clock`].
+    pub tick_timestamps: Vec<f64>,
     pub write_dt: Vec<f64>,
+    pub command_latency: Vec<CommandLatency>,
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl ControlLoopStats {
-    fn __repr__(&self) -> pyo3::PyResult<String> {
-        Ok(format!(
-            "ControlLoopStats(period=~{:.2?}ms, read_dt=~{:.2?} ms, write_dt=~{:.2?} ms)",
-            self.period.iter().sum::<f64>() / self.period.len() as f64 * 1000.0,
-            self.read_dt.iter().sum::<f64>() / self.read_dt.len() as f64 * 1000.0,
-            self.write_dt.iter().sum::<f64>() / self.write_dt.len() as f64 * 1000.0,
-        ))
+    /// Get percentiles of the end to end command latency (s), e.g. `[50, 90, 99]`.
+    ///
+    /// # Arguments
+    /// * `percentiles` - Percentiles between 0 and 100.
+    #[pyo3(name = "latency_percentiles")]
+    fn py_latency_percentiles(&self, percentiles: Vec<f64>) -> Vec<f64> {
+        self.latency_percentiles(&percentiles)
+    }
+
+    /// Get the stats as a list of dict records, e.g. for `pandas.DataFrame(records)`.
+    ///
+    /// Each tick gives a `{"kind": "tick", "timestamp", "period", "read_dt"}` record and
+    /// each command a `{"kind": "command", "timestamp", "id", "queue_wait", "write",
+    /// "latency"}` record, sorted by timestamp (monotonic clock, s). Durations are in s.
+    fn to_records<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, pyo3::types::PyDict>>> {
+        let mut records = Vec::new();
+        let ticks = self.tick_timestamps.iter().zip(&self.read_dt);
+        for (i, (timestamp, read_dt)) in ticks.enumerate() {
+            let record = pyo3::types::PyDict::new(py);
+            record.set_item("kind", "tick")?;
+            record.set_item("timestamp", timestamp)?;
+            record.set_item("period", self.period.get(i).copied().unwrap_or(f64::NAN))?;
+            record.set_item("read_dt", read_dt)?;
+            records.push((*timestamp, record));
+        }
+        for latency in &self.command_latency {
+            let record = pyo3::types::PyDict::new(py);
+            record.set_item("kind", "command")?;
+            record.set_item("timestamp", latency.timestamp)?;
+            record.set_item("id", latency.id)?;
+            record.set_item("queue_wait", latency.queue_wait)?;
+            record.set_item("write", latency.write)?;
+            record.set_item("latency", latency.total())?;
+            records.push((latency.timestamp, record));
+        }
+        records.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(records.into_iter().map(|(_, record)| record).collect())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl ControlLoopStats {
+    /// Drop the oldest samples beyond the `retention`, `now` being the monotonic time (s).
+    ///
+    /// The ticks (`period`, `read_dt`, `tick_timestamps`) and the commands (`write_dt`,
+    /// `command_latency`) are dropped together, so they stay aligned.
+    pub fn trim(&mut self, retention: &StatsRetention, now: f64) {
+        let excess = |len: usize, timestamps: &mut dyn Iterator<Item = f64>| {
+            let too_old = retention.max_age.map_or(0, |max_age| {
+                timestamps
+                    .take_while(|t| now - t > max_age.as_secs_f64())
+                    .count()
+            });
+            let too_many = retention
+                .max_samples
+                .map_or(0, |max| len.saturating_sub(max));
+            too_old.max(too_many)
+        };
+
+        let ticks = excess(
+            self.read_dt.len(),
+            &mut self.tick_timestamps.iter().copied(),
+        );
+        for samples in [
+            &mut self.period,
+            &mut self.read_dt,
+            &mut self.tick_timestamps,
+        ] {
+            samples.drain(..ticks.min(samples.len()));
+        }
+        let commands = excess(
+            self.command_latency.len(),
+            &mut self.command_latency.iter().map(|l| l.timestamp),
+        );
+        self.write_dt.drain(..commands.min(self.write_dt.len()));
+        self.command_latency.drain(..commands);
+    }
+
+    /// Percentiles (between 0 and 100) of the end to end command latency (s).
+    ///
+    /// NaN if no command latency was measured.
+    pub fn latency_percentiles(&self, percentiles: &[f64]) -> Vec<f64> {
+        let mut latencies: Vec<f64> = self.command_latency.iter().map(|l| l.total()).collect();
+        latencies.sort_by(f64::total_cmp);
+        percentiles
+            .iter()
+            .map(|p| {
+                if latencies.is_empty() {
+                    return f64::NAN;
+                }
+                let rank = (p / 100.0 * (latencies.len() - 1) as f64).round();
+                latencies[(rank.max(0.0) as usize).min(latencies.len() - 1)]
+            })
+            .collect()
     }
 }
 
 impl std::fmt::Debug for ControlLoopStats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.__repr__().unwrap())
+        let latency = self.latency_percentiles(&[50.0, 99.0]);
+        write!(
+            f,
+            "ControlLoopStats(period=~{:.2?}ms, read_dt=~{:.2?} ms, write_dt=~{:.2?} ms, latency p50={:.2} ms p99={:.2} ms)",
+            self.period.iter().sum::<f64>() / self.period.len() as f64 * 1000.0,
+            self.read_dt.iter().sum::<f64>() / self.read_dt.len() as f64 * 1000.0,
+            self.write_dt.iter().sum::<f64>() / self.write_dt.len() as f64 * 1000.0,
+            latency[0] * 1000.0,
+            latency[1] * 1000.0,
+        )
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum MotorError {
     MissingMotors(Vec<String>),
     CommunicationError(),
@@ -211,28 +1068,202 @@ pub enum MotorError {
     VoltageRampUpTimeoutError(u16, Duration),
     PortNotFound(String),
     CouldNotOpenPort(String),
+    PortInUse(String, Option<u32>),
+    HomingError(MotorControllerError),
+    UsageFileError(String),
+    NoKinematicsError(),
+    PoseError(String),
+    ConfigurationLocked(),
+    FaultJournalError(String),
+    LastStateFileError(String),
+    ReadTuningError(MotorControllerError),
+    /// A failed operation of the controller, see [`MotorControllerError`].
+    ControllerError(MotorControllerError),
 }
 
-impl std::error::Error for MotorError {}
-impl std::fmt::Display for MotorError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            MotorError::MissingMotors(names) => {
-                write!(f, "Missing motors: {:?}!", names)
-            }
-            MotorError::CommunicationError() => {
-                write!(
-                    f,
-                    "Motor communication error! Check connections and power supply."
-                )
-            }
-            MotorError::NoPowerError() => {
-                write!(
-                    f,
-                    "No motors detected. Check if the power supply is connected and turned on!"
-                )
-            }
-            MotorError::PortNotFound(port) => {
+/// Last successfully read position, with how long ago it was read and the error of the
+/// reads since, if any.
+///
+/// A dying bus can freeze the position (e.g. reads failing the plausibility check, or
+/// retried until the timeout), `fresh` tells live data from such a frozen value.
+#[cfg_attr(
+    feature = "python",
+    gen_stub_pyclass,
+    pyclass(extends = FullBodyPosition)
+)]
+#[derive(Debug, Clone)]
+pub struct LastPosition {
+    pub position: FullBodyPosition,
+    /// Time since the position was read (s).
+    pub age: f64,
+    /// Whether `age` is under the maximum age, see
+    /// [`ReachyMiniControlLoop::set_max_position_age`].
+    pub fresh: bool,
+    /// Error of the last read, None if it succeeded.
+    pub error: Option<MotorError>,
+}
+
+impl LastPosition {
+    /// `position` as read at its monotonic timestamp, fresh if younger than `max_age` (s).
+    pub fn new(position: FullBodyPosition, max_age: f64, error: Option<MotorError>) -> Self {
+        let age = (monotonic_now() - position.monotonic_timestamp).max(0.0);
+        LastPosition {
+            position,
+            age,
+            fresh: age <= max_age,
+            error,
+        }
+    }
+
+    /// Whether the position is live data: fresh and the last read succeeded.
+    pub fn is_valid(&self) -> bool {
+        self.fresh && self.error.is_none()
+    }
+}
+
+impl std::ops::Deref for LastPosition {
+    type Target = FullBodyPosition;
+
+    fn deref(&self) -> &FullBodyPosition {
+        &self.position
+    }
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl LastPosition {
+    /// Time since the position was read (s).
+    #[getter]
+    fn age(&self) -> f64 {
+        self.age
+    }
+
+    /// Whether the age is under the maximum age set on the control loop.
+    #[getter]
+    fn fresh(&self) -> bool {
+        self.fresh
+    }
+
+    /// Error of the last read, None if it succeeded.
+    #[getter]
+    fn error(&self) -> Option<String> {
+        self.error.as_ref().map(|e| e.to_string())
+    }
+
+    /// Whether the position is live data: fresh and the last read succeeded.
+    #[pyo3(name = "is_valid")]
+    fn py_is_valid(&self) -> bool {
+        self.is_valid()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "LastPosition(body_yaw={:.3}, stewart={:?}, antennas={:?}, timestamp={:.3}, age={:.3}, fresh={}, error={:?})",
+            self.body_yaw,
+            self.stewart,
+            self.antennas,
+            self.timestamp,
+            self.age,
+            if self.fresh { "True" } else { "False" },
+            self.error()
+        )
+    }
+}
+
+/// The state a control cycle usually polls, taken in a single call, see
+/// [`ReachyMiniControlLoop::get_snapshot`].
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Clone)]
+pub struct StateSnapshot {
+    /// Last successfully read position.
+    pub position: FullBodyPosition,
+    /// Velocity of each joint (rad/s) between the last two position reads, in joint order.
+    pub velocity: [f64; NUM_JOINTS],
+    /// Present current of each joint (mA), as last polled with the diagnostics, see
+    /// [`crate::diagnostics`].
+    pub current: [i16; NUM_JOINTS],
+    /// Last known torque state, `None` if it could not be read.
+    pub torque_enabled: Option<bool>,
+    pub health: HealthReport,
+    /// `None` if the stats are not published, see `stats_pub_period`.
+    pub stats: Option<ControlLoopStats>,
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl StateSnapshot {
+    fn __repr__(&self) -> String {
+        format!(
+            "StateSnapshot(position={:?}, velocity={:?}, current={:?}, torque_enabled={}, healthy={})",
+            self.position.joints(),
+            self.velocity,
+            self.current,
+            match self.torque_enabled {
+                Some(true) => "True",
+                Some(false) => "False",
+                None => "None",
+            },
+            if self.health.is_healthy() {
+                "True"
+            } else {
+                "False"
+            }
+        )
+    }
+}
+
+/// What to do when some motors do not answer at startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingMotorsPolicy {
+    /// Fail with [`MotorError::MissingMotors`].
+    #[default]
+    Fail,
+    /// Warn and drive the other motors, e.g. move the head with a dead antenna. The
+    /// missing motors read 0 and their goals are dropped, see
+    /// [`ReachyMiniControlLoop::get_missing_motors`].
+    Continue,
+    /// Ping the motors again until they all answer or the timeout elapses, then fail.
+    Retry(Duration),
+}
+
+/// Motors driven by the control loop.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MotorSet {
+    /// Joints explicitly left out, e.g. the antennas removed on a custom build, in joint
+    /// order.
+    ///
+    /// Like the missing motors with [`MissingMotorsPolicy::Continue`], they are skipped
+    /// in the reads (they read 0) and writes, and their goals are not validated.
+    pub disabled_joints: [bool; NUM_JOINTS],
+    /// What to do when some of the other motors do not answer at startup.
+    pub missing_motors: MissingMotorsPolicy,
+}
+
+/// Period between two pings of the motors with [`MissingMotorsPolicy::Retry`].
+const MISSING_MOTORS_RETRY_PERIOD: Duration = Duration::from_millis(500);
+
+impl std::error::Error for MotorError {}
+impl std::fmt::Display for MotorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MotorError::MissingMotors(names) => {
+                write!(f, "Missing motors: {:?}!", names)
+            }
+            MotorError::CommunicationError() => {
+                write!(
+                    f,
+                    "Motor communication error! Check connections and power supply."
+                )
+            }
+            MotorError::NoPowerError() => {
+                write!(
+                    f,
+                    "No motors detected. Check if the power supply is connected and turned on!"
+                )
+            }
+            MotorError::PortNotFound(port) => {
                 write!(
                     f,
                     "Check if your USB cable is connected. Could not find port: {}!",
@@ -246,6 +1277,46 @@ impl std::fmt::Display for MotorError {
                     port
                 )
             }
+            MotorError::PortInUse(port, pid) => {
+                write!(
+                    f,
+                    "{} Close the other script using the robot first.",
+                    PortInUseError {
+                        port: port.clone(),
+                        pid: *pid
+                    }
+                )
+            }
+            MotorError::HomingError(e) => {
+                write!(f, "Body rotation homing failed: {}", e)
+            }
+            MotorError::UsageFileError(msg) => {
+                write!(f, "Usage file error: {}", msg)
+            }
+            MotorError::NoKinematicsError() => {
+                write!(f, "No kinematics set for the head pose commands!")
+            }
+            MotorError::PoseError(msg) => {
+                write!(f, "Pose error: {}", msg)
+            }
+            MotorError::FaultJournalError(msg) => {
+                write!(f, "Fault journal error: {}", msg)
+            }
+            MotorError::LastStateFileError(msg) => {
+                write!(f, "Last state file error: {}", msg)
+            }
+            MotorError::ReadTuningError(e) => {
+                write!(f, "Read tuning error: {}", e)
+            }
+            MotorError::ControllerError(e) => {
+                write!(f, "Motor controller error: {}", e)
+            }
+            MotorError::ConfigurationLocked() => {
+                write!(
+                    f,
+                    "Configuration is locked, only motion commands are accepted!"
+                )
+            }
             MotorError::VoltageRampUpTimeoutError(voltage, duration) => {
                 write!(
                     f,
@@ -264,8 +1335,77 @@ impl ReachyMiniControlLoop {
         stats_pub_period: Option<Duration>,
         read_allowed_retries: u64,
         voltage_rampup_timeout: Duration,
+        motors: MotorSet,
+    ) -> Result<Self, MotorError> {
+        // Validate serial port based on operating system
+
+        // On Unix-like systems, check if the port path exists
+        #[cfg(not(windows))]
+        if !std::path::Path::new(&serialport).exists() {
+            return Err(MotorError::PortNotFound(serialport));
+        }
+        // On Windows, validate COM port format
+        #[cfg(windows)]
+        if !serialport.starts_with("COM") {
+            return Err(MotorError::PortNotFound(serialport));
+        }
+
+        let c = match ReachyMiniMotorController::new(serialport.as_str()) {
+            Ok(c) => c,
+            Err(e) => {
+                return Err(match e {
+                    MotorControllerError::PortInUse(e) => MotorError::PortInUse(e.port, e.pid),
+                    _ => MotorError::CouldNotOpenPort(serialport),
+                });
+            }
+        };
+
+        let mut control_loop = Self::with_controller(
+            c,
+            read_position_loop_period,
+            stats_pub_period,
+            read_allowed_retries,
+            voltage_rampup_timeout,
+            motors,
+        )?;
+        control_loop._hotplug = Some(watch_usb_device(&serialport, &control_loop.shared));
+
+        Ok(control_loop)
+    }
+
+    /// Run the control loop on an already opened controller, e.g. one driving
+    /// [`crate::simulation::Simulation`] motors.
+    pub fn with_controller(
+        c: ReachyMiniMotorController,
+        read_position_loop_period: Duration,
+        stats_pub_period: Option<Duration>,
+        read_allowed_retries: u64,
+        voltage_rampup_timeout: Duration,
+        motors: MotorSet,
+    ) -> Result<Self, MotorError> {
+        Self::with_clock(
+            c,
+            Arc::new(SystemClock::default()),
+            read_position_loop_period,
+            stats_pub_period,
+            read_allowed_retries,
+            voltage_rampup_timeout,
+            motors,
+        )
+    }
+
+    /// Like [`Self::with_controller`], with the loop time and ticks driven by `clock`,
+    /// e.g. a [`crate::clock::ManualClock`] in tests.
+    pub fn with_clock(
+        mut c: ReachyMiniMotorController,
+        clock: Arc<dyn LoopClock>,
+        read_position_loop_period: Duration,
+        stats_pub_period: Option<Duration>,
+        read_allowed_retries: u64,
+        voltage_rampup_timeout: Duration,
+        motors: MotorSet,
     ) -> Result<Self, MotorError> {
-        let stop_signal = Arc::new(Mutex::new(false));
+        let stop_signal = Arc::new(StopSignal::default());
         let stop_signal_clone = stop_signal.clone();
 
         let (tx, rx) = mpsc::channel(100);
@@ -276,60 +1416,108 @@ impl ReachyMiniControlLoop {
                 Arc::new(Mutex::new(ControlLoopStats {
                     period: Vec::new(),
                     read_dt: Vec::new(),
+                    tick_timestamps: Vec::new(),
                     write_dt: Vec::new(),
+                    command_latency: Vec::new(),
                 })),
             )
         });
-        let last_stats_clone = last_stats.clone();
-
-        // Validate serial port based on operating system
 
-        // On Unix-like systems, check if the port path exists
-        #[cfg(not(windows))]
-        if !std::path::Path::new(&serialport).exists() {
-            return Err(MotorError::PortNotFound(serialport));
+        let id_to_name: HashMap<u8, String> = c
+            .get_motor_name_id()
+            .iter()
+            .map(|(name, id)| (*id, name.clone()))
+            .collect();
+        let motor_name_id = c.get_motor_name_id();
+        let disabled_ids: Vec<u8> = JOINT_NAMES
+            .iter()
+            .zip(motors.disabled_joints)
+            .filter(|(_, disabled)| *disabled)
+            .filter_map(|(name, _)| motor_name_id.get(*name).copied())
+            .collect();
+        c.set_missing_ids(&disabled_ids);
+        let start_time = std::time::Instant::now();
+        let missing_ids = loop {
+            let missing_ids = c
+                .check_missing_ids()
+                .map_err(|_| MotorError::CommunicationError())?;
+            match motors.missing_motors {
+                MissingMotorsPolicy::Retry(timeout)
+                    if !missing_ids.is_empty() && start_time.elapsed() < timeout =>
+                {
+                    info!("Waiting for the missing motors {:?}...", missing_ids);
+                    std::thread::sleep(MISSING_MOTORS_RETRY_PERIOD);
+                }
+                _ => break missing_ids,
+            }
+        };
+        let missing_motors_names: Vec<String> = missing_ids
+            .iter()
+            .map(|id| {
+                id_to_name
+                    .get(id)
+                    .unwrap_or(&format!("Unknown({})", id))
+                    .clone()
+            })
+            .collect();
+        if missing_ids.len() == 9 - disabled_ids.len() {
+            return Err(MotorError::NoPowerError());
+        } else if !missing_ids.is_empty() {
+            if motors.missing_motors != MissingMotorsPolicy::Continue {
+                return Err(MotorError::MissingMotors(missing_motors_names));
+            }
+            warn!(
+                "Missing motors {:?}, continuing with the others",
+                missing_motors_names
+            );
+            c.set_missing_ids(&[disabled_ids.as_slice(), &missing_ids].concat());
         }
-        // On Windows, validate COM port format
-        #[cfg(windows)]
-        if !serialport.starts_with("COM") {
-            return Err(MotorError::PortNotFound(serialport));
+        let mut disabled_joints = [false; NUM_JOINTS];
+        for id in c.missing_ids() {
+            if let Some(joint) = c.joint_index(*id) {
+                disabled_joints[joint] = true;
+            }
         }
 
-        let mut c = ReachyMiniMotorController::new(serialport.as_str())
-            .map_err(|_| MotorError::CouldNotOpenPort(serialport.clone()))?;
-
-        match c.check_missing_ids() {
-            Ok(missing_ids) if missing_ids.len() == 9 => {
-                return Err(MotorError::NoPowerError());
+        // Detect firmware versions so features can be gated on older motors
+        let firmware_info = with_retry(|| c.detect_firmware(), read_allowed_retries)
+            .map_err(|_| MotorError::CommunicationError())?;
+        for feature in Feature::ALL {
+            if let Err(e) = c.require(feature) {
+                warn!("{}", e);
             }
-            Ok(missing_ids) if !missing_ids.is_empty() => {
-                let id_to_name: HashMap<u8, String> = c
-                    .get_motor_name_id()
-                    .iter()
-                    .map(|(name, id)| (id.clone(), name.clone()))
-                    .collect();
+        }
 
-                let missing_motors: Vec<String> = missing_ids
-                    .iter()
-                    .map(|id| {
-                        id_to_name
-                            .get(id)
-                            .unwrap_or(&format!("Unknown({})", id))
-                            .clone()
-                    })
-                    .collect();
-                return Err(MotorError::MissingMotors(missing_motors));
+        // Report the motors reconfigured by other tools, they are driven anyway
+        let config_deviations = match with_retry(
+            || c.audit_configuration(&ConfigProfile::default()),
+            read_allowed_retries,
+        ) {
+            Ok(deviations) => deviations,
+            Err(e) => {
+                warn!("Failed to audit the motor configuration: {}", e);
+                Vec::new()
             }
-            Ok(_) => {}
-            Err(_) => return Err(MotorError::CommunicationError()),
+        };
+        for deviation in &config_deviations {
+            warn!("Unexpected motor configuration: {}", deviation);
         }
 
         // Wait until voltage is stable at 5V
         info!("Waiting for voltage to be stable at 5V...");
         let mut current_voltage = with_retry(|| c.read_all_voltages(), read_allowed_retries)
             .map_err(|_| MotorError::CommunicationError())?;
+        // The missing motors read 0
+        let present_voltages = |voltages: [u16; 9]| {
+            voltages
+                .into_iter()
+                .enumerate()
+                .filter(|(joint, _)| !disabled_joints[*joint])
+                .map(|(_, v)| v)
+                .collect::<Vec<u16>>()
+        };
         let start_time = SystemTime::now();
-        while current_voltage
+        while present_voltages(current_voltage)
             .iter()
             .any(|&v| v < 45 && start_time.elapsed().unwrap() < voltage_rampup_timeout)
         {
@@ -337,9 +1525,13 @@ impl ReachyMiniControlLoop {
             current_voltage = with_retry(|| c.read_all_voltages(), read_allowed_retries)
                 .map_err(|_| MotorError::CommunicationError())?;
         }
-        if current_voltage.iter().any(|&v| v < 45) {
+        if present_voltages(current_voltage).iter().any(|&v| v < 45) {
             return Err(MotorError::VoltageRampUpTimeoutError(
-                current_voltage.iter().cloned().min().unwrap_or(0),
+                present_voltages(current_voltage)
+                    .iter()
+                    .cloned()
+                    .min()
+                    .unwrap_or(0),
                 voltage_rampup_timeout,
             ));
         }
@@ -367,13 +1559,69 @@ impl ReachyMiniControlLoop {
         )
         .map_err(|_| MotorError::CommunicationError())?[0];
 
-        let last_position = Arc::new(Mutex::new(Ok(last_position)));
-        let last_position_clone = last_position.clone();
-
-        let last_torque = Arc::new(Mutex::new(Ok(last_torque)));
-        let last_torque_clone = last_torque.clone();
-        let last_control_mode = Arc::new(Mutex::new(Ok(last_control_mode)));
-        let last_control_mode_clone = last_control_mode.clone();
+        let shared = SharedState {
+            body_yaw: Arc::new(Mutex::new(BodyYawTracker::new(last_position.body_yaw))),
+            last_position: Arc::new(Mutex::new(PositionState {
+                position: last_position,
+                error: None,
+            })),
+            last_torque: Arc::new(Mutex::new(Ok(last_torque))),
+            last_control_mode: Arc::new(Mutex::new(Ok(last_control_mode))),
+            last_stats,
+            goal_reached: Arc::new(Mutex::new(false)),
+            goal_reached_callbacks: Arc::new(Mutex::new(Vec::new())),
+            stale_commands: Arc::new(Mutex::new(0)),
+            arbiter: Arc::new(Mutex::new(Arbiter::default())),
+            error_stats: Arc::new(Mutex::new(ErrorStats::default())),
+            usage: Arc::new(Mutex::new(UsageCounters::default())),
+            power_lost: Arc::new(Mutex::new(false)),
+            clock_offset: Arc::new(Mutex::new(ClockOffset::measure())),
+            feedback_stale: Arc::new(Mutex::new(false)),
+            shutdown_signal: Arc::new(Mutex::new(None)),
+            position_bounds: Arc::new(Mutex::new(DEFAULT_POSITION_BOUNDS)),
+            disabled_joints,
+            goal_clamping: Arc::new(Mutex::new(false)),
+            clamped_goals: Arc::new(Mutex::new(0)),
+            safe_mode: Arc::new(Mutex::new(SafeMode::default())),
+            configuration_lock: Arc::new(Mutex::new(None)),
+            clock,
+            events: Arc::new(Mutex::new(EventLog::new())),
+            touch_listeners: Arc::new(Mutex::new(Vec::new())),
+            trajectory: Arc::new(Mutex::new(None)),
+            trajectory_result: Arc::new(Mutex::new(None)),
+            tracks: Arc::new(Mutex::new([None; Track::ALL.len()])),
+            current_budget_scale: Arc::new(Mutex::new(1.0)),
+            duty_cycle_loads: Arc::new(Mutex::new([0.0; NUM_JOINTS])),
+            overruns: Arc::new(Mutex::new(HashMap::new())),
+            beat_grid: Arc::new(Mutex::new(None)),
+            kinematics: Arc::new(Mutex::new(None)),
+            control_law: Arc::new(Mutex::new(None)),
+            gaze_split: Arc::new(Mutex::new(GazeSplit::default())),
+            usb_connected: Arc::new(Mutex::new(None)),
+            hotplug_callbacks: Arc::new(Mutex::new(Vec::new())),
+            stats_retention: Arc::new(Mutex::new(StatsRetention::default())),
+            max_position_age: Arc::new(Mutex::new(DEFAULT_MAX_POSITION_AGE)),
+            read_schedule: Arc::new(Mutex::new(ReadSchedule::default())),
+            diagnostics: Arc::new(Mutex::new(Diagnostics::default())),
+            diagnostics_period: Arc::new(Mutex::new(DEFAULT_DIAGNOSTICS_PERIOD)),
+            tracking: Arc::new(Mutex::new(TrackingStream::default())),
+            state_buffer: StateBuffer::default(),
+            tick_state: Arc::new(Mutex::new(TickState {
+                position: last_position,
+                velocity: [0.0; NUM_JOINTS],
+                current: [0; NUM_JOINTS],
+                torque_enabled: Some(last_torque),
+            })),
+            history: Arc::new(Mutex::new(History {
+                capacity: DEFAULT_HISTORY_SIZE,
+                positions: VecDeque::with_capacity(DEFAULT_HISTORY_SIZE),
+            })),
+        };
+        shared.state_buffer.publish(&last_position);
+        for deviation in &config_deviations {
+            lock_or_recover(&shared.events, "events").record("config", deviation.to_string());
+        }
+        let shared_clone = shared.clone();
 
         let (tx_raw_bytes, rx_raw_bytes) = mpsc::channel(1);
 
@@ -382,10 +1630,7 @@ impl ReachyMiniControlLoop {
                 c,
                 stop_signal_clone,
                 rx,
-                last_position_clone,
-                last_torque_clone,
-                last_control_mode_clone,
-                last_stats_clone,
+                shared_clone,
                 read_position_loop_period,
                 read_allowed_retries,
                 tx_raw_bytes,
@@ -398,35 +1643,44 @@ impl ReachyMiniControlLoop {
             loop_handle: Arc::new(Mutex::new(Some(loop_handle))),
             stop_signal,
             tx,
-            last_position,
-            last_torque,
-            last_control_mode,
-            last_stats,
+            next_command_id: Arc::new(AtomicU64::new(0)),
+            shared,
             rx_raw_bytes,
             motor_name_id,
+            firmware_info,
+            missing_motors: missing_motors_names,
+            disabled_joints: JOINT_NAMES
+                .iter()
+                .zip(motors.disabled_joints)
+                .filter(|(_, disabled)| *disabled)
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            config_deviations,
+            _hotplug: None,
+            target_follower: Mutex::new(None),
+            poses_file: Mutex::new(DEFAULT_POSES_FILE.into()),
+            bounds_before_transport: Mutex::new(None),
         })
     }
 
+    /// Stop the control loop thread and wait for it, which releases the serial port.
+    ///
+    /// Pending commands are still executed. Calling it several times is fine.
     pub fn close(&self) {
-        if let Ok(mut stop) = self.stop_signal.lock() {
-            *stop = true;
-        }
-        match self.loop_handle.lock() {
-            Ok(mut opt_handle) => {
-                if let Some(handle) = opt_handle.take() {
-                    if let Err(e) = handle.join() {
-                        log::error!("Failed to join control loop thread: {:?}", e);
-                    }
-                }
+        self.stop_following();
+        *lock_or_recover(&self.stop_signal.stop, "stop_signal") = true;
+        self.stop_signal.notify.notify_one();
+
+        let handle = lock_or_recover(&self.loop_handle, "loop_handle").take();
+        if let Some(handle) = handle {
+            if handle.thread().id() == std::thread::current().id() {
+                // Dropped from the loop thread itself (e.g. in a callback), it will exit on its own
+                warn!("Control loop closed from its own thread, not joining it");
+                return;
             }
-            Err(poisoned) => {
-                // If the mutex is poisoned, try to recover the handle
-                let mut opt_handle = poisoned.into_inner();
-                if let Some(handle) = opt_handle.take() {
-                    if let Err(e) = handle.join() {
-                        log::error!("Failed to join control loop thread (poisoned): {:?}", e);
-                    }
-                }
+            match handle.join() {
+                Ok(()) => info!("Control loop stopped, serial port released"),
+                Err(e) => log::error!("Failed to join control loop thread: {:?}", e),
             }
         }
     }
@@ -435,131 +1689,1321 @@ impl ReachyMiniControlLoop {
         self.motor_name_id.clone()
     }
 
+    /// Names of the motors left out since startup, with [`MissingMotorsPolicy::Continue`].
+    pub fn get_missing_motors(&self) -> Vec<String> {
+        self.missing_motors.clone()
+    }
+
+    /// Names of the joints disabled at startup, see [`MotorSet::disabled_joints`].
+    pub fn get_disabled_joints(&self) -> Vec<String> {
+        self.disabled_joints.clone()
+    }
+
+    /// Registers which differed from the Reachy Mini profile at init, see
+    /// [`crate::config_audit`].
+    pub fn get_config_deviations(&self) -> Vec<ConfigDeviation> {
+        self.config_deviations.clone()
+    }
+
+    /// Firmware info of each motor (by name), as detected at init.
+    pub fn get_firmware_info(&self) -> HashMap<String, FirmwareInfo> {
+        self.motor_name_id
+            .iter()
+            .filter_map(|(name, id)| self.firmware_info.get(id).map(|info| (name.clone(), *info)))
+            .collect()
+    }
+
+    /// Whether all motors run a firmware supporting the given feature.
+    pub fn supports(&self, feature: Feature) -> bool {
+        !self.firmware_info.is_empty()
+            && self
+                .firmware_info
+                .values()
+                .all(|info| info.supports(feature))
+    }
+
     pub fn push_command(
         &self,
         command: MotorCommand,
     ) -> Result<(), mpsc::error::SendError<MotorCommand>> {
-        self.tx.blocking_send(command)
+        self.push_command_tracked(command).map(|_| ())
     }
 
-    pub fn get_last_position(&self) -> Result<FullBodyPosition, MotorError> {
-        let guard = match self.last_position.lock() {
-            Ok(guard) => guard,
-            Err(poisoned) => {
-                log::error!("last_position mutex was poisoned");
-                poisoned.into_inner()
-            }
-        };
-        match &*guard {
-            Ok(pos) => Ok(*pos),
-            Err(e) => Err(e.clone()),
+    /// Push a command and return its id, to find its latency in [`ControlLoopStats::command_latency`].
+    pub fn push_command_tracked(
+        &self,
+        command: MotorCommand,
+    ) -> Result<u64, mpsc::error::SendError<MotorCommand>> {
+        let id = self.next_command_id.fetch_add(1, Ordering::Relaxed);
+        self.tx
+            .blocking_send(QueuedCommand {
+                id,
+                pushed_at: self.shared.clock.now(),
+                command,
+            })
+            .map(|_| id)
+            .map_err(|e| mpsc::error::SendError(e.0.command))
+    }
+
+    /// Push a configuration command (see [`MotorCommand::is_configuration`]), rejected
+    /// right away while the configuration is locked.
+    pub fn push_configuration(&self, command: MotorCommand) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        if command.bypasses_safe_mode() && self.is_safe_mode() {
+            return Err(MotorError::ControllerError(safe_mode_rejected(&command)));
         }
+        self.push_command(command)
+            .map_err(|_| MotorError::CommunicationError())
     }
 
-    pub fn is_torque_enabled(&self) -> Result<bool, MotorError> {
-        let guard = match self.last_torque.lock() {
-            Ok(guard) => guard,
-            Err(poisoned) => {
-                log::error!("last_torque mutex was poisoned");
-                poisoned.into_inner()
-            }
-        };
-        match &*guard {
-            Ok(enabled) => Ok(*enabled),
-            Err(e) => Err(e.clone()),
+    /// Lock the configuration: the commands changing it (operating modes, limits, raw
+    /// register writes, homing..., see [`MotorCommand::is_configuration`]) and the
+    /// setters of the loop (position bounds, goal clamping, safe mode, kinematics, control
+    /// law, read schedule...) are rejected with [`MotorError::ConfigurationLocked`], while
+    /// motion commands are still accepted.
+    ///
+    /// Only [`Self::unlock_configuration`] with the same `key` unlocks it, so a kiosk
+    /// deployment can configure the robot, then hand the control loop to untrusted code.
+    pub fn lock_configuration(&self, key: &str) -> Result<(), MotorError> {
+        let mut lock = lock_or_recover(&self.shared.configuration_lock, "configuration_lock");
+        if lock.is_some() {
+            return Err(MotorError::ConfigurationLocked());
         }
+        *lock = Some(key.to_string());
+        Ok(())
     }
 
-    pub fn get_control_mode(&self) -> Result<u8, MotorError> {
-        let guard = match self.last_control_mode.lock() {
-            Ok(guard) => guard,
-            Err(poisoned) => {
-                log::error!("last_control_mode mutex was poisoned");
-                poisoned.into_inner()
+    /// Unlock the configuration locked by [`Self::lock_configuration`] with the same `key`.
+    pub fn unlock_configuration(&self, key: &str) -> Result<(), MotorError> {
+        let mut lock = lock_or_recover(&self.shared.configuration_lock, "configuration_lock");
+        match lock.as_deref() {
+            Some(locked) if locked != key => Err(MotorError::ConfigurationLocked()),
+            _ => {
+                *lock = None;
+                Ok(())
             }
-        };
+        }
+    }
 
-        match &*guard {
-            Ok(mode) => Ok(*mode),
-            Err(e) => Err(e.clone()),
+    pub fn is_configuration_locked(&self) -> bool {
+        lock_or_recover(&self.shared.configuration_lock, "configuration_lock").is_some()
+    }
+
+    fn check_configuration_unlocked(&self) -> Result<(), MotorError> {
+        match self.is_configuration_locked() {
+            true => Err(MotorError::ConfigurationLocked()),
+            false => Ok(()),
         }
     }
 
-    pub fn get_stats(&self) -> Result<Option<ControlLoopStats>, MotorError> {
-        match self.last_stats {
-            Some((_, ref stats)) => {
-                let guard = match stats.lock() {
-                    Ok(guard) => guard,
-                    Err(poisoned) => {
-                        log::error!("last_stats mutex was poisoned");
-                        poisoned.into_inner()
-                    }
-                };
-                Ok(Some(guard.clone()))
-            }
-            None => Ok(None),
+    /// Push a command which is dropped by the control loop if not handled before the deadline.
+    ///
+    /// The deadline is in seconds since UNIX epoch, like `FullBodyPosition::timestamp`.
+    /// This avoids executing outdated goals, e.g. delayed by a slow network.
+    pub fn push_command_with_deadline(
+        &self,
+        command: MotorCommand,
+        deadline: Option<f64>,
+    ) -> Result<(), mpsc::error::SendError<MotorCommand>> {
+        match deadline {
+            Some(deadline) => self.push_command(MotorCommand::WithDeadline {
+                deadline,
+                command: Box::new(command),
+            }),
+            None => self.push_command(command),
         }
     }
 
-    pub fn async_read_raw_bytes(
+    /// Number of commands dropped because their deadline had passed.
+    pub fn get_stale_commands_dropped(&self) -> u64 {
+        *lock_or_recover(&self.shared.stale_commands, "stale_commands")
+    }
+
+    /// Push a command on behalf of a named source, see [`crate::arbitration`].
+    ///
+    /// The command is dropped if a source with a higher priority is active.
+    pub fn push_command_from(
         &self,
-        id: u8,
-        addr: u8,
-        length: u8,
-    ) -> Result<Vec<u8>, MotorError> {
-        let command = MotorCommand::ReadRawBytes { id, addr, length };
-        self.push_command(command)
-            .map_err(|_| MotorError::CommunicationError())?;
-        let data = self
-            .rx_raw_bytes
-            .lock()
-            .unwrap()
-            .blocking_recv()
-            .ok_or(MotorError::CommunicationError())?;
-        Ok(data)
+        source: &str,
+        command: MotorCommand,
+    ) -> Result<(), mpsc::error::SendError<MotorCommand>> {
+        self.push_command(MotorCommand::FromSource {
+            source: source.to_string(),
+            command: Box::new(command),
+        })
     }
 
-    pub fn async_write_raw_bytes(&self, id: u8, addr: u8, data: Vec<u8>) -> Result<(), MotorError> {
-        let command = MotorCommand::WriteRawBytes { id, addr, data };
-        self.push_command(command)
-            .map_err(|_| MotorError::CommunicationError())?;
-        Ok(())
+    /// Register (or update) a command source. Higher priorities preempt lower ones.
+    ///
+    /// A source stays active for `timeout` after each of its commands.
+    /// `safety`, `teleop` and `animation` sources are registered by default.
+    pub fn register_source(&self, name: &str, priority: i32, timeout: Duration) {
+        lock_or_recover(&self.shared.arbiter, "arbiter").register(name, priority, timeout);
     }
 
-    pub fn async_read_pid_gains(&self, id: u8) -> Result<(u16, u16, u16), MotorError> {
-        // https://emanual.robotis.com/docs/en/dxl/x/xl330-m288/#velocity-i-gain
-        const DIP_GAIN_ADDR: u8 = 80;
+    /// Let lower priority sources take the control back immediately.
+    pub fn release_source(&self, name: &str) {
+        lock_or_recover(&self.shared.arbiter, "arbiter").release(name);
+    }
 
-        self.async_read_raw_bytes(id, DIP_GAIN_ADDR, 3 * 2)
-            .and_then(|data| {
-                if data.len() != 6 {
-                    return Err(MotorError::CommunicationError());
-                }
-                let d_gain = u16::from_le_bytes([data[0], data[1]]);
-                let i_gain = u16::from_le_bytes([data[2], data[3]]);
-                let p_gain = u16::from_le_bytes([data[4], data[5]]);
-                Ok((p_gain, i_gain, d_gain))
+    /// Call `provider` every `period` to get the next goal positions, from a dedicated thread.
+    ///
+    /// It replaces the provider followed so far, if any. Its goals are sent on behalf of
+    /// `source` (see [`Self::register_source`]) if given, and dropped when they come more
+    /// than `timeout` after the call, so the motors hold the last goal while the
+    /// provider is slow or stuck. Following stops when the provider returns an error.
+    pub fn follow_target(
+        &self,
+        provider: impl FnMut() -> Result<Option<FullBodyPosition>, String> + Send + 'static,
+        period: Duration,
+        timeout: Duration,
+        source: Option<&str>,
+    ) {
+        let provider: TargetProvider = Box::new(provider);
+        let tx = self.tx.clone();
+        let next_command_id = self.next_command_id.clone();
+        let clock = self.shared.clock.clone();
+        let push = move |command| {
+            tx.blocking_send(QueuedCommand {
+                id: next_command_id.fetch_add(1, Ordering::Relaxed),
+                pushed_at: clock.now(),
+                command,
             })
+            .is_ok()
+        };
+        let follower =
+            TargetFollower::start(provider, period, timeout, source.map(str::to_string), push);
+        *lock_or_recover(&self.target_follower, "target_follower") = Some(follower);
     }
 
-    pub fn async_write_pid_gains(
-        &self,
-        id: u8,
-        p_gain: u16,
-        i_gain: u16,
-        d_gain: u16,
-    ) -> Result<(), MotorError> {
-        // https://emanual.robotis.com/docs/en/dxl/x/xl330-m288/#velocity-i-gain
-        const DIP_GAIN_ADDR: u8 = 80;
+    /// Stop calling the provider given to [`Self::follow_target`], the motors hold the last goal.
+    pub fn stop_following(&self) {
+        lock_or_recover(&self.target_follower, "target_follower").take();
+    }
 
-        let mut data = Vec::with_capacity(6);
-        data.extend_from_slice(&d_gain.to_le_bytes());
-        data.extend_from_slice(&i_gain.to_le_bytes());
-        data.extend_from_slice(&p_gain.to_le_bytes());
+    /// Whether a provider given to [`Self::follow_target`] is still followed.
+    pub fn is_following(&self) -> bool {
+        lock_or_recover(&self.target_follower, "target_follower")
+            .as_ref()
+            .is_some_and(TargetFollower::is_running)
+    }
 
-        self.async_write_raw_bytes(id, DIP_GAIN_ADDR, data)
+    /// Shut down the robot when the process receives SIGINT (Ctrl-C) or SIGTERM.
+    ///
+    /// The control loop then drops the pending commands and stops accepting new ones,
+    /// ramps the motors down over `ramp_duration`, disables the torque and stops. The
+    /// process keeps running: exiting is left to its owner, see
+    /// [`Self::get_shutdown_signal`]. The handlers installed before (e.g. Python's
+    /// KeyboardInterrupt) are chained, not replaced, and still run.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use reachy_mini_motor_controller::control_loop::ReachyMiniControlLoop;
+    ///
+    /// fn run_until_signal(control_loop: &ReachyMiniControlLoop) -> ! {
+    ///     control_loop.enable_signal_shutdown(Duration::from_secs(1)).unwrap();
+    ///     loop {
+    ///         if let Some(code) = control_loop.get_shutdown_signal() {
+    ///             control_loop.close();
+    ///             std::process::exit(code);
+    ///         }
+    ///         std::thread::sleep(Duration::from_millis(100));
+    ///     }
+    /// }
+    /// ```
+    pub fn enable_signal_shutdown(&self, ramp_duration: Duration) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::EnableSignalShutdown { ramp_duration })
     }
-}
+
+    /// Process exit code matching the signal the control loop was shut down on (130 for
+    /// SIGINT, 143 for SIGTERM), once it is, see [`Self::enable_signal_shutdown`].
+    pub fn get_shutdown_signal(&self) -> Option<i32> {
+        *lock_or_recover(&self.shared.shutdown_signal, "shutdown_signal")
+    }
+
+    /// The command source currently in control, if any.
+    pub fn get_active_source(&self) -> Option<String> {
+        lock_or_recover(&self.shared.arbiter, "arbiter")
+            .active_source(self.shared.clock.now())
+            .map(str::to_string)
+    }
+
+    /// Latest measured offset between the wall clock and the monotonic clock.
+    ///
+    /// It is measured again every [`CLOCK_SYNC_PERIOD`] to follow the wall clock adjustments.
+    pub fn get_clock_offset(&self) -> ClockOffset {
+        *lock_or_recover(&self.shared.clock_offset, "clock_offset")
+    }
+
+    /// Set how many of the last read positions are kept (0 disables the history).
+    pub fn set_history_size(&self, size: usize) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        lock_or_recover(&self.shared.history, "history").set_capacity(size);
+        Ok(())
+    }
+
+    /// The last `n` read positions (or less if not available yet), oldest first.
+    pub fn get_history(&self, n: usize) -> Vec<FullBodyPosition> {
+        let history = lock_or_recover(&self.shared.history, "history");
+        let skip = history.positions.len().saturating_sub(n);
+        history.positions.iter().skip(skip).copied().collect()
+    }
+
+    /// The read positions kept in the history with a timestamp after `timestamp`
+    /// (seconds since UNIX epoch), oldest first.
+    pub fn get_since(&self, timestamp: f64) -> Vec<FullBodyPosition> {
+        let history = lock_or_recover(&self.shared.history, "history");
+        let first = history
+            .positions
+            .partition_point(|position| position.timestamp <= timestamp);
+        history.positions.range(first..).copied().collect()
+    }
+
+    /// Keep the last `size` goal and position pairs of the tracking stream, 0 (the
+    /// default) to stop publishing them, see [`crate::tracking`].
+    pub fn set_tracking_stream_size(&self, size: usize) {
+        lock_or_recover(&self.shared.tracking, "tracking").set_capacity(size);
+    }
+
+    /// The goal and position pairs of the tracking stream read after `timestamp`
+    /// (seconds since UNIX epoch), oldest first.
+    pub fn get_tracking_since(&self, timestamp: f64) -> Vec<TrackingSample> {
+        lock_or_recover(&self.shared.tracking, "tracking").since(timestamp)
+    }
+
+    /// The last successfully read position, updated in place at each read, for consumers
+    /// polling it without allocating (e.g. through the Python buffer protocol).
+    pub fn get_state_buffer(&self) -> StateBuffer {
+        self.shared.state_buffer.clone()
+    }
+
+    /// Last successfully read position, with its age and whether it is fresh.
+    ///
+    /// The position is kept while the reads are retried or fail the plausibility check,
+    /// it is only replaced by an error once they fail.
+    pub fn get_last_position(&self) -> Result<LastPosition, MotorError> {
+        let last = self.get_last_known_position();
+        match last.error {
+            Some(e) => Err(e),
+            None => Ok(last),
+        }
+    }
+
+    /// Last successfully read position, even if the reads are failing since, with the
+    /// error of the last read.
+    pub fn get_last_known_position(&self) -> LastPosition {
+        let max_age = self.get_max_position_age().as_secs_f64();
+        let state = lock_or_recover(&self.shared.last_position, "last_position");
+        LastPosition::new(state.position, max_age, state.error.clone())
+    }
+
+    /// Error of the last read, None if it succeeded.
+    pub fn get_read_error(&self) -> Option<MotorError> {
+        lock_or_recover(&self.shared.last_position, "last_position")
+            .error
+            .clone()
+    }
+
+    /// Set the age above which the last read position is not fresh anymore.
+    pub fn set_max_position_age(&self, max_age: Duration) {
+        *lock_or_recover(&self.shared.max_position_age, "max_position_age") = max_age;
+    }
+
+    pub fn get_max_position_age(&self) -> Duration {
+        *lock_or_recover(&self.shared.max_position_age, "max_position_age")
+    }
+
+    pub fn is_torque_enabled(&self) -> Result<bool, MotorError> {
+        let guard = lock_or_recover(&self.shared.last_torque, "last_torque");
+        match &*guard {
+            Ok(enabled) => Ok(*enabled),
+            Err(e) => Err(e.clone()),
+        }
+    }
+
+    pub fn get_control_mode(&self) -> Result<u8, MotorError> {
+        let guard = lock_or_recover(&self.shared.last_control_mode, "last_control_mode");
+
+        match &*guard {
+            Ok(mode) => Ok(*mode),
+            Err(e) => Err(e.clone()),
+        }
+    }
+
+    /// Whether all the motors disappeared at once (e.g. power brownout).
+    ///
+    /// The state is latched: motion and torque commands are rejected until the
+    /// motors answer again and their configuration has been verified. The torque
+    /// is then reported as read from the motors (usually off after a power loss).
+    pub fn is_power_lost(&self) -> bool {
+        *lock_or_recover(&self.shared.power_lost, "power_lost")
+    }
+
+    /// Stop writing goals when no position could be read for `timeout` (bus effectively dead).
+    ///
+    /// Motion and torque commands are then rejected, and the torque is disabled as
+    /// soon as the reads succeed again rather than resuming the writes against an
+    /// unknown robot state. Enable the torque again to resume.
+    pub fn enable_stale_feedback_shutdown(&self, timeout: Duration) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetStaleFeedbackTimeout {
+            timeout: Some(timeout),
+        })
+    }
+
+    pub fn disable_stale_feedback_shutdown(&self) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetStaleFeedbackTimeout { timeout: None })
+    }
+
+    /// Ramp the stewart platform goal current up over `duration` when its torque is enabled.
+    ///
+    /// The platform then tensions gently instead of snapping taut, sparing the ball
+    /// joints. The ramp starts from a tenth of the goal current configured before,
+    /// and only when all the stewart motors were off. Setting the goal current stops
+    /// it. Enabled by default, over [`DEFAULT_SOFT_START_DURATION`].
+    pub fn enable_soft_start(&self, duration: Duration) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetSoftStart {
+            duration: Some(duration),
+        })
+    }
+
+    /// Apply the goal current right away when the torque is enabled.
+    pub fn disable_soft_start(&self) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetSoftStart { duration: None })
+    }
+
+    /// Whether the goal writes are stopped because of stale feedback,
+    /// see [`Self::enable_stale_feedback_shutdown`].
+    pub fn is_feedback_stale(&self) -> bool {
+        *lock_or_recover(&self.shared.feedback_stale, "feedback_stale")
+    }
+
+    /// Set the bounds of the goal position of each joint, in joint order.
+    ///
+    /// Goals outside of them are rejected, see [`crate::position_bounds`].
+    pub fn set_position_bounds(
+        &self,
+        bounds: [PositionBounds; NUM_JOINTS],
+    ) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        *lock_or_recover(&self.shared.position_bounds, "position_bounds") = bounds;
+        Ok(())
+    }
+
+    pub fn get_position_bounds(&self) -> [PositionBounds; NUM_JOINTS] {
+        *lock_or_recover(&self.shared.position_bounds, "position_bounds")
+    }
+
+    /// Check the goals set by `command` against the position bounds, without sending it.
+    ///
+    /// With goal clamping enabled, only the non-finite goals are rejected.
+    pub fn validate_goal(&self, command: &MotorCommand) -> Result<(), PositionOutOfBounds> {
+        match command {
+            MotorCommand::WithDeadline { command, .. }
+            | MotorCommand::FromSource { command, .. } => self.validate_goal(command),
+            MotorCommand::PlayTrajectory { trajectory, .. } => trajectory
+                .keyframes()
+                .iter()
+                .try_for_each(|keyframe| self.validate_joints(0, &keyframe.positions)),
+            MotorCommand::PlayTrack {
+                track, trajectory, ..
+            } => trajectory.keyframes().iter().try_for_each(|keyframe| {
+                self.validate_joints(track.joints().start, &keyframe.positions[track.joints()])
+            }),
+            command => match command.goal_joints() {
+                Some((first, targets)) => self.validate_joints(first, &targets),
+                None => Ok(()),
+            },
+        }
+    }
+
+    fn validate_joints(&self, first: usize, targets: &[f64]) -> Result<(), PositionOutOfBounds> {
+        let bounds = goal_bounds(&self.shared);
+        let res = validate_positions(first, targets, &bounds);
+        if res.is_err()
+            && *lock_or_recover(&self.shared.goal_clamping, "goal_clamping")
+            && clamp_positions(first, targets, &bounds).is_some()
+        {
+            return Ok(());
+        }
+        res
+    }
+
+    /// Clamp the out of bounds goals to the nearest legal value instead of rejecting them.
+    ///
+    /// Each clamped goal is counted (see [`Self::get_clamped_goals`]) and a warning
+    /// is logged, at most every few seconds. Non-finite goals are still rejected.
+    pub fn set_goal_clamping(&self, enabled: bool) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        *lock_or_recover(&self.shared.goal_clamping, "goal_clamping") = enabled;
+        Ok(())
+    }
+
+    /// Enable or disable the safe mode, enforcing reduced velocity, current and workspace
+    /// limits on every command path, see [`crate::safe_mode`].
+    ///
+    /// While enabled, goals outside of the safe workspace are rejected like the ones out
+    /// of the position bounds, and so are the raw register writes and the homing.
+    pub fn safe_mode(&self, enabled: bool) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        let limits = {
+            let mut safe_mode = lock_or_recover(&self.shared.safe_mode, "safe_mode");
+            safe_mode.enabled = enabled;
+            safe_mode.active()
+        };
+        self.push_configuration(MotorCommand::SetSafeMode { limits })
+    }
+
+    pub fn is_safe_mode(&self) -> bool {
+        lock_or_recover(&self.shared.safe_mode, "safe_mode").enabled
+    }
+
+    /// Set the limits of the safe mode, applied right away if it is enabled. See
+    /// [`SafeModeLimits::validate`] for the valid ones.
+    pub fn set_safe_mode_limits(&self, limits: SafeModeLimits) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        let limits = {
+            let mut safe_mode = lock_or_recover(&self.shared.safe_mode, "safe_mode");
+            safe_mode.limits = limits;
+            safe_mode.active()
+        };
+        match limits {
+            Some(limits) => self.push_configuration(MotorCommand::SetSafeMode {
+                limits: Some(limits),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn get_safe_mode_limits(&self) -> SafeModeLimits {
+        lock_or_recover(&self.shared.safe_mode, "safe_mode").limits
+    }
+
+    /// Number of goals clamped to the position bounds so far.
+    pub fn get_clamped_goals(&self) -> u64 {
+        *lock_or_recover(&self.shared.clamped_goals, "clamped_goals")
+    }
+
+    /// Wear counters accumulated so far (including the ones loaded from the usage file).
+    pub fn get_usage_counters(&self) -> UsageCounters {
+        *lock_or_recover(&self.shared.usage, "usage")
+    }
+
+    /// Persist the wear counters to `path`, see [`crate::usage`].
+    ///
+    /// The counters already saved in the file are added to the current ones,
+    /// then the file is updated periodically and when the control loop stops.
+    pub fn set_usage_file(&self, path: impl Into<std::path::PathBuf>) -> Result<(), MotorError> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.push_configuration(MotorCommand::SetUsageFile {
+            path: path.into(),
+            tx,
+        })?;
+        rx.recv()
+            .map_err(|_| MotorError::CommunicationError())?
+            .map_err(MotorError::UsageFileError)
+    }
+
+    /// Append the faults to the journal at `path`, rotated once larger than `max_size`
+    /// bytes, keeping `max_files` rotated files, see [`crate::fault_journal`].
+    pub fn set_fault_journal(
+        &self,
+        path: impl Into<std::path::PathBuf>,
+        max_size: u64,
+        max_files: usize,
+    ) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        let journal = FaultJournal::open(path, max_size, max_files)
+            .map_err(|e| MotorError::FaultJournalError(e.to_string()))?;
+        lock_or_recover(&self.shared.events, "events").set_journal(Some(journal));
+        Ok(())
+    }
+
+    /// Stop journaling the faults to disk.
+    pub fn disable_fault_journal(&self) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        lock_or_recover(&self.shared.events, "events").set_journal(None);
+        Ok(())
+    }
+
+    /// Write the last known state (positions, torque, operating mode, safe mode) to
+    /// `path` periodically and when the loop stops, see [`crate::last_state`].
+    ///
+    /// Returns the state the file held, e.g. the one before a crash, to compare it to the
+    /// current positions before re-enabling the torque.
+    pub fn set_last_state_file(
+        &self,
+        path: impl Into<std::path::PathBuf>,
+    ) -> Result<Option<LastState>, MotorError> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.push_configuration(MotorCommand::SetLastStateFile {
+            path: Some(path.into()),
+            tx,
+        })?;
+        rx.recv()
+            .map_err(|_| MotorError::CommunicationError())?
+            .map_err(MotorError::LastStateFileError)
+    }
+
+    /// Stop writing the last state file.
+    pub fn disable_last_state_file(&self) -> Result<(), MotorError> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.push_configuration(MotorCommand::SetLastStateFile { path: None, tx })?;
+        rx.recv()
+            .map_err(|_| MotorError::CommunicationError())?
+            .map(|_| ())
+            .map_err(MotorError::LastStateFileError)
+    }
+
+    /// Read the positions with `strategy` from now on, see [`crate::read_tuning`].
+    pub fn set_read_strategy(&self, strategy: ReadStrategy) -> Result<(), MotorError> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.push_configuration(MotorCommand::SetReadStrategy { strategy, tx })?;
+        rx.recv()
+            .map_err(|_| MotorError::CommunicationError())?
+            .map_err(MotorError::ReadTuningError)
+    }
+
+    /// Benchmark the read strategies with each of the `return_delay_times` (units of
+    /// 2 µs), `samples` reads each, and apply the best configuration, see
+    /// [`crate::read_tuning`].
+    ///
+    /// This blocks until the benchmark is over, the loop does not run meanwhile: tune the
+    /// reads with the torque off, which also lets the return delay times be swept.
+    pub fn auto_tune_reads(
+        &self,
+        return_delay_times: &[u8],
+        samples: usize,
+    ) -> Result<ReadTuning, MotorError> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.push_configuration(MotorCommand::AutoTuneReads {
+            return_delay_times: return_delay_times.to_vec(),
+            samples,
+            tx,
+        })?;
+        rx.recv()
+            .map_err(|_| MotorError::CommunicationError())?
+            .map_err(MotorError::ReadTuningError)
+    }
+
+    /// Errors met by the control loop so far, by kind and by motor.
+    pub fn get_error_stats(&self) -> ErrorStats {
+        lock_or_recover(&self.shared.error_stats, "error_stats").clone()
+    }
+
+    /// Journal events recorded after `timestamp` (seconds since UNIX epoch), oldest first.
+    ///
+    /// See [`crate::events`] for the kinds of events.
+    pub fn get_events(&self, since: f64) -> Vec<Event> {
+        lock_or_recover(&self.shared.events, "events").since(since)
+    }
+
+    /// Current state of the feedback, power and torque, with the errors and last events.
+    pub fn get_health(&self) -> HealthReport {
+        let last = self.get_last_known_position();
+        HealthReport {
+            usb_connected: self.is_usb_connected(),
+            feedback_ok: last.error.is_none(),
+            position_age: last.age,
+            position_fresh: last.fresh,
+            power_lost: self.is_power_lost(),
+            feedback_stale: self.is_feedback_stale(),
+            torque_enabled: self.is_torque_enabled().ok(),
+            errors: self.get_error_stats(),
+            recent_events: lock_or_recover(&self.shared.events, "events")
+                .last(HEALTH_REPORT_EVENTS),
+        }
+    }
+
+    /// Set how much of the stats is kept, applied at their next publication.
+    pub fn set_stats_retention(&self, retention: StatsRetention) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        *lock_or_recover(&self.shared.stats_retention, "stats_retention") = retention;
+        Ok(())
+    }
+
+    pub fn get_stats_retention(&self) -> StatsRetention {
+        *lock_or_recover(&self.shared.stats_retention, "stats_retention")
+    }
+
+    /// Read each group of joints at its own period, see [`crate::read_schedule`].
+    pub fn set_read_schedule(&self, schedule: ReadSchedule) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        *lock_or_recover(&self.shared.read_schedule, "read_schedule") = schedule;
+        Ok(())
+    }
+
+    pub fn get_read_schedule(&self) -> ReadSchedule {
+        *lock_or_recover(&self.shared.read_schedule, "read_schedule")
+    }
+
+    /// Position, velocity, current and torque state of the motors, all from the same tick,
+    /// with the health report and the stats, in a single call.
+    pub fn get_snapshot(&self) -> StateSnapshot {
+        let tick = *lock_or_recover(&self.shared.tick_state, "tick_state");
+        StateSnapshot {
+            position: tick.position,
+            velocity: tick.velocity,
+            current: tick.current,
+            torque_enabled: tick.torque_enabled,
+            health: self.get_health(),
+            stats: self
+                .shared
+                .last_stats
+                .as_ref()
+                .map(|(_, stats)| lock_or_recover(stats, "last_stats").clone()),
+        }
+    }
+
+    pub fn get_stats(&self) -> Result<Option<ControlLoopStats>, MotorError> {
+        match self.shared.last_stats {
+            Some((_, ref stats)) => Ok(Some(lock_or_recover(stats, "last_stats").clone())),
+            None => Ok(None),
+        }
+    }
+
+    /// Body yaw (rad) integrated from the encoder deltas.
+    ///
+    /// Unlike `get_last_position().body_yaw`, it does not wrap around after a full turn,
+    /// which makes it usable to track the body while spinning continuously.
+    pub fn get_integrated_body_yaw(&self) -> f64 {
+        lock_or_recover(&self.shared.body_yaw, "body_yaw").integrated
+    }
+
+    /// Spin the body continuously at the given velocity (rad/s).
+    ///
+    /// The body rotation motor is switched to velocity (wheel) mode if needed.
+    pub fn spin_body(&self, velocity: f64) -> Result<(), MotorError> {
+        self.push_command(MotorCommand::SpinBody { velocity })
+            .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Stop spinning the body and switch the body rotation back to position control.
+    pub fn stop_body_spin(&self) -> Result<(), MotorError> {
+        self.push_command(MotorCommand::StopBodySpin())
+            .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Let the antennas dangle freely (torque off), the rest of the robot stays active.
+    pub fn set_antennas_floppy(&self) -> Result<(), MotorError> {
+        self.push_command(MotorCommand::SetAntennasFloppy())
+            .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Hold the antennas again where they currently are.
+    pub fn set_antennas_stiff(&self) -> Result<(), MotorError> {
+        self.push_command(MotorCommand::SetAntennasStiff())
+            .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Read the velocity (rad/s) of the antennas [right, left], e.g. to detect a flick.
+    ///
+    /// The antennas are read on demand, between two position reads of the loop.
+    pub fn read_antennas_velocity(&self) -> Result<[f64; 2], MotorError> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.push_command(MotorCommand::ReadAntennasVelocity { tx })
+            .map_err(|_| MotorError::CommunicationError())?;
+        rx.recv()
+            .map_err(|_| MotorError::CommunicationError())?
+            .map_err(MotorError::ControllerError)
+    }
+
+    /// Read the current (mA) of the antennas [right, left], i.e. the load applied on
+    /// them, e.g. to detect a user holding one.
+    pub fn read_antennas_current(&self) -> Result<[i16; 2], MotorError> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.push_command(MotorCommand::ReadAntennasCurrent { tx })
+            .map_err(|_| MotorError::CommunicationError())?;
+        rx.recv()
+            .map_err(|_| MotorError::CommunicationError())?
+            .map_err(MotorError::ControllerError)
+    }
+
+    /// Read the temperature (°C) of all motors, in joint order.
+    pub fn read_all_temperatures(&self) -> Result<[u8; NUM_JOINTS], MotorError> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.push_command(MotorCommand::ReadAllTemperatures { tx })
+            .map_err(|_| MotorError::CommunicationError())?;
+        rx.recv()
+            .map_err(|_| MotorError::CommunicationError())?
+            .map_err(MotorError::ControllerError)
+    }
+
+    /// Read the current (mA) of all motors, in joint order.
+    pub fn read_all_currents(&self) -> Result<[i16; NUM_JOINTS], MotorError> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.push_command(MotorCommand::ReadAllCurrents { tx })
+            .map_err(|_| MotorError::CommunicationError())?;
+        rx.recv()
+            .map_err(|_| MotorError::CommunicationError())?
+            .map_err(MotorError::ControllerError)
+    }
+
+    /// Home the body rotation on its mechanical stops and store the center as homing offset.
+    ///
+    /// This blocks until the routine is over. Positions are not read while homing.
+    /// Returns the new homing offset (rad).
+    pub fn home_body_rotation(&self, config: HomingConfig) -> Result<f64, MotorError> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.push_configuration(MotorCommand::HomeBodyRotation { config, tx })?;
+        rx.recv()
+            .map_err(|_| MotorError::CommunicationError())?
+            .map_err(MotorError::HomingError)
+    }
+
+    /// Play a trajectory, sampled at each tick of the loop from the current goals.
+    ///
+    /// It replaces the trajectory being played, if any, and is stopped by any new goal.
+    /// Keyframes outside of the position bounds are rejected (or clamped, see
+    /// [`Self::set_goal_clamping`]). The goal is reached at the end of the trajectory.
+    pub fn play_trajectory(&self, trajectory: Trajectory) -> Result<(), MotorError> {
+        self.push_command(MotorCommand::PlayTrajectory {
+            trajectory,
+            start: None,
+        })
+        .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Use `path` for the named poses, see [`crate::poses`].
+    /// [`crate::poses::DEFAULT_POSES_FILE`] is used until then.
+    pub fn set_poses_file(&self, path: impl Into<std::path::PathBuf>) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        *lock_or_recover(&self.poses_file, "poses_file") = path.into();
+        Ok(())
+    }
+
+    pub fn get_poses_file(&self) -> std::path::PathBuf {
+        lock_or_recover(&self.poses_file, "poses_file").clone()
+    }
+
+    /// Save the measured positions as the pose `name` in the poses file, replacing the
+    /// pose with this name if any.
+    pub fn save_pose(&self, name: &str) -> Result<(), MotorError> {
+        let positions = self.get_last_position()?.joints();
+        let path = self.get_poses_file();
+        let mut poses = PoseLibrary::load_or_default(&path)
+            .map_err(|e| MotorError::PoseError(e.to_string()))?;
+        poses
+            .insert(name, positions)
+            .and_then(|()| poses.save(&path))
+            .map_err(|e| MotorError::PoseError(e.to_string()))
+    }
+
+    /// Move to the pose `name` of the poses file in `duration`, played as a trajectory
+    /// (see [`Self::play_trajectory`]).
+    pub fn goto_pose(&self, name: &str, duration: Duration) -> Result<(), MotorError> {
+        let poses = PoseLibrary::load(&self.get_poses_file())
+            .map_err(|e| MotorError::PoseError(e.to_string()))?;
+        let positions = poses
+            .get(name)
+            .ok_or_else(|| MotorError::PoseError(format!("Unknown pose: {}", name)))?;
+        let trajectory = Trajectory::new(vec![Keyframe::new(duration.as_secs_f64(), positions)])
+            .map_err(|e| MotorError::PoseError(e.to_string()))?;
+        self.play_trajectory(trajectory)
+    }
+
+    /// Names of the poses of the poses file, sorted.
+    pub fn list_poses(&self) -> Result<Vec<String>, MotorError> {
+        let poses = PoseLibrary::load_or_default(&self.get_poses_file())
+            .map_err(|e| MotorError::PoseError(e.to_string()))?;
+        Ok(poses.names().map(str::to_string).collect())
+    }
+
+    /// Fold the robot for transport: move to the [`TRANSPORT_POSE`] of the poses file in
+    /// `duration`, then turn the torque off.
+    ///
+    /// The positions before are saved as [`BEFORE_TRANSPORT_POSE`], for [`Self::wake_up`].
+    /// With `lock_bounds`, the position bounds are narrowed around the transport pose
+    /// until then, so no app can unfold the robot by mistake. Blocks until folded.
+    pub fn prepare_for_transport(
+        &self,
+        duration: Duration,
+        lock_bounds: bool,
+    ) -> Result<(), MotorError> {
+        let folded = PoseLibrary::load_or_default(&self.get_poses_file())
+            .map_err(|e| MotorError::PoseError(e.to_string()))?
+            .get(TRANSPORT_POSE)
+            .ok_or_else(|| {
+                MotorError::PoseError(format!(
+                    "No {} pose, fold the robot by hand and save it first",
+                    TRANSPORT_POSE
+                ))
+            })?;
+        // Already folded and locked otherwise
+        if lock_or_recover(&self.bounds_before_transport, "bounds").is_none() {
+            self.save_pose(BEFORE_TRANSPORT_POSE)?;
+        }
+        self.move_to_pose_with_torque(TRANSPORT_POSE, duration)?;
+
+        if lock_bounds {
+            let mut previous = lock_or_recover(&self.bounds_before_transport, "bounds");
+            if previous.is_none() {
+                *previous = Some(self.get_position_bounds());
+            }
+            self.set_position_bounds(folded.map(|position| {
+                PositionBounds::new(
+                    position - TRANSPORT_LOCK_MARGIN,
+                    position + TRANSPORT_LOCK_MARGIN,
+                )
+            }))?;
+        }
+        self.push_command(MotorCommand::DisableTorque())
+            .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Unfold the robot after [`Self::prepare_for_transport`]: restore the position bounds
+    /// and move back to the positions before it in `duration`. Blocks until unfolded.
+    pub fn wake_up(&self, duration: Duration) -> Result<(), MotorError> {
+        if let Some(bounds) = lock_or_recover(&self.bounds_before_transport, "bounds").take() {
+            self.set_position_bounds(bounds)?;
+        }
+        self.move_to_pose_with_torque(BEFORE_TRANSPORT_POSE, duration)
+    }
+
+    /// Hold the measured positions, turn the torque on and move to the pose `name`.
+    fn move_to_pose_with_torque(&self, name: &str, duration: Duration) -> Result<(), MotorError> {
+        let positions = self.get_last_position()?.position;
+        self.push_command(MotorCommand::SetAllGoalPositions { positions })
+            .and_then(|()| self.push_command(MotorCommand::EnableTorque()))
+            .map_err(|_| MotorError::CommunicationError())?;
+        self.goto_pose(name, duration)?;
+        std::thread::sleep(duration + TRANSPORT_SETTLE_TIME);
+        Ok(())
+    }
+
+    /// Play a trajectory with its keyframes quantized to whole beats, from the next beat.
+    ///
+    /// See [`Self::set_beat_grid`], the trajectory is played right away when no beat grid
+    /// is registered.
+    pub fn play_trajectory_on_beat(&self, trajectory: Trajectory) -> Result<(), MotorError> {
+        let command = match self.get_beat_grid() {
+            Some(grid) => MotorCommand::PlayTrajectory {
+                trajectory: trajectory.quantized(&grid),
+                start: Some(grid.next_beat(monotonic_now())),
+            },
+            None => MotorCommand::PlayTrajectory {
+                trajectory,
+                start: None,
+            },
+        };
+        self.push_command(command)
+            .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Register the beats of the music being played, or `None` to forget them.
+    ///
+    /// Only the trajectories played afterwards with [`Self::play_trajectory_on_beat`]
+    /// follow the new beats, see [`crate::beat`].
+    pub fn set_beat_grid(&self, grid: Option<BeatGrid>) {
+        *lock_or_recover(&self.shared.beat_grid, "beat_grid") = grid;
+    }
+
+    pub fn get_beat_grid(&self) -> Option<BeatGrid> {
+        *lock_or_recover(&self.shared.beat_grid, "beat_grid")
+    }
+
+    /// Set the kinematics used by the head pose commands, see [`crate::kinematics`].
+    pub fn set_kinematics(&self, kinematics: impl Kinematics + 'static) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        *lock_or_recover(&self.shared.kinematics, "kinematics") = Some(Box::new(kinematics));
+        Ok(())
+    }
+
+    /// Install a control law deciding the goals written to the motors at each tick, see
+    /// [`crate::control_law`]. It replaces the one installed so far, if any.
+    pub fn set_control_law(&self, law: impl ControlLaw + 'static) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        *lock_or_recover(&self.shared.control_law, "control_law") = Some(Box::new(law));
+        Ok(())
+    }
+
+    /// Remove the control law, the motors go to its last target.
+    pub fn clear_control_law(&self) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        lock_or_recover(&self.shared.control_law, "control_law").take();
+        Ok(())
+    }
+
+    /// Set how the gaze yaw is shared between the body and the head, see [`GazeSplit`].
+    pub fn set_gaze_split(&self, split: GazeSplit) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        *lock_or_recover(&self.shared.gaze_split, "gaze_split") = split;
+        Ok(())
+    }
+
+    pub fn get_gaze_split(&self) -> GazeSplit {
+        *lock_or_recover(&self.shared.gaze_split, "gaze_split")
+    }
+
+    /// Look at `yaw` (rad, relative to the base) by turning both the body and the head.
+    ///
+    /// The yaw is split according to [`Self::set_gaze_split`], the rest of the head pose
+    /// is kept. The head yaw goes through the kinematics, see [`Self::set_kinematics`],
+    /// and is assumed to turn the same way as the body rotation.
+    pub fn set_gaze_yaw(&self, yaw: f64) -> Result<(), MotorError> {
+        if lock_or_recover(&self.shared.kinematics, "kinematics").is_none() {
+            return Err(MotorError::NoKinematicsError());
+        }
+        self.push_command(MotorCommand::SetGazeYaw { yaw })
+            .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Move the head to `pose` through the kinematics, see [`Self::set_kinematics`], and
+    /// the body to `body_yaw` (rad) in the same tick if given.
+    pub fn set_head_pose(&self, pose: HeadPose, body_yaw: Option<f64>) -> Result<(), MotorError> {
+        if lock_or_recover(&self.shared.kinematics, "kinematics").is_none() {
+            return Err(MotorError::NoKinematicsError());
+        }
+        self.push_command(MotorCommand::SetHeadPose { pose, body_yaw })
+            .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Move the head at a velocity expressed in the head frame, e.g. from a joystick.
+    ///
+    /// `linear` is in m/s and `angular` in rad/s around the head axes. The loop
+    /// integrates the pose at each tick and sends the stewart platform goals through
+    /// the kinematics, holding the head where the pose gets out of reach. The head
+    /// stops if no new twist is received within [`crate::kinematics::HEAD_TWIST_TIMEOUT`],
+    /// or on any new goal.
+    pub fn set_head_twist(&self, linear: [f64; 3], angular: [f64; 3]) -> Result<(), MotorError> {
+        if lock_or_recover(&self.shared.kinematics, "kinematics").is_none() {
+            return Err(MotorError::NoKinematicsError());
+        }
+        let twist = HeadTwist { linear, angular };
+        self.push_command(MotorCommand::SetHeadTwist { twist })
+            .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Hold the current goals of the trajectory until [`Self::resume_trajectory`].
+    pub fn pause_trajectory(&self) -> Result<(), MotorError> {
+        self.push_command(MotorCommand::PauseTrajectory())
+            .map_err(|_| MotorError::CommunicationError())
+    }
+
+    pub fn resume_trajectory(&self) -> Result<(), MotorError> {
+        self.push_command(MotorCommand::ResumeTrajectory())
+            .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Re-time the trajectory being played from now on, e.g. 2 plays it twice as fast.
+    ///
+    /// Negative or NaN speeds are taken as 0, i.e. the trajectory holds its current goals.
+    pub fn set_trajectory_speed(&self, speed: f64) -> Result<(), MotorError> {
+        self.push_command(MotorCommand::SetTrajectorySpeed { speed })
+            .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Stop the trajectory, the motors stay at its current goals.
+    pub fn stop_trajectory(&self) -> Result<(), MotorError> {
+        self.push_command(MotorCommand::StopTrajectory())
+            .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Playback state of the trajectory, or `None` when none is playing.
+    pub fn get_trajectory_status(&self) -> Option<TrajectoryStatus> {
+        *lock_or_recover(&self.shared.trajectory, "trajectory")
+    }
+
+    /// Play a trajectory on the joints of `track` only, sampled at each tick of the loop
+    /// from their current goals.
+    ///
+    /// The tracks play concurrently with each other and with the trajectory (see
+    /// [`Self::play_trajectory`]), whose goals they override on their joints, e.g. an
+    /// antenna wiggle over a head motion. It replaces the trajectory of the same track, if
+    /// any, and is stopped by a new goal of its joints. The positions of the other joints
+    /// in the keyframes are ignored.
+    pub fn play_track(&self, track: Track, trajectory: Trajectory) -> Result<(), MotorError> {
+        self.push_command(MotorCommand::PlayTrack {
+            track,
+            trajectory,
+            start: None,
+        })
+        .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Stop the trajectory of `track`, its joints stay at their current goals.
+    pub fn stop_track(&self, track: Track) -> Result<(), MotorError> {
+        self.push_command(MotorCommand::StopTrack { track })
+            .map_err(|_| MotorError::CommunicationError())
+    }
+
+    /// Playback state of the trajectory of `track`, or `None` when none is playing.
+    pub fn get_track_status(&self, track: Track) -> Option<TrajectoryStatus> {
+        lock_or_recover(&self.shared.tracks, "tracks")[track.index()]
+    }
+
+    /// Result of the last trajectory that ended, completed or not, with its tracking errors.
+    pub fn get_trajectory_result(&self) -> Option<TrajectoryResult> {
+        *lock_or_recover(&self.shared.trajectory_result, "trajectory_result")
+    }
+
+    /// Shape the goal positions so each joint respects the given limits (S-curve).
+    ///
+    /// Limits are given per joint: body rotation, stewart platform (x6), right and left antennas.
+    /// Goals are then followed at the control loop read period instead of being sent directly.
+    pub fn enable_goal_smoothing(
+        &self,
+        limits: [JointLimits; NUM_JOINTS],
+    ) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetGoalSmoothing {
+            limits: Some(Box::new(limits)),
+        })
+    }
+
+    /// Send the goal positions directly again. Motors stay at the last shaped goal.
+    pub fn disable_goal_smoothing(&self) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetGoalSmoothing { limits: None })
+    }
+
+    /// Drop the position reads implying a joint velocity above `max_velocity` (rad/s)
+    /// or holding non-finite values, see [`crate::read_filter`].
+    ///
+    /// The last plausible position keeps being published instead, and the
+    /// rejections are counted as "implausible" in [`Self::get_error_stats`].
+    pub fn enable_read_filter(&self, max_velocity: f64) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetReadFilter {
+            max_velocity: Some(max_velocity),
+        })
+    }
+
+    /// Publish all position reads again.
+    pub fn disable_read_filter(&self) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetReadFilter { max_velocity: None })
+    }
+
+    /// Limit the total current (mA) of the stewart platform motors, see
+    /// [`crate::current_budget`]. `max_total_current` must be strictly positive.
+    ///
+    /// While the sum of their currents exceeds it, their goals are pulled back toward
+    /// the measured positions, so aggressive motions are slowed down instead of browning
+    /// out the supply. A "current_budget" event is recorded when the limiting starts.
+    pub fn enable_current_budget(&self, max_total_current: f64) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetCurrentBudget {
+            max_total_current: Some(max_total_current),
+        })
+    }
+
+    /// Write the profile velocity of the joints from the velocity of the trajectory and
+    /// the tracks being played, so the motors move at the speed of the trajectory
+    /// between two goals instead of rushing to each goal then waiting. This improves
+    /// the tracking of fast trajectories, at the cost of a write of all the motors per
+    /// tick while they play.
+    ///
+    /// The profile velocities are reset to no limit once nothing plays. Only effective
+    /// with the motors in position control mode.
+    pub fn enable_velocity_feedforward(
+        &self,
+        feedforward: VelocityFeedforward,
+    ) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetVelocityFeedforward {
+            feedforward: Some(feedforward),
+        })
+    }
+
+    pub fn disable_velocity_feedforward(&self) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetVelocityFeedforward { feedforward: None })
+    }
+
+    /// Filter the antenna goals with a second-order low-pass filter, to smooth noisy
+    /// targets (e.g. from audio or emotion engines). Applies only to the antennas, and
+    /// keeps moving them toward their last target between two goals.
+    ///
+    /// # Arguments
+    /// * `natural_frequency` - Natural frequency of the filter (Hz).
+    /// * `damping` - Damping ratio, 1 or above for no overshoot.
+    pub fn enable_antenna_smoothing(
+        &self,
+        natural_frequency: f64,
+        damping: f64,
+    ) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetAntennaSmoothing {
+            parameters: Some((natural_frequency, damping)),
+        })
+    }
+
+    /// Stop filtering the antenna goals, the antennas go straight to their last target.
+    pub fn disable_antenna_smoothing(&self) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetAntennaSmoothing { parameters: None })
+    }
+
+    /// Stop limiting the total current of the stewart platform.
+    pub fn disable_current_budget(&self) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetCurrentBudget {
+            max_total_current: None,
+        })
+    }
+
+    /// Scale applied to the stewart platform position errors by the current budget,
+    /// 1 when it is not limiting.
+    pub fn get_current_budget_scale(&self) -> f64 {
+        *lock_or_recover(&self.shared.current_budget_scale, "current_budget_scale")
+    }
+
+    /// Track the heating of each motor from its current (i²t), see [`crate::duty_cycle`].
+    ///
+    /// A "duty_cycle" event is recorded when the RMS current of a motor over
+    /// `time_constant` (s) approaches `continuous_current` (mA), and the goal current of
+    /// the overloaded stewart platform motors is lowered to it until they cool down.
+    pub fn enable_duty_cycle_limiter(
+        &self,
+        continuous_current: f64,
+        time_constant: f64,
+    ) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetDutyCycleLimiter {
+            rating: Some((continuous_current, time_constant)),
+        })
+    }
+
+    /// Stop tracking the heating of the motors, restoring the lowered goal currents.
+    pub fn disable_duty_cycle_limiter(&self) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetDutyCycleLimiter { rating: None })
+    }
+
+    /// RMS current of each joint over its continuous rating, as tracked by the duty-cycle
+    /// limiter (0 while it is disabled).
+    pub fn get_duty_cycle_loads(&self) -> [f64; NUM_JOINTS] {
+        *lock_or_recover(&self.shared.duty_cycle_loads, "duty_cycle_loads")
+    }
+
+    /// Last temperatures, voltages, hardware errors and currents polled in the background,
+    /// see [`crate::diagnostics`].
+    pub fn get_diagnostics(&self) -> Diagnostics {
+        *lock_or_recover(&self.shared.diagnostics, "diagnostics")
+    }
+
+    /// Set how often the diagnostics registers are polled, the hardware errors are also
+    /// checked for overloads then.
+    pub fn set_diagnostics_period(&self, period: Duration) -> Result<(), MotorError> {
+        self.check_configuration_unlocked()?;
+        *lock_or_recover(&self.shared.diagnostics_period, "diagnostics_period") = period;
+        Ok(())
+    }
+
+    pub fn get_diagnostics_period(&self) -> Duration {
+        *lock_or_recover(&self.shared.diagnostics_period, "diagnostics_period")
+    }
+
+    /// Number of ticks which overran the loop period, by main cause: "read",
+    /// "command:<name>" (e.g. "command:SetAllGoalPositions"), "callbacks" (goal reached
+    /// callbacks and touch listeners) or "other" (goal writes, polls...).
+    ///
+    /// The commands handled between two ticks count in the time of the tick. Overruns
+    /// are also logged as warnings, throttled like the other failures of the loop.
+    pub fn get_overrun_counts(&self) -> HashMap<String, u64> {
+        lock_or_recover(&self.shared.overruns, "overruns").clone()
+    }
+
+    /// Set the tolerance (rad) of each joint used to decide when the goal is reached.
+    ///
+    /// Joints are ordered as body rotation, stewart platform (x6), right and left antennas.
+    pub fn set_goal_tolerance(&self, tolerance: [f64; NUM_JOINTS]) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetGoalTolerance { tolerance })
+    }
+
+    /// Whether all joints reached the latest goal (within tolerance) since it was sent.
+    pub fn is_goal_reached(&self) -> bool {
+        *lock_or_recover(&self.shared.goal_reached, "goal_reached")
+    }
+
+    /// Register a callback called once each time a goal is reached.
+    ///
+    /// It runs on the control loop thread so it should return quickly,
+    /// and must not register other callbacks.
+    pub fn on_goal_reached(&self, callback: impl Fn(&FullBodyPosition) + Send + 'static) {
+        let callbacks = &self.shared.goal_reached_callbacks;
+        lock_or_recover(callbacks, "goal_reached_callbacks").push(Box::new(callback));
+    }
+
+    /// Whether the USB device of the serial port is plugged in, `None` if it is not
+    /// watched (the loop was not opened from a serial port name).
+    ///
+    /// See [`crate::hotplug`].
+    pub fn is_usb_connected(&self) -> Option<bool> {
+        *lock_or_recover(&self.shared.usb_connected, "usb_connected")
+    }
+
+    /// Register a callback called with `true` when the USB device is plugged back in
+    /// and `false` when it is unplugged.
+    ///
+    /// The callback runs on the hotplug monitor thread, it is never called if the loop
+    /// was not opened from a serial port name. It must not register other callbacks.
+    pub fn on_hotplug(&self, callback: impl Fn(bool) + Send + 'static) {
+        lock_or_recover(&self.shared.hotplug_callbacks, "hotplug_callbacks")
+            .push(Box::new(callback));
+    }
+
+    /// Register a callback called once per touch of the stewart platform or of an antenna.
+    ///
+    /// `sensitivity` goes from 0 (only strong pushes) to 1 (light touches, with more
+    /// false positives), see [`crate::touch`]. Once a callback is registered the loop
+    /// also reads the motor currents at each tick. The callback runs on the control
+    /// loop thread so it should return quickly, and must not register other callbacks.
+    pub fn on_touch(&self, callback: impl Fn(&Touch) + Send + 'static, sensitivity: f64) {
+        lock_or_recover(&self.shared.touch_listeners, "touch_listeners")
+            .push(TouchListener::new(Box::new(callback), sensitivity));
+    }
+
+    pub fn async_read_raw_bytes(
+        &self,
+        id: u8,
+        addr: u8,
+        length: u8,
+    ) -> Result<Vec<u8>, MotorError> {
+        let command = MotorCommand::ReadRawBytes { id, addr, length };
+        self.push_command(command)
+            .map_err(|_| MotorError::CommunicationError())?;
+        let data = self
+            .rx_raw_bytes
+            .lock()
+            .unwrap()
+            .blocking_recv()
+            .ok_or(MotorError::CommunicationError())?;
+        Ok(data)
+    }
+
+    pub fn async_write_raw_bytes(&self, id: u8, addr: u8, data: Vec<u8>) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::WriteRawBytes { id, addr, data })
+    }
+
+    /// Keep the bus silent for `gap` before and after each raw packet, see
+    /// [`ReachyMiniMotorController::set_raw_packet_gap`].
+    pub fn set_raw_packet_gap(&self, gap: Duration) -> Result<(), MotorError> {
+        self.push_configuration(MotorCommand::SetRawPacketGap { gap })
+    }
+
+    pub fn async_read_pid_gains(&self, id: u8) -> Result<(u16, u16, u16), MotorError> {
+        // https://emanual.robotis.com/docs/en/dxl/x/xl330-m288/#velocity-i-gain
+        const DIP_GAIN_ADDR: u8 = 80;
+
+        self.async_read_raw_bytes(id, DIP_GAIN_ADDR, 3 * 2)
+            .and_then(|data| {
+                if data.len() != 6 {
+                    return Err(MotorError::CommunicationError());
+                }
+                let d_gain = u16::from_le_bytes([data[0], data[1]]);
+                let i_gain = u16::from_le_bytes([data[2], data[3]]);
+                let p_gain = u16::from_le_bytes([data[4], data[5]]);
+                Ok((p_gain, i_gain, d_gain))
+            })
+    }
+
+    pub fn async_write_pid_gains(
+        &self,
+        id: u8,
+        p_gain: u16,
+        i_gain: u16,
+        d_gain: u16,
+    ) -> Result<(), MotorError> {
+        // https://emanual.robotis.com/docs/en/dxl/x/xl330-m288/#velocity-i-gain
+        const DIP_GAIN_ADDR: u8 = 80;
+
+        let mut data = Vec::with_capacity(6);
+        data.extend_from_slice(&d_gain.to_le_bytes());
+        data.extend_from_slice(&i_gain.to_le_bytes());
+        data.extend_from_slice(&p_gain.to_le_bytes());
+
+        self.async_write_raw_bytes(id, DIP_GAIN_ADDR, data)
+    }
+}
 
 impl Drop for ReachyMiniControlLoop {
     fn drop(&mut self) {
@@ -569,134 +3013,1360 @@ impl Drop for ReachyMiniControlLoop {
 
 fn run(
     mut c: ReachyMiniMotorController,
-    stop_signal: Arc<Mutex<bool>>,
-    mut rx: mpsc::Receiver<MotorCommand>,
-    last_position: Arc<Mutex<Result<FullBodyPosition, MotorError>>>,
-    last_torque: Arc<Mutex<Result<bool, MotorError>>>,
-    last_control_mode: Arc<Mutex<Result<u8, MotorError>>>,
-    last_stats: Option<(Duration, Arc<Mutex<ControlLoopStats>>)>,
+    stop_signal: Arc<StopSignal>,
+    mut rx: mpsc::Receiver<QueuedCommand>,
+    shared: SharedState,
     read_position_loop_period: Duration,
     read_allowed_retries: u64,
     tx_raw_bytes: Sender<Vec<u8>>,
 ) {
     tokio::runtime::Runtime::new().unwrap().block_on(async {
-        let mut interval = time::interval(read_position_loop_period);
+        let clock = shared.clock.clone();
 
         // Stats related variables
-        let mut stats_t0 = std::time::Instant::now();
+        let mut stats_t0 = clock.now();
         let mut read_dt = Vec::new();
+        let mut tick_timestamps = Vec::new();
         let mut write_dt = Vec::new();
+        let mut command_latency = Vec::new();
 
-        let mut last_read_tick = std::time::Instant::now();
+        let mut last_read_tick = clock.now();
+        let last_stats = &shared.last_stats;
+
+        let mut state = LoopState {
+            smoother: None,
+            antenna_smoother: None,
+            antenna_last_step: clock.now(),
+            goal: GoalTracker {
+                goal: None,
+                tolerance: [DEFAULT_GOAL_TOLERANCE; NUM_JOINTS],
+            },
+            signal_shutdown: None,
+            usage: UsageTracker::new(),
+            last_state: LastStateWriter::new(),
+            power: PowerMonitor {
+                consecutive_failures: 0,
+                last_check: None,
+            },
+            read_filter: None,
+            stale_feedback: None,
+            clamp_warning: None,
+            torque: TorqueReconciler {
+                intended: None,
+                last_check: clock.now(),
+            },
+            hardware_errors: [0; NUM_JOINTS],
+            touch: TouchDetector::default(),
+            trajectory: None,
+            tracks: Default::default(),
+            velocity_feedforward: None,
+            profile_velocities_written: false,
+            twist: None,
+            soft_start: Some(DEFAULT_SOFT_START_DURATION),
+            current_ramp: None,
+            law_target: None,
+            law_last_update: None,
+            current_budget: None,
+            current_budget_limit: None,
+            safe_mode: None,
+            duty_cycle: None,
+            overrun: OverrunTracker::default(),
+            read_scheduler: ReadScheduler::default(),
+            diagnostics: DiagnosticsPoller::default(),
+            commanded: None,
+            velocity: [0.0; NUM_JOINTS],
+        };
+        let mut signals: Option<ShutdownSignals> = None;
+        let mut failures = LogThrottle::new(DEFAULT_LOG_PERIOD);
+        let motor_names: HashMap<u8, String> = c
+            .get_motor_name_id()
+            .into_iter()
+            .map(|(name, id)| (id, name))
+            .collect();
+        let record_error = |e: &MotorControllerError| {
+            lock_or_recover(&shared.error_stats, "error_stats").record(e, &motor_names);
+        };
 
         loop {
             tokio::select! {
                 maybe_command = rx.recv() => {
-                    if let Some(command) = maybe_command {
-                        let write_tick = std::time::Instant::now();
-                        if let Ok(res) = handle_commands(&mut c, last_torque.clone(), last_control_mode.clone(), command) {
-                            if let Some(data) = res {
-                            // This means we had a ReadRawBytes command
-                                tx_raw_bytes.send(data).await.unwrap();
-                            }
+                    if let Some(QueuedCommand { id, pushed_at, command }) = maybe_command {
+                        let write_tick = clock.now();
+                        let name = command_name(&command);
+                        let res = handle_commands(&mut c, &shared, &mut state, command);
+                        state.overrun.command(name, clock.now().duration_since(write_tick).as_secs_f64());
+                        match res {
+                            Ok(res) => {
+                                if let Some(data) = res {
+                                    // This means we had a ReadRawBytes command
+                                    // Never block the loop if the previous response was not read
+                                    if let Err(e) = tx_raw_bytes.try_send(data) {
+                                        warn!("Dropping raw bytes response: {}", e);
+                                    }
+                                }
 
-                            if last_stats.is_some() {
-                                let elapsed = write_tick.elapsed().as_secs_f64();
-                                write_dt.push(elapsed);
+                                if last_stats.is_some() {
+                                    let elapsed = clock.now().duration_since(write_tick).as_secs_f64();
+                                    write_dt.push(elapsed);
+                                    command_latency.push(CommandLatency {
+                                        id,
+                                        queue_wait: write_tick.duration_since(pushed_at).as_secs_f64(),
+                                        write: elapsed,
+                                        timestamp: monotonic_now(),
+                                    });
+                                }
+                            }
+                            Err(e) => {
+                                record_error(&e);
+                                failures.failure("command", e);
+                            }
+                        }
+                    }
+                }
+                _ = stop_signal.notify.notified() => {}
+                (name, code) = recv_signal(&mut signals) => {
+                    warn!("Received {}, shutting down the motors...", name);
+                    rx.close();
+                    // The pending commands could enable the torque again
+                    while rx.try_recv().is_ok() {}
+                    let ramp_duration = state.signal_shutdown.unwrap_or_default();
+                    match c.ramp_down_and_disable_torque(ramp_duration) {
+                        Ok(()) => {
+                            if let Ok(mut torque) = shared.last_torque.lock() {
+                                *torque = Ok(false);
                             }
+                            info!("Torque disabled");
                         }
+                        Err(e) => log::error!("Failed to disable torque on {}: {}", name, e),
                     }
+                    // Stopped below like on close, the owner decides whether to exit
+                    *lock_or_recover(&shared.shutdown_signal, "shutdown_signal") = Some(code);
+                    *lock_or_recover(&stop_signal.stop, "stop_signal") = true;
                 }
-                _ = interval.tick() => {
-                    let read_tick = std::time::Instant::now();
+                _ = clock.next_tick(read_position_loop_period) => {
+                    #[cfg(feature = "tracing")]
+                    let _tick = tracing::debug_span!("tick").entered();
+                    let read_tick = clock.now();
                     if let Some((_, stats)) = &last_stats {
                         stats.lock().unwrap().period.push(read_tick.duration_since(last_read_tick).as_secs_f64());
                         last_read_tick = read_tick;
                     }
 
-                    match read_pos(&mut c, read_allowed_retries) {
+                    let schedule = *lock_or_recover(&shared.read_schedule, "read_schedule");
+                    let due = state.read_scheduler.due(&schedule, read_tick, read_position_loop_period);
+                    let previous = lock_or_recover(&shared.last_position, "last_position").position;
+                    let read = try_read_scheduled(&mut c, read_allowed_retries, due, &previous).and_then(|positions| {
+                        match state.read_filter.as_mut() {
+                            Some(filter) => filter.check(&positions).map(|_| positions).map_err(|e| e.into()),
+                            None => Ok(positions),
+                        }
+                    });
+                    state.overrun.read = clock.now().duration_since(read_tick).as_secs_f64();
+                    if read.is_err() {
+                        state.read_scheduler.reset();
+                    }
+                    let writes_stopped = *lock_or_recover(&shared.power_lost, "power_lost")
+                        || *lock_or_recover(&shared.feedback_stale, "feedback_stale");
+                    if writes_stopped && state.trajectory.is_some() {
+                        warn!("Goal writes are stopped, dropping the trajectory");
+                        end_trajectory(&shared, &mut state, false);
+                    }
+                    if writes_stopped && state.tracks.iter().any(Option::is_some) {
+                        warn!("Goal writes are stopped, dropping the tracks");
+                        state.tracks = Default::default();
+                    }
+                    if let Some(goals) = advance_trajectories(&shared, &mut state, read_tick) {
+                        if let Ok(positions) = &read {
+                            let positions = positions.joints();
+                            let players = state.trajectory.iter_mut().chain(state.tracks.iter_mut().flatten());
+                            for player in players {
+                                player.track(&goals, &positions);
+                            }
+                        }
+                        // Before the goals, the profile velocity applies to the next goal write
+                        if let Err(e) = write_profile_velocities(&mut c, &mut state) {
+                            record_error(&e);
+                            failures.failure("profile velocity write", e);
+                        }
+                        match state.smoother.as_mut() {
+                            Some(smoother) => smoother.set_targets(goals),
+                            None => {
+                                if let Err(e) = drive_joints(&mut c, &shared, &mut state, 0, &goals) {
+                                    record_error(&e);
+                                    failures.failure("trajectory goal write", e);
+                                }
+                            }
+                        }
+                        for track in &mut state.tracks {
+                            if track.as_ref().is_some_and(TrajectoryPlayer::is_finished) {
+                                *track = None;
+                            }
+                        }
+                        if state.trajectory.as_ref().is_some_and(TrajectoryPlayer::is_finished) {
+                            end_trajectory(&shared, &mut state, true);
+                        }
+                        if state.trajectory.is_none() && state.tracks.iter().all(Option::is_none) {
+                            // Only the end of the trajectories is a goal to reach
+                            state.goal.goal = Some(goals);
+                        }
+                    }
+                    let playing = state.trajectory.is_some() || state.tracks.iter().any(Option::is_some);
+                    if state.profile_velocities_written
+                        && !playing
+                        && let Err(e) = write_profile_velocities(&mut c, &mut state)
+                    {
+                        record_error(&e);
+                        failures.failure("profile velocity write", e);
+                    }
+                    publish_trajectory(&shared, &state);
+                    if writes_stopped && state.twist.take().is_some() {
+                        warn!("Goal writes are stopped, dropping the head twist");
+                    }
+                    if let Err(e) = follow_head_twist(&mut c, &shared, &mut state, read_tick) {
+                        record_error(&e);
+                        failures.failure("head twist", e);
+                    }
+                    if let Some(ramp) = &state.current_ramp
+                        && !writes_stopped
+                    {
+                        let (currents, done) = ramp.currents(read_tick);
+                        match c.set_stewart_platform_goal_current(currents) {
+                            Ok(()) if done => state.current_ramp = None,
+                            Ok(()) => {}
+                            Err(e) => {
+                                record_error(&e);
+                                failures.failure("goal current ramp", e);
+                            }
+                        }
+                    }
+                    if let Some(smoother) = state.smoother.as_mut() {
+                        let torque_off = matches!(*lock_or_recover(&shared.last_torque, "last_torque"), Ok(false));
+                        if torque_off {
+                            // Follow the measured positions so re-enabling the torque does not jump
+                            if let Ok(positions) = &read {
+                                smoother.reset(positions.joints());
+                            }
+                        } else if !smoother.is_settled() && !writes_stopped {
+                            let goals = smoother.step(read_position_loop_period.as_secs_f64());
+                            if let Err(e) = drive_joints(&mut c, &shared, &mut state, 0, &goals) {
+                                record_error(&e);
+                                failures.failure("smoothed goal write", e);
+                            }
+                        }
+                    }
+                    if let Err(e) = step_antenna_smoother(&mut c, &shared, &mut state, &read, read_tick, writes_stopped) {
+                        record_error(&e);
+                        failures.failure("smoothed antennas write", e);
+                    }
+                    if let Err(e) = update_control_law(&mut c, &shared, &mut state, &read, read_tick, read_position_loop_period, writes_stopped) {
+                        record_error(&e);
+                        failures.failure("control law", e);
+                    }
+                    if let Err(e) = update_current_budget(&mut c, &shared, &mut state, &read, read_position_loop_period, writes_stopped) {
+                        record_error(&e);
+                        failures.failure("current budget", e);
+                    }
+                    if let Err(e) = update_duty_cycle(&mut c, &shared, &mut state, read_tick, writes_stopped) {
+                        record_error(&e);
+                        failures.failure("duty cycle", e);
+                    }
+
+                    if let Ok(positions) = &read
+                        && state.goal.is_reached(&positions.joints())
+                    {
+                        let mut reached = lock_or_recover(&shared.goal_reached, "goal_reached");
+                        if !*reached {
+                            *reached = true;
+                            drop(reached);
+                            let start = clock.now();
+                            for callback in lock_or_recover(&shared.goal_reached_callbacks, "goal_reached_callbacks").iter() {
+                                callback(positions);
+                            }
+                            state.overrun.callbacks += clock.now().duration_since(start).as_secs_f64();
+                        }
+                    }
+
+                    {
+                        let mut listeners = lock_or_recover(&shared.touch_listeners, "touch_listeners");
+                        if !listeners.is_empty() {
+                            let torque_on = matches!(*lock_or_recover(&shared.last_torque, "last_torque"), Ok(true));
+                            let goals = state.smoother.as_ref().map(GoalSmoother::positions).or(state.goal.goal);
+                            match (&read, goals) {
+                                (Ok(positions), Some(goals)) if torque_on => match c.read_all_currents() {
+                                    Ok(currents) => {
+                                        let start = clock.now();
+                                        state.touch.update(&mut listeners, goals, positions.joints(), currents);
+                                        state.overrun.callbacks += clock.now().duration_since(start).as_secs_f64();
+                                    }
+                                    Err(e) => {
+                                        state.touch.reset();
+                                        record_error(&e);
+                                        failures.failure("current read", e);
+                                    }
+                                },
+                                _ => state.touch.reset(),
+                            }
+                        }
+                    }
+
+                    {
+                        let torque_on = matches!(*lock_or_recover(&shared.last_torque, "last_torque"), Ok(true));
+                        let mut usage = lock_or_recover(&shared.usage, "usage");
+                        state.usage.tick(
+                            &mut usage,
+                            read_position_loop_period.as_secs_f64(),
+                            torque_on,
+                            read.as_ref().ok().map(FullBodyPosition::joints),
+                        );
+                        if let Err(e) = state.usage.save(&usage, false) {
+                            failures.failure("usage file write", e);
+                        }
+                    }
+                    if let Err(e) = save_last_state(&shared, &mut state, read_tick, false) {
+                        failures.failure("last state file write", e);
+                    }
+
+                    let diagnostics_period = *lock_or_recover(&shared.diagnostics_period, "diagnostics_period");
+                    if let Some(register) = state.diagnostics.next(diagnostics_period, read_tick)
+                        && let Err(e) = poll_diagnostics(&mut c, &shared, &mut state, register)
+                    {
+                        record_error(&e);
+                        failures.failure("diagnostics read", e);
+                    }
+
+                    let power_lost = *lock_or_recover(&shared.power_lost, "power_lost");
+                    match &read {
+                        Ok(_) => {
+                            state.power.read_succeeded();
+                            if power_lost {
+                                match recover_from_power_loss(&mut c, &shared, &mut state) {
+                                    Ok(()) => {
+                                        *lock_or_recover(&shared.power_lost, "power_lost") = false;
+                                        info!("Motors are back, configuration verified");
+                                        lock_or_recover(&shared.events, "events")
+                                            .record("reconnect", "Motors are back after a power loss, configuration verified".to_string());
+                                    }
+                                    Err(e) => failures.failure("power loss recovery", e),
+                                }
+                            }
+                        }
+                        Err(MotorControllerError::ImplausibleRead(_)) => {}
+                        Err(_) => {
+                            if !power_lost && state.power.read_failed(read_tick) {
+                                match c.check_missing_ids() {
+                                    Ok(missing) if missing.len() == NUM_JOINTS - c.missing_ids().len() => {
+                                        *lock_or_recover(&shared.power_lost, "power_lost") = true;
+                                        log::error!("All motors disappeared, power lost! Motion commands are rejected until they come back.");
+                                        lock_or_recover(&shared.events, "events")
+                                            .record("fault", "All motors disappeared, power lost".to_string());
+                                    }
+                                    Ok(_) => {}
+                                    Err(e) => failures.failure("missing motors check", e),
+                                }
+                            }
+                        }
+                    }
+
+                    if let Some(policy) = state.stale_feedback.as_mut() {
+                        let stale = *lock_or_recover(&shared.feedback_stale, "feedback_stale");
+                        match &read {
+                            Ok(_) => {
+                                policy.last_feedback = read_tick;
+                                if stale {
+                                    match c.disable_torque() {
+                                        Ok(()) => {
+                                            *lock_or_recover(&shared.last_torque, "last_torque") = Ok(false);
+                                            state.goal.goal = None;
+                                            state.torque.intended = None;
+                                            *lock_or_recover(&shared.feedback_stale, "feedback_stale") = false;
+                                            warn!("Feedback is back, torque disabled for safety");
+                                            lock_or_recover(&shared.events, "events")
+                                                .record("watchdog", "Feedback is back, torque disabled for safety".to_string());
+                                        }
+                                        Err(e) => failures.failure("torque disable after stale feedback", e),
+                                    }
+                                }
+                            }
+                            Err(MotorControllerError::ImplausibleRead(_)) => {}
+                            Err(_) => {
+                                if !stale && read_tick.duration_since(policy.last_feedback) > policy.timeout {
+                                    *lock_or_recover(&shared.feedback_stale, "feedback_stale") = true;
+                                    log::error!(
+                                        "No feedback for {:?}, goal writes stopped. The torque will be disabled once the bus is back.",
+                                        policy.timeout
+                                    );
+                                    lock_or_recover(&shared.events, "events")
+                                        .record("watchdog", format!("No feedback for {:?}, goal writes stopped", policy.timeout));
+                                }
+                            }
+                        }
+                    }
+
+                    if read.is_ok()
+                        && !*lock_or_recover(&shared.power_lost, "power_lost")
+                        && !*lock_or_recover(&shared.feedback_stale, "feedback_stale")
+                        && state.torque.check_due(read_tick)
+                        && let Err(e) = reconcile_torque(&mut c, &shared, &mut state)
+                    {
+                        record_error(&e);
+                        failures.failure("torque reconciliation", e);
+                    }
+
+                    match read {
                         Ok(positions) => {
-                            let now = std::time::SystemTime::now()
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_else(|_| std::time::Duration::from_secs(0));
                             let last = FullBodyPosition {
                                 body_yaw: positions.body_yaw,
                                 stewart: positions.stewart,
                                 antennas: positions.antennas,
-                                timestamp: now.as_secs_f64(),
+                                timestamp: wall_now(),
+                                monotonic_timestamp: monotonic_now(),
                             };
-                            if let Ok(mut pos) = last_position.lock() {
-                                *pos = Ok(last);
+                            if let Ok(mut body_yaw) = shared.body_yaw.lock() {
+                                body_yaw.update(last.body_yaw);
+                            }
+                            if let Ok(mut pos) = shared.last_position.lock() {
+                                let dt = last.monotonic_timestamp - pos.position.monotonic_timestamp;
+                                if dt > 0.0 {
+                                    let (joints, previous) = (last.joints(), pos.position.joints());
+                                    state.velocity = std::array::from_fn(|joint| (joints[joint] - previous[joint]) / dt);
+                                }
+                                *pos = PositionState { position: last, error: None };
+                            }
+                            shared.state_buffer.publish(&last);
+                            lock_or_recover(&shared.history, "history").push(last);
+                            let mut tracking = lock_or_recover(&shared.tracking, "tracking");
+                            if tracking.is_enabled()
+                                && let Some(goal) = state.commanded
+                                && matches!(*lock_or_recover(&shared.last_torque, "last_torque"), Ok(true))
+                            {
+                                tracking.push(TrackingSample {
+                                    goal,
+                                    position: last.joints(),
+                                    timestamp: last.timestamp,
+                                    monotonic_timestamp: last.monotonic_timestamp,
+                                });
                             }
                         },
+                        Err(e @ MotorControllerError::ImplausibleRead(_)) => {
+                            // Keep publishing the last plausible position
+                            record_error(&e);
+                            failures.failure("read plausibility check", e);
+                        }
                         Err(e) => {
-                            if let Ok(mut pos) = last_position.lock() {
-                                *pos = Err(e);
+                            record_error(&e);
+                            failures.failure("read", e);
+                            if let Ok(mut pos) = shared.last_position.lock() {
+                                pos.error = Some(MotorError::CommunicationError());
                             }
                         },
                     }
+                    failures.flush();
+                    publish_tick_state(&shared, &state);
+                    {
+                        let mut clock_offset = lock_or_recover(&shared.clock_offset, "clock_offset");
+                        if monotonic_now() - clock_offset.measured_at > CLOCK_SYNC_PERIOD.as_secs_f64() {
+                            *clock_offset = ClockOffset::measure();
+                        }
+                    }
+                    let elapsed = clock.now().duration_since(read_tick).as_secs_f64();
+                    if let Some((cause, detail)) = state.overrun.end_tick(elapsed, read_position_loop_period.as_secs_f64()) {
+                        *lock_or_recover(&shared.overruns, "overruns").entry(cause).or_default() += 1;
+                        failures.failure("loop tick", format!("overran the period, {}", detail));
+                    }
                     if last_stats.is_some() {
-                        let elapsed = read_tick.elapsed().as_secs_f64();
                         read_dt.push(elapsed);
+                        tick_timestamps.push(monotonic_now() - elapsed);
+                    }
+
+                    if let Some((period, stats)) = &last_stats
+                        && read_tick.duration_since(stats_t0) > *period {
+                            let mut stats = stats.lock().unwrap();
+                            stats.read_dt.extend(read_dt.iter().cloned());
+                            stats.tick_timestamps.append(&mut tick_timestamps);
+                            stats.write_dt.extend(write_dt.iter().cloned());
+                            stats.command_latency.append(&mut command_latency);
+                            stats.trim(&lock_or_recover(&shared.stats_retention, "stats_retention"), monotonic_now());
+                            drop(stats);
+
+                            read_dt.clear();
+                            write_dt.clear();
+                            stats_t0 = read_tick;
+                    }
+                    clock.tick_handled();
+                }
+            }
+
+            if signals.is_none() && state.signal_shutdown.is_some() {
+                match ShutdownSignals::new() {
+                    Ok(s) => signals = Some(s),
+                    Err(e) => {
+                        log::error!("Failed to install the signal handlers: {}", e);
+                        state.signal_shutdown = None;
+                    }
+                }
+            }
+
+            if *lock_or_recover(&stop_signal.stop, "stop_signal") {
+                // Drain the command channel before exiting
+                loop {
+                    if rx.is_empty() {
+                        break;
+                    }
+                    if let Some(queued) = rx.recv().await {
+                        let _ = handle_commands(&mut c, &shared, &mut state, queued.command);
+                    }
+                }
+                if let Err(e) = state.usage.save(&lock_or_recover(&shared.usage, "usage"), true) {
+                    warn!("Failed to save the usage counters: {}", e);
+                }
+                if let Err(e) = save_last_state(&shared, &mut state, clock.now(), true) {
+                    warn!("Failed to save the last state: {}", e);
+                }
+                break;
+            }
+        }
+    })
+}
+
+/// Lower the stewart platform goal current before its torque is enabled, it is then
+/// raised back at each tick.
+///
+/// Nothing is done if a stewart motor already holds, it would sag.
+fn start_current_ramp(
+    controller: &mut ReachyMiniMotorController,
+    shared: &SharedState,
+    state: &mut LoopState,
+    duration: Duration,
+) -> Result<(), MotorControllerError> {
+    if controller.read_all_torque_enabled()?[1..7]
+        .iter()
+        .any(|on| *on)
+    {
+        return Ok(());
+    }
+    // An unfinished ramp already lowered the goal current
+    let target = match &state.current_ramp {
+        Some(ramp) => ramp.target,
+        None => safe_goal_currents(state, controller.read_stewart_platform_goal_current()?),
+    };
+    let ramp = CurrentRamp {
+        target,
+        start: shared.clock.now(),
+        duration,
+    };
+    controller.set_stewart_platform_goal_current(ramp.currents(ramp.start).0)?;
+    state.current_ramp = Some(ramp);
+    Ok(())
+}
+
+/// Stewart platform goal currents (mA), capped to the safe mode limits if enabled.
+fn safe_goal_currents(state: &LoopState, currents: [i16; 6]) -> [i16; 6] {
+    state
+        .safe_mode
+        .map_or(currents, |limits| limits.goal_currents(currents))
+}
+
+/// Goals the joints are driven to, or their last known positions if unknown.
+fn current_goals(shared: &SharedState, state: &LoopState) -> [f64; NUM_JOINTS] {
+    state
+        .smoother
+        .as_ref()
+        .map(GoalSmoother::positions)
+        .or(state.goal.goal)
+        .unwrap_or_else(|| {
+            lock_or_recover(&shared.last_position, "last_position")
+                .position
+                .joints()
+        })
+}
+
+/// Move the head one tick further along the commanded twist.
+///
+/// The head holds its last pose while the next one is out of the workspace or of
+/// the position bounds.
+fn follow_head_twist(
+    controller: &mut ReachyMiniMotorController,
+    shared: &SharedState,
+    state: &mut LoopState,
+    now: std::time::Instant,
+) -> Result<(), MotorControllerError> {
+    let Some(follower) = state.twist.as_mut() else {
+        return Ok(());
+    };
+    if follower.is_expired(now) {
+        log::debug!("No new head twist, stopping the head");
+        state.twist = None;
+        return Ok(());
+    }
+    let pose = follower.next_pose(now);
+    let stewart = match &*lock_or_recover(&shared.kinematics, "kinematics") {
+        Some(kinematics) => kinematics.inverse(&pose),
+        None => return Err(no_kinematics()),
+    };
+    let bounds = goal_bounds(shared);
+    match stewart {
+        Ok(stewart) if validate_positions(1, &stewart, &bounds).is_ok() => {
+            follower.pose = pose;
+            match state.smoother.as_mut() {
+                Some(smoother) => {
+                    for (i, target) in stewart.iter().enumerate() {
+                        smoother.set_target(1 + i, *target);
                     }
+                }
+                None => drive_joints(controller, shared, state, 1, &stewart)?,
+            }
+        }
+        Ok(_) => log::debug!("Head pose {:?} out of the position bounds", pose),
+        Err(e) => log::debug!("{}", e),
+    }
+    Ok(())
+}
+
+/// Drop the trajectory being played, if any, and publish its result.
+fn end_trajectory(shared: &SharedState, state: &mut LoopState, completed: bool) {
+    let Some(player) = state.trajectory.take() else {
+        return;
+    };
+    let result = player.result(completed);
+    let (joint, rms_error) = result
+        .rms_error
+        .into_iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .unwrap_or_default();
+    let outcome = if completed {
+        "completed"
+    } else {
+        "interrupted"
+    };
+    lock_or_recover(&shared.events, "events").record(
+        "trajectory",
+        format!(
+            "Trajectory {} at {:.2}/{:.2} s, worst RMS tracking error {:.2} deg on {}",
+            outcome,
+            result.time,
+            result.duration,
+            rms_error.to_degrees(),
+            JOINT_NAMES[joint]
+        ),
+    );
+    *lock_or_recover(&shared.trajectory_result, "trajectory_result") = Some(result);
+}
+
+/// Check the `joints` of the keyframes against the position bounds, clamping them if
+/// goal clamping is enabled.
+fn check_keyframes(
+    shared: &SharedState,
+    state: &mut LoopState,
+    trajectory: &mut Trajectory,
+    joints: std::ops::Range<usize>,
+) -> Result<(), PositionOutOfBounds> {
+    let bounds = goal_bounds(shared);
+    let clamping = *lock_or_recover(&shared.goal_clamping, "goal_clamping");
+    for keyframe in trajectory.keyframes_mut() {
+        let positions = &mut keyframe.positions[joints.clone()];
+        if let Err(e) = validate_positions(joints.start, positions, &bounds) {
+            let Some(clamped) =
+                clamp_positions(joints.start, positions, &bounds).filter(|_| clamping)
+            else {
+                return Err(e);
+            };
+            positions.copy_from_slice(&clamped);
+            *lock_or_recover(&shared.clamped_goals, "clamped_goals") += 1;
+            warn_clamped_goal(state, &e);
+        }
+    }
+    Ok(())
+}
+
+/// Write the profile velocities of the velocity feedforward from the trajectory and the
+/// tracks being played, or remove the limit once nothing plays.
+fn write_profile_velocities(
+    controller: &mut ReachyMiniMotorController,
+    state: &mut LoopState,
+) -> Result<(), MotorControllerError> {
+    let playing = state.trajectory.is_some() || state.tracks.iter().any(Option::is_some);
+    let feedforward = state.velocity_feedforward.filter(|_| playing);
+    let Some(feedforward) = feedforward else {
+        if std::mem::take(&mut state.profile_velocities_written) {
+            controller.set_all_profile_velocities([safe_profile_velocity(state); NUM_JOINTS])?;
+        }
+        return Ok(());
+    };
+    // The joints not played are not limited
+    let mut velocities = match &state.trajectory {
+        Some(player) => player.velocity().map(|v| feedforward.profile_velocity(v)),
+        None => [0.0; NUM_JOINTS],
+    };
+    for (track, player) in Track::ALL.iter().zip(&state.tracks) {
+        if let Some(player) = player {
+            let velocity = player.velocity();
+            for joint in track.joints() {
+                velocities[joint] = feedforward.profile_velocity(velocity[joint]);
+            }
+        }
+    }
+    if let Some(limits) = state.safe_mode {
+        // 0 is no limit
+        velocities = velocities.map(|v| match v {
+            0.0 => limits.max_velocity,
+            v => v.min(limits.max_velocity),
+        });
+    }
+    state.profile_velocities_written = true;
+    controller.set_all_profile_velocities(velocities)
+}
+
+/// Profile velocity of the joints outside of the velocity feedforward: the maximum
+/// velocity of the safe mode, or else 0 for no limit.
+fn safe_profile_velocity(state: &LoopState) -> f64 {
+    state.safe_mode.map_or(0.0, |limits| limits.max_velocity)
+}
+
+/// Loop clock instant of the monotonic time `start` (s), now if it is in the past.
+fn loop_instant(shared: &SharedState, start: Option<f64>) -> Option<std::time::Instant> {
+    start.map(|start| {
+        let delay = Duration::try_from_secs_f64(start - monotonic_now());
+        shared.clock.now() + delay.unwrap_or_default()
+    })
+}
+
+/// Publish the playback state of the trajectory and of the tracks being played.
+fn publish_trajectory(shared: &SharedState, state: &LoopState) {
+    *lock_or_recover(&shared.trajectory, "trajectory") =
+        state.trajectory.as_ref().map(TrajectoryPlayer::status);
+    *lock_or_recover(&shared.tracks, "tracks") = state
+        .tracks
+        .each_ref()
+        .map(|player| player.as_ref().map(TrajectoryPlayer::status));
+}
+
+/// Move the trajectory and the tracks forward to `now`, and return the goals of all the
+/// joints, or `None` if nothing is playing.
+///
+/// The tracks override the joints of the trajectory, the other joints hold their last
+/// goals.
+fn advance_trajectories(
+    shared: &SharedState,
+    state: &mut LoopState,
+    now: std::time::Instant,
+) -> Option<[f64; NUM_JOINTS]> {
+    let trajectory = state.trajectory.as_mut().map(|player| player.advance(now));
+    if state.tracks.iter().all(Option::is_none) {
+        return trajectory;
+    }
+    let mut goals = trajectory
+        .or(state.commanded)
+        .unwrap_or_else(|| current_goals(shared, state));
+    for (track, player) in Track::ALL.iter().zip(&mut state.tracks) {
+        if let Some(player) = player {
+            let joints = track.joints();
+            goals[joints.clone()].copy_from_slice(&player.advance(now)[joints]);
+        }
+    }
+    Some(goals)
+}
+
+/// Log the clamped goals, the first one right away then a summary per log period.
+fn warn_clamped_goal(state: &mut LoopState, error: &PositionOutOfBounds) {
+    match &mut state.clamp_warning {
+        Some((last, count)) if last.elapsed() < DEFAULT_LOG_PERIOD => *count += 1,
+        Some((last, count)) if *count > 0 => {
+            warn!(
+                "{} goals clamped in last {:.0} s, last one: {}",
+                *count + 1,
+                last.elapsed().as_secs_f64(),
+                error
+            );
+            *last = std::time::Instant::now();
+            *count = 0;
+        }
+        _ => {
+            warn!("Goal clamped: {}", error);
+            state.clamp_warning = Some((std::time::Instant::now(), 0));
+        }
+    }
+}
+
+/// Keep the goals of the joints from `first` for the tracking stream, the other joints
+/// keep their goal.
+fn track_commanded(shared: &SharedState, state: &mut LoopState, first: usize, goals: &[f64]) {
+    let mut commanded = state
+        .commanded
+        .unwrap_or_else(|| current_goals(shared, state));
+    commanded[first..first + goals.len()].copy_from_slice(goals);
+    state.commanded = Some(commanded);
+}
+
+/// Drive the joints from `first` to `goals`: written to the motors, or only made the
+/// target of the control law if one is installed.
+fn drive_joints(
+    controller: &mut ReachyMiniMotorController,
+    shared: &SharedState,
+    state: &mut LoopState,
+    first: usize,
+    goals: &[f64],
+) -> Result<(), MotorControllerError> {
+    // The goals computed in the loop are kept in the safe workspace too
+    let clamped;
+    let goals = match state.safe_mode {
+        Some(_) => {
+            clamped = clamp_positions(first, goals, &goal_bounds(shared)).ok_or_else(|| {
+                MotorControllerError::OutOfBounds("Non-finite goal in safe mode".to_string())
+            })?;
+            &clamped[..]
+        }
+        None => goals,
+    };
+    track_commanded(shared, state, first, goals);
+    // The antennas go to the output of their smoother, which gets the goals as targets
+    let smoothed;
+    let goals = match state.antenna_smoother.as_mut() {
+        Some(smoother) if first + goals.len() == NUM_JOINTS => {
+            let now = shared.clock.now();
+            let dt = now.saturating_duration_since(state.antenna_last_step);
+            state.antenna_last_step = now;
+            smoother.set_targets([goals[7 - first], goals[8 - first]]);
+            let mut filtered = goals.to_vec();
+            filtered[7 - first..].copy_from_slice(&smoother.step(dt.as_secs_f64()));
+            smoothed = filtered;
+            &smoothed[..]
+        }
+        _ => goals,
+    };
+    if lock_or_recover(&shared.control_law, "control_law").is_some() {
+        let mut target = state
+            .law_target
+            .unwrap_or_else(|| current_goals(shared, state));
+        target[first..first + goals.len()].copy_from_slice(goals);
+        state.law_target = Some(target);
+        return Ok(());
+    }
+    if let Some(budget) = state.current_budget.as_mut() {
+        budget.track(first, goals);
+    }
+    match (first, goals.len()) {
+        (0, NUM_JOINTS) => write_all_goals(controller, goals.try_into()?),
+        (0, 1) => controller.set_body_rotation(goals[0]),
+        (1, 6) => controller.set_stewart_platform_position(goals.try_into()?),
+        (7, 2) => controller.set_antennas_positions(goals.try_into()?),
+        _ => Err(MotorControllerError::OutOfBounds(format!(
+            "No goal write for {} joints from {}",
+            goals.len(),
+            first
+        ))),
+    }
+}
+
+/// Move the smoothed antennas one tick further toward their targets, unless a goal write
+/// already did it during this tick.
+fn step_antenna_smoother(
+    controller: &mut ReachyMiniMotorController,
+    shared: &SharedState,
+    state: &mut LoopState,
+    read: &Result<FullBodyPosition, MotorControllerError>,
+    now: std::time::Instant,
+    writes_stopped: bool,
+) -> Result<(), MotorControllerError> {
+    let Some(smoother) = state.antenna_smoother.as_mut() else {
+        return Ok(());
+    };
+    if matches!(
+        *lock_or_recover(&shared.last_torque, "last_torque"),
+        Ok(false)
+    ) {
+        // Follow the measured positions so re-enabling the torque does not jump
+        if let Ok(positions) = read {
+            let joints = positions.joints();
+            smoother.reset([joints[7], joints[8]]);
+        }
+        return Ok(());
+    }
+    if writes_stopped || smoother.is_settled() || state.antenna_last_step >= now {
+        return Ok(());
+    }
+    let dt = now.saturating_duration_since(state.antenna_last_step);
+    state.antenna_last_step = now;
+    let positions = smoother.step(dt.as_secs_f64());
+    if lock_or_recover(&shared.control_law, "control_law").is_some() {
+        let mut target = state
+            .law_target
+            .unwrap_or_else(|| current_goals(shared, state));
+        target[7..9].copy_from_slice(&positions);
+        state.law_target = Some(target);
+        return Ok(());
+    }
+    controller.set_antennas_positions(positions)
+}
+
+/// Run the control law on the positions just read, or write its target once it is removed.
+fn update_control_law(
+    controller: &mut ReachyMiniMotorController,
+    shared: &SharedState,
+    state: &mut LoopState,
+    read: &Result<FullBodyPosition, MotorControllerError>,
+    now: std::time::Instant,
+    period: Duration,
+    writes_stopped: bool,
+) -> Result<(), MotorControllerError> {
+    let mut law = lock_or_recover(&shared.control_law, "control_law");
+    let Some(law) = law.as_mut() else {
+        // The motors go to the target the law was given
+        state.law_last_update = None;
+        if let Some(target) = state.law_target.take() {
+            write_all_goals(controller, target)?;
+        }
+        return Ok(());
+    };
+    let torque_on = matches!(
+        *lock_or_recover(&shared.last_torque, "last_torque"),
+        Ok(true)
+    );
+    let (Ok(positions), true, false) = (read, torque_on, writes_stopped) else {
+        state.law_last_update = None;
+        return Ok(());
+    };
+    let target = state
+        .law_target
+        .unwrap_or_else(|| current_goals(shared, state));
+
+    let dt = state
+        .law_last_update
+        .map_or(period, |last| now.saturating_duration_since(last));
+    state.law_last_update = Some(now);
+    let mut output = law.update(&ControlInput {
+        dt: dt.as_secs_f64(),
+        positions: positions.joints(),
+        target,
+    });
+    if let Some(limits) = state.safe_mode {
+        output.stewart_currents = output.stewart_currents.map(|c| limits.goal_currents(c));
+        output.goals = output
+            .goals
+            .and_then(|goals| clamp_positions(0, &goals, &goal_bounds(shared)))
+            .and_then(|goals| goals.try_into().ok());
+    }
+    if let Some(currents) = output.stewart_currents {
+        controller.set_stewart_platform_goal_current(currents)?;
+    }
+    if let Some(goals) = output.goals {
+        write_all_goals(controller, goals)?;
+    }
+    Ok(())
+}
+
+/// Scale the stewart platform goals down while its total current exceeds the budget.
+fn update_current_budget(
+    controller: &mut ReachyMiniMotorController,
+    shared: &SharedState,
+    state: &mut LoopState,
+    read: &Result<FullBodyPosition, MotorControllerError>,
+    period: Duration,
+    writes_stopped: bool,
+) -> Result<(), MotorControllerError> {
+    if state.current_budget.is_none() {
+        return Ok(());
+    }
+    let fallback = current_goals(shared, state);
+    let Some(budget) = state.current_budget.as_mut() else {
+        return Ok(());
+    };
+    let torque_on = matches!(
+        *lock_or_recover(&shared.last_torque, "last_torque"),
+        Ok(true)
+    );
+    if !torque_on || writes_stopped || lock_or_recover(&shared.control_law, "control_law").is_some()
+    {
+        budget.reset();
+        *lock_or_recover(&shared.current_budget_scale, "current_budget_scale") = 1.0;
+        return Ok(());
+    }
+    let Ok(positions) = read else {
+        return Ok(());
+    };
+
+    let previous_scale = budget.scale();
+    let total = budget.update(
+        controller.read_stewart_platform_current()?,
+        period.as_secs_f64(),
+    );
+    let scale = budget.scale();
+    *lock_or_recover(&shared.current_budget_scale, "current_budget_scale") = scale;
+    if scale < 1.0 && previous_scale == 1.0 {
+        lock_or_recover(&shared.events, "events").record(
+            "current_budget",
+            format!(
+                "Stewart platform current {:.0} mA over budget, goals scaled down",
+                total
+            ),
+        );
+    }
+    if scale < 1.0 || previous_scale < 1.0 {
+        controller
+            .set_stewart_platform_position(budget.stewart_goals(&positions.joints(), &fallback))?;
+    }
+    Ok(())
+}
+
+fn publish_tick_state(shared: &SharedState, state: &LoopState) {
+    let tick_state = TickState {
+        position: lock_or_recover(&shared.last_position, "last_position").position,
+        velocity: state.velocity,
+        current: lock_or_recover(&shared.diagnostics, "diagnostics").currents,
+        torque_enabled: lock_or_recover(&shared.last_torque, "last_torque")
+            .as_ref()
+            .ok()
+            .copied(),
+    };
+    *lock_or_recover(&shared.tick_state, "tick_state") = tick_state;
+}
+
+/// Read a diagnostics register and publish it, see [`crate::diagnostics`].
+///
+/// The new hardware errors are journaled and the overloads counted in the usage.
+fn poll_diagnostics(
+    controller: &mut ReachyMiniMotorController,
+    shared: &SharedState,
+    state: &mut LoopState,
+    register: DiagnosticsRegister,
+) -> Result<(), MotorControllerError> {
+    match register {
+        DiagnosticsRegister::Temperature => {
+            let temperatures = controller.read_all_temperatures()?;
+            lock_or_recover(&shared.diagnostics, "diagnostics").temperatures = temperatures;
+        }
+        DiagnosticsRegister::Voltage => {
+            // In units of 0.1 V
+            let voltages = controller.read_all_voltages()?.map(|v| v as f64 / 10.0);
+            lock_or_recover(&shared.diagnostics, "diagnostics").voltages = voltages;
+        }
+        DiagnosticsRegister::Current => {
+            let currents = controller.read_all_currents()?;
+            lock_or_recover(&shared.diagnostics, "diagnostics").currents = currents;
+        }
+        DiagnosticsRegister::HardwareErrorStatus => {
+            let status = controller.read_all_hardware_error_status()?;
+            state
+                .usage
+                .error_status(&mut lock_or_recover(&shared.usage, "usage"), status);
+            let mut events = lock_or_recover(&shared.events, "events");
+            for (joint, (&status, previous)) in status
+                .iter()
+                .zip(state.hardware_errors.iter_mut())
+                .enumerate()
+            {
+                if status & !*previous != 0 {
+                    events.record(
+                        "fault",
+                        format!("Hardware error 0x{:02x} on {}", status, JOINT_NAMES[joint]),
+                    );
+                }
+                *previous = status;
+            }
+            lock_or_recover(&shared.diagnostics, "diagnostics").hardware_errors = status;
+        }
+    }
+    lock_or_recover(&shared.diagnostics, "diagnostics").timestamp = wall_now();
+    Ok(())
+}
+
+/// Track the heating of the motors and lower the goal current of the overloaded stewart
+/// platform motors, see [`crate::duty_cycle`].
+fn update_duty_cycle(
+    controller: &mut ReachyMiniMotorController,
+    shared: &SharedState,
+    state: &mut LoopState,
+    now: std::time::Instant,
+    writes_stopped: bool,
+) -> Result<(), MotorControllerError> {
+    let safe_mode = state.safe_mode;
+    let Some(limiter) = state.duty_cycle.as_mut() else {
+        return Ok(());
+    };
+    if limiter
+        .last_update()
+        .is_some_and(|last| now.saturating_duration_since(last) < DUTY_CYCLE_UPDATE_PERIOD)
+    {
+        return Ok(());
+    }
+
+    let changes = limiter.update(controller.read_all_currents()?, now);
+    let loads = limiter.loads();
+    *lock_or_recover(&shared.duty_cycle_loads, "duty_cycle_loads") = loads;
+    for (joint, level) in changes {
+        let name = JOINT_NAMES[joint];
+        let message = match level {
+            DutyCycleLevel::Normal => {
+                format!("{} back under its continuous current rating", name)
+            }
+            DutyCycleLevel::Warning => format!(
+                "{} at {:.0}% of its continuous current rating",
+                name,
+                loads[joint] * 100.0
+            ),
+            DutyCycleLevel::Overloaded if (1..7).contains(&joint) => format!(
+                "{} above its continuous current rating, goal current lowered",
+                name
+            ),
+            DutyCycleLevel::Overloaded => {
+                format!("{} above its continuous current rating", name)
+            }
+        };
+        match level {
+            DutyCycleLevel::Normal => info!("{}", message),
+            _ => warn!("{}", message),
+        }
+        lock_or_recover(&shared.events, "events").record("duty_cycle", message);
+    }
+
+    // The goal current is left alone while something else drives it
+    if state.current_ramp.is_some()
+        || writes_stopped
+        || lock_or_recover(&shared.control_law, "control_law").is_some()
+    {
+        return Ok(());
+    }
+    if limiter.is_limiting() {
+        let nominal = match limiter.nominal_currents() {
+            Some(nominal) => nominal,
+            None => controller.read_stewart_platform_goal_current()?,
+        };
+        limiter.set_nominal_currents(Some(nominal));
+        controller.set_stewart_platform_goal_current(limiter.limited_currents(nominal))?;
+    } else if let Some(nominal) = limiter.nominal_currents() {
+        let nominal = safe_mode.map_or(nominal, |limits| limits.goal_currents(nominal));
+        controller.set_stewart_platform_goal_current(nominal)?;
+        limiter.set_nominal_currents(None);
+    }
+    Ok(())
+}
 
-                    if let Some((period, stats)) = &last_stats
-                        && stats_t0.elapsed() > *period {
-                            stats.lock().unwrap().read_dt.extend(read_dt.iter().cloned());
-                            stats.lock().unwrap().write_dt.extend(write_dt.iter().cloned());
+/// Write the last state file if set and due, see [`crate::last_state`].
+fn save_last_state(
+    shared: &SharedState,
+    state: &mut LoopState,
+    now: std::time::Instant,
+    clean_shutdown: bool,
+) -> std::io::Result<()> {
+    if !state.last_state.is_due(now, clean_shutdown) {
+        return Ok(());
+    }
+    let position = lock_or_recover(&shared.last_position, "last_position").position;
+    let last_state = LastState {
+        timestamp: position.timestamp,
+        positions: position.joints(),
+        torque_enabled: matches!(
+            *lock_or_recover(&shared.last_torque, "last_torque"),
+            Ok(true)
+        ),
+        control_mode: lock_or_recover(&shared.last_control_mode, "last_control_mode")
+            .as_ref()
+            .ok()
+            .copied(),
+        safe_mode: lock_or_recover(&shared.safe_mode, "safe_mode").enabled,
+        clean_shutdown,
+    };
+    state.last_state.save(&last_state, now)
+}
 
-                            read_dt.clear();
-                            write_dt.clear();
-                            stats_t0 = std::time::Instant::now();
-                    }
-                }
-            }
+/// Budget the stewart platform current with the lowest of the budget enabled and the one
+/// of the safe mode.
+fn set_current_budget(
+    controller: &mut ReachyMiniMotorController,
+    shared: &SharedState,
+    state: &mut LoopState,
+) -> Result<(), MotorControllerError> {
+    let safe_mode = state.safe_mode.map(|limits| limits.max_total_current);
+    let max_total_current = state
+        .current_budget_limit
+        .into_iter()
+        .chain(safe_mode)
+        .reduce(f64::min);
+    let previous = state.current_budget.take();
+    *lock_or_recover(&shared.current_budget_scale, "current_budget_scale") = 1.0;
+    if let Some(budget) = previous
+        && budget.scale() < 1.0
+    {
+        // Drop the scaling, the stewart platform goes to its actual goals
+        let goals = current_goals(shared, state);
+        controller.set_stewart_platform_position(budget.goals(&goals))?;
+    }
+    state.current_budget = max_total_current.map(CurrentBudget::new);
+    Ok(())
+}
 
-            if *stop_signal.lock().unwrap() {
-                // Drain the command channel before exiting
-                loop {
-                    if rx.is_empty() {
-                        break;
-                    }
-                    if let Some(command) = rx.recv().await {
-                        let _ = handle_commands(&mut c, last_torque.clone(), last_control_mode.clone(), command);
-                    }
-                }
-                break;
-            }
-        }
-    })
+/// Write the goal positions of all motors in a single sync write, or group by group
+/// if it fails so the healthy groups keep being driven.
+fn write_all_goals(
+    controller: &mut ReachyMiniMotorController,
+    positions: [f64; NUM_JOINTS],
+) -> Result<(), MotorControllerError> {
+    if controller.set_all_goal_positions(positions).is_ok() {
+        return Ok(());
+    }
+    controller.set_all_goal_positions_by_group(positions)?;
+    Ok(())
 }
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(command = command_name(&command)))
+)]
 fn handle_commands(
     controller: &mut ReachyMiniMotorController,
-    last_torque: Arc<Mutex<Result<bool, MotorError>>>,
-    last_control_mode: Arc<Mutex<Result<u8, MotorError>>>,
-    command: MotorCommand,
-) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    shared: &SharedState,
+    state: &mut LoopState,
+    mut command: MotorCommand,
+) -> Result<Option<Vec<u8>>, MotorControllerError> {
     use MotorCommand::*;
 
-    match command {
-        SetAllGoalPositions { positions } => controller
-            .set_all_goal_positions([
-                positions.body_yaw,
-                positions.stewart[0],
-                positions.stewart[1],
-                positions.stewart[2],
-                positions.stewart[3],
-                positions.stewart[4],
-                positions.stewart[5],
-                positions.antennas[0],
-                positions.antennas[1],
-            ])
-            .map(|_| None),
-        SetStewartPlatformPosition { position } => controller
-            .set_stewart_platform_position(position)
-            .map(|_| None),
-        SetBodyRotation { position } => controller.set_body_rotation(position).map(|_| None),
-        SetAntennasPositions { positions } => {
-            controller.set_antennas_positions(positions).map(|_| None)
+    let last_torque = &shared.last_torque;
+    let last_control_mode = &shared.last_control_mode;
+
+    if command.is_motion() && *lock_or_recover(&shared.power_lost, "power_lost") {
+        return Err(MotorControllerError::Rejected(format!(
+            "Power lost, rejecting {:?}",
+            command
+        )));
+    }
+    if command.is_motion() && *lock_or_recover(&shared.feedback_stale, "feedback_stale") {
+        return Err(MotorControllerError::Rejected(format!(
+            "Feedback is stale, rejecting {:?}",
+            command
+        )));
+    }
+    if command.is_configuration()
+        && lock_or_recover(&shared.configuration_lock, "configuration_lock").is_some()
+    {
+        return Err(MotorControllerError::Rejected(format!(
+            "Configuration is locked, rejecting {:?}",
+            command
+        )));
+    }
+    if command.bypasses_safe_mode() && state.safe_mode.is_some() {
+        return Err(safe_mode_rejected(&command));
+    }
+
+    let mut new_goal = command.goal_joints();
+    if let Some((first, targets)) = &new_goal {
+        let bounds = goal_bounds(shared);
+        if let Err(e) = validate_positions(*first, targets, &bounds) {
+            let clamping = *lock_or_recover(&shared.goal_clamping, "goal_clamping");
+            let Some(clamped) = clamp_positions(*first, targets, &bounds).filter(|_| clamping)
+            else {
+                return Err(e.into());
+            };
+            command.set_goal_joints(&clamped);
+            new_goal = Some((*first, clamped));
+            *lock_or_recover(&shared.clamped_goals, "clamped_goals") += 1;
+            warn_clamped_goal(state, &e);
+        }
+    }
+
+    // A new goal takes over the joints, stop following the trajectory or head twist
+    if new_goal.is_some()
+        || matches!(
+            command,
+            SpinBody { .. } | HomeBodyRotation { .. } | PlayTrajectory { .. }
+        )
+    {
+        end_trajectory(shared, state, false);
+        state.twist = None;
+    }
+    // and the tracks of its joints
+    let taken = match (&new_goal, &command) {
+        (Some((first, targets)), _) => Some(*first..first + targets.len()),
+        (None, SpinBody { .. } | HomeBodyRotation { .. }) => Some(Track::Body.joints()),
+        (None, SetHeadTwist { .. }) => Some(Track::Head.joints()),
+        _ => None,
+    };
+    if let Some(taken) = taken {
+        for (track, player) in Track::ALL.iter().zip(&mut state.tracks) {
+            if track.joints().any(|joint| taken.contains(&joint)) {
+                *player = None;
+            }
+        }
+    }
+
+    // Track the new goal of the joints, for the goal reached event
+    if let Some((first, targets)) = new_goal {
+        let current = lock_or_recover(&shared.last_position, "last_position")
+            .position
+            .joints();
+        state.goal.set_joints(first, &targets, current);
+        if let Some(budget) = state.current_budget.as_mut() {
+            budget.track(first, &targets);
+        }
+        *lock_or_recover(&shared.goal_reached, "goal_reached") = false;
+    }
+
+    if let Some(limits) = state.safe_mode {
+        match &mut command {
+            SetStewartPlatformGoalCurrent { current: currents }
+            | SetStewartGoal { currents, .. } => *currents = limits.goal_currents(*currents),
+            SpinBody { velocity } => *velocity = limits.velocity(*velocity),
+            _ => {}
         }
+    }
+
+    let torque_change = torque_change(&command, controller);
+    let mode_change = mode_change(&command);
+    if matches!(
+        command,
+        SetStewartPlatformGoalCurrent { .. } | SetStewartGoal { .. }
+    ) {
+        // The new goal current is the one to apply
+        state.current_ramp = None;
+    }
+    if let SetStewartPlatformGoalCurrent { current: currents } | SetStewartGoal { currents, .. } =
+        &command
+        && let Some(limiter) = state.duty_cycle.as_mut()
+        && limiter.nominal_currents().is_some()
+    {
+        // Restored once no motor is overloaded anymore
+        limiter.set_nominal_currents(Some(*currents));
+    }
+    if let Some(TorqueChange::Joints(joints, true)) = &torque_change
+        && let Some(duration) = state.soft_start
+        && joints.iter().any(|joint| (1..7).contains(joint))
+        && let Err(e) = start_current_ramp(controller, shared, state, duration)
+    {
+        warn!("Enabling the torque without soft start: {}", e);
+    }
+    let smoother = &mut state.smoother;
+
+    let res = match command {
+        SetAllGoalPositions { positions } => match smoother {
+            Some(smoother) => {
+                smoother.set_targets(positions.joints());
+                Ok(None)
+            }
+            None => drive_joints(controller, shared, state, 0, &positions.joints()).map(|_| None),
+        },
+        SetStewartPlatformPosition { position } => match smoother {
+            Some(smoother) => {
+                for (i, target) in position.iter().enumerate() {
+                    smoother.set_target(1 + i, *target);
+                }
+                Ok(None)
+            }
+            None => drive_joints(controller, shared, state, 1, &position).map(|_| None),
+        },
+        SetBodyRotation { position } => match smoother {
+            Some(smoother) => {
+                smoother.set_target(0, position);
+                Ok(None)
+            }
+            None => drive_joints(controller, shared, state, 0, &[position]).map(|_| None),
+        },
+        SetAntennasPositions { positions } => match smoother {
+            Some(smoother) => {
+                smoother.set_target(7, positions[0]);
+                smoother.set_target(8, positions[1]);
+                Ok(None)
+            }
+            None => drive_joints(controller, shared, state, 7, &positions).map(|_| None),
+        },
         EnableTorque() => {
             let res = controller.enable_torque();
             if res.is_ok()
@@ -736,6 +4406,28 @@ fn handle_commands(
         SetStewartPlatformGoalCurrent { current } => controller
             .set_stewart_platform_goal_current(current)
             .map(|_| None),
+        SetStewartGoal {
+            positions,
+            currents,
+        } => match smoother {
+            Some(smoother) => {
+                controller.set_stewart_platform_goal_current(currents)?;
+                for (i, target) in positions.iter().enumerate() {
+                    smoother.set_target(1 + i, *target);
+                }
+                Ok(None)
+            }
+            None if lock_or_recover(&shared.control_law, "control_law").is_some() => {
+                controller.set_stewart_platform_goal_current(currents)?;
+                drive_joints(controller, shared, state, 1, &positions).map(|_| None)
+            }
+            None => {
+                track_commanded(shared, state, 1, &positions);
+                controller
+                    .set_stewart_goal(positions, currents)
+                    .map(|_| None)
+            }
+        },
         SetStewartPlatformOperatingMode { mode } => {
             let res = controller.set_stewart_platform_operating_mode(mode);
             if res.is_ok()
@@ -768,31 +4460,660 @@ fn handle_commands(
             tx.send(response)?;
             Ok(None)
         }
+        SetRawPacketGap { gap } => {
+            controller.set_raw_packet_gap(gap);
+            Ok(None)
+        }
+        SpinBody { velocity } => {
+            // The body has no position goal while spinning
+            state.goal.goal = None;
+            controller.spin_body(velocity).map(|_| None)
+        }
+        StopBodySpin() => {
+            controller.stop_body_spin()?;
+            // The body now holds wherever it stopped
+            if let Some(smoother) = smoother {
+                smoother.reset(controller.read_all_positions()?);
+            }
+            Ok(None)
+        }
+        SetAntennasFloppy() => {
+            // The antennas have no position goal while floppy
+            state.goal.goal = None;
+            controller.set_antennas_floppy().map(|_| None)
+        }
+        SetAntennasStiff() => {
+            controller.set_antennas_stiff()?;
+            // The antennas now hold wherever they were left
+            if smoother.is_some() || state.antenna_smoother.is_some() {
+                let positions = controller.read_all_positions()?;
+                if let Some(smoother) = state.smoother.as_mut() {
+                    smoother.reset_joint(7, positions[7]);
+                    smoother.reset_joint(8, positions[8]);
+                }
+                if let Some(smoother) = state.antenna_smoother.as_mut() {
+                    smoother.reset([positions[7], positions[8]]);
+                }
+            }
+            Ok(None)
+        }
+        ReadAntennasVelocity { tx } => {
+            tx.send(controller.read_antennas_velocity())?;
+            Ok(None)
+        }
+        ReadAntennasCurrent { tx } => {
+            tx.send(controller.read_antennas_current())?;
+            Ok(None)
+        }
+        ReadAllTemperatures { tx } => {
+            tx.send(controller.read_all_temperatures())?;
+            Ok(None)
+        }
+        ReadAllCurrents { tx } => {
+            tx.send(controller.read_all_currents())?;
+            Ok(None)
+        }
+        HomeBodyRotation { config, tx } => {
+            state.goal.goal = None;
+            let res = controller.home_body_rotation(config);
+            if res.is_ok() {
+                if let Ok(mut body_yaw) = shared.body_yaw.lock() {
+                    // The body is now at the center of the new frame
+                    *body_yaw = BodyYawTracker::new(0.0);
+                }
+                if let Some(smoother) = smoother {
+                    smoother.reset(controller.read_all_positions()?);
+                }
+            }
+            tx.send(res)?;
+            Ok(None)
+        }
+        SetGoalSmoothing { limits } => {
+            match (limits, smoother.as_mut()) {
+                (Some(limits), Some(smoother)) => smoother.set_limits(*limits),
+                (Some(limits), None) => {
+                    *smoother = Some(GoalSmoother::new(*limits, controller.read_all_positions()?));
+                }
+                (None, _) => *smoother = None,
+            }
+            Ok(None)
+        }
+        EnableSignalShutdown { ramp_duration } => {
+            state.signal_shutdown = Some(ramp_duration);
+            Ok(None)
+        }
+        SetStaleFeedbackTimeout { timeout } => {
+            state.stale_feedback = timeout.map(|timeout| StaleFeedbackPolicy {
+                timeout,
+                last_feedback: shared.clock.now(),
+            });
+            if timeout.is_none() {
+                *lock_or_recover(&shared.feedback_stale, "feedback_stale") = false;
+            }
+            Ok(None)
+        }
+        SetSoftStart { duration } => {
+            state.soft_start = duration;
+            Ok(None)
+        }
+        SetReadFilter { max_velocity } => {
+            state.read_filter = max_velocity.map(ReadFilter::new);
+            Ok(None)
+        }
+        SetAntennaSmoothing { parameters } => {
+            match (parameters, state.antenna_smoother.as_mut()) {
+                (Some((frequency, damping)), Some(smoother)) => {
+                    smoother.set_parameters(frequency, damping)
+                }
+                (Some((frequency, damping)), None) => {
+                    let goals = state
+                        .commanded
+                        .unwrap_or_else(|| current_goals(shared, state));
+                    state.antenna_smoother = Some(AntennaSmoother::new(
+                        frequency,
+                        damping,
+                        [goals[7], goals[8]],
+                    ));
+                    state.antenna_last_step = shared.clock.now();
+                }
+                (None, _) => {
+                    // The antennas go straight to their targets
+                    if let Some(smoother) = state.antenna_smoother.take()
+                        && !smoother.is_settled()
+                    {
+                        drive_joints(controller, shared, state, 7, &smoother.targets())?;
+                    }
+                }
+            }
+            Ok(None)
+        }
+        SetVelocityFeedforward { feedforward } => {
+            state.velocity_feedforward = feedforward;
+            if feedforward.is_none() {
+                write_profile_velocities(controller, state)?;
+            }
+            Ok(None)
+        }
+        SetCurrentBudget { max_total_current } => {
+            state.current_budget_limit = max_total_current;
+            set_current_budget(controller, shared, state)?;
+            Ok(None)
+        }
+        SetSafeMode { limits } => {
+            state.safe_mode = limits;
+            state.profile_velocities_written = false;
+            controller.set_all_profile_velocities([safe_profile_velocity(state); NUM_JOINTS])?;
+            set_current_budget(controller, shared, state)?;
+            if let Some(limits) = limits {
+                // Cap the goal currents already written, and the ones to restore later
+                let currents = controller.read_stewart_platform_goal_current()?;
+                controller.set_stewart_platform_goal_current(limits.goal_currents(currents))?;
+                if let Some(ramp) = state.current_ramp.as_mut() {
+                    ramp.target = limits.goal_currents(ramp.target);
+                }
+                if let Some(limiter) = state.duty_cycle.as_mut()
+                    && let Some(nominal) = limiter.nominal_currents()
+                {
+                    limiter.set_nominal_currents(Some(limits.goal_currents(nominal)));
+                }
+            }
+            Ok(None)
+        }
+        SetDutyCycleLimiter { rating } => {
+            let previous = state.duty_cycle.take();
+            *lock_or_recover(&shared.duty_cycle_loads, "duty_cycle_loads") = [0.0; NUM_JOINTS];
+            if let Some(nominal) = previous.and_then(|limiter| limiter.nominal_currents()) {
+                controller.set_stewart_platform_goal_current(safe_goal_currents(state, nominal))?;
+            }
+            state.duty_cycle = rating.map(|(continuous_current, time_constant)| {
+                DutyCycleLimiter::new(continuous_current, time_constant)
+            });
+            Ok(None)
+        }
+        SetUsageFile { path, tx } => {
+            let res = state
+                .usage
+                .set_path(path, &mut lock_or_recover(&shared.usage, "usage"));
+            tx.send(res.map_err(|e| e.to_string()))?;
+            Ok(None)
+        }
+        SetLastStateFile { path, tx } => {
+            let res = state.last_state.set_path(path);
+            tx.send(res.map_err(|e| e.to_string()))?;
+            Ok(None)
+        }
+        SetReadStrategy { strategy, tx } => {
+            tx.send(controller.set_read_strategy(strategy))?;
+            Ok(None)
+        }
+        AutoTuneReads {
+            return_delay_times,
+            samples,
+            tx,
+        } => {
+            let res = auto_tune_reads(controller, &return_delay_times, samples);
+            if let Ok(tuning) = &res {
+                info!(
+                    "Reads tuned: {} strategy, return delay time {:?}, p99 {:.3} ms",
+                    tuning.best.strategy,
+                    tuning.best.return_delay_time,
+                    tuning.best.p99 * 1000.0
+                );
+            }
+            tx.send(res)?;
+            Ok(None)
+        }
+        SetGoalTolerance { tolerance } => {
+            state.goal.tolerance = tolerance;
+            Ok(None)
+        }
+        PlayTrajectory {
+            mut trajectory,
+            start,
+        } => {
+            check_keyframes(shared, state, &mut trajectory, 0..NUM_JOINTS)?;
+            // Start from the current goals rather than jumping to the first keyframe
+            trajectory = trajectory.starting_from(current_goals(shared, state));
+            state.goal.goal = None;
+            *lock_or_recover(&shared.goal_reached, "goal_reached") = false;
+            let start = loop_instant(shared, start);
+            state.trajectory = Some(TrajectoryPlayer::new(trajectory, start));
+            Ok(None)
+        }
+        PlayTrack {
+            track,
+            mut trajectory,
+            start,
+        } => {
+            check_keyframes(shared, state, &mut trajectory, track.joints())?;
+            let current = state
+                .commanded
+                .unwrap_or_else(|| current_goals(shared, state));
+            trajectory = trajectory.starting_from(current);
+            if track == Track::Head {
+                state.twist = None;
+            }
+            state.goal.goal = None;
+            *lock_or_recover(&shared.goal_reached, "goal_reached") = false;
+            let start = loop_instant(shared, start);
+            state.tracks[track.index()] = Some(TrajectoryPlayer::new(trajectory, start));
+            Ok(None)
+        }
+        StopTrack { track } => {
+            state.tracks[track.index()] = None;
+            Ok(None)
+        }
+        PauseTrajectory() => {
+            if let Some(player) = state.trajectory.as_mut() {
+                player.set_paused(true);
+            }
+            Ok(None)
+        }
+        ResumeTrajectory() => {
+            if let Some(player) = state.trajectory.as_mut() {
+                player.set_paused(false);
+            }
+            Ok(None)
+        }
+        SetTrajectorySpeed { speed } => {
+            if let Some(player) = state.trajectory.as_mut() {
+                player.set_speed(speed);
+            }
+            Ok(None)
+        }
+        StopTrajectory() => {
+            end_trajectory(shared, state, false);
+            Ok(None)
+        }
+        SetHeadTwist { twist } => {
+            end_trajectory(shared, state, false);
+            let now = shared.clock.now();
+            match state.twist.as_mut() {
+                Some(follower) => follower.set_twist(twist, now),
+                None => {
+                    let goals = current_goals(shared, state);
+                    let stewart = goals[1..7].try_into()?;
+                    let pose = match &*lock_or_recover(&shared.kinematics, "kinematics") {
+                        Some(kinematics) => kinematics.forward(&stewart)?,
+                        None => return Err(no_kinematics()),
+                    };
+                    state.goal.goal = None;
+                    *lock_or_recover(&shared.goal_reached, "goal_reached") = false;
+                    state.twist = Some(HeadTwistFollower::new(pose, twist, now));
+                }
+            }
+            Ok(None)
+        }
+        SetGazeYaw { yaw } => {
+            let mut goals = current_goals(shared, state);
+            let bounds = *lock_or_recover(&shared.position_bounds, "position_bounds");
+            let split = *lock_or_recover(&shared.gaze_split, "gaze_split");
+            let (body_yaw, head_yaw) = split.split(yaw, &bounds[0]);
+            let stewart = match &*lock_or_recover(&shared.kinematics, "kinematics") {
+                Some(kinematics) => {
+                    // Keep the rest of the head pose
+                    let mut pose = kinematics.forward(&goals[1..7].try_into()?)?;
+                    pose.rotation[2] = head_yaw;
+                    kinematics.inverse(&pose)?
+                }
+                None => return Err(no_kinematics()),
+            };
+            goals[0] = body_yaw;
+            goals[1..7].copy_from_slice(&stewart);
+            let positions = FullBodyPosition::from_joints(goals, 0.0);
+            handle_commands(controller, shared, state, SetAllGoalPositions { positions })
+        }
+        SetHeadPose { pose, body_yaw } => {
+            let mut goals = current_goals(shared, state);
+            let stewart = match &*lock_or_recover(&shared.kinematics, "kinematics") {
+                Some(kinematics) => kinematics.inverse(&pose)?,
+                None => return Err(no_kinematics()),
+            };
+            if let Some(body_yaw) = body_yaw {
+                goals[0] = body_yaw;
+            }
+            goals[1..7].copy_from_slice(&stewart);
+            let positions = FullBodyPosition::from_joints(goals, 0.0);
+            handle_commands(controller, shared, state, SetAllGoalPositions { positions })
+        }
+        WithDeadline { deadline, command } => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or(Duration::from_secs(0))
+                .as_secs_f64();
+            if now > deadline {
+                log::debug!(
+                    "Dropping stale command ({:.3}s late): {:?}",
+                    now - deadline,
+                    command
+                );
+                *lock_or_recover(&shared.stale_commands, "stale_commands") += 1;
+                return Ok(None);
+            }
+            handle_commands(controller, shared, state, *command)
+        }
+        FromSource { source, command } => {
+            let now = shared.clock.now();
+            if !lock_or_recover(&shared.arbiter, "arbiter").accept(&source, now) {
+                log::debug!(
+                    "Dropping command from source {:?} (preempted or unknown): {:?}",
+                    source,
+                    command
+                );
+                return Ok(None);
+            }
+            handle_commands(controller, shared, state, *command)
+        }
+    };
+    if res.is_ok() {
+        let mut events = lock_or_recover(&shared.events, "events");
+        if let Some(TorqueChange::Joints(joints, enable)) = &torque_change {
+            events.record(
+                "torque",
+                format!(
+                    "Torque {} on {}",
+                    if *enable { "enabled" } else { "disabled" },
+                    describe_joints(joints)
+                ),
+            );
+        }
+        if let Some(message) = mode_change {
+            events.record("mode", message);
+        }
+    }
+    state.torque.command_handled(torque_change, res.is_ok());
+    publish_trajectory(shared, state);
+    res
+}
+
+/// Journal message of the commands changing an operating mode.
+/// Name of the variant of `command`, for the spans and the overrun warnings.
+fn command_name(command: &MotorCommand) -> String {
+    // Stops the Debug formatting at the end of the name, the fields are not formatted
+    struct VariantName(String);
+    impl std::fmt::Write for VariantName {
+        fn write_str(&mut self, s: &str) -> std::fmt::Result {
+            match s.find(|c: char| !c.is_alphanumeric()) {
+                Some(end) => {
+                    self.0.push_str(&s[..end]);
+                    Err(std::fmt::Error)
+                }
+                None => {
+                    self.0.push_str(s);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    let mut name = VariantName(String::new());
+    let _ = std::fmt::Write::write_fmt(&mut name, format_args!("{:?}", command));
+    name.0
+}
+
+fn mode_change(command: &MotorCommand) -> Option<String> {
+    use MotorCommand::*;
+
+    match command {
+        SetStewartPlatformOperatingMode { mode } => {
+            Some(format!("Stewart platform operating mode set to {}", mode))
+        }
+        SetAntennasOperatingMode { mode } => {
+            Some(format!("Antennas operating mode set to {}", mode))
+        }
+        SetBodyRotationOperatingMode { mode } => {
+            Some(format!("Body rotation operating mode set to {}", mode))
+        }
+        _ => None,
+    }
+}
+
+fn describe_joints(joints: &[usize]) -> String {
+    if joints.len() == NUM_JOINTS {
+        return "all joints".to_string();
+    }
+    joints
+        .iter()
+        .map(|&joint| JOINT_NAMES[joint])
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn torque_change(
+    command: &MotorCommand,
+    controller: &ReachyMiniMotorController,
+) -> Option<TorqueChange> {
+    use MotorCommand::*;
+    use TorqueChange::*;
+
+    let joints_of = |ids: &[u8]| {
+        ids.iter()
+            .filter_map(|id| controller.joint_index(*id))
+            .collect()
+    };
+    match command {
+        EnableTorque() => Some(Joints((0..NUM_JOINTS).collect(), true)),
+        DisableTorque() => Some(Joints((0..NUM_JOINTS).collect(), false)),
+        EnableTorqueOnIds { ids } => Some(Joints(joints_of(ids), true)),
+        DisableTorqueOnIds { ids } => Some(Joints(joints_of(ids), false)),
+        EnableBodyRotation { enable } => Some(Joints(vec![0], *enable)),
+        EnableStewartPlatform { enable } => Some(Joints((1..7).collect(), *enable)),
+        EnableAntennas { enable } => Some(Joints(vec![7, 8], *enable)),
+        SetAntennasFloppy() => Some(Joints(vec![7, 8], false)),
+        SetAntennasStiff() => Some(Joints(vec![7, 8], true)),
+        HomeBodyRotation { .. } | WriteRawBytes { .. } | WriteRawPacket { .. } => Some(Unknown),
+        _ => None,
+    }
+}
+
+/// Apply the intended torque and stewart platform operating mode again where the
+/// motors lost them, which happens when a motor reboots after a hardware error.
+fn reconcile_torque(
+    controller: &mut ReachyMiniMotorController,
+    shared: &SharedState,
+    state: &mut LoopState,
+) -> Result<(), MotorControllerError> {
+    let mut actual = controller.read_all_torque_enabled()?;
+    let Some(intended) = state.torque.intended else {
+        state.torque.intended = Some(actual);
+        return Ok(());
+    };
+
+    let expected_mode = lock_or_recover(&shared.last_control_mode, "last_control_mode").clone();
+    if let Ok(expected_mode) = expected_mode {
+        let modes = controller.read_stewart_platform_operating_mode()?;
+        if modes.iter().any(|mode| *mode != expected_mode) {
+            warn!(
+                "Stewart platform operating modes {:?} differ from {}, applying it again",
+                modes, expected_mode
+            );
+            lock_or_recover(&shared.events, "events").record(
+                "mode",
+                format!(
+                    "Stewart platform operating modes {:?} differ from {}, applied again",
+                    modes, expected_mode
+                ),
+            );
+            // The operating mode can only be written with the torque off
+            controller.enable_stewart_platform(false)?;
+            actual[1..7].fill(false);
+            controller.set_stewart_platform_operating_mode(expected_mode)?;
+        }
+    }
+
+    if actual != intended {
+        let joints: Vec<usize> = (0..NUM_JOINTS)
+            .filter(|&i| actual[i] != intended[i])
+            .collect();
+        warn!(
+            "Torque of {} differs from the intended state (motor reboot?), applying it again",
+            describe_joints(&joints)
+        );
+        lock_or_recover(&shared.events, "events").record(
+            "torque",
+            format!(
+                "Torque of {} differs from the intended state, applied again",
+                describe_joints(&joints)
+            ),
+        );
+        controller.set_all_torque(intended)?;
+    }
+    Ok(())
+}
+
+/// Check that the motors came back as they were before accepting motion commands again.
+fn recover_from_power_loss(
+    controller: &mut ReachyMiniMotorController,
+    shared: &SharedState,
+    state: &mut LoopState,
+) -> Result<(), MotorControllerError> {
+    let missing = controller.check_missing_ids()?;
+    if !missing.is_empty() {
+        return Err(MotorControllerError::MotorNotResponding { id: missing[0] });
+    }
+    let expected_mode = lock_or_recover(&shared.last_control_mode, "last_control_mode").clone();
+    if let Ok(expected_mode) = expected_mode {
+        let modes = controller.read_stewart_platform_operating_mode()?;
+        if modes.iter().any(|mode| *mode != expected_mode) {
+            return Err(MotorControllerError::Other(format!(
+                "Stewart platform operating modes {:?} differ from {}",
+                modes, expected_mode
+            )));
+        }
+    }
+
+    // RAM registers (torque, goals) were reset, start again from where the motors are
+    let torque = controller.is_torque_enabled()?;
+    *lock_or_recover(&shared.last_torque, "last_torque") = Ok(torque);
+    state.torque.intended = None;
+    state.goal.goal = None;
+    if let Some(smoother) = state.smoother.as_mut() {
+        smoother.reset(controller.read_all_positions()?);
+    }
+    if state.safe_mode.is_some() {
+        state.profile_velocities_written = false;
+        controller.set_all_profile_velocities([safe_profile_velocity(state); NUM_JOINTS])?;
+    }
+    Ok(())
+}
+
+/// A command bypassing the safe mode limits received while it is enabled.
+fn safe_mode_rejected(command: &MotorCommand) -> MotorControllerError {
+    MotorControllerError::Rejected(format!("Safe mode is enabled, rejecting {:?}", command))
+}
+
+/// A head pose command received while no kinematics is set.
+fn no_kinematics() -> MotorControllerError {
+    MotorControllerError::Rejected(MotorError::NoKinematicsError().to_string())
+}
+
+/// SIGINT/SIGTERM listeners (Ctrl-C on Windows).
+struct ShutdownSignals {
+    #[cfg(unix)]
+    interrupt: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+    #[cfg(windows)]
+    ctrl_c: tokio::signal::windows::CtrlC,
+}
+
+impl ShutdownSignals {
+    #[cfg(unix)]
+    fn new() -> std::io::Result<Self> {
+        use tokio::signal::unix::{SignalKind, signal};
+        Ok(ShutdownSignals {
+            interrupt: signal(SignalKind::interrupt())?,
+            terminate: signal(SignalKind::terminate())?,
+        })
+    }
+
+    #[cfg(windows)]
+    fn new() -> std::io::Result<Self> {
+        Ok(ShutdownSignals {
+            ctrl_c: tokio::signal::windows::ctrl_c()?,
+        })
+    }
+
+    /// Wait for a signal, returning its name and the matching process exit code.
+    #[cfg(unix)]
+    async fn recv(&mut self) -> (&'static str, i32) {
+        tokio::select! {
+            _ = self.interrupt.recv() => ("SIGINT", 128 + 2),
+            _ = self.terminate.recv() => ("SIGTERM", 128 + 15),
+        }
+    }
+
+    #[cfg(windows)]
+    async fn recv(&mut self) -> (&'static str, i32) {
+        self.ctrl_c.recv().await;
+        ("Ctrl-C", 130)
+    }
+}
+
+/// Wait for a shutdown signal, forever if the signal handling is not enabled.
+async fn recv_signal(signals: &mut Option<ShutdownSignals>) -> (&'static str, i32) {
+    match signals {
+        Some(signals) => signals.recv().await,
+        None => std::future::pending().await,
     }
 }
 
+/// Wrap an angle (rad) to [-pi, pi].
+fn wrap_angle(angle: f64) -> f64 {
+    let wrapped = (angle + std::f64::consts::PI).rem_euclid(2.0 * std::f64::consts::PI);
+    wrapped - std::f64::consts::PI
+}
+
 pub fn read_pos(
     c: &mut ReachyMiniMotorController,
     read_allowed_retries: u64,
 ) -> Result<FullBodyPosition, MotorError> {
-    with_retry(|| c.read_all_positions(), read_allowed_retries)
-        .map(|positions| {
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_else(|_| std::time::Duration::from_secs(0));
-            FullBodyPosition {
-                body_yaw: positions[0],
-                stewart: [
-                    positions[1],
-                    positions[2],
-                    positions[3],
-                    positions[4],
-                    positions[5],
-                    positions[6],
-                ],
-                antennas: [positions[7], positions[8]],
-                timestamp: now.as_secs_f64(),
-            }
-        })
-        .map_err(|_| MotorError::CommunicationError())
+    try_read_pos(c, read_allowed_retries).map_err(|_| MotorError::CommunicationError())
+}
+
+/// Read the `due` joints only, the others keep their `previous` position.
+fn try_read_scheduled(
+    c: &mut ReachyMiniMotorController,
+    read_allowed_retries: u64,
+    due: [bool; NUM_JOINTS],
+    previous: &FullBodyPosition,
+) -> Result<FullBodyPosition, MotorControllerError> {
+    if due.iter().all(|&due| due) {
+        return try_read_pos(c, read_allowed_retries);
+    }
+    let joints: Vec<usize> = (0..NUM_JOINTS).filter(|&joint| due[joint]).collect();
+    if joints.is_empty() {
+        return Ok(*previous);
+    }
+    let read = with_retry(|| c.read_joint_positions(&joints), read_allowed_retries)?;
+    let mut positions = previous.joints();
+    for (joint, position) in joints.into_iter().zip(read) {
+        positions[joint] = position;
+    }
+    Ok(FullBodyPosition {
+        timestamp: wall_now(),
+        monotonic_timestamp: monotonic_now(),
+        ..FullBodyPosition::from_joints(positions, 0.0)
+    })
+}
+
+/// Same as [`read_pos`] but keeps the underlying error, for logging.
+fn try_read_pos(
+    c: &mut ReachyMiniMotorController,
+    read_allowed_retries: u64,
+) -> Result<FullBodyPosition, MotorControllerError> {
+    with_retry(|| c.read_all_positions(), read_allowed_retries).map(|positions| FullBodyPosition {
+        body_yaw: positions[0],
+        stewart: [
+            positions[1],
+            positions[2],
+            positions[3],
+            positions[4],
+            positions[5],
+            positions[6],
+        ],
+        antennas: [positions[7], positions[8]],
+        timestamp: wall_now(),
+        monotonic_timestamp: monotonic_now(),
+    })
 }
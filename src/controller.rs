@@ -1,43 +1,446 @@
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    time::Duration,
+};
 
-use log::warn;
+use log::{info, warn};
 use rustypot::servo::dynamixel::xl330;
+use serialport::SerialPort;
 
-pub struct ReachyMiniMotorController {
+use crate::{
+    MotorControllerError,
+    config_audit::{
+        AUDIT_LENGTH, AUDIT_START_ADDRESS, ConfigDeviation, ConfigProfile, audit_registers,
+        restore_write,
+    },
+    dry_run::DryRunLog,
+    emergency::EmergencyStop,
+    fast_sync_read::fast_sync_read_present_position,
+    firmware::{Feature, FirmwareInfo},
+    port_lock::PortLock,
+    read_schedule::GROUPS,
+    read_tuning::ReadStrategy,
+    transport::{SerialTransport, Transport, TransportPort},
+};
+
+/// Controller of the 9 motors of Reachy Mini, over the serial port by default (see
+/// [`crate::transport`] for the other transports).
+pub struct ReachyMiniMotorController<T: Transport = SerialTransport> {
     dph_v2: rustypot::DynamixelProtocolHandler,
-    serial_port: Box<dyn serialport::SerialPort>,
+    port: TransportPort<T>,
     all_ids: [u8; 9],
+    /// Motors left out of the bus transactions, see [`Self::set_missing_ids`].
+    missing_ids: Vec<u8>,
+    firmware: HashMap<u8, FirmwareInfo>,
+    body_rotation_mode: Option<u8>,
+    /// Bus silence kept around the raw packets, see [`Self::set_raw_packet_gap`].
+    raw_packet_gap: Duration,
+    /// Where the writes are recorded instead of sent, see [`Self::set_dry_run`].
+    dry_run: Option<DryRunLog>,
+    /// How the positions are read, see [`Self::set_read_strategy`].
+    read_strategy: ReadStrategy,
+    /// Path of the port, to reopen it (`None` if given an opened port).
+    port_name: Option<String>,
+    baud_rate: u32,
+    /// Held as long as the port is open.
+    _port_lock: Option<PortLock>,
+    /// Disables the torque if the process panics.
+    _emergency_stop: Option<EmergencyStop>,
 }
 
 const ANTENNAS_IDS: [u8; 2] = [17, 18]; // Right and Left antennas
 const STEWART_PLATFORM_IDS: [u8; 6] = [11, 12, 13, 14, 15, 16];
 const BODY_ROTATION_ID: u8 = 10;
+/// Baud rate of the motors bus, as configured at the factory.
+pub const DEFAULT_BAUD_RATE: u32 = 1_000_000;
+/// Ids of all the motors, in joint order.
+pub(crate) const ALL_IDS: [u8; 9] = [
+    BODY_ROTATION_ID,
+    STEWART_PLATFORM_IDS[0],
+    STEWART_PLATFORM_IDS[1],
+    STEWART_PLATFORM_IDS[2],
+    STEWART_PLATFORM_IDS[3],
+    STEWART_PLATFORM_IDS[4],
+    STEWART_PLATFORM_IDS[5],
+    ANTENNAS_IDS[0],
+    ANTENNAS_IDS[1],
+];
+
+// https://emanual.robotis.com/docs/en/dxl/x/xl330-m288/#operating-mode
+const VELOCITY_CONTROL_MODE: u8 = 1;
+const POSITION_CONTROL_MODE: u8 = 3;
+// https://emanual.robotis.com/docs/en/dxl/x/xl330-m288/#goal-velocity
+const VELOCITY_UNIT_RPM: f64 = 0.229;
+// https://emanual.robotis.com/docs/en/dxl/x/xl330-m288/#homing-offset
+const POSITION_UNIT_RAD: f64 = 2.0 * std::f64::consts::PI / 4096.0;
+/// Number of bytes of the robot identity, see [`ReachyMiniMotorController::write_identity`].
+pub const IDENTITY_SIZE: usize = ALL_IDS.len() - 1;
+
+// https://emanual.robotis.com/docs/en/dxl/x/xl330-m288/#control-table-of-eeprom-area
+const EEPROM_END: u8 = 64;
+const ID_ADDRESS: u8 = 7;
+const BAUD_RATE_ADDRESS: u8 = 8;
+/// Attempts of an EEPROM write before giving up, see
+/// [`ReachyMiniMotorController::write_eeprom_verified`].
+const EEPROM_WRITE_ATTEMPTS: usize = 3;
+/// Wait between two attempts, to let a brown-out pass.
+const EEPROM_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Failure of some groups of a goal write, see
+/// [`ReachyMiniMotorController::set_all_goal_positions_by_group`].
+#[derive(Debug, Clone)]
+pub struct GroupWriteError {
+    /// Name of each failed group ("body_rotation", "stewart_platform" or "antennas") and its error.
+    pub failures: Vec<(&'static str, MotorControllerError)>,
+}
+
+impl std::error::Error for GroupWriteError {}
+impl std::fmt::Display for GroupWriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Goal write failed for")?;
+        for (i, (group, e)) in self.failures.iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(f, "{} {} ({})", sep, group, e)?;
+        }
+        Ok(())
+    }
+}
+
+/// A value that never read back as written, see
+/// [`ReachyMiniMotorController::write_eeprom_verified`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct WriteVerificationError {
+    /// What was written, e.g. "stewart platform operating mode".
+    pub register: String,
+    /// Value written.
+    pub expected: String,
+    /// Value read back by the last attempt, `None` if it could not be read.
+    pub read_back: Option<String>,
+    /// Communication error of the last attempt, if any.
+    pub error: Option<String>,
+    pub attempts: usize,
+}
+
+impl std::error::Error for WriteVerificationError {}
+impl std::fmt::Display for WriteVerificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to persist the {} ({}) in EEPROM after {} attempts: ",
+            self.register, self.expected, self.attempts
+        )?;
+        match (&self.read_back, &self.error) {
+            (Some(value), _) => write!(f, "read back {}", value),
+            (None, Some(e)) => write!(f, "{}", e),
+            (None, None) => write!(f, "nothing read back"),
+        }
+    }
+}
+
+/// Parameters of the body rotation end-stop homing routine.
+#[derive(Debug, Clone, Copy)]
+pub struct HomingConfig {
+    /// Rotation velocity used to look for the mechanical stops (rad/s).
+    pub velocity: f64,
+    /// Absolute present current (mA) above which the stop is considered reached.
+    pub current_threshold: i16,
+    /// Maximum duration to find each stop.
+    pub timeout: Duration,
+}
+
+impl Default for HomingConfig {
+    fn default() -> Self {
+        Self {
+            velocity: 0.3,
+            current_threshold: 300,
+            timeout: Duration::from_secs(20),
+        }
+    }
+}
 
 impl ReachyMiniMotorController {
-    pub fn new(serialport: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let dph_v2 = rustypot::DynamixelProtocolHandler::v2();
-
-        let serial_port = serialport::new(serialport, 1_000_000)
-            .timeout(Duration::from_millis(10))
-            .open()?;
-
-        let all_ids = [
-            BODY_ROTATION_ID,
-            STEWART_PLATFORM_IDS[0],
-            STEWART_PLATFORM_IDS[1],
-            STEWART_PLATFORM_IDS[2],
-            STEWART_PLATFORM_IDS[3],
-            STEWART_PLATFORM_IDS[4],
-            STEWART_PLATFORM_IDS[5],
-            ANTENNAS_IDS[0],
-            ANTENNAS_IDS[1],
-        ];
-
-        Ok(Self {
-            dph_v2,
-            serial_port,
-            all_ids,
-        })
+    pub fn new(serialport: &str) -> Result<Self, MotorControllerError> {
+        Self::with_baud_rate(serialport, DEFAULT_BAUD_RATE)
+    }
+
+    /// Like [`Self::new`], for motors configured with another baud rate.
+    pub fn with_baud_rate(serialport: &str, baud_rate: u32) -> Result<Self, MotorControllerError> {
+        let (serial_port, port_lock) = open_port(serialport, baud_rate)?;
+        let emergency_stop = register_emergency_stop(serial_port.as_ref());
+
+        let mut controller = Self::with_port(serial_port);
+        controller.port_name = Some(serialport.to_string());
+        controller.baud_rate = baud_rate;
+        controller._port_lock = Some(port_lock);
+        controller._emergency_stop = emergency_stop;
+        Ok(controller)
+    }
+
+    /// Drive the motors through an already opened port, e.g. a
+    /// [`crate::simulation::SimulatedPort`].
+    ///
+    /// Unlike [`Self::new`], the port is neither locked nor registered for the emergency stop.
+    pub fn with_port(serial_port: Box<dyn serialport::SerialPort>) -> Self {
+        Self::with_transport(serial_port)
+    }
+
+    /// Close and open the serial port again, e.g. after a USB reset.
+    ///
+    /// The port is retried until it is back or `timeout` expires, as the device takes
+    /// some time to show up again. Only available if the controller was created from
+    /// a port name (see [`Self::new`]).
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn reopen(&mut self, timeout: Duration) -> Result<(), MotorControllerError> {
+        const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+        let Some(port_name) = self.port_name.clone() else {
+            return Err(MotorControllerError::Unsupported(
+                "The port was given already opened, it cannot be reopened".to_string(),
+            ));
+        };
+        // The lock must be released first, as we take it again
+        self._emergency_stop = None;
+        self._port_lock = None;
+
+        let start = std::time::Instant::now();
+        let (serial_port, port_lock) = loop {
+            match open_port(&port_name, self.baud_rate) {
+                Ok(opened) => break opened,
+                Err(e) if start.elapsed() >= timeout => {
+                    warn!("Could not reopen {}: {}", port_name, e);
+                    return Err(e);
+                }
+                Err(_) => std::thread::sleep(RETRY_DELAY),
+            }
+        };
+        self._emergency_stop = register_emergency_stop(serial_port.as_ref());
+        self.port = TransportPort(serial_port);
+        self._port_lock = Some(port_lock);
+        // The motors may have been power cycled as well
+        self.body_rotation_mode = None;
+        info!("Serial port {} reopened", port_name);
+
+        Ok(())
+    }
+
+    /// Run `op`, and if it failed because the port was lost (e.g. a USB reset),
+    /// reopen the port within `timeout` and run `op` once more.
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # let mut controller = reachy_mini_motor_controller::ReachyMiniMotorController::new("/dev/ttyACM0")?;
+    /// let positions =
+    ///     controller.with_auto_reopen(Duration::from_secs(2), |c| c.read_all_positions())?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_auto_reopen<T>(
+        &mut self,
+        timeout: Duration,
+        mut op: impl FnMut(&mut Self) -> Result<T, MotorControllerError>,
+    ) -> Result<T, MotorControllerError> {
+        match op(self) {
+            Err(e) if self.port_name.is_some() && self.is_port_lost(&e) => {
+                warn!("Serial port lost ({}), reopening it", e);
+                self.reopen(timeout)?;
+                op(self)
+            }
+            res => res,
+        }
+    }
+
+    /// Whether `e` comes from the port itself rather than from the motors.
+    fn is_port_lost(&self, e: &MotorControllerError) -> bool {
+        let vanished = self
+            .port_name
+            .as_ref()
+            .is_some_and(|port| !std::path::Path::new(port).exists());
+        let port_failure = match e {
+            MotorControllerError::SerialIo(_) => !e.is_transient(),
+            MotorControllerError::SerialPort(e) => {
+                matches!(e.kind(), serialport::ErrorKind::NoDevice)
+            }
+            _ => false,
+        };
+        vanished || port_failure
+    }
+}
+
+impl<T: Transport> ReachyMiniMotorController<T> {
+    /// Drive the motors through another [`Transport`] than the serial port.
+    ///
+    /// Like with [`ReachyMiniMotorController::with_port`], the transport is neither locked
+    /// nor registered for the emergency stop, and cannot be reopened.
+    pub fn with_transport(transport: T) -> Self {
+        Self {
+            dph_v2: rustypot::DynamixelProtocolHandler::v2(),
+            port: TransportPort(transport),
+            all_ids: ALL_IDS,
+            missing_ids: Vec::new(),
+            firmware: HashMap::new(),
+            body_rotation_mode: None,
+            raw_packet_gap: Duration::ZERO,
+            dry_run: None,
+            read_strategy: ReadStrategy::default(),
+            port_name: None,
+            baud_rate: DEFAULT_BAUD_RATE,
+            _port_lock: None,
+            _emergency_stop: None,
+        }
+    }
+
+    /// Run rustypot calls not wrapped by this crate on the bus of the controller, with its
+    /// protocol v2 handler and port (held under the port lock).
+    ///
+    /// Combined with [`Self::with_auto_reopen`], the port is reopened if it is lost:
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// use reachy_mini_motor_controller::rustypot::servo::dynamixel::xl330;
+    /// # let mut controller = reachy_mini_motor_controller::ReachyMiniMotorController::new("/dev/ttyACM0")?;
+    /// let temperatures = controller.with_auto_reopen(Duration::from_secs(2), |c| {
+    ///     c.with_bus(|dph, port| Ok(xl330::sync_read_present_temperature(dph, port, &[11, 12])?))
+    /// })?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn with_bus<R>(
+        &mut self,
+        op: impl FnOnce(
+            &rustypot::DynamixelProtocolHandler,
+            &mut dyn serialport::SerialPort,
+        ) -> Result<R, Box<dyn std::error::Error>>,
+    ) -> Result<R, MotorControllerError> {
+        Ok(op(&self.dph_v2, &mut self.port)?)
+    }
+
+    /// Drive a reduced motor set: the `ids` (e.g. a dead or removed antenna) are left out
+    /// of the sync reads and writes and of [`Self::check_missing_ids`], so the other
+    /// motors keep working.
+    ///
+    /// Their reads give default values (0, false) and their writes are dropped. The
+    /// operations on the body rotation motor alone still fail if it is missing.
+    pub fn set_missing_ids(&mut self, ids: &[u8]) {
+        self.missing_ids = ids.to_vec();
+    }
+
+    /// Motors left out of the bus transactions.
+    pub fn missing_ids(&self) -> &[u8] {
+        &self.missing_ids
+    }
+
+    /// Record the writes in `log` instead of sending them to the motors, `None` to write
+    /// them again, see [`crate::dry_run`].
+    ///
+    /// The write methods still validate their arguments and fail as usual, the reads
+    /// still go to the bus. The homing routine, which needs the body to move, fails and
+    /// [`Self::with_bus`] calls are not intercepted.
+    pub fn set_dry_run(&mut self, log: Option<DryRunLog>) {
+        if log.is_some() {
+            info!("Dry-run mode: the writes are recorded, not sent to the motors");
+        }
+        self.dry_run = log;
+    }
+
+    /// The log of the writes in dry-run mode, `None` if they are sent to the motors.
+    pub fn dry_run(&self) -> Option<&DryRunLog> {
+        self.dry_run.as_ref()
+    }
+
+    /// Read the positions with `strategy` from now on, see [`crate::read_tuning`].
+    ///
+    /// The fast sync read needs a recent firmware on all the motors, see
+    /// [`Feature::FastSyncRead`].
+    pub fn set_read_strategy(
+        &mut self,
+        strategy: ReadStrategy,
+    ) -> Result<(), MotorControllerError> {
+        if strategy == ReadStrategy::FastSync {
+            self.require(Feature::FastSyncRead)?;
+        }
+        self.read_strategy = strategy;
+        Ok(())
+    }
+
+    pub fn read_strategy(&self) -> ReadStrategy {
+        self.read_strategy
+    }
+
+    /// In dry-run mode, record the write of `values` to the `register` of `ids` and
+    /// return true, the caller must then skip the write.
+    fn record_dry_run(&self, register: &str, ids: &[u8], values: &impl std::fmt::Debug) -> bool {
+        match &self.dry_run {
+            Some(log) => {
+                log.record(register, ids, format!("{:?}", values));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sync read the present motors of `ids`, `fill` standing for the missing ones.
+    fn sync_read_present<V: Copy>(
+        &mut self,
+        ids: &[u8],
+        fill: V,
+        read: impl FnOnce(
+            &rustypot::DynamixelProtocolHandler,
+            &mut dyn serialport::SerialPort,
+            &[u8],
+        ) -> Result<Vec<V>, Box<dyn std::error::Error>>,
+    ) -> Result<Vec<V>, MotorControllerError> {
+        if self.missing_ids.is_empty() {
+            return Ok(read(&self.dph_v2, &mut self.port, ids)?);
+        }
+        let present: Vec<u8> = ids
+            .iter()
+            .copied()
+            .filter(|id| !self.missing_ids.contains(id))
+            .collect();
+        let mut values = match present.is_empty() {
+            true => Vec::new(),
+            false => read(&self.dph_v2, &mut self.port, &present)?,
+        }
+        .into_iter();
+        Ok(ids
+            .iter()
+            .map(|id| match self.missing_ids.contains(id) {
+                true => fill,
+                false => values.next().unwrap_or(fill),
+            })
+            .collect())
+    }
+
+    /// Sync write the `values` of the present motors of `ids`, recorded as writes of
+    /// `register` in dry-run mode.
+    fn sync_write_present<V: Copy + std::fmt::Debug>(
+        &mut self,
+        register: &str,
+        ids: &[u8],
+        values: &[V],
+        write: impl FnOnce(
+            &rustypot::DynamixelProtocolHandler,
+            &mut dyn serialport::SerialPort,
+            &[u8],
+            &[V],
+        ) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), MotorControllerError> {
+        if self.missing_ids.is_empty() {
+            if self.record_dry_run(register, ids, &values) {
+                return Ok(());
+            }
+            return Ok(write(&self.dph_v2, &mut self.port, ids, values)?);
+        }
+        let (present, values): (Vec<u8>, Vec<V>) = ids
+            .iter()
+            .zip(values)
+            .filter(|(id, _)| !self.missing_ids.contains(id))
+            .map(|(id, value)| (*id, *value))
+            .unzip();
+        if present.is_empty() || self.record_dry_run(register, &present, &values) {
+            return Ok(());
+        }
+        Ok(write(&self.dph_v2, &mut self.port, &present, &values)?)
     }
 
     pub fn get_motor_name_id(&self) -> HashMap<String, u8> {
@@ -54,19 +457,18 @@ impl ReachyMiniMotorController {
         motor_id_name
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     pub fn reboot(
         &mut self,
         on_error_status_only: bool,
         reboot_timeout: Duration,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), MotorControllerError> {
         let mut error_status = Vec::new();
 
         if on_error_status_only {
-            error_status = xl330::sync_read_hardware_error_status(
-                &self.dph_v2,
-                self.serial_port.as_mut(),
-                &self.all_ids,
-            )?;
+            error_status = self.sync_read_present(&ALL_IDS, 0, |dph, port, ids| {
+                xl330::sync_read_hardware_error_status(dph, port, ids)
+            })?;
         }
 
         let faulty_ids: Vec<u8> = if on_error_status_only {
@@ -83,7 +485,11 @@ impl ReachyMiniMotorController {
                 })
                 .collect()
         } else {
-            self.all_ids.to_vec()
+            self.all_ids
+                .iter()
+                .copied()
+                .filter(|id| !self.missing_ids.contains(id))
+                .collect()
         };
 
         let name2id = self.get_motor_name_id();
@@ -93,23 +499,25 @@ impl ReachyMiniMotorController {
         for id in &faulty_ids {
             let name = id2name.get(id).unwrap();
             warn!("Rebooting motor {} (id={})", name, id);
-            self.dph_v2.reboot(self.serial_port.as_mut(), *id as u8)?;
+            if !self.record_dry_run("reboot", &[*id], &()) {
+                self.dph_v2.reboot(&mut self.port, *id)?;
+            }
+        }
+        if self.dry_run.is_some() {
+            return Ok(());
         }
 
         let mut missing_ids = faulty_ids.clone();
         let start_time = std::time::Instant::now();
         while !missing_ids.is_empty() && start_time.elapsed() < reboot_timeout {
             std::thread::sleep(Duration::from_millis(100));
-            missing_ids = missing_ids
-                .into_iter()
-                .filter(|id| {
-                    let ping_result = self.dph_v2.ping(self.serial_port.as_mut(), *id);
-                    match ping_result {
-                        Ok(res) => !res,
-                        Err(_) => true,
-                    }
-                })
-                .collect();
+            missing_ids.retain(|id| {
+                let ping_result = self.dph_v2.ping(&mut self.port, *id);
+                match ping_result {
+                    Ok(res) => !res,
+                    Err(_) => true,
+                }
+            });
         }
         for id in &missing_ids {
             let name = id2name.get(id).unwrap();
@@ -122,23 +530,22 @@ impl ReachyMiniMotorController {
         if missing_ids.is_empty() {
             Ok(())
         } else {
-            let names = missing_ids
-                .iter()
-                .map(|id| id2name.get(id).unwrap().to_string())
-                .collect::<Vec<String>>();
-            Err(format!(
-                "Some motors did not respond after reboot ({:?} - ids: {:?})",
-                names, missing_ids
-            )
-            .into())
+            // Each one is named in the warnings above
+            Err(MotorControllerError::MotorNotResponding { id: missing_ids[0] })
         }
     }
 
-    pub fn check_missing_ids(&mut self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn check_missing_ids(&mut self) -> Result<Vec<u8>, MotorControllerError> {
         let mut missing_ids = Vec::new();
 
-        for id in self.all_ids {
-            if xl330::read_id(&self.dph_v2, self.serial_port.as_mut(), id).is_err() {
+        // Already left out, see Self::set_missing_ids
+        for id in self
+            .all_ids
+            .into_iter()
+            .filter(|id| !self.missing_ids.contains(id))
+        {
+            if xl330::read_id(&self.dph_v2, &mut self.port, id).is_err() {
                 missing_ids.push(id);
             }
         }
@@ -146,83 +553,368 @@ impl ReachyMiniMotorController {
         Ok(missing_ids)
     }
 
+    /// Read the model number and firmware version of all servos.
+    /// The result is kept so features can be gated with [`Self::supports`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn detect_firmware(&mut self) -> Result<HashMap<u8, FirmwareInfo>, MotorControllerError> {
+        let present: Vec<u8> = self
+            .all_ids
+            .iter()
+            .copied()
+            .filter(|id| !self.missing_ids.contains(id))
+            .collect();
+        let models = xl330::sync_read_model_number(&self.dph_v2, &mut self.port, &present)?;
+        let versions = xl330::sync_read_firmware_version(&self.dph_v2, &mut self.port, &present)?;
+
+        if models.len() != present.len() || versions.len() != present.len() {
+            return Err(MotorControllerError::UnexpectedResponse(
+                "Invalid firmware info length: expected 9 elements".to_string(),
+            ));
+        }
+
+        self.firmware = present
+            .iter()
+            .zip(models.iter().zip(versions.iter()))
+            .map(|(&id, (&model_number, &firmware_version))| {
+                (
+                    id,
+                    FirmwareInfo {
+                        model_number,
+                        firmware_version,
+                    },
+                )
+            })
+            .collect();
+
+        Ok(self.firmware.clone())
+    }
+
+    /// Firmware info of all servos, as read by the last [`Self::detect_firmware`] call.
+    pub fn firmware_info(&self) -> &HashMap<u8, FirmwareInfo> {
+        &self.firmware
+    }
+
+    /// Whether all servos run a firmware supporting the given feature.
+    /// Returns false if the firmware versions have not been detected yet.
+    pub fn supports(&self, feature: Feature) -> bool {
+        !self.firmware.is_empty() && self.firmware.values().all(|info| info.supports(feature))
+    }
+
+    /// Same as [`Self::supports`] but returns an explicit error naming the outdated motors.
+    pub fn require(&self, feature: Feature) -> Result<(), MotorControllerError> {
+        if self.firmware.is_empty() {
+            return Err(MotorControllerError::Unsupported(format!(
+                "Cannot use {}: motor firmware versions have not been detected",
+                feature
+            )));
+        }
+
+        let id2name: HashMap<u8, String> = self
+            .get_motor_name_id()
+            .into_iter()
+            .map(|(name, id)| (id, name))
+            .collect();
+        let mut outdated: Vec<String> = self
+            .firmware
+            .iter()
+            .filter(|(_, info)| !info.supports(feature))
+            .map(|(id, info)| {
+                format!(
+                    "{} (id={}, v{})",
+                    id2name.get(id).map(String::as_str).unwrap_or("unknown"),
+                    id,
+                    info.firmware_version
+                )
+            })
+            .collect();
+        outdated.sort();
+
+        if outdated.is_empty() {
+            Ok(())
+        } else {
+            Err(MotorControllerError::Unsupported(format!(
+                "{} requires firmware v{} or newer, outdated motors: {:?}",
+                feature,
+                feature.min_firmware_version(),
+                outdated
+            )))
+        }
+    }
+
+    /// Compare the configuration registers of the present motors to `profile`, see
+    /// [`crate::config_audit`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn audit_configuration(
+        &mut self,
+        profile: &ConfigProfile,
+    ) -> Result<Vec<ConfigDeviation>, MotorControllerError> {
+        let mut deviations = Vec::new();
+        for (joint, id) in self
+            .all_ids
+            .into_iter()
+            .enumerate()
+            .filter(|(_, id)| !self.missing_ids.contains(id))
+        {
+            let registers =
+                self.dph_v2
+                    .read(&mut self.port, id, AUDIT_START_ADDRESS, AUDIT_LENGTH)?;
+            deviations.extend(
+                audit_registers(joint, &registers, profile)
+                    .map_err(MotorControllerError::UnexpectedResponse)?,
+            );
+        }
+        Ok(deviations)
+    }
+
+    /// Write the registers of the present motors which differ from `profile`, and return
+    /// the deviations found. The torque of the motors written is disabled first, the
+    /// EEPROM area is locked while it is on.
+    ///
+    /// With `dry_run`, nothing is written: the deviations are the registers which would be.
+    /// A baud rate deviation cannot be fixed on the bus in use and fails before any write.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn apply_profile(
+        &mut self,
+        profile: &ConfigProfile,
+        dry_run: bool,
+    ) -> Result<Vec<ConfigDeviation>, MotorControllerError> {
+        let deviations = self.audit_configuration(profile)?;
+        if dry_run || deviations.is_empty() {
+            return Ok(deviations);
+        }
+
+        let motor_name_id = self.get_motor_name_id();
+        let writes = deviations
+            .iter()
+            .map(|deviation| {
+                let id = motor_name_id[&deviation.joint];
+                restore_write(deviation)
+                    .map(|(address, data)| (id, address, data))
+                    .ok_or_else(|| {
+                        MotorControllerError::Unsupported(format!(
+                            "Cannot apply the profile: {}",
+                            deviation
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut ids: Vec<u8> = writes.iter().map(|(id, _, _)| *id).collect();
+        ids.dedup();
+        self.disable_torque_on_ids(&ids)?;
+        for (id, address, data) in writes {
+            self.write_raw_bytes(id, address, &data)?;
+        }
+        Ok(deviations)
+    }
+
+    /// [`Self::apply_profile`] with the canonical Reachy Mini profile, to restore a robot
+    /// after experimentation.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn apply_factory_profile(
+        &mut self,
+        dry_run: bool,
+    ) -> Result<Vec<ConfigDeviation>, MotorControllerError> {
+        self.apply_profile(&ConfigProfile::default(), dry_run)
+    }
+
     /// Read the current input voltage of all servos.
     /// Returns an array of 9 input voltages in the following order:
     /// [body_rotation, stewart_1, stewart_2, stewart_3, stewart_4, stewart_5, stewart_6, antenna_right, antenna_left]
-    pub fn read_all_voltages(&mut self) -> Result<[u16; 9], Box<dyn std::error::Error>> {
-        let volt = xl330::sync_read_present_input_voltage(
-            &self.dph_v2,
-            self.serial_port.as_mut(),
-            &self.all_ids,
-        )?;
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn read_all_voltages(&mut self) -> Result<[u16; 9], MotorControllerError> {
+        let volt = self.sync_read_present(&ALL_IDS, 0, |dph, port, ids| {
+            xl330::sync_read_present_input_voltage(dph, port, ids)
+        })?;
         
-        volt.try_into()
-            .map_err(|_| "Invalid voltage array length: expected 9 elements".into())
+        volt.try_into().map_err(|_| {
+            MotorControllerError::UnexpectedResponse(
+                "Invalid voltage array length: expected 9 elements".to_string(),
+            )
+        })
     }
         
 
+    /// Read the present current (mA) of all servos, in the same order as the positions.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn read_all_currents(&mut self) -> Result<[i16; 9], MotorControllerError> {
+        let currents = self.sync_read_present(&ALL_IDS, 0, |dph, port, ids| {
+            xl330::sync_read_present_current(dph, port, ids)
+        })?;
+
+        currents.try_into().map_err(|_| {
+            MotorControllerError::UnexpectedResponse(
+                "Invalid current array length: expected 9 elements".to_string(),
+            )
+        })
+    }
+
+    /// Read the present temperature (°C) of all servos, in the same order as the positions.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn read_all_temperatures(&mut self) -> Result<[u8; 9], MotorControllerError> {
+        let temperatures = self.sync_read_present(&ALL_IDS, 0, |dph, port, ids| {
+            xl330::sync_read_present_temperature(dph, port, ids)
+        })?;
+
+        temperatures.try_into().map_err(|_| {
+            MotorControllerError::UnexpectedResponse(
+                "Invalid temperature array length: expected 9 elements".to_string(),
+            )
+        })
+    }
+
+    /// Read the hardware error status of all servos, in the same order as the positions.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn read_all_hardware_error_status(&mut self) -> Result<[u8; 9], MotorControllerError> {
+        let status = self.sync_read_present(&ALL_IDS, 0, |dph, port, ids| {
+            xl330::sync_read_hardware_error_status(dph, port, ids)
+        })?;
+
+        status.try_into().map_err(|_| {
+            MotorControllerError::UnexpectedResponse(
+                "Invalid error status array length: expected 9 elements".to_string(),
+            )
+        })
+    }
+
     /// Read the current position of all servos.
     /// Returns an array of 9 positions in the following order:
     /// [body_rotation, stewart_1, stewart_2, stewart_3, stewart_4, stewart_5, stewart_6, antenna_right, antenna_left]
-    pub fn read_all_positions(&mut self) -> Result<[f64; 9], Box<dyn std::error::Error>> {
-        let pos = xl330::sync_read_present_position(
-            &self.dph_v2,
-            self.serial_port.as_mut(),
-            &self.all_ids,
-        )?;
-        
-        pos.try_into()
-            .map_err(|_| "Invalid position array length: expected 9 elements".into())
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn read_all_positions(&mut self) -> Result<[f64; 9], MotorControllerError> {
+        let pos = self.read_present_positions(&ALL_IDS)?;
+
+        pos.try_into().map_err(|_| {
+            MotorControllerError::UnexpectedResponse(
+                "Invalid position array length: expected 9 elements".to_string(),
+            )
+        })
+    }
+
+    /// Read the current position of some joints, given by their index in the order of
+    /// [`Self::read_all_positions`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn read_joint_positions(
+        &mut self,
+        joints: &[usize],
+    ) -> Result<Vec<f64>, MotorControllerError> {
+        let ids = joints
+            .iter()
+            .map(|&joint| ALL_IDS.get(joint).copied())
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| MotorControllerError::OutOfBounds("Invalid joint index".to_string()))?;
+        self.read_present_positions(&ids)
+    }
+
+    /// Read the position of the motors `ids` with the read strategy.
+    fn read_present_positions(&mut self, ids: &[u8]) -> Result<Vec<f64>, MotorControllerError> {
+        match self.read_strategy {
+            ReadStrategy::Combined => self.sync_read_present(ids, 0.0, |dph, port, ids| {
+                xl330::sync_read_present_position(dph, port, ids)
+            }),
+            ReadStrategy::Split => {
+                let group_of = |id: &u8| {
+                    let joint = ALL_IDS.iter().position(|x| x == id);
+                    GROUPS
+                        .iter()
+                        .position(|group| joint.is_some_and(|j| group.contains(&j)))
+                };
+                let mut positions = Vec::with_capacity(ids.len());
+                for group in ids.chunk_by(|a, b| group_of(a) == group_of(b)) {
+                    positions.extend(self.sync_read_present(group, 0.0, |dph, port, ids| {
+                        xl330::sync_read_present_position(dph, port, ids)
+                    })?);
+                }
+                Ok(positions)
+            }
+            ReadStrategy::FastSync => self.sync_read_present(ids, 0.0, |_, port, ids| {
+                Ok(fast_sync_read_present_position(port, ids)?)
+            }),
+        }
     }
 
     /// Set the goal position of all servos.
     /// The positions array must be in the following order:
     /// [body_rotation, stewart_1, stewart_2, stewart_3, stewart_4, stewart_5, stewart_6, antenna_right, antenna_left]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     pub fn set_all_goal_positions(
         &mut self,
         positions: [f64; 9],
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        xl330::sync_write_goal_position(
-            &self.dph_v2,
-            self.serial_port.as_mut(),
-            &self.all_ids,
+    ) -> Result<(), MotorControllerError> {
+        self.sync_write_present(
+            "goal_position",
+            &ALL_IDS,
             &positions,
+            |dph, port, ids, values| xl330::sync_write_goal_position(dph, port, ids, values),
         )?;
 
         Ok(())
     }
 
+    /// Write the goal positions of each group separately (body rotation, stewart
+    /// platform and antennas), so a failing group does not prevent the others
+    /// from being written.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn set_all_goal_positions_by_group(
+        &mut self,
+        positions: [f64; 9],
+    ) -> Result<(), GroupWriteError> {
+        let mut failures = Vec::new();
+        if let Err(e) = self.set_body_rotation(positions[0]) {
+            failures.push(("body_rotation", e));
+        }
+        if let Err(e) =
+            self.set_stewart_platform_position(std::array::from_fn(|i| positions[1 + i]))
+        {
+            failures.push(("stewart_platform", e));
+        }
+        if let Err(e) = self.set_antennas_positions([positions[7], positions[8]]) {
+            failures.push(("antennas", e));
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(GroupWriteError { failures })
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     pub fn set_antennas_positions(
         &mut self,
         positions: [f64; 2],
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        xl330::sync_write_goal_position(
-            &self.dph_v2,
-            self.serial_port.as_mut(),
+    ) -> Result<(), MotorControllerError> {
+        self.sync_write_present(
+            "goal_position",
             &ANTENNAS_IDS,
             &positions,
+            |dph, port, ids, values| xl330::sync_write_goal_position(dph, port, ids, values),
         )?;
 
         Ok(())
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     pub fn set_stewart_platform_position(
         &mut self,
         position: [f64; 6],
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        xl330::sync_write_goal_position(
-            &self.dph_v2,
-            self.serial_port.as_mut(),
+    ) -> Result<(), MotorControllerError> {
+        self.sync_write_present(
+            "goal_position",
             &STEWART_PLATFORM_IDS,
             &position,
+            |dph, port, ids, values| xl330::sync_write_goal_position(dph, port, ids, values),
         )?;
 
         Ok(())
     }
-    pub fn set_body_rotation(&mut self, position: f64) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn set_body_rotation(&mut self, position: f64) -> Result<(), MotorControllerError> {
+        if self.record_dry_run("goal_position", &[BODY_ROTATION_ID], &[position]) {
+            return Ok(());
+        }
         xl330::sync_write_goal_position(
             &self.dph_v2,
-            self.serial_port.as_mut(),
+            &mut self.port,
             &[BODY_ROTATION_ID],
             &[position],
         )?;
@@ -230,137 +922,502 @@ impl ReachyMiniMotorController {
         Ok(())
     }
 
-    pub fn is_torque_enabled(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
-        let xl_torque =
-            xl330::sync_read_torque_enable(&self.dph_v2, self.serial_port.as_mut(), &self.all_ids)?;
+    /// Set the profile velocity (rad/s) of all servos, the highest velocity of their moves
+    /// to the goal positions, in joint order. 0 removes the limit (the default).
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn set_all_profile_velocities(
+        &mut self,
+        velocities: [f64; 9],
+    ) -> Result<(), MotorControllerError> {
+        let rad_per_s = VELOCITY_UNIT_RPM * 2.0 * std::f64::consts::PI / 60.0;
+        // Rounded up, so a slow velocity does not become the 0 of no limit
+        let raw = velocities.map(|v| (v.abs() / rad_per_s).ceil().min(32767.0) as u32);
+        self.sync_write_present(
+            "profile_velocity",
+            &ALL_IDS,
+            &raw,
+            |dph, port, ids, values| xl330::sync_write_profile_velocity(dph, port, ids, values),
+        )
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn is_torque_enabled(&mut self) -> Result<bool, MotorControllerError> {
+        // The missing motors do not count
+        let xl_torque = self.sync_read_present(&ALL_IDS, true, |dph, port, ids| {
+            xl330::sync_read_torque_enable(dph, port, ids)
+        })?;
 
         Ok(xl_torque.iter().all(|&x| x))
     }
 
-    pub fn enable_torque(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    /// Read the torque enable of each servo, in the same order as [`Self::read_all_positions`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn read_all_torque_enabled(&mut self) -> Result<[bool; 9], MotorControllerError> {
+        let torque = self.sync_read_present(&ALL_IDS, false, |dph, port, ids| {
+            xl330::sync_read_torque_enable(dph, port, ids)
+        })?;
+
+        torque.try_into().map_err(|_| {
+            MotorControllerError::UnexpectedResponse(
+                "Invalid torque array length: expected 9 elements".to_string(),
+            )
+        })
+    }
+
+    /// Read the return delay time register of each servo (units of 2 µs), in the same
+    /// order as [`Self::read_all_positions`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn read_all_return_delay_times(&mut self) -> Result<[u8; 9], MotorControllerError> {
+        let delays = self.sync_read_present(&ALL_IDS, 0, |dph, port, ids| {
+            xl330::sync_read_return_delay_time(dph, port, ids)
+        })?;
+
+        delays.try_into().map_err(|_| {
+            MotorControllerError::UnexpectedResponse(
+                "Invalid return delay array length: expected 9 elements".to_string(),
+            )
+        })
+    }
+
+    /// Write the return delay time register of each servo (units of 2 µs), in the same
+    /// order as [`Self::read_all_positions`]. It is in the EEPROM area, locked while the
+    /// torque is on.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn set_all_return_delay_times(
+        &mut self,
+        delays: [u8; 9],
+    ) -> Result<(), MotorControllerError> {
+        self.sync_write_present(
+            "return_delay_time",
+            &ALL_IDS,
+            &delays,
+            |dph, port, ids, values| xl330::sync_write_return_delay_time(dph, port, ids, values),
+        )
+    }
+
+    /// Enable or disable the torque of each servo, in the same order as [`Self::read_all_positions`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn set_all_torque(&mut self, enable: [bool; 9]) -> Result<(), MotorControllerError> {
+        self.sync_write_present(
+            "torque_enable",
+            &ALL_IDS,
+            &enable,
+            |dph, port, ids, values| xl330::sync_write_torque_enable(dph, port, ids, values),
+        )?;
+
+        Ok(())
+    }
+
+    /// Index of the motor `id` in the joint order, see [`Self::read_all_positions`].
+    pub fn joint_index(&self, id: u8) -> Option<usize> {
+        self.all_ids.iter().position(|&x| x == id)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn enable_torque(&mut self) -> Result<(), MotorControllerError> {
         self.set_torque(true)
     }
 
-    pub fn enable_torque_on_ids(&mut self, ids: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn enable_torque_on_ids(&mut self, ids: &[u8]) -> Result<(), MotorControllerError> {
         self.set_torque_on_ids(ids, true)
     }
 
-    pub fn disable_torque(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn disable_torque(&mut self) -> Result<(), MotorControllerError> {
         self.set_torque(false)
     }
 
-    pub fn disable_torque_on_ids(&mut self, ids: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn disable_torque_on_ids(&mut self, ids: &[u8]) -> Result<(), MotorControllerError> {
         self.set_torque_on_ids(ids, false)
     }
 
-    fn set_torque(&mut self, enable: bool) -> Result<(), Box<dyn std::error::Error>> {
-        xl330::sync_write_torque_enable(
-            &self.dph_v2,
-            self.serial_port.as_mut(),
-            &self.all_ids,
+    /// Progressively lower the goal PWM of all motors before disabling the torque.
+    ///
+    /// The head then goes down gently instead of falling. The goal PWM is restored
+    /// afterwards (with torque off) so the next user is not left with weak motors.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn ramp_down_and_disable_torque(
+        &mut self,
+        duration: Duration,
+    ) -> Result<(), MotorControllerError> {
+        const STEPS: u32 = 20;
+
+        let initial_pwm = self.sync_read_present(&ALL_IDS, 0, |dph, port, ids| {
+            xl330::sync_read_goal_pwm(dph, port, ids)
+        })?;
+
+        for step in 1..STEPS {
+            let ratio = 1.0 - step as f64 / STEPS as f64;
+            let pwm: Vec<u16> = initial_pwm
+                .iter()
+                .map(|pwm| (*pwm as f64 * ratio) as u16)
+                .collect();
+            self.sync_write_present("goal_pwm", &ALL_IDS, &pwm, |dph, port, ids, values| {
+                xl330::sync_write_goal_pwm(dph, port, ids, values)
+            })?;
+            std::thread::sleep(duration / STEPS);
+        }
+
+        self.disable_torque()?;
+        self.sync_write_present(
+            "goal_pwm",
+            &ALL_IDS,
+            &initial_pwm,
+            |dph, port, ids, values| xl330::sync_write_goal_pwm(dph, port, ids, values),
+        )?;
+
+        Ok(())
+    }
+
+    fn set_torque(&mut self, enable: bool) -> Result<(), MotorControllerError> {
+        self.sync_write_present(
+            "torque_enable",
+            &ALL_IDS,
             &[enable; 9],
+            |dph, port, ids, values| xl330::sync_write_torque_enable(dph, port, ids, values),
         )?;
 
         Ok(())
     }
 
-    fn set_torque_on_ids(
-        &mut self,
-        ids: &[u8],
-        enable: bool,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    fn set_torque_on_ids(&mut self, ids: &[u8], enable: bool) -> Result<(), MotorControllerError> {
         let enables = vec![enable; ids.len()];
-        xl330::sync_write_torque_enable(&self.dph_v2, self.serial_port.as_mut(), ids, &enables)?;
+        self.sync_write_present("torque_enable", ids, &enables, |dph, port, ids, values| {
+            xl330::sync_write_torque_enable(dph, port, ids, values)
+        })?;
 
         Ok(())
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     pub fn set_stewart_platform_goal_current(
         &mut self,
         current: [i16; 6],
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        xl330::sync_write_goal_current(
-            &self.dph_v2,
-            self.serial_port.as_mut(),
+    ) -> Result<(), MotorControllerError> {
+        self.sync_write_present(
+            "goal_current",
             &STEWART_PLATFORM_IDS,
             &current,
+            |dph, port, ids, values| xl330::sync_write_goal_current(dph, port, ids, values),
         )?;
 
         Ok(())
     }
 
-    pub fn read_stewart_platform_current(
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn read_stewart_platform_goal_current(&mut self) -> Result<[i16; 6], MotorControllerError> {
+        let currents = self.sync_read_present(&STEWART_PLATFORM_IDS, 0, |dph, port, ids| {
+            xl330::sync_read_goal_current(dph, port, ids)
+        })?;
+
+        currents.try_into().map_err(|_| {
+            MotorControllerError::UnexpectedResponse(
+                "Invalid current array length: expected 6 elements".to_string(),
+            )
+        })
+    }
+
+    /// Set the goal current and goal position of the Stewart platform motors, for
+    /// the current-based position mode.
+    ///
+    /// The currents are written right before the positions, so the new positions
+    /// never run with the previous current limits.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn set_stewart_goal(
         &mut self,
-    ) -> Result<[i16; 6], Box<dyn std::error::Error>> {
-        let currents = xl330::sync_read_present_current(
-            &self.dph_v2,
-            self.serial_port.as_mut(),
-            &STEWART_PLATFORM_IDS,
-        )?;
+        positions: [f64; 6],
+        currents: [i16; 6],
+    ) -> Result<(), MotorControllerError> {
+        self.set_stewart_platform_goal_current(currents)?;
+        self.set_stewart_platform_position(positions)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn read_stewart_platform_current(&mut self) -> Result<[i16; 6], MotorControllerError> {
+        let currents = self.sync_read_present(&STEWART_PLATFORM_IDS, 0, |dph, port, ids| {
+            xl330::sync_read_present_current(dph, port, ids)
+        })?;
 
-        currents.try_into()
-            .map_err(|_| "Invalid current array length: expected 6 elements".into())
+        currents.try_into().map_err(|_| {
+            MotorControllerError::UnexpectedResponse(
+                "Invalid current array length: expected 6 elements".to_string(),
+            )
+        })
+    }
+
+    /// Read the velocity (rad/s) of the antennas [right, left].
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn read_antennas_velocity(&mut self) -> Result<[f64; 2], MotorControllerError> {
+        let velocities = self.sync_read_present(&ANTENNAS_IDS, 0, |dph, port, ids| {
+            xl330::sync_read_present_velocity(dph, port, ids)
+        })?;
+        let velocities: [i32; 2] = velocities.try_into().map_err(|_| {
+            MotorControllerError::UnexpectedResponse(
+                "Invalid velocity array length: expected 2 elements".to_string(),
+            )
+        })?;
+
+        let rad_per_s = VELOCITY_UNIT_RPM * 2.0 * std::f64::consts::PI / 60.0;
+        Ok(velocities.map(|raw| raw as f64 * rad_per_s))
     }
 
+    /// Read the current (mA) of the antennas [right, left], i.e. the load applied on them.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn read_antennas_current(&mut self) -> Result<[i16; 2], MotorControllerError> {
+        let currents = self.sync_read_present(&ANTENNAS_IDS, 0, |dph, port, ids| {
+            xl330::sync_read_present_current(dph, port, ids)
+        })?;
+
+        currents.try_into().map_err(|_| {
+            MotorControllerError::UnexpectedResponse(
+                "Invalid current array length: expected 2 elements".to_string(),
+            )
+        })
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     pub fn set_stewart_platform_operating_mode(
         &mut self,
         mode: u8,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        xl330::sync_write_operating_mode(
-            &self.dph_v2,
-            self.serial_port.as_mut(),
+    ) -> Result<(), MotorControllerError> {
+        self.write_eeprom_verified(
+            "stewart platform operating mode",
             &STEWART_PLATFORM_IDS,
-            &[mode; 6],
-        )?;
-
-        Ok(())
+            &vec![mode; 6],
+            |c| {
+                xl330::sync_write_operating_mode(
+                    &c.dph_v2,
+                    &mut c.port,
+                    &STEWART_PLATFORM_IDS,
+                    &[mode; 6],
+                )
+            },
+            |c| xl330::sync_read_operating_mode(&c.dph_v2, &mut c.port, &STEWART_PLATFORM_IDS),
+        )
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     pub fn read_stewart_platform_operating_mode(
         &mut self,
-    ) -> Result<[u8; 6], Box<dyn std::error::Error>> {
-        let modes = xl330::sync_read_operating_mode(
-            &self.dph_v2,
-            self.serial_port.as_mut(),
-            &STEWART_PLATFORM_IDS,
-        )?;
+    ) -> Result<[u8; 6], MotorControllerError> {
+        let modes =
+            xl330::sync_read_operating_mode(&self.dph_v2, &mut self.port, &STEWART_PLATFORM_IDS)?;
 
-        modes.try_into()
-            .map_err(|_| "Invalid mode array length: expected 6 elements".into())
+        modes.try_into().map_err(|_| {
+            MotorControllerError::UnexpectedResponse(
+                "Invalid mode array length: expected 6 elements".to_string(),
+            )
+        })
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn set_antennas_operating_mode(&mut self, mode: u8) -> Result<(), MotorControllerError> {
+        self.write_eeprom_verified(
+            "antennas operating mode",
+            &ANTENNAS_IDS,
+            &vec![mode; 2],
+            |c| xl330::sync_write_operating_mode(&c.dph_v2, &mut c.port, &ANTENNAS_IDS, &[mode; 2]),
+            |c| xl330::sync_read_operating_mode(&c.dph_v2, &mut c.port, &ANTENNAS_IDS),
+        )
     }
 
-    pub fn set_antennas_operating_mode(
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn set_body_rotation_operating_mode(
         &mut self,
         mode: u8,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        xl330::sync_write_operating_mode(
-            &self.dph_v2,
-            self.serial_port.as_mut(),
-            &ANTENNAS_IDS,
-            &[mode; 2],
+    ) -> Result<(), MotorControllerError> {
+        // Unknown until the write is confirmed
+        self.body_rotation_mode = None;
+        self.write_eeprom_verified(
+            "body rotation operating mode",
+            &[BODY_ROTATION_ID],
+            &mode,
+            |c| xl330::write_operating_mode(&c.dph_v2, &mut c.port, BODY_ROTATION_ID, mode),
+            |c| xl330::read_operating_mode(&c.dph_v2, &mut c.port, BODY_ROTATION_ID),
         )?;
+        self.body_rotation_mode = Some(mode);
 
         Ok(())
     }
 
-    pub fn set_body_rotation_operating_mode(
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn read_body_rotation_operating_mode(&mut self) -> Result<u8, MotorControllerError> {
+        let mode = xl330::read_operating_mode(&self.dph_v2, &mut self.port, BODY_ROTATION_ID)
+            .map_err(MotorControllerError::from_motor(BODY_ROTATION_ID))?;
+        self.body_rotation_mode = Some(mode);
+
+        Ok(mode)
+    }
+
+    /// Spin the body continuously at the given velocity (rad/s).
+    ///
+    /// The body rotation motor is switched to velocity (wheel) mode first if needed.
+    /// As the operating mode is stored in EEPROM, torque is briefly disabled during the switch.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn spin_body(&mut self, velocity: f64) -> Result<(), MotorControllerError> {
+        self.switch_body_rotation_mode(VELOCITY_CONTROL_MODE)?;
+
+        let rpm = velocity * 60.0 / (2.0 * std::f64::consts::PI);
+        let raw = (rpm / VELOCITY_UNIT_RPM).round() as i32;
+        if self.record_dry_run("goal_velocity", &[BODY_ROTATION_ID], &[raw]) {
+            return Ok(());
+        }
+        xl330::write_goal_velocity(&self.dph_v2, &mut self.port, BODY_ROTATION_ID, raw)?;
+
+        Ok(())
+    }
+
+    /// Stop spinning the body and switch it back to position control,
+    /// holding the position where it stopped.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn stop_body_spin(&mut self) -> Result<(), MotorControllerError> {
+        if !self.record_dry_run("goal_velocity", &[BODY_ROTATION_ID], &[0]) {
+            xl330::write_goal_velocity(&self.dph_v2, &mut self.port, BODY_ROTATION_ID, 0)?;
+        }
+        self.switch_body_rotation_mode(POSITION_CONTROL_MODE)
+    }
+
+    /// Find the center of the body rotation from its mechanical stops and store it as homing offset.
+    ///
+    /// The body is slowly rotated in each direction until the present current indicates
+    /// a mechanical stop. The center between both stops is written to the homing offset
+    /// register (EEPROM) so that a body yaw of 0 rad corresponds to the center.
+    /// The body is then moved back to the center in position control.
+    ///
+    /// Returns the new homing offset (rad).
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn home_body_rotation(
         &mut self,
-        mode: u8,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        xl330::sync_write_operating_mode(
-            &self.dph_v2,
-            self.serial_port.as_mut(),
+        config: HomingConfig,
+    ) -> Result<f64, MotorControllerError> {
+        if self.dry_run.is_some() {
+            return Err(MotorControllerError::Unsupported(
+                "The body rotation cannot be homed in dry-run mode".to_string(),
+            ));
+        }
+        // Start from a zero offset so both stops are expressed in the raw motor frame
+        self.enable_body_rotation(false)?;
+        self.write_body_rotation_homing_offset(0)?;
+        self.enable_body_rotation(true)?;
+
+        let stops = self
+            .find_body_rotation_stop(config.velocity.abs(), &config)
+            .and_then(|positive| {
+                let negative = self.find_body_rotation_stop(-config.velocity.abs(), &config)?;
+                Ok((positive, negative))
+            });
+        // Always try to leave the body in position control, even if a stop was not found
+        self.stop_body_spin()?;
+        let (positive_stop, negative_stop) = stops?;
+
+        let center = (positive_stop + negative_stop) / 2.0;
+        let offset_raw = -(center / POSITION_UNIT_RAD).round() as i32;
+        info!(
+            "Body rotation stops found at {:.3} and {:.3} rad, center at {:.3} rad",
+            negative_stop, positive_stop, center
+        );
+
+        self.enable_body_rotation(false)?;
+        self.write_body_rotation_homing_offset(offset_raw)?;
+        self.set_body_rotation(0.0)?;
+        self.enable_body_rotation(true)?;
+
+        Ok(offset_raw as f64 * POSITION_UNIT_RAD)
+    }
+
+    fn write_body_rotation_homing_offset(
+        &mut self,
+        offset: i32,
+    ) -> Result<(), MotorControllerError> {
+        self.write_eeprom_verified(
+            "body rotation homing offset",
             &[BODY_ROTATION_ID],
-            &[mode],
-        )?;
+            &offset,
+            |c| xl330::write_homing_offset(&c.dph_v2, &mut c.port, BODY_ROTATION_ID, offset),
+            |c| xl330::read_homing_offset(&c.dph_v2, &mut c.port, BODY_ROTATION_ID),
+        )
+    }
+
+    fn find_body_rotation_stop(
+        &mut self,
+        velocity: f64,
+        config: &HomingConfig,
+    ) -> Result<f64, MotorControllerError> {
+        // Ignore the current peak due to the acceleration
+        const SETTLE_TIME: Duration = Duration::from_millis(300);
+        // Number of consecutive samples above the threshold to consider the stop reached
+        const DEBOUNCE_SAMPLES: usize = 3;
+
+        self.spin_body(velocity)?;
+
+        let start = std::time::Instant::now();
+        let mut above_threshold = 0;
+        while start.elapsed() < config.timeout {
+            std::thread::sleep(Duration::from_millis(10));
+            if start.elapsed() < SETTLE_TIME {
+                continue;
+            }
+
+            let current =
+                xl330::read_present_current(&self.dph_v2, &mut self.port, BODY_ROTATION_ID)?;
+            if current.unsigned_abs() >= config.current_threshold.unsigned_abs() {
+                above_threshold += 1;
+            } else {
+                above_threshold = 0;
+            }
+
+            if above_threshold >= DEBOUNCE_SAMPLES {
+                let position =
+                    xl330::read_present_position(&self.dph_v2, &mut self.port, BODY_ROTATION_ID)?;
+                xl330::write_goal_velocity(&self.dph_v2, &mut self.port, BODY_ROTATION_ID, 0)?;
+                return Ok(position);
+            }
+        }
+
+        xl330::write_goal_velocity(&self.dph_v2, &mut self.port, BODY_ROTATION_ID, 0)?;
+        Err(MotorControllerError::Other(format!(
+            "Body rotation stop not found within {:?} (velocity {} rad/s)",
+            config.timeout, velocity
+        )))
+    }
+
+    fn switch_body_rotation_mode(&mut self, mode: u8) -> Result<(), MotorControllerError> {
+        let current_mode = match self.body_rotation_mode {
+            Some(mode) => mode,
+            None => self.read_body_rotation_operating_mode()?,
+        };
+        if current_mode == mode {
+            return Ok(());
+        }
+
+        let torque_on = xl330::read_torque_enable(&self.dph_v2, &mut self.port, BODY_ROTATION_ID)?;
+        if torque_on {
+            self.enable_body_rotation(false)?;
+        }
+        self.set_body_rotation_operating_mode(mode)?;
+        if mode == POSITION_CONTROL_MODE {
+            // Hold the current position instead of jumping back to the last goal
+            let present =
+                xl330::read_present_position(&self.dph_v2, &mut self.port, BODY_ROTATION_ID)?;
+            self.set_body_rotation(present)?;
+        }
+        if torque_on {
+            self.enable_body_rotation(true)?;
+        }
 
         Ok(())
     }
 
-    pub fn enable_body_rotation(&mut self, enable: bool) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn enable_body_rotation(&mut self, enable: bool) -> Result<(), MotorControllerError> {
+        if self.record_dry_run("torque_enable", &[BODY_ROTATION_ID], &[enable]) {
+            return Ok(());
+        }
         xl330::sync_write_torque_enable(
             &self.dph_v2,
-            self.serial_port.as_mut(),
+            &mut self.port,
             &[BODY_ROTATION_ID],
             &[enable],
         )?;
@@ -368,64 +1425,303 @@ impl ReachyMiniMotorController {
         Ok(())
     }
 
-    pub fn enable_antennas(&mut self, enable: bool) -> Result<(), Box<dyn std::error::Error>> {
-        xl330::sync_write_torque_enable(
-            &self.dph_v2,
-            self.serial_port.as_mut(),
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn enable_antennas(&mut self, enable: bool) -> Result<(), MotorControllerError> {
+        self.sync_write_present(
+            "torque_enable",
             &ANTENNAS_IDS,
             &[enable; 2],
+            |dph, port, ids, values| xl330::sync_write_torque_enable(dph, port, ids, values),
         )?;
 
         Ok(())
     }
 
-    pub fn enable_stewart_platform(
-        &mut self,
-        enable: bool,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        xl330::sync_write_torque_enable(
-            &self.dph_v2,
-            self.serial_port.as_mut(),
+    /// Let the antennas dangle freely by disabling their torque, the rest of the robot stays active.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn set_antennas_floppy(&mut self) -> Result<(), MotorControllerError> {
+        self.enable_antennas(false)
+    }
+
+    /// Hold the antennas again where they currently are (inverse of [`Self::set_antennas_floppy`]).
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn set_antennas_stiff(&mut self) -> Result<(), MotorControllerError> {
+        let positions = self.sync_read_present(&ANTENNAS_IDS, 0.0, |dph, port, ids| {
+            xl330::sync_read_present_position(dph, port, ids)
+        })?;
+        // Avoid jumping back to the goal sent before they were made floppy
+        self.sync_write_present(
+            "goal_position",
+            &ANTENNAS_IDS,
+            &positions,
+            |dph, port, ids, values| xl330::sync_write_goal_position(dph, port, ids, values),
+        )?;
+        self.enable_antennas(true)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn enable_stewart_platform(&mut self, enable: bool) -> Result<(), MotorControllerError> {
+        self.sync_write_present(
+            "torque_enable",
             &STEWART_PLATFORM_IDS,
             &[enable; 6],
+            |dph, port, ids, values| xl330::sync_write_torque_enable(dph, port, ids, values),
         )?;
 
         Ok(())
     }
 
+    /// Store a robot identity (e.g. its serial number or name) in the motors, to tell
+    /// robots apart whatever the port they are plugged in.
+    ///
+    /// The XL330 has no spare EEPROM, so the identity is stored one byte per motor in
+    /// the moving threshold register, which only sets the speed above which a motor
+    /// reports itself as moving. The body rotation motor holds the length, the other
+    /// ones up to [`IDENTITY_SIZE`] bytes. An empty identity clears it.
+    ///
+    /// The torque must be disabled on all motors, as the registers are in EEPROM.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn write_identity(&mut self, identity: &[u8]) -> Result<(), MotorControllerError> {
+        if identity.len() > IDENTITY_SIZE {
+            return Err(MotorControllerError::OutOfBounds(format!(
+                "Identity too long: {} bytes, at most {}",
+                identity.len(),
+                IDENTITY_SIZE
+            )));
+        }
+        if self.read_all_torque_enabled()?.contains(&true) {
+            return Err(MotorControllerError::Rejected(
+                "Disable the torque of all motors before writing the identity".to_string(),
+            ));
+        }
+
+        let mut values = vec![0u32; ALL_IDS.len()];
+        values[0] = identity.len() as u32;
+        for (value, byte) in values[1..].iter_mut().zip(identity) {
+            *value = *byte as u32;
+        }
+        self.write_eeprom_verified(
+            "robot identity",
+            &ALL_IDS,
+            &values,
+            |c| xl330::sync_write_moving_threshold(&c.dph_v2, &mut c.port, &ALL_IDS, &values),
+            |c| xl330::sync_read_moving_threshold(&c.dph_v2, &mut c.port, &ALL_IDS),
+        )
+    }
+
+    /// Read the robot identity stored by [`Self::write_identity`], `None` if there is none.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn read_identity(&mut self) -> Result<Option<Vec<u8>>, MotorControllerError> {
+        let values = xl330::sync_read_moving_threshold(&self.dph_v2, &mut self.port, &ALL_IDS)?;
+        let Some((&length, bytes)) = values.split_first() else {
+            return Err(MotorControllerError::UnexpectedResponse(
+                "Invalid moving threshold array length: expected 9 elements".to_string(),
+            ));
+        };
+
+        // Out of range, e.g. the factory default of 10
+        let length = length as usize;
+        if length == 0 || length > IDENTITY_SIZE || bytes.len() < length {
+            return Ok(None);
+        }
+        let identity = bytes[..length]
+            .iter()
+            .map(|&value| u8::try_from(value).ok())
+            .collect();
+
+        Ok(identity)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     pub fn read_raw_bytes(
         &mut self,
         id: u8,
         address: u8,
         length: u8,
-    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    ) -> Result<Vec<u8>, MotorControllerError> {
         self.dph_v2
-            .read(self.serial_port.as_mut(), id, address, length)
+            .read(&mut self.port, id, address, length)
+            .map_err(MotorControllerError::from_motor(id))
     }
 
+    /// Write raw bytes to the control table of a motor.
+    ///
+    /// Writes to the EEPROM area (limits, id, offsets...) are read back and retried,
+    /// see [`Self::write_eeprom_verified`]. After an id change, the motor is read back
+    /// at its new id. Baud rate changes cannot be read back on the current bus and
+    /// are written as is.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     pub fn write_raw_bytes(
         &mut self,
         id: u8,
         address: u8,
         data: &[u8],
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        self.dph_v2
-            .write(self.serial_port.as_mut(), id, address, data)
+    ) -> Result<(), MotorControllerError> {
+        let registers = address as usize..address as usize + data.len();
+        if address >= EEPROM_END || registers.contains(&(BAUD_RATE_ADDRESS as usize)) {
+            if self.record_dry_run(&format!("register {}", address), &[id], &data) {
+                return Ok(());
+            }
+            return self
+                .dph_v2
+                .write(&mut self.port, id, address, data)
+                .map_err(MotorControllerError::from_motor(id));
+        }
+        let read_id = if registers.contains(&(ID_ADDRESS as usize)) {
+            data[(ID_ADDRESS - address) as usize]
+        } else {
+            id
+        };
+        let length = u8::try_from(data.len()).map_err(|_| {
+            MotorControllerError::OutOfBounds("Too many bytes to write".to_string())
+        })?;
+
+        self.write_eeprom_verified(
+            &format!("register {} of motor {}", address, id),
+            &[id],
+            &data.to_vec(),
+            |c| c.dph_v2.write(&mut c.port, id, address, data),
+            |c| c.dph_v2.read(&mut c.port, read_id, address, length),
+        )
     }
 
+    /// Read `length` bytes of the control table of a motor, change them with `modify`
+    /// and write them back with [`Self::write_raw_bytes`], so EEPROM registers (operating
+    /// mode, limits...) are verified. Nothing is written if `modify` left them unchanged.
+    ///
+    /// Returns the bytes written.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn update_raw_bytes(
+        &mut self,
+        id: u8,
+        address: u8,
+        length: u8,
+        modify: impl FnOnce(&mut [u8]),
+    ) -> Result<Vec<u8>, MotorControllerError> {
+        let current = self.read_raw_bytes(id, address, length)?;
+        let mut data = current.clone();
+        modify(&mut data);
+        if data != current {
+            self.write_raw_bytes(id, address, &data)?;
+        }
+
+        Ok(data)
+    }
+
+    /// Write an EEPROM value and read it back, retrying up to
+    /// [`EEPROM_WRITE_ATTEMPTS`] times.
+    ///
+    /// The value is read back even if the write reported an error, as the status
+    /// packet may be lost while the value was persisted. A [`WriteVerificationError`]
+    /// is returned if the value read back never matches `expected`, the motors must not
+    /// be used as if the value was persisted.
+    ///
+    /// In dry-run mode, the write of `expected` to `ids` is only recorded.
+    fn write_eeprom_verified<V: PartialEq + std::fmt::Debug>(
+        &mut self,
+        name: &str,
+        ids: &[u8],
+        expected: &V,
+        write: impl Fn(&mut Self) -> Result<(), Box<dyn std::error::Error>>,
+        read: impl Fn(&mut Self) -> Result<V, Box<dyn std::error::Error>>,
+    ) -> Result<(), MotorControllerError> {
+        if self.record_dry_run(name, ids, expected) {
+            return Ok(());
+        }
+        let mut failure = WriteVerificationError {
+            register: name.to_string(),
+            expected: format!("{:?}", expected),
+            read_back: None,
+            error: None,
+            attempts: EEPROM_WRITE_ATTEMPTS,
+        };
+        for attempt in 1..=EEPROM_WRITE_ATTEMPTS {
+            let written = write(self);
+            (failure.read_back, failure.error) = match (read(self), written) {
+                (Ok(value), _) if value == *expected => return Ok(()),
+                (Ok(value), Ok(())) => (Some(format!("{:?}", value)), None),
+                (Ok(value), Err(e)) => (Some(format!("{:?}", value)), Some(e.to_string())),
+                (Err(e), _) => (None, Some(e.to_string())),
+            };
+            warn!(
+                "EEPROM write of the {} failed (attempt {}/{}): {}",
+                name,
+                attempt,
+                EEPROM_WRITE_ATTEMPTS,
+                failure.error.as_deref().unwrap_or("value not persisted")
+            );
+            if attempt < EEPROM_WRITE_ATTEMPTS {
+                std::thread::sleep(EEPROM_RETRY_DELAY);
+            }
+        }
+
+        Err(failure.into())
+    }
+
+    /// Keep the bus silent for `gap` before and after each raw packet.
+    ///
+    /// Raw packets are how devices speaking another protocol (e.g. STS servos on
+    /// protocol v1) share the bus with the XL330s (protocol v2). On marginal wiring,
+    /// packets of both protocols issued back-to-back can get corrupted: the gap lets
+    /// the line settle and the devices go back to listening between them.
+    pub fn set_raw_packet_gap(&mut self, gap: Duration) {
+        self.raw_packet_gap = gap;
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     pub fn write_raw_packet(&mut self, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
-        self.serial_port.write_all(data)?;
-        self.serial_port.flush()?;
+        // No answer to a packet which was not sent
+        if self.record_dry_run("raw_packet", &[], &data) {
+            return Ok(Vec::new());
+        }
+        if !self.raw_packet_gap.is_zero() {
+            std::thread::sleep(self.raw_packet_gap);
+        }
+        self.port.write_all(data)?;
+        self.port.flush()?;
 
-        let mut n = self.serial_port.bytes_to_read()? as usize;
+        let mut n = self.port.bytes_to_read()? as usize;
         let start = std::time::Instant::now();
         while n == 0 && start.elapsed() < Duration::from_millis(10) {
             std::thread::sleep(Duration::from_millis(5));
-            n = self.serial_port.bytes_to_read()? as usize;
+            n = self.port.bytes_to_read()? as usize;
         }
         let mut buff = vec![0u8; n];
-        self.serial_port.read_exact(&mut buff)?;
+        self.port.read_exact(&mut buff)?;
+        if !self.raw_packet_gap.is_zero() {
+            std::thread::sleep(self.raw_packet_gap);
+        }
 
         Ok(buff)
     }
 }
+
+/// Lock and open `serialport`.
+fn open_port(
+    serialport: &str,
+    baud_rate: u32,
+) -> Result<(Box<dyn serialport::SerialPort>, PortLock), MotorControllerError> {
+    // Make sure no other process is talking to the motors
+    let port_lock = PortLock::acquire(serialport)?;
+
+    let serial_port = serialport::new(serialport, baud_rate)
+        .timeout(Duration::from_millis(10))
+        .open()?;
+
+    Ok((serial_port, port_lock))
+}
+
+fn register_emergency_stop(serial_port: &dyn serialport::SerialPort) -> Option<EmergencyStop> {
+    EmergencyStop::register(serial_port)
+        .map_err(|e| warn!("Could not register the port for emergency stop: {}", e))
+        .ok()
+}
+
+impl<T: Transport> Drop for ReachyMiniMotorController<T> {
+    fn drop(&mut self) {
+        // The port is about to be abandoned by a panicking thread (e.g. the control loop)
+        if std::thread::panicking() {
+            let _ = self.disable_torque();
+        }
+    }
+}
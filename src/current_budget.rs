@@ -0,0 +1,87 @@
+//! Budget on the total current of the stewart platform motors, to protect the 5 V supply.
+//!
+//! The six XL330 of the stewart platform share the supply: during aggressive motions
+//! their currents add up and can brown it out, rebooting all the motors. With a budget
+//! enabled with [`crate::control_loop::ReachyMiniControlLoop::enable_current_budget`],
+//! the control loop reads their currents at each tick and, while the total exceeds the
+//! budget, pulls their goals back toward the measured positions: the position error,
+//! hence the current asked by the position controller of the motors, is scaled down.
+//! The scale recovers progressively once the total is back under the budget.
+
+use crate::smoothing::NUM_JOINTS;
+
+/// Joints of the stewart platform, in joint order.
+const STEWART_JOINTS: std::ops::Range<usize> = 1..7;
+/// Increase of the scale per second while under the budget.
+const RECOVERY_RATE: f64 = 2.0;
+/// The goals are never pulled all the way back, so the joints keep moving slowly.
+const MIN_SCALE: f64 = 0.05;
+
+pub struct CurrentBudget {
+    /// Maximum total current (mA).
+    max_total: f64,
+    scale: f64,
+    /// Last goals written to the stewart platform, before scaling.
+    goals: Option<[f64; 6]>,
+}
+
+impl CurrentBudget {
+    pub fn new(max_total: f64) -> Self {
+        CurrentBudget {
+            max_total,
+            scale: 1.0,
+            goals: None,
+        }
+    }
+
+    /// Forget the goals and stop limiting, e.g. while the torque is off.
+    pub fn reset(&mut self) {
+        self.scale = 1.0;
+        self.goals = None;
+    }
+
+    /// Keep track of goals written for `goals.len()` joints from `first`, in joint order.
+    pub fn track(&mut self, first: usize, goals: &[f64]) {
+        let stewart = STEWART_JOINTS.start.checked_sub(first).and_then(|start| {
+            goals
+                .get(start..start + STEWART_JOINTS.len())
+                .and_then(|goals| goals.try_into().ok())
+        });
+        if let Some(stewart) = stewart {
+            self.goals = Some(stewart);
+        }
+    }
+
+    /// Scale of the position errors, 1 when the budget is not limiting.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Update the scale from the currents (mA) of the stewart platform motors, `dt` (s)
+    /// after the previous update. Returns their total.
+    pub fn update(&mut self, currents: [i16; 6], dt: f64) -> f64 {
+        let total: f64 = currents.iter().map(|c| (*c as f64).abs()).sum();
+        if total > self.max_total {
+            self.scale = (self.scale * self.max_total / total).max(MIN_SCALE);
+        } else {
+            self.scale = (self.scale + RECOVERY_RATE * dt).min(1.0);
+        }
+        total
+    }
+
+    /// Stewart platform goals, the tracked ones or else the ones of `fallback`.
+    pub fn goals(&self, fallback: &[f64; NUM_JOINTS]) -> [f64; 6] {
+        self.goals
+            .unwrap_or_else(|| std::array::from_fn(|i| fallback[STEWART_JOINTS.start + i]))
+    }
+
+    /// Stewart platform goals with the error to the measured `positions` scaled down.
+    pub fn stewart_goals(
+        &self,
+        positions: &[f64; NUM_JOINTS],
+        fallback: &[f64; NUM_JOINTS],
+    ) -> [f64; 6] {
+        let (positions, goals) = (&positions[STEWART_JOINTS], self.goals(fallback));
+        std::array::from_fn(|i| positions[i] + self.scale * (goals[i] - positions[i]))
+    }
+}
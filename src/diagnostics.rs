@@ -0,0 +1,87 @@
+//! Slow background polling of the diagnostics registers.
+//!
+//! The temperature, input voltage and hardware error status of the motors change slowly,
+//! yet reading them on demand competes with the position reads. The present current is
+//! polled along, for monitoring (e.g. in
+//! [`crate::control_loop::ReachyMiniControlLoop::get_snapshot`]). The control loop polls
+//! them on its own once per period (see
+//! [`crate::control_loop::ReachyMiniControlLoop::set_diagnostics_period`]), one register
+//! per tick so a poll never costs more than one extra sync read to a tick, and publishes
+//! the last values with [`crate::control_loop::ReachyMiniControlLoop::get_diagnostics`].
+
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::gen_stub_pyclass;
+
+use crate::smoothing::NUM_JOINTS;
+
+/// How often the diagnostics registers are polled until another period is set.
+pub const DEFAULT_DIAGNOSTICS_PERIOD: Duration = Duration::from_secs(1);
+
+/// Last polled diagnostics of each motor, in joint order. The missing motors read 0.
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Diagnostics {
+    /// Temperature (°C).
+    pub temperatures: [u8; NUM_JOINTS],
+    /// Input voltage (V).
+    pub voltages: [f64; NUM_JOINTS],
+    /// Hardware error status register, 0 without error.
+    pub hardware_errors: [u8; NUM_JOINTS],
+    /// Present current (mA).
+    pub currents: [i16; NUM_JOINTS],
+    /// Time (s since UNIX epoch) of the last register read, 0 before the first one.
+    pub timestamp: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticsRegister {
+    Temperature,
+    Voltage,
+    HardwareErrorStatus,
+    Current,
+}
+
+const REGISTERS: [DiagnosticsRegister; 4] = [
+    DiagnosticsRegister::Temperature,
+    DiagnosticsRegister::Voltage,
+    DiagnosticsRegister::HardwareErrorStatus,
+    DiagnosticsRegister::Current,
+];
+
+pub struct DiagnosticsPoller {
+    last_poll: Option<Instant>,
+    /// Index of the next register of the poll in progress.
+    next: usize,
+}
+
+impl Default for DiagnosticsPoller {
+    fn default() -> Self {
+        DiagnosticsPoller {
+            last_poll: None,
+            next: REGISTERS.len(),
+        }
+    }
+}
+
+impl DiagnosticsPoller {
+    /// Register to read at the tick `now`, if any.
+    pub fn next(&mut self, period: Duration, now: Instant) -> Option<DiagnosticsRegister> {
+        if self.next == REGISTERS.len() {
+            if self
+                .last_poll
+                .is_some_and(|last| now.saturating_duration_since(last) < period)
+            {
+                return None;
+            }
+            self.last_poll = Some(now);
+            self.next = 0;
+        }
+        self.next += 1;
+        Some(REGISTERS[self.next - 1])
+    }
+}
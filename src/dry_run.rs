@@ -0,0 +1,107 @@
+//! Dry-run mode of the motor controller, to test application logic against the real API
+//! without moving the motors (e.g. on a developer laptop).
+//!
+//! With a [`DryRunLog`] set by
+//! [`crate::ReachyMiniMotorController::set_dry_run`], the write methods still validate
+//! their arguments, but log and record what they would send instead of writing it to the
+//! bus. The reads still go to the port: pair it with a
+//! [`crate::simulation::Simulation`] when no robot is plugged in. Only the last
+//! [`DEFAULT_DRY_RUN_LOG_SIZE`] writes are kept.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::clock::monotonic_now;
+
+/// Number of writes kept in a [`DryRunLog`].
+pub const DEFAULT_DRY_RUN_LOG_SIZE: usize = 10_000;
+
+/// A write which was not sent to the bus.
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DryRunWrite {
+    /// What would have been written, e.g. "goal_position", "torque_enable", "reboot" or
+    /// "raw_packet".
+    pub register: String,
+    /// Motors written, empty for a raw packet.
+    pub ids: Vec<u8>,
+    /// Values written, one per motor (debug formatted).
+    pub values: String,
+    /// Monotonic clock time (s), see [`crate::clock`].
+    pub monotonic_timestamp: f64,
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl DryRunWrite {
+    fn __repr__(&self) -> String {
+        format!(
+            "DryRunWrite(register={:?}, ids={:?}, values={:?})",
+            self.register, self.ids, self.values
+        )
+    }
+}
+
+/// Writes recorded in dry-run mode, oldest first.
+///
+/// Cloning gives another handle on the same log, to read it while the controller is
+/// owned by e.g. the control loop.
+#[derive(Debug, Clone)]
+pub struct DryRunLog {
+    writes: Arc<Mutex<VecDeque<DryRunWrite>>>,
+    size: usize,
+}
+
+impl Default for DryRunLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_DRY_RUN_LOG_SIZE)
+    }
+}
+
+impl DryRunLog {
+    /// A log keeping the last `size` writes.
+    pub fn new(size: usize) -> Self {
+        DryRunLog {
+            writes: Arc::new(Mutex::new(VecDeque::new())),
+            size: size.max(1),
+        }
+    }
+
+    /// The recorded writes.
+    pub fn writes(&self) -> Vec<DryRunWrite> {
+        self.lock().iter().cloned().collect()
+    }
+
+    /// The recorded writes, clearing the log.
+    pub fn take(&self) -> Vec<DryRunWrite> {
+        self.lock().drain(..).collect()
+    }
+
+    pub(crate) fn record(&self, register: &str, ids: &[u8], values: String) {
+        log::debug!("Dry run: {} of {:?} <- {}", register, ids, values);
+        let mut writes = self.lock();
+        if writes.len() == self.size {
+            writes.pop_front();
+        }
+        writes.push_back(DryRunWrite {
+            register: register.to_string(),
+            ids: ids.to_vec(),
+            values,
+            monotonic_timestamp: monotonic_now(),
+        });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<DryRunWrite>> {
+        // A panic while recording leaves the log consistent
+        self.writes.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,143 @@
+//! Duty-cycle limiter: an i²t model of the heating of each motor.
+//!
+//! The XL330s shut down with an overload error when they run above their continuous
+//! current rating for too long, e.g. holding the head against gravity while nodding.
+//! Enabled with [`crate::control_loop::ReachyMiniControlLoop::enable_duty_cycle_limiter`],
+//! the control loop keeps the mean square current of each motor over the thermal time
+//! constant and compares its root to the continuous rating:
+//! - above [`WARNING_LOAD`], a "duty_cycle" event is recorded and a warning logged,
+//! - above 1, the goal current of the stewart platform motors is lowered to the
+//!   continuous rating (the body and antennas are only warned about), until the load is
+//!   back under [`WARNING_LOAD`].
+
+use std::time::{Duration, Instant};
+
+use crate::smoothing::NUM_JOINTS;
+
+/// Continuous current rating (mA) used until another one is given.
+pub const DEFAULT_CONTINUOUS_CURRENT: f64 = 500.0;
+/// Thermal time constant (s) used until another one is given.
+pub const DEFAULT_THERMAL_TIME_CONSTANT: f64 = 60.0;
+/// Load (RMS current over the continuous rating) above which a motor is warned about.
+pub const WARNING_LOAD: f64 = 0.9;
+/// How often the currents are read, heating is slow.
+pub const DUTY_CYCLE_UPDATE_PERIOD: Duration = Duration::from_millis(100);
+/// The load must go this much under a threshold to leave its level.
+const HYSTERESIS: f64 = 0.1;
+
+/// Joints of the stewart platform, whose goal current can be lowered.
+const STEWART_JOINTS: std::ops::Range<usize> = 1..7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DutyCycleLevel {
+    Normal,
+    /// Approaching the continuous rating.
+    Warning,
+    /// Above the continuous rating.
+    Overloaded,
+}
+
+impl DutyCycleLevel {
+    fn of(load: f64) -> Self {
+        if load >= 1.0 {
+            DutyCycleLevel::Overloaded
+        } else if load >= WARNING_LOAD {
+            DutyCycleLevel::Warning
+        } else {
+            DutyCycleLevel::Normal
+        }
+    }
+}
+
+pub struct DutyCycleLimiter {
+    /// Continuous current rating (mA).
+    continuous_current: f64,
+    /// Thermal time constant (s).
+    time_constant: f64,
+    /// Mean square current of each joint over about the time constant (mA²).
+    mean_square: [f64; NUM_JOINTS],
+    levels: [DutyCycleLevel; NUM_JOINTS],
+    last_update: Option<Instant>,
+    /// Goal currents of the stewart platform before they were lowered.
+    nominal_currents: Option<[i16; 6]>,
+}
+
+impl DutyCycleLimiter {
+    pub fn new(continuous_current: f64, time_constant: f64) -> Self {
+        DutyCycleLimiter {
+            continuous_current,
+            time_constant,
+            mean_square: [0.0; NUM_JOINTS],
+            levels: [DutyCycleLevel::Normal; NUM_JOINTS],
+            last_update: None,
+            nominal_currents: None,
+        }
+    }
+
+    pub fn last_update(&self) -> Option<Instant> {
+        self.last_update
+    }
+
+    /// Account for the currents (mA) of all joints, in joint order, measured at `now`.
+    ///
+    /// Returns the joints whose level changed, with their new level.
+    pub fn update(
+        &mut self,
+        currents: [i16; NUM_JOINTS],
+        now: Instant,
+    ) -> Vec<(usize, DutyCycleLevel)> {
+        let dt = self.last_update.map_or(0.0, |last| {
+            now.saturating_duration_since(last).as_secs_f64()
+        });
+        self.last_update = Some(now);
+        let alpha = 1.0 - (-dt / self.time_constant).exp();
+        for (mean_square, current) in self.mean_square.iter_mut().zip(currents) {
+            *mean_square += alpha * ((current as f64).powi(2) - *mean_square);
+        }
+
+        let loads = self.loads();
+        let mut changes = Vec::new();
+        for (joint, level) in self.levels.iter_mut().enumerate() {
+            let new_level = match DutyCycleLevel::of(loads[joint]) {
+                higher if higher > *level => higher,
+                _ => DutyCycleLevel::of(loads[joint] + HYSTERESIS).min(*level),
+            };
+            if new_level != *level {
+                *level = new_level;
+                changes.push((joint, new_level));
+            }
+        }
+        changes
+    }
+
+    /// RMS current of each joint over its continuous rating.
+    pub fn loads(&self) -> [f64; NUM_JOINTS] {
+        self.mean_square
+            .map(|mean_square| mean_square.sqrt() / self.continuous_current)
+    }
+
+    /// Whether a stewart platform motor is overloaded, so its goal current is lowered.
+    pub fn is_limiting(&self) -> bool {
+        self.levels[STEWART_JOINTS].contains(&DutyCycleLevel::Overloaded)
+    }
+
+    /// Goal currents of the stewart platform to restore once no motor is overloaded.
+    pub fn nominal_currents(&self) -> Option<[i16; 6]> {
+        self.nominal_currents
+    }
+
+    pub fn set_nominal_currents(&mut self, currents: Option<[i16; 6]>) {
+        self.nominal_currents = currents;
+    }
+
+    /// The `nominal` goal currents, lowered to the continuous rating for the overloaded
+    /// motors.
+    pub fn limited_currents(&self, nominal: [i16; 6]) -> [i16; 6] {
+        let rating = self.continuous_current.round().min(i16::MAX as f64) as i16;
+        let levels = &self.levels[STEWART_JOINTS];
+        std::array::from_fn(|i| match levels[i] {
+            DutyCycleLevel::Overloaded => nominal[i].min(rating),
+            _ => nominal[i],
+        })
+    }
+}
@@ -0,0 +1,70 @@
+//! Best-effort torque disable when the process panics.
+//!
+//! Each controller registers a clone of its serial port handle. A panic hook,
+//! installed once and chained with the previous one, broadcasts a torque disable
+//! on all the registered ports, so a panic anywhere in the process does not leave
+//! the head powered with nobody driving it.
+
+use std::sync::{
+    Mutex, Once,
+    atomic::{AtomicU64, Ordering},
+};
+
+use rustypot::servo::dynamixel::xl330;
+use serialport::SerialPort;
+
+const BROADCAST_ID: u8 = 254;
+
+static PORTS: Mutex<Vec<(u64, Box<dyn SerialPort>)>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static INSTALL_HOOK: Once = Once::new();
+
+/// Registration of a port in the panic hook, removed when dropped.
+#[derive(Debug)]
+pub struct EmergencyStop {
+    id: u64,
+}
+
+impl EmergencyStop {
+    /// Register the port so the torque is disabled on it if the process panics.
+    pub fn register(port: &dyn SerialPort) -> Result<EmergencyStop, serialport::Error> {
+        let port = port.try_clone()?;
+
+        INSTALL_HOOK.call_once(|| {
+            let previous_hook = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                disable_torque_on_all_ports();
+                previous_hook(info);
+            }));
+        });
+
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        match PORTS.lock() {
+            Ok(mut ports) => ports.push((id, port)),
+            Err(poisoned) => poisoned.into_inner().push((id, port)),
+        }
+        Ok(EmergencyStop { id })
+    }
+}
+
+impl Drop for EmergencyStop {
+    fn drop(&mut self) {
+        let mut ports = match PORTS.lock() {
+            Ok(ports) => ports,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        ports.retain(|(id, _)| *id != self.id);
+    }
+}
+
+fn disable_torque_on_all_ports() {
+    // Never block in a panic hook: if the lock is held, we cannot do anything safely
+    let Ok(mut ports) = PORTS.try_lock() else {
+        return;
+    };
+    let dph = rustypot::DynamixelProtocolHandler::v2();
+    for (_, port) in ports.iter_mut() {
+        // Broadcast write: there is no status packet so this always "fails" on the reply
+        let _ = xl330::write_torque_enable(&dph, port.as_mut(), BROADCAST_ID, false);
+    }
+}
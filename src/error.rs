@@ -0,0 +1,263 @@
+//! Errors of the controller and of the control loop bus operations.
+//!
+//! [`MotorControllerError`] tells the transient bus glitches (timeouts, corrupted
+//! packets), which are worth retrying, from the failures which need a human: the port
+//! lost or used by another process, goals out of bounds, a firmware lacking a feature...
+//! see [`MotorControllerError::is_transient`].
+
+use std::{fmt, sync::Arc};
+
+use rustypot::CommunicationErrorKind;
+
+use crate::{
+    controller::{GroupWriteError, WriteVerificationError},
+    kinematics::KinematicsError,
+    port_lock::PortInUseError,
+    position_bounds::PositionOutOfBounds,
+    read_filter::ImplausibleRead,
+};
+
+#[derive(Debug, Clone)]
+pub enum MotorControllerError {
+    /// The serial port failed, e.g. it was unplugged.
+    SerialIo(Arc<std::io::Error>),
+    /// The serial port could not be opened or configured.
+    SerialPort(serialport::Error),
+    /// The serial port is already used by another process.
+    PortInUse(PortInUseError),
+    /// No answer from the motors before the port timeout.
+    Timeout,
+    /// A single motor addressed did not answer.
+    MotorNotResponding {
+        id: u8,
+    },
+    /// A status packet failed its checksum, e.g. corrupted by noise on the bus.
+    Checksum,
+    /// A status packet came from another motor than the one addressed.
+    IncorrectId {
+        expected: u8,
+        received: u8,
+    },
+    /// A status packet could not be parsed or does not match the request.
+    UnexpectedResponse(String),
+    /// A value is outside of the accepted range, e.g. a goal beyond the position bounds
+    /// or an invalid joint index.
+    OutOfBounds(String),
+    /// The motors (firmware, dry-run mode...) do not support the operation.
+    Unsupported(String),
+    /// Some groups of a goal write failed, see
+    /// [`crate::ReachyMiniMotorController::set_all_goal_positions_by_group`].
+    GroupWrite(GroupWriteError),
+    /// An EEPROM register never read back as written.
+    WriteVerification(Box<WriteVerificationError>),
+    /// A read position was dropped by the read filter, see [`crate::read_filter`].
+    ImplausibleRead(ImplausibleRead),
+    Kinematics(KinematicsError),
+    /// The control loop refused the command, e.g. the power is lost or the configuration
+    /// locked.
+    Rejected(String),
+    /// Any other error, e.g. of an operation run with
+    /// [`crate::ReachyMiniMotorController::with_bus`].
+    Other(String),
+}
+
+impl MotorControllerError {
+    /// Whether the error is a bus glitch which may not happen again, so the operation
+    /// can be retried right away.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            MotorControllerError::Timeout
+            | MotorControllerError::MotorNotResponding { .. }
+            | MotorControllerError::Checksum
+            | MotorControllerError::IncorrectId { .. }
+            | MotorControllerError::UnexpectedResponse(_)
+            | MotorControllerError::ImplausibleRead(_) => true,
+            MotorControllerError::SerialIo(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::TimedOut | std::io::ErrorKind::Interrupted
+            ),
+            _ => false,
+        }
+    }
+
+    /// Id of the motor the error comes from, when known.
+    pub fn motor_id(&self) -> Option<u8> {
+        match self {
+            MotorControllerError::MotorNotResponding { id } => Some(*id),
+            MotorControllerError::IncorrectId { expected, .. } => Some(*expected),
+            _ => None,
+        }
+    }
+
+    /// Conversion of the errors of a request addressed to the motor `id` only, a timeout
+    /// then meaning this motor is not responding.
+    pub(crate) fn from_motor(
+        id: u8,
+    ) -> impl FnOnce(Box<dyn std::error::Error>) -> MotorControllerError {
+        move |e| match MotorControllerError::from(e) {
+            MotorControllerError::Timeout => MotorControllerError::MotorNotResponding { id },
+            e => e,
+        }
+    }
+}
+
+impl std::error::Error for MotorControllerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MotorControllerError::SerialIo(e) => Some(e.as_ref()),
+            MotorControllerError::SerialPort(e) => Some(e),
+            MotorControllerError::PortInUse(e) => Some(e),
+            MotorControllerError::GroupWrite(e) => Some(e),
+            MotorControllerError::WriteVerification(e) => Some(e.as_ref()),
+            MotorControllerError::ImplausibleRead(e) => Some(e),
+            MotorControllerError::Kinematics(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MotorControllerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MotorControllerError::SerialIo(e) => write!(f, "Serial I/O error: {}", e),
+            MotorControllerError::SerialPort(e) => write!(f, "Serial port error: {}", e),
+            MotorControllerError::PortInUse(e) => write!(f, "{}", e),
+            MotorControllerError::Timeout => write!(f, "Timeout error"),
+            MotorControllerError::MotorNotResponding { id } => {
+                write!(f, "Motor {} is not responding", id)
+            }
+            MotorControllerError::Checksum => write!(f, "Checksum error"),
+            MotorControllerError::IncorrectId { expected, received } => {
+                write!(f, "Incorrect id ({} instead of {})", received, expected)
+            }
+            MotorControllerError::UnexpectedResponse(msg) => {
+                write!(f, "Unexpected response: {}", msg)
+            }
+            MotorControllerError::OutOfBounds(msg) => write!(f, "Out of bounds: {}", msg),
+            MotorControllerError::Unsupported(msg) => write!(f, "Unsupported: {}", msg),
+            MotorControllerError::GroupWrite(e) => write!(f, "{}", e),
+            MotorControllerError::WriteVerification(e) => write!(f, "{}", e),
+            MotorControllerError::ImplausibleRead(e) => write!(f, "{}", e),
+            MotorControllerError::Kinematics(e) => write!(f, "{}", e),
+            MotorControllerError::Rejected(msg) => write!(f, "{}", msg),
+            MotorControllerError::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+/// Serialized as its message, e.g. in [`crate::control_loop::MotorError`].
+#[cfg(feature = "serde")]
+impl serde::Serialize for MotorControllerError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl From<std::io::Error> for MotorControllerError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::TimedOut => MotorControllerError::Timeout,
+            _ => MotorControllerError::SerialIo(Arc::new(e)),
+        }
+    }
+}
+
+impl From<serialport::Error> for MotorControllerError {
+    fn from(e: serialport::Error) -> Self {
+        match e.kind() {
+            serialport::ErrorKind::Io(kind) => {
+                MotorControllerError::from(std::io::Error::new(kind, e.description))
+            }
+            _ => MotorControllerError::SerialPort(e),
+        }
+    }
+}
+
+impl From<CommunicationErrorKind> for MotorControllerError {
+    fn from(kind: CommunicationErrorKind) -> Self {
+        match kind {
+            CommunicationErrorKind::TimeoutError => MotorControllerError::Timeout,
+            CommunicationErrorKind::ChecksumError => MotorControllerError::Checksum,
+            // (received id, expected id)
+            CommunicationErrorKind::IncorrectId(received, expected) => {
+                MotorControllerError::IncorrectId { expected, received }
+            }
+            CommunicationErrorKind::ParsingError => {
+                MotorControllerError::UnexpectedResponse(kind.to_string())
+            }
+            CommunicationErrorKind::Unsupported => {
+                MotorControllerError::Unsupported(kind.to_string())
+            }
+        }
+    }
+}
+
+/// The errors of rustypot (and of the operations given to
+/// [`crate::ReachyMiniMotorController::with_bus`]) are boxed.
+impl From<Box<dyn std::error::Error>> for MotorControllerError {
+    fn from(e: Box<dyn std::error::Error>) -> Self {
+        let e = match e.downcast::<MotorControllerError>() {
+            Ok(e) => return *e,
+            Err(e) => e,
+        };
+        if let Some(kind) = e.downcast_ref::<CommunicationErrorKind>() {
+            return MotorControllerError::from(*kind);
+        }
+        let e = match e.downcast::<std::io::Error>() {
+            Ok(e) => return MotorControllerError::from(*e),
+            Err(e) => e,
+        };
+        let e = match e.downcast::<serialport::Error>() {
+            Ok(e) => return MotorControllerError::from(*e),
+            Err(e) => e,
+        };
+        match e.downcast::<PortInUseError>() {
+            Ok(e) => MotorControllerError::PortInUse(*e),
+            Err(e) => MotorControllerError::Other(e.to_string()),
+        }
+    }
+}
+
+/// A reply of the control loop not received, e.g. the caller gave up waiting.
+impl<T> From<std::sync::mpsc::SendError<T>> for MotorControllerError {
+    fn from(e: std::sync::mpsc::SendError<T>) -> Self {
+        MotorControllerError::Other(e.to_string())
+    }
+}
+
+/// Joints given as a slice of the wrong length.
+impl From<std::array::TryFromSliceError> for MotorControllerError {
+    fn from(e: std::array::TryFromSliceError) -> Self {
+        MotorControllerError::OutOfBounds(e.to_string())
+    }
+}
+
+impl From<GroupWriteError> for MotorControllerError {
+    fn from(e: GroupWriteError) -> Self {
+        MotorControllerError::GroupWrite(e)
+    }
+}
+
+impl From<WriteVerificationError> for MotorControllerError {
+    fn from(e: WriteVerificationError) -> Self {
+        MotorControllerError::WriteVerification(Box::new(e))
+    }
+}
+
+impl From<ImplausibleRead> for MotorControllerError {
+    fn from(e: ImplausibleRead) -> Self {
+        MotorControllerError::ImplausibleRead(e)
+    }
+}
+
+impl From<KinematicsError> for MotorControllerError {
+    fn from(e: KinematicsError) -> Self {
+        MotorControllerError::Kinematics(e)
+    }
+}
+
+impl From<PositionOutOfBounds> for MotorControllerError {
+    fn from(e: PositionOutOfBounds) -> Self {
+        MotorControllerError::OutOfBounds(e.to_string())
+    }
+}
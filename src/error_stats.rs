@@ -0,0 +1,138 @@
+//! Counters of the errors met by the control loop.
+//!
+//! Applications can poll them (see [`crate::control_loop::ReachyMiniControlLoop::get_error_stats`])
+//! to implement their own degradation logic, e.g. stop an animation when a motor
+//! keeps timing out.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::MotorControllerError;
+
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ErrorCounter {
+    pub count: u64,
+    /// Time of the last occurrence (seconds since the UNIX epoch).
+    pub last_timestamp: f64,
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl ErrorCounter {
+    fn __repr__(&self) -> String {
+        format!(
+            "ErrorCounter(count={}, last_timestamp={:.3})",
+            self.count, self.last_timestamp
+        )
+    }
+}
+
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ErrorStats {
+    /// All errors, whatever their kind.
+    pub total: ErrorCounter,
+    /// Errors by kind: "timeout", "checksum", "parsing", "incorrect_id", "unsupported", "io",
+    /// "implausible" (see [`crate::read_filter`]), "out_of_bounds" (see [`crate::position_bounds`]),
+    /// "write_verification" (an EEPROM value not read back as written) or "other".
+    pub per_kind: HashMap<String, ErrorCounter>,
+    /// Errors by motor name.
+    ///
+    /// Only the errors identifying the faulty motor are counted here: a timeout
+    /// during a sync read of all motors, for instance, is not attributed to any.
+    pub per_motor: HashMap<String, ErrorCounter>,
+    /// Failed goal writes by motor group: "body_rotation", "stewart_platform" or "antennas".
+    pub per_group: HashMap<String, ErrorCounter>,
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl ErrorStats {
+    fn __repr__(&self) -> String {
+        format!(
+            "ErrorStats(total={}, per_kind={:?}, per_motor={:?}, per_group={:?})",
+            self.total.count,
+            self.per_kind
+                .iter()
+                .map(|(kind, counter)| (kind, counter.count))
+                .collect::<HashMap<_, _>>(),
+            self.per_motor
+                .iter()
+                .map(|(name, counter)| (name, counter.count))
+                .collect::<HashMap<_, _>>(),
+            self.per_group
+                .iter()
+                .map(|(group, counter)| (group, counter.count))
+                .collect::<HashMap<_, _>>(),
+        )
+    }
+}
+
+impl ErrorCounter {
+    fn record(&mut self, timestamp: f64) {
+        self.count += 1;
+        self.last_timestamp = timestamp;
+    }
+}
+
+impl ErrorStats {
+    /// Count an error, `motor_names` mapping the motor ids to their names.
+    ///
+    /// Each failure of a [`MotorControllerError::GroupWrite`] is counted on its own.
+    pub fn record(&mut self, error: &MotorControllerError, motor_names: &HashMap<u8, String>) {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+
+        if let MotorControllerError::GroupWrite(group_error) = error {
+            for (group, e) in &group_error.failures {
+                self.record(e, motor_names);
+                self.per_group
+                    .entry(group.to_string())
+                    .or_default()
+                    .record(timestamp);
+            }
+            return;
+        }
+
+        self.total.record(timestamp);
+        self.per_kind
+            .entry(kind(error).to_string())
+            .or_default()
+            .record(timestamp);
+        if let Some(name) = error.motor_id().and_then(|id| motor_names.get(&id)) {
+            self.per_motor
+                .entry(name.clone())
+                .or_default()
+                .record(timestamp);
+        }
+    }
+}
+
+/// Kind of the error, as counted in [`ErrorStats::per_kind`].
+fn kind(error: &MotorControllerError) -> &'static str {
+    match error {
+        MotorControllerError::Timeout | MotorControllerError::MotorNotResponding { .. } => {
+            "timeout"
+        }
+        MotorControllerError::Checksum => "checksum",
+        MotorControllerError::UnexpectedResponse(_) => "parsing",
+        MotorControllerError::IncorrectId { .. } => "incorrect_id",
+        MotorControllerError::Unsupported(_) => "unsupported",
+        MotorControllerError::SerialIo(_) => "io",
+        MotorControllerError::ImplausibleRead(_) => "implausible",
+        MotorControllerError::OutOfBounds(_) => "out_of_bounds",
+        MotorControllerError::WriteVerification(_) => "write_verification",
+        _ => "other",
+    }
+}
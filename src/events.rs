@@ -0,0 +1,107 @@
+//! Journal of the notable control loop events, a timeline for postmortems.
+//!
+//! The control loop records the torque and operating mode changes, the motor faults,
+//! the power losses and recoveries, the watchdog trips, the USB device being
+//! plugged in or unplugged, the end of the trajectories, the current budget of the
+//! stewart platform starting to limit its goals, the motors approaching their
+//! continuous current rating and the motor configuration deviations found at init, see
+//! [`crate::control_loop::ReachyMiniControlLoop::get_events`]. Only the last
+//! [`DEFAULT_EVENT_LOG_SIZE`] events are kept, the faults can also be persisted with a
+//! [`crate::fault_journal`].
+
+use std::collections::VecDeque;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::{
+    clock::{monotonic_now, wall_now},
+    fault_journal::FaultJournal,
+};
+
+/// Number of events kept in the journal.
+pub const DEFAULT_EVENT_LOG_SIZE: usize = 1000;
+
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Event {
+    /// "torque", "mode", "fault", "reconnect", "watchdog", "hotplug", "trajectory",
+    /// "current_budget", "duty_cycle" or "config".
+    pub kind: String,
+    pub message: String,
+    /// Seconds since the UNIX epoch.
+    pub timestamp: f64,
+    /// Same instant on the monotonic clock (s), see [`crate::clock`].
+    pub monotonic_timestamp: f64,
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl Event {
+    fn __repr__(&self) -> String {
+        format!(
+            "Event(kind={:?}, message={:?}, timestamp={:.3})",
+            self.kind, self.message, self.timestamp
+        )
+    }
+}
+
+/// Ring buffer of the last events, oldest first.
+pub(crate) struct EventLog {
+    events: VecDeque<Event>,
+    journal: Option<FaultJournal>,
+}
+
+impl EventLog {
+    pub(crate) fn new() -> Self {
+        EventLog {
+            events: VecDeque::with_capacity(DEFAULT_EVENT_LOG_SIZE),
+            journal: None,
+        }
+    }
+
+    pub(crate) fn set_journal(&mut self, journal: Option<FaultJournal>) {
+        self.journal = journal;
+    }
+
+    pub(crate) fn record(&mut self, kind: &str, message: String) {
+        if self.events.len() == DEFAULT_EVENT_LOG_SIZE {
+            self.events.pop_front();
+        }
+        let event = Event {
+            kind: kind.to_string(),
+            message,
+            timestamp: wall_now(),
+            monotonic_timestamp: monotonic_now(),
+        };
+        if FaultJournal::is_fault(kind)
+            && let Some(journal) = self.journal.as_mut()
+            && let Err(e) = journal.append(&event)
+        {
+            log::error!(
+                "Could not write to the fault journal {}: {}",
+                journal.path().display(),
+                e
+            );
+        }
+        self.events.push_back(event);
+    }
+
+    /// Events recorded after `timestamp` (seconds since UNIX epoch), oldest first.
+    pub(crate) fn since(&self, timestamp: f64) -> Vec<Event> {
+        let first = self
+            .events
+            .partition_point(|event| event.timestamp <= timestamp);
+        self.events.range(first..).cloned().collect()
+    }
+
+    /// The last `n` events, oldest first.
+    pub(crate) fn last(&self, n: usize) -> Vec<Event> {
+        let skip = self.events.len().saturating_sub(n);
+        self.events.iter().skip(skip).cloned().collect()
+    }
+}
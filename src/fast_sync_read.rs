@@ -0,0 +1,166 @@
+//! Fast Sync Read instruction (0x8A) of the Dynamixel protocol v2.
+//!
+//! A sync read (0x82) gets one status packet per motor, each with its own header, id,
+//! length and CRC, and the motors answer one after the other once their predecessor is
+//! done. With a fast sync read the motors fill a single status packet in turn, saving
+//! about 10 bytes per motor on the bus, and the host waits for one packet only.
+//!
+//! rustypot does not provide it, the packets are built and parsed here. The status
+//! packet holds, for each motor: its error, its id, the data read and a CRC, the last
+//! one being the CRC of the whole packet:
+//! ```text
+//! FF FF FD 00 FE LEN_L LEN_H 55 | ERR ID DATA... CRC_L CRC_H | ERR ID DATA... CRC_L CRC_H | ...
+//! ```
+//!
+//! Only supported from firmware v45, see [`crate::firmware::Feature::FastSyncRead`].
+
+use std::f64::consts::PI;
+
+use crate::MotorControllerError;
+
+pub(crate) const FAST_SYNC_READ: u8 = 0x8A;
+pub(crate) const HEADER: [u8; 4] = [0xFF, 0xFF, 0xFD, 0x00];
+pub(crate) const BROADCAST_ID: u8 = 0xFE;
+const STATUS: u8 = 0x55;
+const PRESENT_POSITION_ADDRESS: u16 = 132;
+const PRESENT_POSITION_LENGTH: u16 = 4;
+
+/// CRC-16 of the Dynamixel protocol v2 (polynomial 0x8005).
+pub(crate) fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Instruction packet reading `length` bytes at `address` from the motors `ids`.
+pub(crate) fn instruction_packet(ids: &[u8], address: u16, length: u16) -> Vec<u8> {
+    let mut packet = HEADER.to_vec();
+    packet.push(BROADCAST_ID);
+    // Instruction, address, length, ids and CRC
+    packet.extend((ids.len() as u16 + 7).to_le_bytes());
+    packet.push(FAST_SYNC_READ);
+    packet.extend(address.to_le_bytes());
+    packet.extend(length.to_le_bytes());
+    packet.extend(ids);
+    packet.extend(crc16(&packet).to_le_bytes());
+    packet
+}
+
+/// Length field of the status packet answering a read of `length` bytes from `count`
+/// motors: the status instruction, then per motor its error, id, data and CRC.
+pub(crate) fn status_length(count: usize, length: usize) -> usize {
+    1 + count * (length + 4)
+}
+
+/// Remove the 0xFD stuffed after each 0xFF 0xFF 0xFD of the packet content.
+fn unstuff(content: &[u8]) -> Vec<u8> {
+    let mut unstuffed = Vec::with_capacity(content.len());
+    let mut stuffed = false;
+    for &byte in content {
+        if stuffed && byte == 0xFD {
+            stuffed = false;
+            continue;
+        }
+        unstuffed.push(byte);
+        stuffed = unstuffed.ends_with(&[0xFF, 0xFF, 0xFD]);
+    }
+    unstuffed
+}
+
+/// Data read from each of the motors `ids` in the status `packet`, header included.
+pub(crate) fn parse_status(
+    packet: &[u8],
+    ids: &[u8],
+    length: usize,
+) -> Result<Vec<Vec<u8>>, MotorControllerError> {
+    if packet.len() < 10 || packet[..4] != HEADER {
+        return Err(MotorControllerError::UnexpectedResponse(
+            "Invalid fast sync read status packet header".to_string(),
+        ));
+    }
+    let (content, crc) = packet.split_at(packet.len() - 2);
+    if crc16(content) != u16::from_le_bytes([crc[0], crc[1]]) {
+        return Err(MotorControllerError::Checksum);
+    }
+    let content = unstuff(&content[7..]);
+    if content.first() != Some(&STATUS) {
+        return Err(MotorControllerError::UnexpectedResponse(
+            "Invalid fast sync read status packet instruction".to_string(),
+        ));
+    }
+    // The CRC of the last motor is the one of the packet, checked above
+    if content.len() + 2 != status_length(ids.len(), length) {
+        return Err(MotorControllerError::UnexpectedResponse(format!(
+            "Invalid fast sync read status packet length: {} for {} motors",
+            content.len() + 2,
+            ids.len()
+        )));
+    }
+
+    content[1..]
+        .chunks(length + 4)
+        .zip(ids)
+        .map(|(block, &id)| match (block[0], block[1]) {
+            (0, actual) if actual == id => Ok(block[2..2 + length].to_vec()),
+            (0, actual) => Err(MotorControllerError::IncorrectId {
+                expected: id,
+                received: actual,
+            }),
+            (error, _) => Err(MotorControllerError::UnexpectedResponse(format!(
+                "Fast sync read error 0x{:02x} from motor {}",
+                error, id
+            ))),
+        })
+        .collect()
+}
+
+/// Read `length` bytes at `address` from the motors `ids` in a single status packet.
+pub(crate) fn fast_sync_read(
+    port: &mut dyn serialport::SerialPort,
+    ids: &[u8],
+    address: u16,
+    length: u16,
+) -> Result<Vec<Vec<u8>>, MotorControllerError> {
+    // Drop what is left of a previous corrupted answer, like rustypot does
+    let pending = port.bytes_to_read()? as usize;
+    if pending > 0 {
+        port.read_exact(&mut vec![0u8; pending])?;
+    }
+    port.write_all(&instruction_packet(ids, address, length))?;
+
+    let mut packet = vec![0u8; 7];
+    port.read_exact(&mut packet)?;
+    if packet[..4] != HEADER {
+        return Err(MotorControllerError::UnexpectedResponse(
+            "Invalid fast sync read status packet header".to_string(),
+        ));
+    }
+    let content_length = u16::from_le_bytes([packet[5], packet[6]]) as usize;
+    packet.resize(7 + content_length, 0);
+    port.read_exact(&mut packet[7..])?;
+    parse_status(&packet, ids, length as usize)
+}
+
+/// Present position (rad) of the motors `ids`, with the conversion of rustypot.
+pub(crate) fn fast_sync_read_present_position(
+    port: &mut dyn serialport::SerialPort,
+    ids: &[u8],
+) -> Result<Vec<f64>, MotorControllerError> {
+    let data = fast_sync_read(port, ids, PRESENT_POSITION_ADDRESS, PRESENT_POSITION_LENGTH)?;
+    Ok(data
+        .into_iter()
+        .map(|bytes| {
+            let raw = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            2.0 * PI * raw as f64 / 4096.0 - PI
+        })
+        .collect())
+}
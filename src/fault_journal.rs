@@ -0,0 +1,148 @@
+//! Append-only journal of the faults on disk, with rotation.
+//!
+//! The event log (see [`crate::events`]) only keeps the last events in memory. Once a
+//! journal is set with [`crate::control_loop::ReachyMiniControlLoop::set_fault_journal`],
+//! the faults among the events (hardware errors such as overloads, power losses and
+//! reconnections, watchdog trips, USB device changes, current budget limiting and thermal
+//! load warnings, see [`FAULT_KINDS`]) are also appended to a file, one per line:
+//! ```text
+//! 1718000000.123456 fault Hardware error 0x20 on stewart_1
+//! ```
+//! so intermittent hardware issues happening overnight can be investigated after the
+//! fact, with [`read_fault_journal`] or any text tool. Once the file would exceed its
+//! maximum size it is renamed with a `.1` suffix, the older ones shifting to `.2`, `.3`...
+//! up to the number of files kept, and a new file is started.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::events::Event;
+
+/// Kinds of the events written to the journal.
+pub const FAULT_KINDS: [&str; 6] = [
+    "fault",
+    "reconnect",
+    "watchdog",
+    "hotplug",
+    "current_budget",
+    "duty_cycle",
+];
+
+/// Size (bytes) above which the journal is rotated by default.
+pub const DEFAULT_MAX_JOURNAL_SIZE: u64 = 1 << 20;
+
+/// Number of rotated files kept by default, besides the current one.
+pub const DEFAULT_MAX_JOURNAL_FILES: usize = 5;
+
+pub struct FaultJournal {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl FaultJournal {
+    /// Open (or create) the journal at `path`, appending to it.
+    pub fn open(path: impl Into<PathBuf>, max_size: u64, max_files: usize) -> io::Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(FaultJournal {
+            path,
+            max_size,
+            max_files,
+            file,
+            size,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the events of this kind are journaled.
+    pub fn is_fault(kind: &str) -> bool {
+        FAULT_KINDS.contains(&kind)
+    }
+
+    /// Append `event` to the journal, rotating it first if it would get too large.
+    pub fn append(&mut self, event: &Event) -> io::Result<()> {
+        let line = format!(
+            "{:.6} {} {}\n",
+            event.timestamp,
+            event.kind,
+            event.message.replace('\n', " ")
+        );
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files > 0 {
+            for index in (1..self.max_files).rev() {
+                let older = rotated_path(&self.path, index);
+                if older.exists() {
+                    std::fs::rename(older, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Faults journaled in `path` and in its rotated files, oldest first.
+///
+/// The monotonic timestamps are not journaled, they are NaN. Malformed lines are skipped.
+pub fn read_fault_journal(path: impl AsRef<Path>) -> io::Result<Vec<Event>> {
+    let path = path.as_ref();
+    let mut files: Vec<PathBuf> = (1..)
+        .map(|index| rotated_path(path, index))
+        .take_while(|path| path.exists())
+        .collect();
+    files.reverse();
+    files.push(path.to_path_buf());
+
+    let mut events = Vec::new();
+    for file in files {
+        for line in BufReader::new(File::open(file)?).lines() {
+            let line = line?;
+            let mut fields = line.splitn(3, ' ');
+            let (Some(timestamp), Some(kind), message) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let Ok(timestamp) = timestamp.parse() else {
+                continue;
+            };
+            events.push(Event {
+                kind: kind.to_string(),
+                message: message.unwrap_or_default().to_string(),
+                timestamp,
+                monotonic_timestamp: f64::NAN,
+            });
+        }
+    }
+    Ok(events)
+}
@@ -0,0 +1,111 @@
+//! Firmware revisions of the motors used on Reachy Mini.
+//!
+//! Some protocol features and registers are only available starting from a given
+//! firmware version. The table below lists them so the controller can detect the
+//! installed versions at init and gate (or adapt) those features instead of failing
+//! with obscure communication errors on older motors.
+//!
+//! See <https://emanual.robotis.com/docs/en/dxl/x/xl330-m288/> for details.
+
+use std::fmt;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+/// Protocol features whose availability depends on the motor firmware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// Fast Sync Read instruction (0x8A), a single status packet for all ids.
+    FastSyncRead,
+    /// Bus Watchdog register (98).
+    BusWatchdog,
+    /// Startup Configuration register (60).
+    StartupConfiguration,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 3] = [
+        Feature::FastSyncRead,
+        Feature::BusWatchdog,
+        Feature::StartupConfiguration,
+    ];
+
+    /// Minimum firmware version supporting this feature.
+    pub fn min_firmware_version(&self) -> u8 {
+        match self {
+            Feature::FastSyncRead => 45,
+            Feature::BusWatchdog => 38,
+            Feature::StartupConfiguration => 45,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::FastSyncRead => "fast_sync_read",
+            Feature::BusWatchdog => "bus_watchdog",
+            Feature::StartupConfiguration => "startup_configuration",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Feature> {
+        Feature::ALL.into_iter().find(|f| f.name() == name)
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Model numbers (register 0) of the motors we know how to drive.
+const KNOWN_MODELS: [(u16, &str); 6] = [
+    (1190, "XL330-M077"),
+    (1200, "XL330-M288"),
+    (1210, "XC330-T181"),
+    (1220, "XC330-T288"),
+    (1230, "XC330-M181"),
+    (1240, "XC330-M288"),
+];
+
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FirmwareInfo {
+    pub model_number: u16,
+    pub firmware_version: u8,
+}
+
+impl FirmwareInfo {
+    pub fn supports(&self, feature: Feature) -> bool {
+        self.firmware_version >= feature.min_firmware_version()
+    }
+
+    /// Human readable model name, if the model number is known.
+    pub fn model_name(&self) -> Option<&'static str> {
+        KNOWN_MODELS
+            .iter()
+            .find(|(number, _)| *number == self.model_number)
+            .map(|(_, name)| *name)
+    }
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl FirmwareInfo {
+    /// Human readable model name, if the model number is known.
+    #[getter(model_name)]
+    fn py_model_name(&self) -> Option<&'static str> {
+        self.model_name()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FirmwareInfo(model={}, firmware_version={})",
+            self.model_name().unwrap_or("unknown"),
+            self.firmware_version
+        )
+    }
+}
@@ -0,0 +1,107 @@
+//! Goals pulled from a provider at a fixed rate, for apps without their own timing loop.
+//!
+//! See [`crate::control_loop::ReachyMiniControlLoop::follow_target`]. The provider is
+//! called from a dedicated thread, so a slow one never delays the control loop: its
+//! goals are dropped once older than the timeout, and the motors hold the last goal.
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{JoinHandle, sleep},
+    time::{Duration, Instant},
+};
+
+use log::error;
+
+use crate::{
+    clock::wall_now,
+    control_loop::{FullBodyPosition, MotorCommand},
+    log_throttle::{DEFAULT_LOG_PERIOD, LogThrottle},
+};
+
+/// Returns the next goal, `None` to keep the current one, or an error to stop following.
+pub type TargetProvider = Box<dyn FnMut() -> Result<Option<FullBodyPosition>, String> + Send>;
+
+/// Thread calling the provider, stopped when dropped.
+///
+/// The thread is not joined: a Python provider waits for the GIL, which the thread
+/// dropping the follower may hold. It exits after the provider call in progress, if any.
+pub(crate) struct TargetFollower {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl TargetFollower {
+    /// Call `provider` every `period` and send its goals with `push`, which returns
+    /// whether the control loop is still running.
+    pub(crate) fn start(
+        mut provider: TargetProvider,
+        period: Duration,
+        timeout: Duration,
+        source: Option<String>,
+        push: impl Fn(MotorCommand) -> bool + Send + 'static,
+    ) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let handle = std::thread::spawn(move || {
+            let mut failures = LogThrottle::new(DEFAULT_LOG_PERIOD);
+            let mut next_tick = Instant::now();
+            while !stopped.load(Ordering::Relaxed) {
+                let called_at = Instant::now();
+                let deadline = wall_now() + timeout.as_secs_f64();
+                match provider() {
+                    Err(e) => {
+                        error!("Target provider failed, stop following it: {}", e);
+                        break;
+                    }
+                    Ok(None) => {}
+                    Ok(Some(_)) if called_at.elapsed() > timeout => {
+                        failures.failure(
+                            "target provider",
+                            format!("answered after {:?}, goal dropped", called_at.elapsed()),
+                        );
+                    }
+                    Ok(Some(positions)) => {
+                        let mut command = MotorCommand::SetAllGoalPositions { positions };
+                        if let Some(source) = &source {
+                            command = MotorCommand::FromSource {
+                                source: source.clone(),
+                                command: Box::new(command),
+                            };
+                        }
+                        // Also dropped if it waits too long in the command queue
+                        let command = MotorCommand::WithDeadline {
+                            deadline,
+                            command: Box::new(command),
+                        };
+                        if stopped.load(Ordering::Relaxed) || !push(command) {
+                            break;
+                        }
+                    }
+                }
+                failures.flush();
+
+                // Skip the ticks missed by a slow provider rather than catching up
+                next_tick += period;
+                let now = Instant::now();
+                match next_tick.checked_duration_since(now) {
+                    Some(delay) => sleep(delay),
+                    None => next_tick = now,
+                }
+            }
+        });
+        TargetFollower { stop, handle }
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        !self.stop.load(Ordering::Relaxed) && !self.handle.is_finished()
+    }
+}
+
+impl Drop for TargetFollower {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
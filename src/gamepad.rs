@@ -0,0 +1,175 @@
+//! Gamepad teleoperation (`gamepad` feature): sticks mapped to the head pose and body yaw.
+//!
+//! Each [`AxisMapping`] drives a component of the goal from a gamepad axis: the axis
+//! value goes through its [`AxisCurve`] (deadzone, then a power curve for a finer control
+//! around the center) and is scaled to the component range. The goals are absolute, the
+//! head goes back home when the sticks are released.
+//!
+//! ```no_run
+//! use reachy_mini_motor_controller::gamepad::{GamepadTeleop, TeleopMapping};
+//!
+//! let mut teleop = GamepadTeleop::new(TeleopMapping::default())?;
+//! if let Some(goal) = teleop.poll() {
+//!     println!("{:?}, body yaw {:.2}", goal.pose, goal.body_yaw);
+//! }
+//! # Ok::<(), Box<gilrs::Error>>(())
+//! ```
+
+pub use gilrs::{Axis, Button};
+use gilrs::{GamepadId, Gilrs};
+
+use crate::kinematics::HeadPose;
+
+/// Shape of the response of an axis, from its raw value in [-1, 1] to [-1, 1].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisCurve {
+    /// Values below it are taken as 0, e.g. for sticks not centered at rest.
+    pub deadzone: f64,
+    /// Power applied past the deadzone, 1 for a linear response.
+    pub exponent: f64,
+}
+
+impl Default for AxisCurve {
+    fn default() -> Self {
+        AxisCurve {
+            deadzone: 0.1,
+            exponent: 2.0,
+        }
+    }
+}
+
+impl AxisCurve {
+    pub fn apply(&self, value: f64) -> f64 {
+        let magnitude = value.abs().min(1.0);
+        if magnitude <= self.deadzone || !value.is_finite() {
+            return 0.0;
+        }
+        // Rescaled so the output starts from 0 at the deadzone
+        let magnitude = (magnitude - self.deadzone) / (1.0 - self.deadzone);
+        magnitude.powf(self.exponent).copysign(value)
+    }
+}
+
+/// Component of the goal driven by an axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoalComponent {
+    /// Head translation (m).
+    X,
+    Y,
+    Z,
+    /// Head rotation (rad).
+    Roll,
+    Pitch,
+    Yaw,
+    /// Body rotation (rad).
+    BodyYaw,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisMapping {
+    pub axis: Axis,
+    pub component: GoalComponent,
+    /// Value of the component with the axis at 1, negative to invert the axis.
+    pub range: f64,
+    pub curve: AxisCurve,
+}
+
+/// Axes of the gamepad driving the goal, the components driven by several axes get the
+/// sum of their values.
+///
+/// By default, the left stick drives the head yaw and pitch, and the right stick the
+/// body yaw and the head height.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeleopMapping {
+    pub axes: Vec<AxisMapping>,
+}
+
+impl Default for TeleopMapping {
+    fn default() -> Self {
+        let mapping = |axis, component, range| AxisMapping {
+            axis,
+            component,
+            range,
+            curve: AxisCurve::default(),
+        };
+        TeleopMapping {
+            axes: vec![
+                // Stick right turns right and stick up looks up
+                mapping(Axis::LeftStickX, GoalComponent::Yaw, -0.6),
+                mapping(Axis::LeftStickY, GoalComponent::Pitch, -0.35),
+                mapping(Axis::RightStickX, GoalComponent::BodyYaw, -1.0),
+                mapping(Axis::RightStickY, GoalComponent::Z, 0.02),
+            ],
+        }
+    }
+}
+
+impl TeleopMapping {
+    /// Goal for the axes values given by `value`.
+    pub fn goal(&self, value: impl Fn(Axis) -> f64) -> TeleopGoal {
+        let mut goal = TeleopGoal::default();
+        for mapping in &self.axes {
+            let offset = mapping.range * mapping.curve.apply(value(mapping.axis));
+            let component = match mapping.component {
+                GoalComponent::X => &mut goal.pose.translation[0],
+                GoalComponent::Y => &mut goal.pose.translation[1],
+                GoalComponent::Z => &mut goal.pose.translation[2],
+                GoalComponent::Roll => &mut goal.pose.rotation[0],
+                GoalComponent::Pitch => &mut goal.pose.rotation[1],
+                GoalComponent::Yaw => &mut goal.pose.rotation[2],
+                GoalComponent::BodyYaw => &mut goal.body_yaw,
+            };
+            *component += offset;
+        }
+        goal
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TeleopGoal {
+    /// Relative to the home pose, see [`crate::kinematics`].
+    pub pose: HeadPose,
+    pub body_yaw: f64,
+}
+
+/// Reads the gamepads and maps the last one used.
+pub struct GamepadTeleop {
+    gilrs: Gilrs,
+    mapping: TeleopMapping,
+    active: Option<GamepadId>,
+}
+
+impl GamepadTeleop {
+    pub fn new(mapping: TeleopMapping) -> Result<Self, Box<gilrs::Error>> {
+        Ok(GamepadTeleop {
+            gilrs: Gilrs::new().map_err(Box::new)?,
+            mapping,
+            active: None,
+        })
+    }
+
+    /// Handle the pending gamepad events and return the goal of the active gamepad,
+    /// `None` if none is connected.
+    pub fn poll(&mut self) -> Option<TeleopGoal> {
+        while let Some(event) = self.gilrs.next_event() {
+            self.active = Some(event.id);
+        }
+        // Fall back on any gamepad, e.g. once the active one is unplugged
+        if self
+            .active
+            .and_then(|id| self.gilrs.connected_gamepad(id))
+            .is_none()
+        {
+            self.active = self.gilrs.gamepads().next().map(|(id, _)| id);
+        }
+        let gamepad = self.gilrs.connected_gamepad(self.active?)?;
+        Some(self.mapping.goal(|axis| gamepad.value(axis) as f64))
+    }
+
+    /// Whether `button` of the active gamepad is pressed.
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.active
+            .and_then(|id| self.gilrs.connected_gamepad(id))
+            .is_some_and(|gamepad| gamepad.is_pressed(button))
+    }
+}
@@ -0,0 +1,74 @@
+//! Summary of the control loop state, to tell at a glance whether the robot is usable.
+//!
+//! See [`crate::control_loop::ReachyMiniControlLoop::get_health`].
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::{error_stats::ErrorStats, events::Event};
+
+/// Number of events included in a [`HealthReport`].
+pub const HEALTH_REPORT_EVENTS: usize = 20;
+
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HealthReport {
+    /// Whether the last position read succeeded.
+    pub feedback_ok: bool,
+    /// Age (s) of the last known position, see
+    /// [`crate::control_loop::ReachyMiniControlLoop::get_last_known_position`].
+    pub position_age: f64,
+    /// Whether the last known position is younger than the maximum age.
+    pub position_fresh: bool,
+    /// See [`crate::control_loop::ReachyMiniControlLoop::is_power_lost`].
+    pub power_lost: bool,
+    /// See [`crate::control_loop::ReachyMiniControlLoop::is_feedback_stale`].
+    pub feedback_stale: bool,
+    /// See [`crate::control_loop::ReachyMiniControlLoop::is_usb_connected`].
+    pub usb_connected: Option<bool>,
+    /// Last known torque state, `None` if it could not be read.
+    pub torque_enabled: Option<bool>,
+    pub errors: ErrorStats,
+    /// The last [`HEALTH_REPORT_EVENTS`] events, oldest first.
+    pub recent_events: Vec<Event>,
+}
+
+impl HealthReport {
+    /// Whether the motors are there and driven.
+    pub fn is_healthy(&self) -> bool {
+        self.feedback_ok
+            && self.position_fresh
+            && !self.power_lost
+            && !self.feedback_stale
+            && self.usb_connected != Some(false)
+    }
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl HealthReport {
+    /// Whether the motors are there and driven.
+    #[pyo3(name = "is_healthy")]
+    fn py_is_healthy(&self) -> bool {
+        self.is_healthy()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "HealthReport(feedback_ok={}, position_age={:.3}, position_fresh={}, power_lost={}, feedback_stale={}, usb_connected={:?}, torque_enabled={:?}, errors={}, recent_events={})",
+            self.feedback_ok,
+            self.position_age,
+            self.position_fresh,
+            self.power_lost,
+            self.feedback_stale,
+            self.usb_connected,
+            self.torque_enabled,
+            self.errors.total.count,
+            self.recent_events.len()
+        )
+    }
+}
@@ -0,0 +1,175 @@
+//! Detection of the robot USB device being plugged in or unplugged.
+//!
+//! A [`HotplugMonitor`] watches the serial port device from a dedicated thread and
+//! reports each change, so that supervising software does not have to wait for the
+//! communication errors. On Linux it is woken up by the kernel uevents (the ones udev
+//! listens to), elsewhere and as a fallback the device is checked every
+//! [`HOTPLUG_POLL_PERIOD`].
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use log::{info, warn};
+
+/// Maximum time between two checks of the device.
+pub const HOTPLUG_POLL_PERIOD: Duration = Duration::from_millis(250);
+
+/// Watches a serial port device until dropped.
+pub struct HotplugMonitor {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HotplugMonitor {
+    /// Start watching `port`, `on_change` is called with `true` when the device
+    /// appears and `false` when it disappears. It runs on the monitor thread.
+    pub fn start(port: &str, on_change: impl Fn(bool) + Send + 'static) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let port = port.to_string();
+
+        let thread = std::thread::spawn({
+            let stop = stop.clone();
+            move || {
+                let events = DeviceEvents::open()
+                    .map_err(|e| warn!("Hotplug events unavailable, polling {}: {}", port, e))
+                    .ok();
+                let mut present = is_present(&port);
+
+                while !stop.load(Ordering::Relaxed) {
+                    match &events {
+                        Some(events) => events.wait(HOTPLUG_POLL_PERIOD),
+                        None => std::thread::sleep(HOTPLUG_POLL_PERIOD),
+                    }
+                    if is_present(&port) != present {
+                        present = !present;
+                        info!(
+                            "Serial port {} {}",
+                            port,
+                            if present { "connected" } else { "disconnected" }
+                        );
+                        on_change(present);
+                    }
+                }
+            }
+        });
+
+        HotplugMonitor {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for HotplugMonitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Whether the device of `port` is there.
+#[cfg(not(windows))]
+pub fn is_present(port: &str) -> bool {
+    std::path::Path::new(port).exists()
+}
+
+/// Whether the device of `port` is there.
+#[cfg(windows)]
+pub fn is_present(port: &str) -> bool {
+    serialport::available_ports()
+        .is_ok_and(|ports| ports.iter().any(|p| p.port_name.eq_ignore_ascii_case(port)))
+}
+
+/// Kernel device events, received on a netlink socket.
+#[cfg(target_os = "linux")]
+struct DeviceEvents {
+    socket: std::os::fd::OwnedFd,
+}
+
+#[cfg(target_os = "linux")]
+impl DeviceEvents {
+    fn open() -> std::io::Result<Self> {
+        use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+        // Multicast group of the events sent by the kernel
+        const KERNEL_EVENTS: u32 = 1;
+
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_KOBJECT_UEVENT,
+            )
+        };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let mut address: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        address.nl_groups = KERNEL_EVENTS;
+        let bound = unsafe {
+            libc::bind(
+                socket.as_raw_fd(),
+                &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if bound != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(DeviceEvents { socket })
+    }
+
+    /// Wait for the next device event, at most `timeout`.
+    fn wait(&self, timeout: Duration) {
+        use std::os::fd::AsRawFd;
+
+        let mut poll = libc::pollfd {
+            fd: self.socket.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut poll, 1, timeout.as_millis() as libc::c_int) };
+        if ready > 0 {
+            // The event itself does not matter, the device is checked afterwards
+            let mut buffer = [0u8; 4096];
+            unsafe {
+                libc::recv(
+                    self.socket.as_raw_fd(),
+                    buffer.as_mut_ptr() as *mut libc::c_void,
+                    buffer.len(),
+                    libc::MSG_DONTWAIT,
+                )
+            };
+        }
+    }
+}
+
+/// No device events outside of Linux, the device is polled.
+#[cfg(not(target_os = "linux"))]
+struct DeviceEvents;
+
+#[cfg(not(target_os = "linux"))]
+impl DeviceEvents {
+    fn open() -> std::io::Result<Self> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "no device events on this platform",
+        ))
+    }
+
+    fn wait(&self, timeout: Duration) {
+        std::thread::sleep(timeout);
+    }
+}
@@ -0,0 +1,431 @@
+//! Head pose kinematics of the stewart platform.
+//!
+//! The control loop drives joints, the head pose commands (e.g.
+//! [`crate::control_loop::ReachyMiniControlLoop::set_head_twist`]) go through a
+//! [`Kinematics`] installed with
+//! [`crate::control_loop::ReachyMiniControlLoop::set_kinematics`].
+//! [`RotaryStewart`] solves a stewart platform with rotary actuators from its
+//! geometry, other solvers (e.g. the Python one of the robot SDK) can be plugged in
+//! by implementing the trait.
+//!
+//! Poses are given relative to the home pose of the head: translation (m) and
+//! roll, pitch, yaw (rad), applied in this order in the fixed frame.
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::position_bounds::PositionBounds;
+
+type Matrix3 = [[f64; 3]; 3];
+
+/// Head twists not renewed within this time are stopped, e.g. if the teleop client died.
+pub const HEAD_TWIST_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HeadPose {
+    /// x, y, z (m).
+    pub translation: [f64; 3],
+    /// Roll, pitch, yaw (rad).
+    pub rotation: [f64; 3],
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl HeadPose {
+    #[new]
+    #[pyo3(signature = (translation = [0.0; 3], rotation = [0.0; 3]))]
+    fn py_new(translation: [f64; 3], rotation: [f64; 3]) -> Self {
+        HeadPose {
+            translation,
+            rotation,
+        }
+    }
+
+    /// Left/right mirror of the pose, through the x-z plane of the robot.
+    #[pyo3(name = "mirrored")]
+    fn py_mirrored(&self) -> Self {
+        self.mirrored()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "HeadPose(translation=[{:.4}, {:.4}, {:.4}], rotation=[{:.3}, {:.3}, {:.3}])",
+            self.translation[0],
+            self.translation[1],
+            self.translation[2],
+            self.rotation[0],
+            self.rotation[1],
+            self.rotation[2]
+        )
+    }
+}
+
+/// How a gaze yaw is shared between the body rotation and the head (stewart yaw).
+///
+/// The head takes `head_weight` of the gaze, up to `max_head_yaw`, and the body the
+/// rest, so the head stays close to the center of its workspace. The head takes
+/// more when the body reaches its bounds.
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GazeSplit {
+    /// Share of the gaze yaw taken by the head, from 0 to 1.
+    pub head_weight: f64,
+    /// Largest head yaw (rad) relative to the body.
+    pub max_head_yaw: f64,
+}
+
+impl Default for GazeSplit {
+    fn default() -> Self {
+        GazeSplit {
+            head_weight: 0.3,
+            max_head_yaw: 0.4,
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl GazeSplit {
+    #[new]
+    #[pyo3(signature = (head_weight = 0.3, max_head_yaw = 0.4))]
+    fn py_new(head_weight: f64, max_head_yaw: f64) -> PyResult<Self> {
+        if !(0.0..=1.0).contains(&head_weight) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "The head weight must be between 0 and 1",
+            ));
+        }
+        if !(max_head_yaw >= 0.0 && max_head_yaw.is_finite()) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "The max head yaw must be a positive number",
+            ));
+        }
+        Ok(GazeSplit::new(head_weight, max_head_yaw))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "GazeSplit(head_weight={:.2}, max_head_yaw={:.3})",
+            self.head_weight, self.max_head_yaw
+        )
+    }
+}
+
+impl GazeSplit {
+    /// `head_weight` is clamped to 0..1 and `max_head_yaw` to positive values.
+    pub fn new(head_weight: f64, max_head_yaw: f64) -> Self {
+        GazeSplit {
+            head_weight: if head_weight.is_nan() {
+                0.0
+            } else {
+                head_weight.clamp(0.0, 1.0)
+            },
+            max_head_yaw: if max_head_yaw.is_nan() {
+                0.0
+            } else {
+                max_head_yaw.max(0.0)
+            },
+        }
+    }
+
+    /// Body rotation and head yaw (rad) looking at `gaze_yaw`, within `body_bounds`.
+    ///
+    /// Their sum is `gaze_yaw`, unless both the body and the head are at their limit.
+    pub fn split(&self, gaze_yaw: f64, body_bounds: &PositionBounds) -> (f64, f64) {
+        let max = self.max_head_yaw;
+        let head = (gaze_yaw * self.head_weight).clamp(-max, max);
+        let body = (gaze_yaw - head).clamp(body_bounds.min, body_bounds.max);
+        (body, (gaze_yaw - body).clamp(-max, max))
+    }
+}
+
+/// Velocity of the head, expressed in the head frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HeadTwist {
+    /// m/s.
+    pub linear: [f64; 3],
+    /// rad/s, around the head x, y, z axes.
+    pub angular: [f64; 3],
+}
+
+impl HeadPose {
+    pub fn rotation_matrix(&self) -> Matrix3 {
+        let [roll, pitch, yaw] = self.rotation;
+        let (sr, cr) = roll.sin_cos();
+        let (sp, cp) = pitch.sin_cos();
+        let (sy, cy) = yaw.sin_cos();
+        [
+            [cy * cp, cy * sp * sr - sy * cr, cy * sp * cr + sy * sr],
+            [sy * cp, sy * sp * sr + cy * cr, sy * sp * cr - cy * sr],
+            [-sp, cp * sr, cp * cr],
+        ]
+    }
+
+    fn from_matrix(translation: [f64; 3], r: &Matrix3) -> Self {
+        HeadPose {
+            translation,
+            rotation: [
+                r[2][1].atan2(r[2][2]),
+                (-r[2][0]).clamp(-1.0, 1.0).asin(),
+                r[1][0].atan2(r[0][0]),
+            ],
+        }
+    }
+
+    /// Pose reached by moving at `twist` for `dt` seconds.
+    pub fn integrate(&self, twist: &HeadTwist, dt: f64) -> Self {
+        let r = self.rotation_matrix();
+        let step = mat_vec(&r, &twist.linear);
+        let translation = std::array::from_fn(|i| self.translation[i] + step[i] * dt);
+        let rotation = mat_mul(&r, &axis_angle(twist.angular.map(|w| w * dt)));
+        HeadPose::from_matrix(translation, &rotation)
+    }
+
+    /// Left/right mirror of the pose, through the x-z plane of the robot.
+    pub fn mirrored(&self) -> Self {
+        let [x, y, z] = self.translation;
+        let [roll, pitch, yaw] = self.rotation;
+        HeadPose {
+            translation: [x, -y, z],
+            rotation: [-roll, pitch, -yaw],
+        }
+    }
+}
+
+fn mat_mul(a: &Matrix3, b: &Matrix3) -> Matrix3 {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn mat_vec(a: &Matrix3, v: &[f64; 3]) -> [f64; 3] {
+    std::array::from_fn(|i| (0..3).map(|k| a[i][k] * v[k]).sum())
+}
+
+/// Rotation of `|v|` rad around `v` (Rodrigues' formula).
+fn axis_angle(v: [f64; 3]) -> Matrix3 {
+    let angle = v.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if angle < 1e-12 {
+        return [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    }
+    let [x, y, z] = v.map(|x| x / angle);
+    let (s, c) = angle.sin_cos();
+    let t = 1.0 - c;
+    [
+        [c + x * x * t, x * y * t - z * s, x * z * t + y * s],
+        [y * x * t + z * s, c + y * y * t, y * z * t - x * s],
+        [z * x * t - y * s, z * y * t + x * s, c + z * z * t],
+    ]
+}
+
+/// A pose the head cannot reach, or joints matching no pose.
+#[derive(Debug, Clone)]
+pub struct KinematicsError(pub String);
+
+impl std::error::Error for KinematicsError {}
+impl fmt::Display for KinematicsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Kinematics error: {}", self.0)
+    }
+}
+
+/// Conversion between head poses and stewart platform joint positions (rad).
+///
+/// It is called from the control loop thread, at the loop period while a head
+/// twist is followed, so it should return quickly.
+pub trait Kinematics: Send {
+    fn inverse(&self, pose: &HeadPose) -> Result<[f64; 6], KinematicsError>;
+    fn forward(&self, joints: &[f64; 6]) -> Result<HeadPose, KinematicsError>;
+}
+
+/// Geometry of a stewart platform driven by 6 rotary actuators (horn and rod legs).
+///
+/// Positions are in meters, in the base frame (z up) for the motors and in the head
+/// frame for the rod attachments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StewartGeometry {
+    /// Position of each motor shaft.
+    pub motors: [[f64; 3]; 6],
+    /// Angle (rad) around z of the plane each horn turns in.
+    pub horn_angles: [f64; 6],
+    /// Attachment of each rod on the head.
+    pub head_anchors: [[f64; 3]; 6],
+    pub horn_length: f64,
+    pub rod_length: f64,
+    /// Height of the head frame above the base frame at the home pose.
+    pub home_height: f64,
+    /// +1 or -1, whether the joint position increases with the horn angle.
+    pub directions: [f64; 6],
+    /// Joint position (rad) with a horizontal horn.
+    pub offsets: [f64; 6],
+}
+
+/// Analytical inverse kinematics of a [`StewartGeometry`], and Newton forward kinematics.
+#[derive(Debug, Clone)]
+pub struct RotaryStewart {
+    pub geometry: StewartGeometry,
+}
+
+/// Convergence of the forward kinematics, on the joint positions (rad).
+const FORWARD_TOLERANCE: f64 = 1e-9;
+const FORWARD_MAX_ITERATIONS: usize = 30;
+const FORWARD_MIN_STEP_SCALE: f64 = 1e-3;
+
+impl RotaryStewart {
+    pub fn new(geometry: StewartGeometry) -> Self {
+        RotaryStewart { geometry }
+    }
+}
+
+impl Kinematics for RotaryStewart {
+    fn inverse(&self, pose: &HeadPose) -> Result<[f64; 6], KinematicsError> {
+        let g = &self.geometry;
+        let r = pose.rotation_matrix();
+        let mut joints = [0.0; 6];
+        for (leg, joint) in joints.iter_mut().enumerate() {
+            let anchor = mat_vec(&r, &g.head_anchors[leg]);
+            let mut origin = pose.translation;
+            origin[2] += g.home_height;
+            let l: [f64; 3] = std::array::from_fn(|i| origin[i] + anchor[i] - g.motors[leg][i]);
+            // Horn tip at motor + a (cos α cos β, cos α sin β, sin α), rod of length s:
+            // M sin α + N cos α = L
+            let a = g.horn_length;
+            let big_l = l.iter().map(|x| x * x).sum::<f64>() - (g.rod_length.powi(2) - a * a);
+            let m = 2.0 * a * l[2];
+            let (sb, cb) = g.horn_angles[leg].sin_cos();
+            let n = 2.0 * a * (cb * l[0] + sb * l[1]);
+            let ratio = big_l / m.hypot(n);
+            if ratio.is_nan() || ratio.abs() > 1.0 {
+                return Err(KinematicsError(format!(
+                    "leg {} cannot reach the pose",
+                    leg + 1
+                )));
+            }
+            let alpha = ratio.asin() - n.atan2(m);
+            *joint = g.directions[leg] * alpha + g.offsets[leg];
+        }
+        Ok(joints)
+    }
+
+    fn forward(&self, joints: &[f64; 6]) -> Result<HeadPose, KinematicsError> {
+        let to_pose = |x: &[f64; 6]| HeadPose {
+            translation: [x[0], x[1], x[2]],
+            rotation: [x[3], x[4], x[5]],
+        };
+        let residual = |x: &[f64; 6]| -> Result<[f64; 6], KinematicsError> {
+            let reached = self.inverse(&to_pose(x))?;
+            Ok(std::array::from_fn(|i| reached[i] - joints[i]))
+        };
+
+        // Newton iterations from the home pose, with a numerical jacobian
+        let mut x = [0.0; 6];
+        for _ in 0..FORWARD_MAX_ITERATIONS {
+            let f = residual(&x)?;
+            if f.iter().all(|v| v.abs() < FORWARD_TOLERANCE) {
+                return Ok(to_pose(&x));
+            }
+            const H: f64 = 1e-7;
+            let mut jacobian = [[0.0; 6]; 6];
+            for j in 0..6 {
+                let mut shifted = x;
+                shifted[j] += H;
+                let fj = residual(&shifted)?;
+                for i in 0..6 {
+                    jacobian[i][j] = (fj[i] - f[i]) / H;
+                }
+            }
+            let step = solve(jacobian, f.map(|v| -v))
+                .ok_or_else(|| KinematicsError("singular configuration".to_string()))?;
+            // Shorten the step while it gets out of the workspace
+            let mut scale = 1.0;
+            x = loop {
+                let next = std::array::from_fn(|i| x[i] + step[i] * scale);
+                if self.inverse(&to_pose(&next)).is_ok() {
+                    break next;
+                }
+                scale /= 2.0;
+                if scale < FORWARD_MIN_STEP_SCALE {
+                    return Err(KinematicsError(format!(
+                        "no pose found for joints {:?}",
+                        joints
+                    )));
+                }
+            };
+        }
+        Err(KinematicsError(format!(
+            "no pose found for joints {:?}",
+            joints
+        )))
+    }
+}
+
+/// Solve `a x = b` by Gaussian elimination with partial pivoting.
+fn solve(mut a: [[f64; 6]; 6], mut b: [f64; 6]) -> Option<[f64; 6]> {
+    for col in 0..6 {
+        let pivot = (col..6).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let pivot_row = a[col];
+        for row in col + 1..6 {
+            let factor = a[row][col] / pivot_row[col];
+            for (value, pivot) in a[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = [0.0; 6];
+    for row in (0..6).rev() {
+        let sum: f64 = (row + 1..6).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// Head pose integrated from the commanded twist by the control loop.
+pub(crate) struct HeadTwistFollower {
+    twist: HeadTwist,
+    /// Last pose sent to the joints.
+    pub(crate) pose: HeadPose,
+    last_update: Instant,
+    commanded_at: Instant,
+}
+
+impl HeadTwistFollower {
+    pub(crate) fn new(pose: HeadPose, twist: HeadTwist, now: Instant) -> Self {
+        HeadTwistFollower {
+            twist,
+            pose,
+            last_update: now,
+            commanded_at: now,
+        }
+    }
+
+    pub(crate) fn set_twist(&mut self, twist: HeadTwist, now: Instant) {
+        self.twist = twist;
+        self.commanded_at = now;
+    }
+
+    pub(crate) fn is_expired(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.commanded_at) > HEAD_TWIST_TIMEOUT
+    }
+
+    /// Pose to reach at `now`, it becomes the current one once sent.
+    pub(crate) fn next_pose(&mut self, now: Instant) -> HeadPose {
+        let dt = now
+            .saturating_duration_since(self.last_update)
+            .as_secs_f64();
+        self.last_update = now;
+        self.pose.integrate(&self.twist, dt)
+    }
+}
@@ -0,0 +1,277 @@
+//! Crash-safe file of the last known state of the robot.
+//!
+//! Once set with [`crate::control_loop::ReachyMiniControlLoop::set_last_state_file`],
+//! the control loop periodically writes the last positions read with the torque, the
+//! operating mode and the safe mode state, replacing the file atomically so a crash
+//! never leaves it half written. When the loop stops it writes them one last time,
+//! marked as a clean shutdown.
+//!
+//! After a crash or a restart, the state loaded from the file tells where the robot was
+//! and whether its torque was on: if the current positions deviate from it (see
+//! [`LastState::max_deviation`]), the robot was moved or fell while unpowered, and the
+//! torque should not be re-enabled blindly toward goals computed before the crash.
+//!
+//! The file is a small text file, one field per line:
+//! ```text
+//! timestamp 1718000000.123
+//! positions 0 0.1 -0.1 0 0.1 -0.1 0 0.5 -0.5
+//! torque_enabled 1
+//! control_mode 3
+//! safe_mode 0
+//! clean_shutdown 0
+//! ```
+
+use std::{
+    f64::consts::PI,
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::smoothing::NUM_JOINTS;
+
+/// How often the state is written to the last state file.
+pub const LAST_STATE_SAVE_PERIOD: Duration = Duration::from_secs(1);
+
+/// Deviation (rad) under which a pose is considered unchanged since the last state.
+pub const DEFAULT_LAST_STATE_TOLERANCE: f64 = 0.1;
+
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LastState {
+    /// Seconds since the UNIX epoch, when the positions were read.
+    pub timestamp: f64,
+    /// Last positions read (rad), in joint order.
+    pub positions: [f64; NUM_JOINTS],
+    pub torque_enabled: bool,
+    /// Operating mode of the stewart platform, if known.
+    pub control_mode: Option<u8>,
+    pub safe_mode: bool,
+    /// Whether the control loop stopped normally after writing this state, false after
+    /// a crash.
+    pub clean_shutdown: bool,
+}
+
+impl LastState {
+    /// Read a state saved with [`LastState::save`].
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid last state file line: {:?}", line),
+            )
+        };
+        let flag = |value: Option<&str>| match value {
+            Some("1") => Some(true),
+            Some("0") => Some(false),
+            _ => None,
+        };
+
+        let mut state = LastState {
+            timestamp: 0.0,
+            positions: [0.0; NUM_JOINTS],
+            torque_enabled: false,
+            control_mode: None,
+            safe_mode: false,
+            clean_shutdown: false,
+        };
+        let mut has_positions = false;
+        for line in std::fs::read_to_string(path)?.lines() {
+            let mut values = line.split_whitespace();
+            match values.next() {
+                Some("timestamp") => {
+                    state.timestamp = values
+                        .next()
+                        .and_then(|v| v.parse().ok())
+                        .ok_or_else(|| invalid(line))?;
+                }
+                Some("positions") => {
+                    for position in state.positions.iter_mut() {
+                        *position = values
+                            .next()
+                            .and_then(|v| v.parse().ok())
+                            .ok_or_else(|| invalid(line))?;
+                    }
+                    has_positions = true;
+                }
+                Some("torque_enabled") => {
+                    state.torque_enabled = flag(values.next()).ok_or_else(|| invalid(line))?;
+                }
+                Some("control_mode") => {
+                    state.control_mode = match values.next() {
+                        Some("none") => None,
+                        value => Some(
+                            value
+                                .and_then(|v| v.parse().ok())
+                                .ok_or_else(|| invalid(line))?,
+                        ),
+                    };
+                }
+                Some("safe_mode") => {
+                    state.safe_mode = flag(values.next()).ok_or_else(|| invalid(line))?;
+                }
+                Some("clean_shutdown") => {
+                    state.clean_shutdown = flag(values.next()).ok_or_else(|| invalid(line))?;
+                }
+                // Unknown fields (e.g. from a newer version) are ignored
+                _ => {}
+            }
+        }
+        if !has_positions {
+            return Err(invalid("no positions"));
+        }
+        Ok(state)
+    }
+
+    /// Write the state, replacing the file atomically: the content is written to the disk
+    /// next to it (`<path>.tmp`) before it is renamed over the previous state.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let positions: Vec<String> = self.positions.iter().map(|v| v.to_string()).collect();
+        let content = format!(
+            "timestamp {}\npositions {}\ntorque_enabled {}\ncontrol_mode {}\nsafe_mode {}\n\
+             clean_shutdown {}\n",
+            self.timestamp,
+            positions.join(" "),
+            self.torque_enabled as u8,
+            self.control_mode
+                .map_or("none".to_string(), |mode| mode.to_string()),
+            self.safe_mode as u8,
+            self.clean_shutdown as u8,
+        );
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        let mut file = File::create(&tmp)?;
+        file.write_all(content.as_bytes())?;
+        // Otherwise a power loss after the rename can leave the file empty
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        sync_parent_dir(path)
+    }
+
+    /// Largest deviation (rad) of `positions` from the last positions. The body rotation
+    /// reading wraps around, so the deviations are taken modulo a turn.
+    pub fn max_deviation(&self, positions: &[f64; NUM_JOINTS]) -> f64 {
+        positions
+            .iter()
+            .zip(&self.positions)
+            .map(|(position, last)| ((position - last + PI).rem_euclid(2.0 * PI) - PI).abs())
+            .fold(0.0, f64::max)
+    }
+
+    /// Whether `positions` deviate by at most `tolerance` (rad) from the last positions,
+    /// i.e. the robot was not moved since and re-enabling the torque there is safe.
+    pub fn is_pose_unchanged(&self, positions: &[f64; NUM_JOINTS], tolerance: f64) -> bool {
+        self.max_deviation(positions) <= tolerance
+    }
+}
+
+/// Flush the directory entries of the parent of `path`, so a rename in it survives a
+/// power loss. Directories cannot be opened as files on Windows, where it is a no-op.
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        File::open(dir)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl LastState {
+    /// Read a last state file.
+    ///
+    /// # Arguments
+    /// * `path` - Path given to `set_last_state_file`.
+    #[staticmethod]
+    #[pyo3(name = "load")]
+    fn py_load(path: PathBuf) -> PyResult<Self> {
+        LastState::load(&path).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))
+    }
+
+    /// Largest deviation (rad) of the positions from the last positions.
+    ///
+    /// # Arguments
+    /// * `positions` - Current positions of the 9 joints, in joint order.
+    #[pyo3(name = "max_deviation")]
+    fn py_max_deviation(&self, positions: [f64; NUM_JOINTS]) -> f64 {
+        self.max_deviation(&positions)
+    }
+
+    /// Whether the positions deviate by at most `tolerance` (rad) from the last
+    /// positions, i.e. the robot was not moved since and re-enabling the torque is safe.
+    ///
+    /// # Arguments
+    /// * `positions` - Current positions of the 9 joints, in joint order.
+    /// * `tolerance` - Largest deviation allowed (rad).
+    #[pyo3(name = "is_pose_unchanged", signature = (positions, tolerance = DEFAULT_LAST_STATE_TOLERANCE))]
+    fn py_is_pose_unchanged(&self, positions: [f64; NUM_JOINTS], tolerance: f64) -> bool {
+        self.is_pose_unchanged(&positions, tolerance)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "LastState(timestamp={:.3}, positions={:.3?}, torque_enabled={}, clean_shutdown={})",
+            self.timestamp, self.positions, self.torque_enabled, self.clean_shutdown
+        )
+    }
+}
+
+/// Control loop side of the last state file.
+pub(crate) struct LastStateWriter {
+    path: Option<PathBuf>,
+    last_save: Option<Instant>,
+}
+
+impl LastStateWriter {
+    pub(crate) fn new() -> Self {
+        LastStateWriter {
+            path: None,
+            last_save: None,
+        }
+    }
+
+    /// Write the state to `path` from now on, returning the state it held, if any.
+    pub(crate) fn set_path(&mut self, path: Option<PathBuf>) -> io::Result<Option<LastState>> {
+        let previous = match path.as_deref().map(LastState::load) {
+            Some(Ok(previous)) => Some(previous),
+            Some(Err(e)) if e.kind() == io::ErrorKind::NotFound => None,
+            Some(Err(e)) => return Err(e),
+            None => None,
+        };
+        self.path = path;
+        self.last_save = None;
+        Ok(previous)
+    }
+
+    /// Whether a last state file is set and `force` or the save period elapsed.
+    pub(crate) fn is_due(&self, now: Instant, force: bool) -> bool {
+        self.path.is_some()
+            && (force
+                || self
+                    .last_save
+                    .is_none_or(|last| now.duration_since(last) >= LAST_STATE_SAVE_PERIOD))
+    }
+
+    pub(crate) fn save(&mut self, state: &LastState, now: Instant) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        self.last_save = Some(now);
+        state.save(path)
+    }
+}
@@ -1,6 +1,84 @@
+// Without the serial feature, the loop-side helpers of the shared modules (trajectory
+// player, touch detector, usage tracker...) have no user.
+#![cfg_attr(not(feature = "serial"), allow(dead_code))]
+
+#[cfg(feature = "serial")]
 mod controller;
-pub use controller::ReachyMiniMotorController;
+#[cfg(feature = "serial")]
+pub use controller::{
+    DEFAULT_BAUD_RATE, GroupWriteError, HomingConfig, IDENTITY_SIZE, ReachyMiniMotorController,
+    WriteVerificationError,
+};
+#[cfg(feature = "serial")]
+pub use error::MotorControllerError;
+/// The rustypot version used by the controller, for [`ReachyMiniMotorController::with_bus`].
+#[cfg(feature = "serial")]
+pub use rustypot;
 
+pub mod animation;
+pub mod arbitration;
+#[cfg(feature = "serial")]
+pub mod async_controller;
+pub mod beat;
+#[cfg(feature = "python")]
 pub mod bindings;
+pub mod calibration;
 
+#[cfg(feature = "serial")]
+pub mod cli;
+pub mod clock;
+#[cfg(feature = "serial")]
+pub mod config_audit;
+pub mod control_law;
+#[cfg(feature = "serial")]
 pub mod control_loop;
+pub mod current_budget;
+pub mod diagnostics;
+pub mod dry_run;
+#[cfg(feature = "serial")]
+pub mod duty_cycle;
+
+#[cfg(feature = "serial")]
+pub mod emergency;
+#[cfg(feature = "serial")]
+mod error;
+#[cfg(feature = "serial")]
+pub mod error_stats;
+pub mod events;
+#[cfg(feature = "serial")]
+mod fast_sync_read;
+pub mod fault_journal;
+pub mod firmware;
+#[cfg(feature = "serial")]
+pub mod follow;
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
+#[cfg(feature = "serial")]
+pub mod health;
+#[cfg(feature = "serial")]
+pub mod hotplug;
+pub mod kinematics;
+pub mod last_state;
+mod log_throttle;
+#[cfg(feature = "serial")]
+pub mod port_lock;
+pub mod poses;
+pub mod position;
+pub mod position_bounds;
+pub mod read_filter;
+pub mod read_schedule;
+#[cfg(feature = "serial")]
+pub mod read_tuning;
+#[cfg(feature = "serial")]
+pub mod registry;
+pub mod safe_mode;
+#[cfg(feature = "serial")]
+pub mod simulation;
+pub mod smoothing;
+pub mod state_buffer;
+pub mod touch;
+pub mod tracking;
+pub mod trajectory;
+#[cfg(feature = "serial")]
+pub mod transport;
+pub mod usage;
@@ -0,0 +1,82 @@
+//! Rate-limited logging of repeated failures.
+//!
+//! The control loop runs at a few hundred hertz, so a persistent failure (e.g. an
+//! unplugged motor) logged on every tick floods the journal. Here the first failure
+//! of a kind is logged right away, then the following ones are only counted and
+//! summarized once per period ("read failed 250× in last 10 s: Timeout").
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Period between two summaries of the same failure.
+pub const DEFAULT_LOG_PERIOD: Duration = Duration::from_secs(10);
+
+struct Window {
+    start: Instant,
+    count: u64,
+    last_detail: String,
+}
+
+pub struct LogThrottle {
+    period: Duration,
+    windows: HashMap<&'static str, Window>,
+}
+
+impl LogThrottle {
+    pub fn new(period: Duration) -> Self {
+        LogThrottle {
+            period,
+            windows: HashMap::new(),
+        }
+    }
+
+    /// Record a failure of the given kind (e.g. "read"), logging it if it is the first one.
+    pub fn failure(&mut self, what: &'static str, detail: impl ToString) {
+        let detail = detail.to_string();
+        match self.windows.get_mut(what) {
+            Some(window) => {
+                window.count += 1;
+                window.last_detail = detail;
+            }
+            None => {
+                log::warn!("{} failed: {}", what, detail);
+                self.windows.insert(
+                    what,
+                    Window {
+                        start: Instant::now(),
+                        count: 0,
+                        last_detail: detail,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Log the summaries of the periods which are over.
+    ///
+    /// A kind of failure which did not happen again during its period is forgotten,
+    /// so its next occurrence is logged right away.
+    pub fn flush(&mut self) {
+        let period = self.period;
+        self.windows.retain(|what, window| {
+            if window.start.elapsed() < period {
+                return true;
+            }
+            if window.count == 0 {
+                return false;
+            }
+            log::warn!(
+                "{} failed {}× in last {:.0} s: {}",
+                what,
+                window.count,
+                window.start.elapsed().as_secs_f64(),
+                window.last_detail
+            );
+            window.start = Instant::now();
+            window.count = 0;
+            true
+        });
+    }
+}
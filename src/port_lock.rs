@@ -0,0 +1,101 @@
+//! Exclusive ownership of the serial port.
+//!
+//! Two processes talking to the motors at the same time corrupt each other's packets,
+//! which shows up as random timeouts. To detect it early, we take an advisory lock
+//! (`flock`, like pyserial's `exclusive=True`) on the serial device before opening it
+//! and report which process owns the port if the lock is already held.
+
+use std::fmt;
+
+/// The serial port is already used by another process.
+#[derive(Debug, Clone)]
+pub struct PortInUseError {
+    pub port: String,
+    /// Pid of the owner, if it could be found (Linux only).
+    pub pid: Option<u32>,
+}
+
+impl std::error::Error for PortInUseError {}
+impl fmt::Display for PortInUseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.pid {
+            Some(pid) => write!(f, "Serial port {} is in use by PID {}!", self.port, pid),
+            None => write!(f, "Serial port {} is in use by another process!", self.port),
+        }
+    }
+}
+
+/// Advisory lock on a serial device, released when dropped.
+#[derive(Debug)]
+pub struct PortLock {
+    #[cfg(unix)]
+    _file: std::fs::File,
+}
+
+impl PortLock {
+    /// Lock the serial device, failing with a [`PortInUseError`] if another process owns it.
+    #[cfg(unix)]
+    pub fn acquire(port: &str) -> Result<PortLock, Box<dyn std::error::Error>> {
+        use std::os::unix::{fs::OpenOptionsExt, io::AsRawFd};
+
+        let in_use = || PortInUseError {
+            port: port.to_string(),
+            pid: find_port_owner(port),
+        };
+
+        // Do not become the controlling terminal nor wait for the modem lines
+        let file = match std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+            .open(port)
+        {
+            Ok(file) => file,
+            // The owner opened the port in exclusive mode (TIOCEXCL)
+            Err(e) if e.raw_os_error() == Some(libc::EBUSY) => return Err(Box::new(in_use())),
+            Err(e) => return Err(Box::new(e)),
+        };
+
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let e = std::io::Error::last_os_error();
+            if e.raw_os_error() == Some(libc::EWOULDBLOCK) {
+                return Err(Box::new(in_use()));
+            }
+            return Err(Box::new(e));
+        }
+
+        Ok(PortLock { _file: file })
+    }
+
+    /// COM ports are already opened exclusively on Windows.
+    #[cfg(not(unix))]
+    pub fn acquire(_port: &str) -> Result<PortLock, Box<dyn std::error::Error>> {
+        Ok(PortLock {})
+    }
+}
+
+/// Look for another process with the device open in /proc.
+#[cfg(target_os = "linux")]
+fn find_port_owner(port: &str) -> Option<u32> {
+    let device = std::fs::canonicalize(port).ok()?;
+    let me = std::process::id();
+
+    std::fs::read_dir("/proc")
+        .ok()?
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| *pid != me)
+        .find(|pid| {
+            std::fs::read_dir(format!("/proc/{}/fd", pid))
+                .map(|fds| {
+                    fds.flatten()
+                        .any(|fd| std::fs::read_link(fd.path()).is_ok_and(|p| p == device))
+                })
+                .unwrap_or(false)
+        })
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn find_port_owner(_port: &str) -> Option<u32> {
+    None
+}
@@ -0,0 +1,111 @@
+//! Named poses (e.g. rest, look-up, shipping-lock) shared by the apps and the `poses` tool.
+//!
+//! The file is a small text file, one pose per line, its name followed by the 9 joint
+//! positions (rad) in joint order:
+//! ```text
+//! rest 0 0.5 -0.5 0.5 -0.5 0.5 -0.5 0 0
+//! look_up 0 0.3 -0.3 0.3 -0.3 0.3 -0.3 0 0
+//! ```
+//! Empty lines and lines starting with `#` are ignored. The poses are saved sorted by name.
+
+use std::{collections::BTreeMap, io, path::Path};
+
+use crate::smoothing::NUM_JOINTS;
+
+/// File used by [`crate::control_loop::ReachyMiniControlLoop`] until another one is set.
+pub const DEFAULT_POSES_FILE: &str = "poses.txt";
+
+/// Folded pose reached by
+/// [`crate::control_loop::ReachyMiniControlLoop::prepare_for_transport`], saved once with
+/// the robot folded by hand.
+pub const TRANSPORT_POSE: &str = "transport";
+
+/// Positions saved before folding the robot for transport, restored by
+/// [`crate::control_loop::ReachyMiniControlLoop::wake_up`].
+pub const BEFORE_TRANSPORT_POSE: &str = "before_transport";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PoseLibrary {
+    poses: BTreeMap<String, [f64; NUM_JOINTS]>,
+}
+
+impl PoseLibrary {
+    /// Read poses saved with [`PoseLibrary::save`].
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid poses file line: {:?}", line),
+            )
+        };
+
+        let mut library = PoseLibrary::default();
+        for line in std::fs::read_to_string(path)?.lines() {
+            let mut values = line.split_whitespace();
+            let name = match values.next() {
+                None => continue,
+                Some(name) if name.starts_with('#') => continue,
+                Some(name) => name,
+            };
+            let positions: Vec<f64> = values
+                .map(|v| v.parse().map_err(|_| invalid(line)))
+                .collect::<io::Result<_>>()?;
+            let positions = positions.try_into().map_err(|_| invalid(line))?;
+            library.insert(name, positions).map_err(|_| invalid(line))?;
+        }
+        Ok(library)
+    }
+
+    /// Like [`PoseLibrary::load`], but no poses if the file does not exist yet.
+    pub fn load_or_default(path: &Path) -> io::Result<Self> {
+        match Self::load(path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            res => res,
+        }
+    }
+
+    /// Write the poses, replacing the file atomically.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut content = String::new();
+        for (name, positions) in &self.poses {
+            let positions: Vec<String> = positions.iter().map(f64::to_string).collect();
+            content += &format!("{} {}\n", name, positions.join(" "));
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, content)?;
+        std::fs::rename(tmp, path)
+    }
+
+    /// Joint positions (rad) of the pose, in joint order.
+    pub fn get(&self, name: &str) -> Option<[f64; NUM_JOINTS]> {
+        self.poses.get(name).copied()
+    }
+
+    /// Add or replace a pose. Names are single words (no whitespace, not starting with
+    /// `#`) and the positions must be finite.
+    pub fn insert(&mut self, name: &str, positions: [f64; NUM_JOINTS]) -> io::Result<()> {
+        if name.is_empty() || name.starts_with('#') || name.contains(char::is_whitespace) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid pose name: {:?}", name),
+            ));
+        }
+        if positions.iter().any(|p| !p.is_finite()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Pose {} has a non finite position", name),
+            ));
+        }
+        self.poses.insert(name.to_string(), positions);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<[f64; NUM_JOINTS]> {
+        self.poses.remove(name)
+    }
+
+    /// Names of the poses, sorted.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.poses.keys().map(String::as_str)
+    }
+}
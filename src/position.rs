@@ -0,0 +1,172 @@
+//! Positions of all the joints, as read from or sent to the motors.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::{
+    clock::{monotonic_now, wall_now},
+    smoothing::NUM_JOINTS,
+};
+
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all, subclass))]
+#[derive(Debug, Clone, Copy)]
+pub struct FullBodyPosition {
+    pub body_yaw: f64,
+    pub stewart: [f64; 6],
+    pub antennas: [f64; 2],
+    pub timestamp: f64, // seconds since UNIX epoch
+    /// Same instant on the monotonic clock (s), see [`crate::clock`].
+    pub monotonic_timestamp: f64,
+}
+
+impl FullBodyPosition {
+    pub fn new(body_yaw: f64, stewart: Vec<f64>, antennas: Vec<f64>) -> Self {
+        if stewart.len() != 6 || antennas.len() != 2 {
+            panic!("Stewart platform must have 6 positions and antennas must have 2 positions.");
+        }
+        FullBodyPosition {
+            body_yaw,
+            stewart: [
+                stewart[0], stewart[1], stewart[2], stewart[3], stewart[4], stewart[5],
+            ],
+            antennas: [antennas[0], antennas[1]],
+            timestamp: wall_now(),
+            monotonic_timestamp: monotonic_now(),
+        }
+    }
+
+    /// Build a position from the joints in the order of [`FullBodyPosition::joints`].
+    ///
+    /// The monotonic timestamp is left unknown (0).
+    pub fn from_joints(joints: [f64; NUM_JOINTS], timestamp: f64) -> Self {
+        FullBodyPosition {
+            body_yaw: joints[0],
+            stewart: [
+                joints[1], joints[2], joints[3], joints[4], joints[5], joints[6],
+            ],
+            antennas: [joints[7], joints[8]],
+            timestamp,
+            monotonic_timestamp: 0.0,
+        }
+    }
+
+    /// Joint positions in the order expected by `set_all_goal_positions`.
+    pub fn joints(&self) -> [f64; NUM_JOINTS] {
+        [
+            self.body_yaw,
+            self.stewart[0],
+            self.stewart[1],
+            self.stewart[2],
+            self.stewart[3],
+            self.stewart[4],
+            self.stewart[5],
+            self.antennas[0],
+            self.antennas[1],
+        ]
+    }
+
+    /// Linear interpolation between `a` (`t = 0`) and `b` (`t = 1`), timestamps included.
+    pub fn lerp(a: &FullBodyPosition, b: &FullBodyPosition, t: f64) -> Self {
+        let (a_joints, b_joints) = (a.joints(), b.joints());
+        FullBodyPosition {
+            monotonic_timestamp: a.monotonic_timestamp
+                + (b.monotonic_timestamp - a.monotonic_timestamp) * t,
+            ..FullBodyPosition::from_joints(
+                std::array::from_fn(|i| a_joints[i] + (b_joints[i] - a_joints[i]) * t),
+                a.timestamp + (b.timestamp - a.timestamp) * t,
+            )
+        }
+    }
+
+    /// Joint-wise `self - other`, keeping the timestamps of `self`.
+    pub fn difference(&self, other: &FullBodyPosition) -> Self {
+        let (joints, other_joints) = (self.joints(), other.joints());
+        FullBodyPosition {
+            monotonic_timestamp: self.monotonic_timestamp,
+            ..FullBodyPosition::from_joints(
+                std::array::from_fn(|i| joints[i] - other_joints[i]),
+                self.timestamp,
+            )
+        }
+    }
+
+    /// Clamp each joint between the matching joints of `lower` and `upper`.
+    pub fn clamp_to_limits(&self, lower: &FullBodyPosition, upper: &FullBodyPosition) -> Self {
+        let (joints, lower, upper) = (self.joints(), lower.joints(), upper.joints());
+        FullBodyPosition {
+            monotonic_timestamp: self.monotonic_timestamp,
+            ..FullBodyPosition::from_joints(
+                std::array::from_fn(|i| joints[i].max(lower[i]).min(upper[i])),
+                self.timestamp,
+            )
+        }
+    }
+
+    /// Euclidean distance (rad) between the two positions in joint space.
+    pub fn distance(&self, other: &FullBodyPosition) -> f64 {
+        self.difference(other)
+            .joints()
+            .iter()
+            .map(|d| d * d)
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    /// Largest joint difference (rad) between the two positions.
+    pub fn max_distance(&self, other: &FullBodyPosition) -> f64 {
+        self.difference(other)
+            .joints()
+            .iter()
+            .fold(0.0, |max, d| max.max(d.abs()))
+    }
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl FullBodyPosition {
+    #[new]
+    fn py_new(body_yaw: f64, stewart: Vec<f64>, antennas: Vec<f64>) -> Self {
+        FullBodyPosition::new(body_yaw, stewart, antennas)
+    }
+
+    /// Linear interpolation between `a` (`t = 0`) and `b` (`t = 1`), timestamp included.
+    #[staticmethod]
+    #[pyo3(name = "lerp")]
+    fn py_lerp(a: &FullBodyPosition, b: &FullBodyPosition, t: f64) -> Self {
+        FullBodyPosition::lerp(a, b, t)
+    }
+
+    /// Joint-wise `self - other`, keeping the timestamp of `self`.
+    #[pyo3(name = "difference")]
+    fn py_difference(&self, other: &FullBodyPosition) -> Self {
+        self.difference(other)
+    }
+
+    /// Clamp each joint between the matching joints of `lower` and `upper`.
+    #[pyo3(name = "clamp_to_limits")]
+    fn py_clamp_to_limits(&self, lower: &FullBodyPosition, upper: &FullBodyPosition) -> Self {
+        self.clamp_to_limits(lower, upper)
+    }
+
+    /// Euclidean distance (rad) between the two positions in joint space.
+    #[pyo3(name = "distance")]
+    fn py_distance(&self, other: &FullBodyPosition) -> f64 {
+        self.distance(other)
+    }
+
+    /// Largest joint difference (rad) between the two positions.
+    #[pyo3(name = "max_distance")]
+    fn py_max_distance(&self, other: &FullBodyPosition) -> f64 {
+        self.max_distance(other)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FullBodyPosition(body_yaw={:.3}, stewart={:?}, antennas={:?}, timestamp={:.3})",
+            self.body_yaw, self.stewart, self.antennas, self.timestamp
+        )
+    }
+}
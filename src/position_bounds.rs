@@ -0,0 +1,136 @@
+//! Per joint bounds of the goal positions.
+//!
+//! Every goal is checked against them before reaching the motors, see
+//! [`crate::control_loop::ReachyMiniControlLoop::set_position_bounds`]. Out of
+//! bounds goals are rejected, or clamped to the nearest legal value if goal clamping
+//! is enabled (usually preferable for continuous teleop streams). The joints
+//! have very different ranges: the antennas can turn almost freely while the
+//! stewart arms quickly hit the platform.
+
+use std::{f64::consts::PI, fmt};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::smoothing::NUM_JOINTS;
+
+/// Joint names, in joint order.
+pub const JOINT_NAMES: [&str; NUM_JOINTS] = [
+    "body_rotation",
+    "stewart_1",
+    "stewart_2",
+    "stewart_3",
+    "stewart_4",
+    "stewart_5",
+    "stewart_6",
+    "right_antenna",
+    "left_antenna",
+];
+
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all, set_all))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionBounds {
+    /// Lowest goal position (rad).
+    pub min: f64,
+    /// Highest goal position (rad).
+    pub max: f64,
+}
+
+impl PositionBounds {
+    pub const fn new(min: f64, max: f64) -> Self {
+        PositionBounds { min, max }
+    }
+
+    pub fn contains(&self, position: f64) -> bool {
+        position >= self.min && position <= self.max
+    }
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl PositionBounds {
+    #[new]
+    fn py_new(min: f64, max: f64) -> Self {
+        PositionBounds::new(min, max)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("PositionBounds(min={:.3}, max={:.3})", self.min, self.max)
+    }
+}
+
+const STEWART_BOUNDS: PositionBounds = PositionBounds::new(-PI / 2.0, PI / 2.0);
+const FULL_TURN_BOUNDS: PositionBounds = PositionBounds::new(-PI, PI);
+
+/// Full motor range for the body rotation and the antennas, half of it for the stewart arms.
+pub const DEFAULT_POSITION_BOUNDS: [PositionBounds; NUM_JOINTS] = [
+    FULL_TURN_BOUNDS,
+    STEWART_BOUNDS,
+    STEWART_BOUNDS,
+    STEWART_BOUNDS,
+    STEWART_BOUNDS,
+    STEWART_BOUNDS,
+    STEWART_BOUNDS,
+    FULL_TURN_BOUNDS,
+    FULL_TURN_BOUNDS,
+];
+
+/// A goal position outside of the [`PositionBounds`] of its joint.
+#[derive(Debug, Clone)]
+pub struct PositionOutOfBounds {
+    pub joint: usize,
+    pub value: f64,
+    pub bounds: PositionBounds,
+}
+
+impl std::error::Error for PositionOutOfBounds {}
+impl fmt::Display for PositionOutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Goal position of {} ({:.3} rad) out of bounds [{:.3}, {:.3}]",
+            JOINT_NAMES[self.joint], self.value, self.bounds.min, self.bounds.max
+        )
+    }
+}
+
+/// Check the goals of the joints `first..first + positions.len()`.
+pub fn validate_positions(
+    first: usize,
+    positions: &[f64],
+    bounds: &[PositionBounds; NUM_JOINTS],
+) -> Result<(), PositionOutOfBounds> {
+    for (joint, &value) in positions.iter().enumerate().map(|(i, v)| (first + i, v)) {
+        if !bounds[joint].contains(value) {
+            return Err(PositionOutOfBounds {
+                joint,
+                value,
+                bounds: bounds[joint],
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Clamp the goals of the joints `first..first + positions.len()` to their bounds.
+///
+/// Returns `None` if a goal is not finite, there is no nearest legal value then.
+pub fn clamp_positions(
+    first: usize,
+    positions: &[f64],
+    bounds: &[PositionBounds; NUM_JOINTS],
+) -> Option<Vec<f64>> {
+    positions
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            let bounds = bounds[first + i];
+            value
+                .is_finite()
+                .then(|| value.max(bounds.min).min(bounds.max))
+        })
+        .collect()
+}
@@ -0,0 +1,80 @@
+//! Plausibility filtering of the position reads.
+//!
+//! A corrupted sync read payload can still pass the checksum now and then, and
+//! would otherwise be published as is. When enabled, the control loop drops the
+//! samples holding non-finite values or implying a joint velocity no motor can
+//! reach, and keeps publishing the last plausible position instead.
+
+use std::{f64::consts::PI, fmt};
+
+use crate::{position::FullBodyPosition, smoothing::NUM_JOINTS};
+
+/// Default maximum plausible joint velocity (rad/s), well above what the XL330 can do.
+pub const DEFAULT_MAX_READ_VELOCITY: f64 = 50.0;
+
+/// After this many rejections in a row the joint most likely really moved
+/// (e.g. while the reads were failing), so the new sample is accepted.
+const MAX_CONSECUTIVE_REJECTIONS: u32 = 5;
+
+/// A position read rejected by the [`ReadFilter`].
+#[derive(Debug, Clone)]
+pub struct ImplausibleRead(pub String);
+
+impl std::error::Error for ImplausibleRead {}
+impl fmt::Display for ImplausibleRead {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Implausible position read: {}", self.0)
+    }
+}
+
+pub struct ReadFilter {
+    max_velocity: f64,
+    last: Option<([f64; NUM_JOINTS], f64)>,
+    consecutive_rejections: u32,
+}
+
+impl ReadFilter {
+    pub fn new(max_velocity: f64) -> Self {
+        ReadFilter {
+            max_velocity,
+            last: None,
+            consecutive_rejections: 0,
+        }
+    }
+
+    /// Check a new sample, it becomes the reference for the next ones if accepted.
+    pub fn check(&mut self, position: &FullBodyPosition) -> Result<(), ImplausibleRead> {
+        let joints = position.joints();
+        if let Some(joint) = joints.iter().position(|v| !v.is_finite()) {
+            return Err(ImplausibleRead(format!(
+                "joint {} is {}",
+                joint, joints[joint]
+            )));
+        }
+
+        if let Some((last, last_timestamp)) = self.last
+            && self.consecutive_rejections < MAX_CONSECUTIVE_REJECTIONS
+        {
+            let dt = (position.timestamp - last_timestamp).max(1e-3);
+            for (i, (joint, last)) in joints.iter().zip(last.iter()).enumerate() {
+                // The body rotation reading wraps around while spinning
+                let delta = (joint - last + PI).rem_euclid(2.0 * PI) - PI;
+                let velocity = delta.abs() / dt;
+                if velocity > self.max_velocity {
+                    self.consecutive_rejections += 1;
+                    return Err(ImplausibleRead(format!(
+                        "joint {} moved {:.3} rad in {:.1} ms ({:.0} rad/s)",
+                        i,
+                        delta,
+                        dt * 1000.0,
+                        velocity
+                    )));
+                }
+            }
+        }
+
+        self.last = Some((joints, position.timestamp));
+        self.consecutive_rejections = 0;
+        Ok(())
+    }
+}
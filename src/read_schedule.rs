@@ -0,0 +1,97 @@
+//! Per-group read scheduling of the positions.
+//!
+//! By default the control loop reads all the joints at each tick. The stewart platform
+//! needs the fastest feedback, while the body rotation and the antennas are often fine at
+//! a much lower rate: with a [`ReadSchedule`] set with
+//! [`crate::control_loop::ReachyMiniControlLoop::set_read_schedule`], each group is only
+//! read once its period has elapsed, so the bus time saved goes to the stewart platform
+//! (e.g. stewart platform at 100 Hz, body and antennas at 20 Hz). The joints not read at
+//! a tick keep their last read position.
+
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::smoothing::NUM_JOINTS;
+
+/// Groups of joints read together, in joint order.
+pub(crate) const GROUPS: [std::ops::Range<usize>; 3] = [0..1, 1..7, 7..9];
+
+/// Read period of each group, zero to read it at every tick.
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all, set_all))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReadSchedule {
+    pub body: Duration,
+    pub stewart: Duration,
+    pub antennas: Duration,
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl ReadSchedule {
+    #[new]
+    #[pyo3(signature = (body = Duration::ZERO, stewart = Duration::ZERO, antennas = Duration::ZERO))]
+    fn py_new(body: Duration, stewart: Duration, antennas: Duration) -> Self {
+        ReadSchedule {
+            body,
+            stewart,
+            antennas,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ReadSchedule(body={:?}, stewart={:?}, antennas={:?})",
+            self.body, self.stewart, self.antennas
+        )
+    }
+}
+
+impl ReadSchedule {
+    fn periods(&self) -> [Duration; 3] {
+        [self.body, self.stewart, self.antennas]
+    }
+}
+
+#[derive(Default)]
+pub struct ReadScheduler {
+    last_reads: [Option<Instant>; 3],
+}
+
+impl ReadScheduler {
+    /// Joints to read at the tick `now`, with the loop `period`, and mark them as read.
+    ///
+    /// A group is due half a loop period early, so a period multiple of the loop one
+    /// is kept despite the jitter of the ticks.
+    pub fn due(
+        &mut self,
+        schedule: &ReadSchedule,
+        now: Instant,
+        period: Duration,
+    ) -> [bool; NUM_JOINTS] {
+        let mut due = [false; NUM_JOINTS];
+        for ((joints, group_period), last_read) in GROUPS
+            .into_iter()
+            .zip(schedule.periods())
+            .zip(self.last_reads.iter_mut())
+        {
+            let is_due = last_read.is_none_or(|last| {
+                now.saturating_duration_since(last) + period / 2 >= group_period
+            });
+            if is_due {
+                *last_read = Some(now);
+                due[joints].fill(true);
+            }
+        }
+        due
+    }
+
+    /// Read all the groups at the next tick, e.g. after a failed read.
+    pub fn reset(&mut self) {
+        self.last_reads = [None; 3];
+    }
+}
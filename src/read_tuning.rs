@@ -0,0 +1,305 @@
+//! Benchmark and auto-tuning of the position reads.
+//!
+//! How fast the positions can be read depends on the hardware: the USB adapter latency,
+//! the firmware of the motors and the return delay time they wait before answering. The
+//! control loop reads them at each tick, so [`auto_tune_reads`] measures on the actual
+//! robot each [`ReadStrategy`]:
+//! - combined: a single sync read of the 9 motors, the default.
+//! - split: a sync read per group of joints (body rotation, stewart platform, antennas),
+//!   as done by the read schedule (see [`crate::read_schedule`]).
+//! - fast sync: a single fast sync read (instruction 0x8A) of the 9 motors, answered in one
+//!   status packet, if the firmware supports it.
+//!
+//! with each candidate return delay time, and applies the best configuration: the fewest
+//! failed reads, then the lowest 99th percentile read time, which bounds the loop period.
+//! The return delay time is in the EEPROM area and only swept while the torque is off,
+//! otherwise the strategies are compared with the current one.
+//!
+//! The return delay time written stays in the motors, while the read strategy has to be
+//! set again after a restart (see
+//! [`crate::control_loop::ReachyMiniControlLoop::set_read_strategy`]).
+
+use std::{fmt, time::Instant};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::{MotorControllerError, ReachyMiniMotorController, firmware::Feature};
+
+/// Return delay times (units of 2 µs) tried by default.
+pub const DEFAULT_RETURN_DELAY_TIMES: [u8; 4] = [0, 1, 5, 25];
+
+/// Number of reads timed by default for each configuration.
+pub const DEFAULT_TUNING_SAMPLES: usize = 200;
+
+/// How the positions of the motors are read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ReadStrategy {
+    /// A single sync read of all the motors.
+    #[default]
+    Combined,
+    /// A sync read per group of joints.
+    Split,
+    /// A single fast sync read of all the motors.
+    FastSync,
+}
+
+impl ReadStrategy {
+    pub const ALL: [ReadStrategy; 3] = [
+        ReadStrategy::Combined,
+        ReadStrategy::Split,
+        ReadStrategy::FastSync,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReadStrategy::Combined => "combined",
+            ReadStrategy::Split => "split",
+            ReadStrategy::FastSync => "fast_sync",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ReadStrategy> {
+        ReadStrategy::ALL.into_iter().find(|s| s.name() == name)
+    }
+
+    /// Firmware feature needed by the strategy, if any.
+    pub fn feature(&self) -> Option<Feature> {
+        match self {
+            ReadStrategy::FastSync => Some(Feature::FastSyncRead),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ReadStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Read times of a configuration.
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ReadBenchmark {
+    pub strategy: ReadStrategy,
+    /// Return delay time of the motors (units of 2 µs), None if left unchanged.
+    pub return_delay_time: Option<u8>,
+    /// Number of reads timed.
+    pub samples: usize,
+    /// Number of failed reads.
+    pub failures: usize,
+    /// Mean time of the successful reads (s), infinite if they all failed.
+    pub mean: f64,
+    /// 99th percentile of the time of the successful reads (s).
+    pub p99: f64,
+    /// Longest successful read (s).
+    pub max: f64,
+}
+
+impl ReadBenchmark {
+    /// Whether the configuration is better for the control loop than `other`: fewer
+    /// failed reads, then a lower 99th percentile read time.
+    pub fn is_better_than(&self, other: &ReadBenchmark) -> bool {
+        (self.failures, self.p99) < (other.failures, other.p99)
+    }
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl ReadBenchmark {
+    /// Name of the read strategy, "combined", "split" or "fast_sync".
+    #[getter]
+    fn strategy(&self) -> &'static str {
+        self.strategy.name()
+    }
+
+    #[getter]
+    fn return_delay_time(&self) -> Option<u8> {
+        self.return_delay_time
+    }
+
+    #[getter]
+    fn samples(&self) -> usize {
+        self.samples
+    }
+
+    #[getter]
+    fn failures(&self) -> usize {
+        self.failures
+    }
+
+    #[getter]
+    fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    #[getter]
+    fn p99(&self) -> f64 {
+        self.p99
+    }
+
+    #[getter]
+    fn max(&self) -> f64 {
+        self.max
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ReadBenchmark(strategy={}, return_delay_time={:?}, failures={}/{}, \
+             mean={:.6}, p99={:.6})",
+            self.strategy, self.return_delay_time, self.failures, self.samples, self.mean, self.p99
+        )
+    }
+}
+
+/// Result of [`auto_tune_reads`].
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ReadTuning {
+    /// Every configuration measured.
+    pub benchmarks: Vec<ReadBenchmark>,
+    /// The configuration applied.
+    pub best: ReadBenchmark,
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl ReadTuning {
+    fn __repr__(&self) -> String {
+        format!(
+            "ReadTuning(best={}, benchmarks={})",
+            self.best.__repr__(),
+            self.benchmarks.len()
+        )
+    }
+}
+
+/// Time `samples` reads of all the positions with `strategy`.
+pub fn benchmark_reads(
+    controller: &mut ReachyMiniMotorController,
+    strategy: ReadStrategy,
+    samples: usize,
+) -> Result<ReadBenchmark, MotorControllerError> {
+    let previous = controller.read_strategy();
+    controller.set_read_strategy(strategy)?;
+
+    // The first read after a change also flushes what is left of the previous ones
+    let _ = controller.read_all_positions();
+    let mut failures = 0;
+    let mut times = Vec::with_capacity(samples);
+    for _ in 0..samples {
+        let start = Instant::now();
+        match controller.read_all_positions() {
+            Ok(_) => times.push(start.elapsed().as_secs_f64()),
+            Err(_) => failures += 1,
+        }
+    }
+    controller.set_read_strategy(previous)?;
+
+    times.sort_by(f64::total_cmp);
+    let percentile = |p: f64| match times.len() {
+        0 => f64::INFINITY,
+        n => times[((n as f64 * p).ceil() as usize).clamp(1, n) - 1],
+    };
+    Ok(ReadBenchmark {
+        strategy,
+        return_delay_time: None,
+        samples,
+        failures,
+        mean: match times.len() {
+            0 => f64::INFINITY,
+            n => times.iter().sum::<f64>() / n as f64,
+        },
+        p99: percentile(0.99),
+        max: percentile(1.0),
+    })
+}
+
+/// Benchmark the read strategies supported by the motors with each of the
+/// `return_delay_times`, then apply the best configuration to `controller`.
+///
+/// The return delay times are only swept while the torque of all the motors is off,
+/// otherwise (or if none is given) the current ones are kept.
+pub fn auto_tune_reads(
+    controller: &mut ReachyMiniMotorController,
+    return_delay_times: &[u8],
+    samples: usize,
+) -> Result<ReadTuning, MotorControllerError> {
+    if samples == 0 {
+        return Err(MotorControllerError::OutOfBounds(
+            "At least one sample is needed to tune the reads".to_string(),
+        ));
+    }
+    if controller.firmware_info().is_empty() {
+        controller.detect_firmware()?;
+    }
+    let strategies: Vec<ReadStrategy> = ReadStrategy::ALL
+        .into_iter()
+        .filter(|strategy| strategy.feature().is_none_or(|f| controller.supports(f)))
+        .collect();
+
+    let initial_delays = controller.read_all_return_delay_times()?;
+    let torque_off = !controller.read_all_torque_enabled()?.contains(&true);
+    let delays: Vec<Option<u8>> = match torque_off && !return_delay_times.is_empty() {
+        true => return_delay_times.iter().copied().map(Some).collect(),
+        false => vec![None],
+    };
+
+    let mut benchmarks = Vec::new();
+    if let Err(e) = sweep(controller, &delays, &strategies, samples, &mut benchmarks) {
+        // Leave the motors as they were
+        if delays[0].is_some() {
+            controller.set_all_return_delay_times(initial_delays)?;
+        }
+        return Err(e);
+    }
+
+    let best = benchmarks
+        .iter()
+        .copied()
+        .reduce(|best, benchmark| match benchmark.is_better_than(&best) {
+            true => benchmark,
+            false => best,
+        })
+        .ok_or_else(|| {
+            MotorControllerError::Unsupported("No read strategy was benchmarked".to_string())
+        })?;
+    if let Some(delay) = best.return_delay_time {
+        controller.set_all_return_delay_times([delay; 9])?;
+    }
+    controller.set_read_strategy(best.strategy)?;
+    Ok(ReadTuning { benchmarks, best })
+}
+
+fn sweep(
+    controller: &mut ReachyMiniMotorController,
+    delays: &[Option<u8>],
+    strategies: &[ReadStrategy],
+    samples: usize,
+    benchmarks: &mut Vec<ReadBenchmark>,
+) -> Result<(), MotorControllerError> {
+    for &delay in delays {
+        if let Some(delay) = delay {
+            controller.set_all_return_delay_times([delay; 9])?;
+        }
+        for &strategy in strategies {
+            benchmarks.push(ReadBenchmark {
+                return_delay_time: delay,
+                ..benchmark_reads(controller, strategy, samples)?
+            });
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,217 @@
+//! Several Reachy Mini robots driven from the same host.
+//!
+//! [`discover_robots`] probes the USB serial ports for Reachy Mini motors and names
+//! each robot from the identity stored in its motors (see
+//! [`crate::ReachyMiniMotorController::write_identity`]) or the serial number of its
+//! USB adapter, which do not depend on the plug order. A [`RobotRegistry`] then runs
+//! one independent control loop per robot, keyed by name.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::Arc,
+    time::Duration,
+};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use log::{info, warn};
+
+use crate::{
+    ReachyMiniMotorController,
+    control_loop::{MotorError, MotorSet, ReachyMiniControlLoop},
+};
+
+/// A Reachy Mini found on a serial port.
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RobotInfo {
+    /// Name of the robot, see [`discover_robots`].
+    pub name: String,
+    /// Path (Unix) or COM ID (Windows) of the serial port.
+    pub port: String,
+    /// Serial number of the USB adapter, if reported.
+    pub serial_number: Option<String>,
+    /// Identity stored in the motors, if any.
+    pub identity: Option<String>,
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl RobotInfo {
+    fn __repr__(&self) -> String {
+        format!(
+            "RobotInfo(name={:?}, port={:?}, serial_number={:?}, identity={:?})",
+            self.name, self.port, self.serial_number, self.identity
+        )
+    }
+}
+
+/// Look for the Reachy Mini robots on the USB serial ports.
+///
+/// A port is taken as a robot if any of the Reachy Mini motors answers on it. Ports
+/// used by another process are skipped. Each robot is named from `names`, which maps
+/// identities or USB serial numbers to names. By default a robot is named after its
+/// identity, else its serial number, else its port.
+pub fn discover_robots(names: &HashMap<String, String>) -> Vec<RobotInfo> {
+    let ports = match serialport::available_ports() {
+        Ok(ports) => ports,
+        Err(e) => {
+            warn!("Could not list the serial ports: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut robots = Vec::new();
+    for port in ports {
+        let serialport::SerialPortType::UsbPort(usb) = port.port_type else {
+            continue;
+        };
+        let identity = match probe(&port.port_name) {
+            Ok(Some(identity)) => identity,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("Skipping serial port {}: {}", port.port_name, e);
+                continue;
+            }
+        };
+
+        let keys = [&identity, &usb.serial_number];
+        let name = keys
+            .iter()
+            .filter_map(|key| key.as_ref())
+            .find_map(|key| names.get(key))
+            .or(identity.as_ref())
+            .or(usb.serial_number.as_ref())
+            .cloned()
+            .unwrap_or_else(|| port_file_name(&port.port_name));
+        info!("Found Reachy Mini {} on {}", name, port.port_name);
+        robots.push(RobotInfo {
+            name,
+            port: port.port_name,
+            serial_number: usb.serial_number,
+            identity,
+        });
+    }
+    robots
+}
+
+/// The identity stored in the motors of `port`, `None` if there is no Reachy Mini motor.
+fn probe(port: &str) -> Result<Option<Option<String>>, Box<dyn std::error::Error>> {
+    let mut controller = ReachyMiniMotorController::new(port)?;
+    let missing = controller.check_missing_ids()?;
+    if missing.len() == controller.get_motor_name_id().len() {
+        return Ok(None);
+    }
+
+    let identity = controller
+        .read_identity()
+        .map_err(|e| {
+            warn!(
+                "Could not read the identity of the robot on {}: {}",
+                port, e
+            )
+        })
+        .ok()
+        .flatten()
+        .map(|identity| String::from_utf8_lossy(&identity).into_owned());
+    Ok(Some(identity))
+}
+
+fn port_file_name(port: &str) -> String {
+    std::path::Path::new(port)
+        .file_name()
+        .map_or_else(|| port.to_string(), |name| name.to_string_lossy().into())
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+    /// Two robots have this name.
+    DuplicateName(String),
+    /// The control loop of the robot could not be started.
+    Open { name: String, error: MotorError },
+}
+
+impl std::error::Error for RegistryError {}
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::DuplicateName(name) => {
+                write!(f, "Several robots are named {}!", name)
+            }
+            RegistryError::Open { name, error } => {
+                write!(f, "Could not open robot {}: {}", name, error)
+            }
+        }
+    }
+}
+
+/// Independent control loops of several robots, keyed by name.
+pub struct RobotRegistry {
+    robots: BTreeMap<String, (RobotInfo, Arc<ReachyMiniControlLoop>)>,
+}
+
+impl RobotRegistry {
+    /// Open a control loop for each robot, with the parameters of
+    /// [`ReachyMiniControlLoop::new`].
+    ///
+    /// Fails if a robot cannot be opened or if two robots have the same name, the
+    /// loops already opened are closed then.
+    pub fn open(
+        robots: Vec<RobotInfo>,
+        read_position_loop_period: Duration,
+        stats_pub_period: Option<Duration>,
+        read_allowed_retries: u64,
+        voltage_rampup_timeout: Duration,
+        motors: MotorSet,
+    ) -> Result<Self, RegistryError> {
+        let mut opened = BTreeMap::new();
+        for robot in robots {
+            if opened.contains_key(&robot.name) {
+                return Err(RegistryError::DuplicateName(robot.name));
+            }
+            let control_loop = ReachyMiniControlLoop::new(
+                robot.port.clone(),
+                read_position_loop_period,
+                stats_pub_period,
+                read_allowed_retries,
+                voltage_rampup_timeout,
+                motors,
+            )
+            .map_err(|error| RegistryError::Open {
+                name: robot.name.clone(),
+                error,
+            })?;
+            opened.insert(robot.name.clone(), (robot, Arc::new(control_loop)));
+        }
+
+        Ok(RobotRegistry { robots: opened })
+    }
+
+    /// Names of the robots, sorted.
+    pub fn names(&self) -> Vec<String> {
+        self.robots.keys().cloned().collect()
+    }
+
+    pub fn robots(&self) -> Vec<RobotInfo> {
+        self.robots.values().map(|(info, _)| info.clone()).collect()
+    }
+
+    /// Control loop of the robot `name`.
+    pub fn get(&self, name: &str) -> Option<Arc<ReachyMiniControlLoop>> {
+        self.robots
+            .get(name)
+            .map(|(_, control_loop)| control_loop.clone())
+    }
+
+    /// Close the control loops of all the robots.
+    pub fn close(&self) {
+        for (_, control_loop) in self.robots.values() {
+            control_loop.close();
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Safe mode with reduced limits, for demos with the public and classroom use.
+//!
+//! Once enabled with [`crate::control_loop::ReachyMiniControlLoop::safe_mode`], the
+//! control loop enforces the [`SafeModeLimits`] on every command path, on top of the
+//! usual checks:
+//! - the workspace: the position bounds are shrunk around their center. Goals outside
+//!   are rejected (or clamped with goal clamping), and the goals computed by the loop
+//!   itself (trajectories, head twist, control laws...) are clamped.
+//! - the velocity: the profile velocity of every joint is limited, and so is the body
+//!   spinning velocity.
+//! - the current: the goal currents of the stewart platform are capped, the ones already
+//!   written when the safe mode is enabled included, and its total current is budgeted
+//!   (see [`crate::current_budget`]) with the lowest of the safe mode budget and the one
+//!   enabled.
+//!
+//! The raw register writes and the homing of the body rotation (driven into its end-stops)
+//! would bypass these limits, they are rejected meanwhile.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::{position_bounds::PositionBounds, smoothing::NUM_JOINTS};
+
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all, set_all))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SafeModeLimits {
+    /// Maximum velocity of every joint (rad/s).
+    pub max_velocity: f64,
+    /// Maximum goal current of each stewart platform motor (mA).
+    pub max_goal_current: f64,
+    /// Maximum total current of the stewart platform motors (mA).
+    pub max_total_current: f64,
+    /// Fraction of the range of the position bounds kept, around their center.
+    pub workspace_scale: f64,
+}
+
+impl Default for SafeModeLimits {
+    fn default() -> Self {
+        SafeModeLimits {
+            max_velocity: 1.0,
+            max_goal_current: 300.0,
+            max_total_current: 1200.0,
+            workspace_scale: 0.5,
+        }
+    }
+}
+
+impl SafeModeLimits {
+    pub fn new(
+        max_velocity: f64,
+        max_goal_current: f64,
+        max_total_current: f64,
+        workspace_scale: f64,
+    ) -> Self {
+        SafeModeLimits {
+            max_velocity,
+            max_goal_current,
+            max_total_current,
+            workspace_scale,
+        }
+    }
+
+    /// Check that the limits are usable: strictly positive, and a workspace scale of at
+    /// most 1.
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = [
+            self.max_velocity,
+            self.max_goal_current,
+            self.max_total_current,
+            self.workspace_scale,
+        ]
+        .iter()
+        .all(|limit| limit.is_finite() && *limit > 0.0);
+        if !positive {
+            return Err(format!(
+                "Safe mode limits must be strictly positive: {self:?}"
+            ));
+        }
+        if self.workspace_scale > 1.0 {
+            return Err(format!(
+                "Safe mode workspace scale must be at most 1, got {}",
+                self.workspace_scale
+            ));
+        }
+        Ok(())
+    }
+
+    /// `bounds` shrunk around their center to the safe workspace. Unbounded joints stay
+    /// unbounded.
+    pub fn workspace(&self, bounds: &[PositionBounds; NUM_JOINTS]) -> [PositionBounds; NUM_JOINTS] {
+        bounds.map(|bounds| {
+            if !(bounds.min.is_finite() && bounds.max.is_finite()) {
+                return bounds;
+            }
+            let center = (bounds.min + bounds.max) / 2.0;
+            let half_range = (bounds.max - bounds.min) / 2.0 * self.workspace_scale;
+            PositionBounds::new(center - half_range, center + half_range)
+        })
+    }
+
+    /// Stewart platform goal currents (mA) capped to the maximum goal current.
+    pub fn goal_currents(&self, currents: [i16; 6]) -> [i16; 6] {
+        let max = self.max_goal_current.min(i16::MAX as f64) as i16;
+        currents.map(|current| current.clamp(-max, max))
+    }
+
+    /// Velocity (rad/s) capped to the maximum velocity, keeping its sign.
+    pub fn velocity(&self, velocity: f64) -> f64 {
+        velocity.clamp(-self.max_velocity, self.max_velocity)
+    }
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl SafeModeLimits {
+    #[new]
+    #[pyo3(signature = (
+        max_velocity = 1.0,
+        max_goal_current = 300.0,
+        max_total_current = 1200.0,
+        workspace_scale = 0.5
+    ))]
+    fn py_new(
+        max_velocity: f64,
+        max_goal_current: f64,
+        max_total_current: f64,
+        workspace_scale: f64,
+    ) -> Self {
+        SafeModeLimits::new(
+            max_velocity,
+            max_goal_current,
+            max_total_current,
+            workspace_scale,
+        )
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SafeModeLimits(max_velocity={:.3}, max_goal_current={:.1}, \
+             max_total_current={:.1}, workspace_scale={:.3})",
+            self.max_velocity, self.max_goal_current, self.max_total_current, self.workspace_scale
+        )
+    }
+}
@@ -0,0 +1,707 @@
+//! Simulated motors, to run the controller and the control loop without hardware.
+//!
+//! [`SimulatedPort`] emulates the serial bus: it decodes the Dynamixel protocol v2
+//! instructions sent by the controller and answers like the 9 XL330 of Reachy Mini
+//! would, so the whole stack (rustypot included) is exercised. Each joint follows
+//! its goal with configurable dynamics ([`JointDynamics`]), and faults (timeouts,
+//! corrupted packets, power loss, hardware errors) can be injected on demand to
+//! test the failure handling:
+//!
+//! ```no_run
+//! use reachy_mini_motor_controller::simulation::{Simulation, SimulationConfig, SimulatedFault};
+//!
+//! let sim = Simulation::new(SimulationConfig::default());
+//! let mut controller = sim.controller();
+//! controller.enable_torque().unwrap();
+//!
+//! // The right antenna stops answering
+//! sim.set_fault(17, Some(SimulatedFault::Timeout));
+//! assert!(controller.read_all_positions().is_err());
+//! ```
+//!
+//! By default the physics follows the wall clock. With [`SimulationConfig::realtime`]
+//! disabled it only advances through [`Simulation::advance`], and the measurement
+//! noise comes from a seeded generator, so runs are deterministic (e.g. in CI).
+
+use std::{
+    collections::VecDeque,
+    f64::consts::PI,
+    io,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+use crate::{
+    ReachyMiniMotorController,
+    controller::ALL_IDS,
+    fast_sync_read::{BROADCAST_ID, FAST_SYNC_READ, HEADER, crc16, status_length},
+    smoothing::NUM_JOINTS,
+};
+
+/// Model number of the XL330-M288.
+const MODEL_NUMBER: u16 = 1200;
+const FIRMWARE_VERSION: u8 = 46;
+const CONTROL_TABLE_SIZE: usize = 256;
+/// Torque enable register, the registers below it are in EEPROM.
+const TORQUE_ENABLE: usize = 64;
+const OPERATING_MODE: usize = 11;
+const HOMING_OFFSET: usize = 20;
+const HARDWARE_ERROR_STATUS: usize = 70;
+const GOAL_VELOCITY: usize = 104;
+const GOAL_POSITION: usize = 116;
+const MOVING: usize = 122;
+const PRESENT_CURRENT: usize = 126;
+const PRESENT_VELOCITY: usize = 128;
+const PRESENT_POSITION: usize = 132;
+const PRESENT_INPUT_VOLTAGE: usize = 144;
+const PRESENT_TEMPERATURE: usize = 146;
+
+const VELOCITY_CONTROL_MODE: u8 = 1;
+const POSITION_CONTROL_MODE: u8 = 3;
+const VELOCITY_UNIT_RPM: f64 = 0.229;
+const POSITION_UNIT_RAD: f64 = 2.0 * PI / 4096.0;
+/// Current drawn per radian of tracking error (mA/rad), a rough proportional controller.
+const CURRENT_PER_RAD: f64 = 2000.0;
+/// Current limit of the XL330 (mA).
+const MAX_CURRENT: f64 = 1750.0;
+
+/// Error field of a status packet refusing a write (protocol v2 "Access" error).
+const ACCESS_ERROR: u8 = 0x07;
+
+/// How a simulated joint follows its goal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JointDynamics {
+    /// Maximum velocity (rad/s).
+    pub max_velocity: f64,
+    /// Time constant of the first-order lag towards the goal position (s).
+    pub time_constant: f64,
+    /// Amplitude of the uniform noise added to the present position (rad).
+    pub noise: f64,
+}
+
+impl Default for JointDynamics {
+    fn default() -> Self {
+        JointDynamics {
+            max_velocity: 6.0,
+            time_constant: 0.02,
+            noise: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimulationConfig {
+    /// Dynamics of each joint, in joint order.
+    pub dynamics: [JointDynamics; NUM_JOINTS],
+    /// Seed of the measurement noise.
+    pub seed: u64,
+    /// Advance the physics with the wall clock, otherwise only with [`Simulation::advance`].
+    pub realtime: bool,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            dynamics: [JointDynamics::default(); NUM_JOINTS],
+            seed: 0,
+            realtime: true,
+        }
+    }
+}
+
+impl SimulationConfig {
+    /// The same `dynamics` for every joint.
+    pub fn uniform(dynamics: JointDynamics) -> Self {
+        SimulationConfig {
+            dynamics: [dynamics; NUM_JOINTS],
+            ..Default::default()
+        }
+    }
+}
+
+/// Fault injected on a simulated motor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulatedFault {
+    /// The motor executes the instructions but never answers.
+    Timeout,
+    /// The status packets of the motor have a wrong checksum.
+    Checksum,
+}
+
+struct SimulatedMotor {
+    id: u8,
+    table: [u8; CONTROL_TABLE_SIZE],
+    /// Actual position (rad), before the homing offset.
+    position: f64,
+    velocity: f64,
+    dynamics: JointDynamics,
+    fault: Option<SimulatedFault>,
+}
+
+impl SimulatedMotor {
+    fn new(id: u8, dynamics: JointDynamics) -> Self {
+        let mut motor = SimulatedMotor {
+            id,
+            table: [0; CONTROL_TABLE_SIZE],
+            position: 0.0,
+            velocity: 0.0,
+            dynamics,
+            fault: None,
+        };
+        motor.write(0, &MODEL_NUMBER.to_le_bytes());
+        motor.table[6] = FIRMWARE_VERSION;
+        motor.table[7] = id;
+        motor.table[8] = 3; // Baud rate: 1 Mbps
+        motor.table[OPERATING_MODE] = POSITION_CONTROL_MODE;
+        motor.write(48, &4095u32.to_le_bytes()); // Max position limit
+        motor.table[68] = 2; // Status return level: answer all instructions
+        motor.write(PRESENT_INPUT_VOLTAGE, &50u16.to_le_bytes());
+        motor.table[PRESENT_TEMPERATURE] = 30;
+        motor.hold_position();
+        motor
+    }
+
+    fn read_i32(&self, addr: usize) -> i32 {
+        i32::from_le_bytes(self.table[addr..addr + 4].try_into().unwrap_or_default())
+    }
+
+    fn write(&mut self, addr: usize, data: &[u8]) {
+        let end = (addr + data.len()).min(CONTROL_TABLE_SIZE);
+        if addr < end {
+            self.table[addr..end].copy_from_slice(&data[..end - addr]);
+        }
+    }
+
+    /// Write from an instruction, the EEPROM area is locked while the torque is on.
+    fn write_register(&mut self, addr: usize, data: &[u8]) -> Result<(), ()> {
+        if addr < TORQUE_ENABLE && self.table[TORQUE_ENABLE] != 0 {
+            return Err(());
+        }
+        self.write(addr, data);
+        Ok(())
+    }
+
+    fn raw_position(&self, position: f64) -> i32 {
+        ((position + PI) / POSITION_UNIT_RAD).round() as i32 + self.read_i32(HOMING_OFFSET)
+    }
+
+    /// Goal position (rad), before the homing offset.
+    fn goal_position(&self) -> f64 {
+        let raw_goal = self.read_i32(GOAL_POSITION) - self.read_i32(HOMING_OFFSET);
+        raw_goal as f64 * POSITION_UNIT_RAD - PI
+    }
+
+    fn hold_position(&mut self) {
+        let raw = self.raw_position(self.position);
+        self.write(GOAL_POSITION, &raw.to_le_bytes());
+        self.write(PRESENT_POSITION, &raw.to_le_bytes());
+    }
+
+    fn step(&mut self, dt: f64) {
+        if dt <= 0.0 {
+            return;
+        }
+        let max_step = self.dynamics.max_velocity * dt;
+        let step = if self.table[TORQUE_ENABLE] == 0 {
+            0.0
+        } else {
+            match self.table[OPERATING_MODE] {
+                VELOCITY_CONTROL_MODE => {
+                    let rpm = self.read_i32(GOAL_VELOCITY) as f64 * VELOCITY_UNIT_RPM;
+                    rpm * 2.0 * PI / 60.0 * dt
+                }
+                // Current control, there is no load to move against
+                0 => 0.0,
+                _ => {
+                    let goal = self.goal_position();
+                    let lag = (-dt / self.dynamics.time_constant).exp();
+                    goal + (self.position - goal) * lag - self.position
+                }
+            }
+        };
+        let step = step.max(-max_step).min(max_step);
+        self.position += step;
+        self.velocity = step / dt;
+    }
+
+    /// Update the present registers, `noise` being drawn in [-1, 1].
+    fn update_present(&mut self, noise: f64) {
+        let raw = self.raw_position(self.position + noise * self.dynamics.noise);
+        self.write(PRESENT_POSITION, &raw.to_le_bytes());
+        let velocity = (self.velocity * 60.0 / (2.0 * PI) / VELOCITY_UNIT_RPM).round() as i32;
+        self.write(PRESENT_VELOCITY, &velocity.to_le_bytes());
+        self.table[MOVING] = (velocity != 0) as u8;
+
+        let position_control = !matches!(self.table[OPERATING_MODE], 0 | VELOCITY_CONTROL_MODE);
+        let current = if self.table[TORQUE_ENABLE] != 0 && position_control {
+            ((self.goal_position() - self.position) * CURRENT_PER_RAD)
+                .clamp(-MAX_CURRENT, MAX_CURRENT)
+        } else {
+            0.0
+        };
+        self.write(PRESENT_CURRENT, &(current.round() as i16).to_le_bytes());
+    }
+
+    fn reboot(&mut self) {
+        self.table[TORQUE_ENABLE] = 0;
+        self.table[HARDWARE_ERROR_STATUS] = 0;
+        self.velocity = 0.0;
+        self.hold_position();
+    }
+}
+
+struct SimulatedBus {
+    motors: Vec<SimulatedMotor>,
+    powered: bool,
+    realtime: bool,
+    last_update: Instant,
+    rng: u64,
+    /// Bytes written by the host, not forming a complete packet yet.
+    input: Vec<u8>,
+    /// Status packets waiting to be read by the host.
+    output: VecDeque<u8>,
+}
+
+impl SimulatedBus {
+    fn motor_mut(&mut self, id: u8) -> Option<&mut SimulatedMotor> {
+        self.motors.iter_mut().find(|m| m.id == id)
+    }
+
+    /// xorshift64*, uniform in [-1, 1].
+    fn noise(&mut self) -> f64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        let value = self.rng.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        (value >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+    }
+
+    fn advance(&mut self, dt: f64) {
+        for i in 0..self.motors.len() {
+            self.motors[i].step(dt);
+            let noise = self.noise();
+            self.motors[i].update_present(noise);
+        }
+    }
+
+    fn sync_time(&mut self) {
+        let now = Instant::now();
+        if self.realtime {
+            let dt = now.duration_since(self.last_update).as_secs_f64();
+            self.advance(dt);
+        }
+        self.last_update = now;
+    }
+
+    fn receive(&mut self, data: &[u8]) {
+        self.input.extend_from_slice(data);
+        loop {
+            // Drop the bytes before the next header, like a motor waiting for a packet
+            match self.input.windows(HEADER.len()).position(|w| w == HEADER) {
+                Some(start) => {
+                    self.input.drain(..start);
+                }
+                None => {
+                    let keep = self.input.len().min(HEADER.len() - 1);
+                    self.input.drain(..self.input.len() - keep);
+                    return;
+                }
+            }
+            if self.input.len() < 7 {
+                return;
+            }
+            let length = u16::from_le_bytes([self.input[5], self.input[6]]) as usize;
+            if self.input.len() < 7 + length {
+                return;
+            }
+            let packet: Vec<u8> = self.input.drain(..7 + length).collect();
+            let crc = u16::from_le_bytes([packet[packet.len() - 2], packet[packet.len() - 1]]);
+            if length >= 3 && crc == crc16(&packet[..packet.len() - 2]) {
+                self.execute(packet[4], packet[7], &packet[8..packet.len() - 2]);
+            }
+        }
+    }
+
+    fn execute(&mut self, id: u8, instruction: u8, params: &[u8]) {
+        if !self.powered {
+            return;
+        }
+        self.sync_time();
+
+        let addr = |params: &[u8]| u16::from_le_bytes([params[0], params[1]]) as usize;
+        match (instruction, params.len()) {
+            // Ping
+            (0x01, _) => {
+                let mut reply = MODEL_NUMBER.to_le_bytes().to_vec();
+                reply.push(FIRMWARE_VERSION);
+                self.reply(id, 0, &reply);
+            }
+            // Read
+            (0x02, 4..) => {
+                let (addr, length) = (addr(params), addr(&params[2..]));
+                if let Some(motor) = self.motor_mut(id) {
+                    let data = read_table(motor, addr, length);
+                    self.reply(id, 0, &data);
+                }
+            }
+            // Write
+            (0x03, 2..) => {
+                let res = self
+                    .motor_mut(id)
+                    .map(|motor| motor.write_register(addr(params), &params[2..]));
+                if let Some(res) = res {
+                    self.reply(id, if res.is_ok() { 0 } else { ACCESS_ERROR }, &[]);
+                }
+            }
+            // Factory reset
+            (0x06, _) => self.reply(id, 0, &[]),
+            // Reboot
+            (0x08, _) => {
+                if let Some(motor) = self.motor_mut(id) {
+                    motor.reboot();
+                    self.reply(id, 0, &[]);
+                }
+            }
+            // Sync read
+            (0x82, 4..) => {
+                let (addr, length) = (addr(params), addr(&params[2..]));
+                for &id in &params[4..] {
+                    if let Some(motor) = self.motor_mut(id) {
+                        let data = read_table(motor, addr, length);
+                        self.reply(id, 0, &data);
+                    }
+                }
+            }
+            // Fast sync read, a single status packet
+            (FAST_SYNC_READ, 4..) => {
+                let (addr, length) = (addr(params), addr(&params[2..]));
+                self.fast_sync_reply(&params[4..], addr, length);
+            }
+            // Sync write, no status packet
+            (0x83, 4..) => {
+                let (addr, length) = (addr(params), addr(&params[2..]));
+                for chunk in params[4..].chunks_exact(length + 1) {
+                    if let Some(motor) = self.motor_mut(chunk[0]) {
+                        let _ = motor.write_register(addr, &chunk[1..]);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn reply(&mut self, id: u8, error: u8, params: &[u8]) {
+        if id == BROADCAST_ID {
+            return;
+        }
+        let fault = match self.motors.iter().find(|m| m.id == id) {
+            Some(motor) => motor.fault,
+            None => return,
+        };
+        if fault == Some(SimulatedFault::Timeout) {
+            return;
+        }
+
+        let mut packet = HEADER.to_vec();
+        packet.push(id);
+        packet.extend((params.len() as u16 + 4).to_le_bytes());
+        packet.push(0x55);
+        packet.push(error);
+        packet.extend(params);
+        let mut crc = crc16(&packet);
+        if fault == Some(SimulatedFault::Checksum) {
+            crc ^= 0xFFFF;
+        }
+        packet.extend(crc.to_le_bytes());
+        self.output.extend(packet);
+    }
+
+    /// Status packet of a fast sync read, filled by each motor in turn. A motor which
+    /// does not answer breaks the chain and no packet is completed.
+    fn fast_sync_reply(&mut self, ids: &[u8], addr: usize, length: usize) {
+        let mut packet = HEADER.to_vec();
+        packet.push(BROADCAST_ID);
+        packet.extend((status_length(ids.len(), length) as u16).to_le_bytes());
+        packet.push(0x55);
+        let mut corrupted = false;
+        for &id in ids {
+            let Some(motor) = self.motors.iter().find(|m| m.id == id) else {
+                return;
+            };
+            match motor.fault {
+                Some(SimulatedFault::Timeout) => return,
+                Some(SimulatedFault::Checksum) => corrupted = true,
+                _ => {}
+            }
+            packet.push(0);
+            packet.push(id);
+            packet.extend(read_table(motor, addr, length));
+            // Each motor appends the CRC of the packet so far
+            packet.extend(crc16(&packet).to_le_bytes());
+        }
+        if corrupted {
+            let last = packet.len() - 1;
+            packet[last] ^= 0xFF;
+        }
+        self.output.extend(packet);
+    }
+}
+
+fn read_table(motor: &SimulatedMotor, addr: usize, length: usize) -> Vec<u8> {
+    let end = (addr + length).min(CONTROL_TABLE_SIZE);
+    let mut data = motor.table[addr.min(end)..end].to_vec();
+    data.resize(length, 0);
+    data
+}
+
+/// Handle on the simulated motors, to drive them and inject faults.
+#[derive(Clone)]
+pub struct Simulation {
+    bus: Arc<Mutex<SimulatedBus>>,
+}
+
+impl Simulation {
+    pub fn new(config: SimulationConfig) -> Self {
+        let motors = ALL_IDS
+            .iter()
+            .zip(config.dynamics)
+            .map(|(&id, dynamics)| SimulatedMotor::new(id, dynamics))
+            .collect();
+        Simulation {
+            bus: Arc::new(Mutex::new(SimulatedBus {
+                motors,
+                powered: true,
+                realtime: config.realtime,
+                last_update: Instant::now(),
+                // xorshift gets stuck on 0
+                rng: config.seed.max(1),
+                input: Vec::new(),
+                output: VecDeque::new(),
+            })),
+        }
+    }
+
+    fn bus(&self) -> std::sync::MutexGuard<'_, SimulatedBus> {
+        self.bus.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// A new serial port connected to the simulated motors.
+    pub fn port(&self) -> SimulatedPort {
+        SimulatedPort {
+            bus: self.bus.clone(),
+            timeout: Duration::from_millis(10),
+        }
+    }
+
+    /// A controller driving the simulated motors.
+    pub fn controller(&self) -> ReachyMiniMotorController {
+        ReachyMiniMotorController::with_port(Box::new(self.port()))
+    }
+
+    /// Advance the physics, the only way it moves when the simulation is not realtime.
+    pub fn advance(&self, dt: Duration) {
+        self.bus().advance(dt.as_secs_f64());
+    }
+
+    /// Inject a fault on the motor `id`, or clear it with `None`.
+    pub fn set_fault(&self, id: u8, fault: Option<SimulatedFault>) {
+        if let Some(motor) = self.bus().motor_mut(id) {
+            motor.fault = fault;
+        }
+    }
+
+    /// Cut (or restore) the power of all the motors.
+    ///
+    /// Unpowered motors ignore the instructions, and come back rebooted.
+    pub fn set_powered(&self, powered: bool) {
+        let mut bus = self.bus();
+        if powered && !bus.powered {
+            bus.motors.iter_mut().for_each(SimulatedMotor::reboot);
+        }
+        bus.powered = powered;
+    }
+
+    /// Raise a hardware error on the motor `id`, which disables its torque until it is rebooted.
+    ///
+    /// `status` is the value of the hardware error status register, e.g. `0x20` for an overload.
+    pub fn set_hardware_error(&self, id: u8, status: u8) {
+        if let Some(motor) = self.bus().motor_mut(id) {
+            motor.table[HARDWARE_ERROR_STATUS] = status;
+            motor.table[TORQUE_ENABLE] = 0;
+        }
+    }
+
+    /// Actual position of each joint (rad), without noise.
+    pub fn positions(&self) -> [f64; NUM_JOINTS] {
+        let bus = self.bus();
+        std::array::from_fn(|i| bus.motors[i].position)
+    }
+
+    /// Move a joint as if pushed by hand.
+    pub fn set_position(&self, joint: usize, position: f64) {
+        let mut bus = self.bus();
+        let noise = bus.noise();
+        if let Some(motor) = bus.motors.get_mut(joint) {
+            motor.position = position;
+            motor.update_present(noise);
+        }
+    }
+}
+
+/// Serial port connected to the motors of a [`Simulation`].
+pub struct SimulatedPort {
+    bus: Arc<Mutex<SimulatedBus>>,
+    timeout: Duration,
+}
+
+impl SimulatedPort {
+    fn bus(&self) -> std::sync::MutexGuard<'_, SimulatedBus> {
+        self.bus.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl io::Read for SimulatedPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut bus = self.bus();
+        if bus.output.is_empty() {
+            // Nothing will ever come, no need to actually wait for the timeout
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "Operation timed out",
+            ));
+        }
+        let n = buf.len().min(bus.output.len());
+        for (dst, src) in buf.iter_mut().zip(bus.output.drain(..n)) {
+            *dst = src;
+        }
+        Ok(n)
+    }
+}
+
+impl io::Write for SimulatedPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bus().receive(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for SimulatedPort {
+    fn name(&self) -> Option<String> {
+        Some("simulated".to_string())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(1_000_000)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, _baud_rate: u32) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, _data_bits: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, _flow_control: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_parity(&mut self, _parity: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, _stop_bits: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        Ok(self.bus().output.len() as u32)
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        let mut bus = self.bus();
+        match buffer_to_clear {
+            ClearBuffer::Input => bus.output.clear(),
+            ClearBuffer::Output => bus.input.clear(),
+            ClearBuffer::All => {
+                bus.output.clear();
+                bus.input.clear();
+            }
+        }
+        Ok(())
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(SimulatedPort {
+            bus: self.bus.clone(),
+            timeout: self.timeout,
+        }))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+//! Acceleration-limited shaping of the goal positions.
+//!
+//! When enabled, goals sent by the host are not forwarded as is to the motors.
+//! Each joint instead follows its goal with bounded velocity, acceleration and jerk
+//! (S-curve profile), computed by the control loop at each tick. This does not rely
+//! on the firmware profile registers, so even a host streaming raw step goals
+//! produces mechanically gentle motion.
+//!
+//! The antennas, often driven by noisy targets (e.g. from audio or emotion engines),
+//! can also be smoothed alone with the second-order filter of [`AntennaSmoother`].
+//!
+//! Joints are ordered as in [`crate::ReachyMiniMotorController::set_all_goal_positions`]:
+//! body rotation, the 6 stewart platform motors, then the right and left antennas.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+pub const NUM_JOINTS: usize = 9;
+
+/// Below those thresholds a joint is considered to have reached its target.
+const POSITION_TOLERANCE: f64 = 1e-4; // rad
+const VELOCITY_TOLERANCE: f64 = 1e-3; // rad/s
+
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all, set_all))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JointLimits {
+    /// Maximum velocity (rad/s).
+    pub max_velocity: f64,
+    /// Maximum acceleration (rad/s^2).
+    pub max_acceleration: f64,
+    /// Maximum jerk (rad/s^3).
+    pub max_jerk: f64,
+}
+
+impl Default for JointLimits {
+    fn default() -> Self {
+        JointLimits {
+            max_velocity: 4.0,
+            max_acceleration: 20.0,
+            max_jerk: 400.0,
+        }
+    }
+}
+
+impl JointLimits {
+    pub fn new(max_velocity: f64, max_acceleration: f64, max_jerk: f64) -> Self {
+        JointLimits {
+            max_velocity,
+            max_acceleration,
+            max_jerk,
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl JointLimits {
+    #[new]
+    #[pyo3(signature = (max_velocity = 4.0, max_acceleration = 20.0, max_jerk = 400.0))]
+    fn py_new(max_velocity: f64, max_acceleration: f64, max_jerk: f64) -> Self {
+        JointLimits::new(max_velocity, max_acceleration, max_jerk)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "JointLimits(max_velocity={:.3}, max_acceleration={:.3}, max_jerk={:.3})",
+            self.max_velocity, self.max_acceleration, self.max_jerk
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct JointState {
+    position: f64,
+    velocity: f64,
+    acceleration: f64,
+}
+
+impl JointState {
+    fn at(position: f64) -> Self {
+        JointState {
+            position,
+            ..Default::default()
+        }
+    }
+
+    fn is_settled(&self, target: f64) -> bool {
+        (target - self.position).abs() < POSITION_TOLERANCE
+            && self.velocity.abs() < VELOCITY_TOLERANCE
+    }
+
+    fn step(&mut self, target: f64, limits: &JointLimits, dt: f64) {
+        let error = target - self.position;
+
+        // Fastest velocity from which we can still stop on the target
+        let braking_velocity = (2.0 * limits.max_acceleration * error.abs()).sqrt();
+        let desired_velocity = error.signum() * braking_velocity.min(limits.max_velocity);
+
+        let desired_acceleration = ((desired_velocity - self.velocity) / dt)
+            .clamp(-limits.max_acceleration, limits.max_acceleration);
+        let max_acceleration_change = limits.max_jerk * dt;
+        self.acceleration += (desired_acceleration - self.acceleration)
+            .clamp(-max_acceleration_change, max_acceleration_change);
+
+        self.velocity = (self.velocity + self.acceleration * dt)
+            .clamp(-limits.max_velocity, limits.max_velocity);
+        self.position += self.velocity * dt;
+
+        // Do not overshoot: the jerk limit delays the braking a bit, land on the target instead
+        let crossed = (target - self.position).signum() != error.signum();
+        if crossed || self.is_settled(target) {
+            *self = JointState::at(target);
+        }
+    }
+}
+
+/// Shapes a stream of goal positions so each joint respects its [`JointLimits`].
+#[derive(Debug, Clone)]
+pub struct GoalSmoother {
+    limits: [JointLimits; NUM_JOINTS],
+    targets: [f64; NUM_JOINTS],
+    joints: [JointState; NUM_JOINTS],
+}
+
+impl GoalSmoother {
+    /// Create a smoother at rest at the given positions.
+    pub fn new(limits: [JointLimits; NUM_JOINTS], positions: [f64; NUM_JOINTS]) -> Self {
+        GoalSmoother {
+            limits,
+            targets: positions,
+            joints: positions.map(JointState::at),
+        }
+    }
+
+    pub fn limits(&self) -> &[JointLimits; NUM_JOINTS] {
+        &self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: [JointLimits; NUM_JOINTS]) {
+        self.limits = limits;
+    }
+
+    /// Set the target of a single joint.
+    pub fn set_target(&mut self, joint: usize, target: f64) {
+        self.targets[joint] = target;
+    }
+
+    pub fn set_targets(&mut self, targets: [f64; NUM_JOINTS]) {
+        self.targets = targets;
+    }
+
+    /// Put all joints at rest at the given positions (e.g. the measured ones while torque is off).
+    pub fn reset(&mut self, positions: [f64; NUM_JOINTS]) {
+        self.targets = positions;
+        self.joints = positions.map(JointState::at);
+    }
+
+    /// Put a single joint at rest at the given position.
+    pub fn reset_joint(&mut self, joint: usize, position: f64) {
+        self.targets[joint] = position;
+        self.joints[joint] = JointState::at(position);
+    }
+
+    /// Current shaped goal positions, as returned by the last [`Self::step`].
+    pub fn positions(&self) -> [f64; NUM_JOINTS] {
+        self.joints.map(|joint| joint.position)
+    }
+
+    /// Whether all joints are at rest on their target.
+    pub fn is_settled(&self) -> bool {
+        self.joints
+            .iter()
+            .zip(self.targets.iter())
+            .all(|(joint, target)| joint.is_settled(*target))
+    }
+
+    /// Advance all joints by `dt` seconds and return the shaped goal positions.
+    pub fn step(&mut self, dt: f64) -> [f64; NUM_JOINTS] {
+        if dt > 0.0 {
+            for ((joint, target), limits) in self
+                .joints
+                .iter_mut()
+                .zip(self.targets.iter())
+                .zip(self.limits.iter())
+            {
+                joint.step(*target, limits, dt);
+            }
+        }
+        self.joints.map(|joint| joint.position)
+    }
+}
+
+/// Largest time step of [`AntennaSmoother::step`] (s), so a target coming after a pause
+/// does not make the antennas jump to it.
+const ANTENNA_MAX_STEP: f64 = 0.05;
+
+/// Second-order low-pass filter of the antenna goals ([right, left]): each antenna
+/// follows its target as a mass-spring-damper, so noisy targets give smooth motions.
+///
+/// The natural frequency (Hz) sets how fast the antennas follow, the damping ratio how
+/// much they overshoot: 1 is critically damped, lower values overshoot and feel lively.
+#[derive(Debug, Clone)]
+pub struct AntennaSmoother {
+    /// Natural angular frequency (rad/s).
+    omega: f64,
+    damping: f64,
+    targets: [f64; 2],
+    positions: [f64; 2],
+    velocities: [f64; 2],
+}
+
+impl AntennaSmoother {
+    /// Create a filter at rest at the given positions.
+    pub fn new(natural_frequency: f64, damping: f64, positions: [f64; 2]) -> Self {
+        AntennaSmoother {
+            omega: 2.0 * std::f64::consts::PI * natural_frequency,
+            damping,
+            targets: positions,
+            positions,
+            velocities: [0.0; 2],
+        }
+    }
+
+    pub fn set_parameters(&mut self, natural_frequency: f64, damping: f64) {
+        self.omega = 2.0 * std::f64::consts::PI * natural_frequency;
+        self.damping = damping;
+    }
+
+    pub fn set_targets(&mut self, targets: [f64; 2]) {
+        self.targets = targets;
+    }
+
+    /// Put the antennas at rest at the given positions.
+    pub fn reset(&mut self, positions: [f64; 2]) {
+        self.targets = positions;
+        self.positions = positions;
+        self.velocities = [0.0; 2];
+    }
+
+    /// Targets given with the last [`Self::set_targets`].
+    pub fn targets(&self) -> [f64; 2] {
+        self.targets
+    }
+
+    /// Current filtered goals, as returned by the last [`Self::step`].
+    pub fn positions(&self) -> [f64; 2] {
+        self.positions
+    }
+
+    /// Whether both antennas are at rest on their target.
+    pub fn is_settled(&self) -> bool {
+        (0..2).all(|i| {
+            (self.targets[i] - self.positions[i]).abs() < POSITION_TOLERANCE
+                && self.velocities[i].abs() < VELOCITY_TOLERANCE
+        })
+    }
+
+    /// Advance the filter by `dt` seconds (at most 50 ms) and return the filtered goals.
+    pub fn step(&mut self, dt: f64) -> [f64; 2] {
+        let dt = dt.clamp(0.0, ANTENNA_MAX_STEP);
+        // Semi-implicit Euler is stable for omega * dt well below 2
+        let substeps = (self.omega * dt / 0.2).ceil().max(1.0);
+        let h = dt / substeps;
+        for _ in 0..substeps as usize {
+            for i in 0..2 {
+                let acceleration = self.omega * self.omega * (self.targets[i] - self.positions[i])
+                    - 2.0 * self.damping * self.omega * self.velocities[i];
+                self.velocities[i] += acceleration * h;
+                self.positions[i] += self.velocities[i] * h;
+            }
+        }
+        if self.is_settled() {
+            self.reset(self.targets);
+        }
+        self.positions
+    }
+}
@@ -0,0 +1,182 @@
+//! Latest state of the control loop in a fixed block of memory updated in place, for
+//! consumers polling it at a high rate without allocating.
+//!
+//! The block holds [`STATE_BUFFER_LEN`] floats, at the indices:
+//! - [`SEQUENCE`]: incremented before and after each update, odd while it is written.
+//! - [`TIMESTAMP`]: seconds since the UNIX epoch of the position read.
+//! - [`MONOTONIC_TIMESTAMP`]: same instant on the monotonic clock (s).
+//! - [`POSITIONS`]: position of each joint (rad), in joint order.
+//!
+//! In Python, the buffer protocol gives a view on it, e.g. with numpy:
+//! ```python
+//! state = np.asarray(control_loop.get_state_buffer())
+//! while True:
+//!     sequence = state[0]
+//!     positions = state[3:].copy()
+//!     if sequence % 2 == 0 and state[0] == sequence:
+//!         break  # Not updated while copied
+//! ```
+//! [`StateBuffer::read`] does the same in Rust.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering, fence},
+};
+
+#[cfg(feature = "python")]
+use pyo3::{exceptions::PyBufferError, ffi, prelude::*};
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::{position::FullBodyPosition, smoothing::NUM_JOINTS};
+
+pub const SEQUENCE: usize = 0;
+pub const TIMESTAMP: usize = 1;
+pub const MONOTONIC_TIMESTAMP: usize = 2;
+/// Index of the first joint position.
+pub const POSITIONS: usize = 3;
+/// Number of floats of the buffer.
+pub const STATE_BUFFER_LEN: usize = POSITIONS + NUM_JOINTS;
+
+/// Shape and strides of the buffer seen from Python.
+#[cfg(feature = "python")]
+static SHAPE: [ffi::Py_ssize_t; 1] = [STATE_BUFFER_LEN as ffi::Py_ssize_t];
+#[cfg(feature = "python")]
+static STRIDES: [ffi::Py_ssize_t; 1] = [size_of::<f64>() as ffi::Py_ssize_t];
+
+/// Handle on the state published by the control loop, see
+/// [`crate::control_loop::ReachyMiniControlLoop::get_state_buffer`].
+///
+/// The floats are stored as their bits in atomics, so each one is always read whole.
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(frozen))]
+#[derive(Clone)]
+pub struct StateBuffer {
+    values: Arc<[AtomicU64; STATE_BUFFER_LEN]>,
+}
+
+impl Default for StateBuffer {
+    fn default() -> Self {
+        StateBuffer {
+            values: Arc::new(std::array::from_fn(|_| AtomicU64::new(0))),
+        }
+    }
+}
+
+impl StateBuffer {
+    /// Write `position` in place. Only one thread (the control loop) may publish.
+    pub(crate) fn publish(&self, position: &FullBodyPosition) {
+        let sequence = self.get(SEQUENCE);
+        self.set(SEQUENCE, sequence + 1.0, Ordering::Relaxed);
+        fence(Ordering::Release);
+        self.set(TIMESTAMP, position.timestamp, Ordering::Relaxed);
+        self.set(
+            MONOTONIC_TIMESTAMP,
+            position.monotonic_timestamp,
+            Ordering::Relaxed,
+        );
+        for (joint, value) in position.joints().into_iter().enumerate() {
+            self.set(POSITIONS + joint, value, Ordering::Relaxed);
+        }
+        self.set(SEQUENCE, sequence + 2.0, Ordering::Release);
+    }
+
+    /// Number of updates, 0 until the first one.
+    pub fn sequence(&self) -> u64 {
+        self.get(SEQUENCE) as u64 / 2
+    }
+
+    /// Consistent copy of the latest state, retried while it is being updated.
+    pub fn read(&self) -> FullBodyPosition {
+        loop {
+            let sequence = f64::from_bits(self.values[SEQUENCE].load(Ordering::Acquire));
+            if sequence % 2.0 != 0.0 {
+                std::hint::spin_loop();
+                continue;
+            }
+            let mut position = FullBodyPosition::from_joints(
+                std::array::from_fn(|joint| self.get(POSITIONS + joint)),
+                self.get(TIMESTAMP),
+            );
+            position.monotonic_timestamp = self.get(MONOTONIC_TIMESTAMP);
+            fence(Ordering::Acquire);
+            if self.get(SEQUENCE) == sequence {
+                return position;
+            }
+        }
+    }
+
+    fn get(&self, index: usize) -> f64 {
+        f64::from_bits(self.values[index].load(Ordering::Relaxed))
+    }
+
+    fn set(&self, index: usize, value: f64, ordering: Ordering) {
+        self.values[index].store(value.to_bits(), ordering);
+    }
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl StateBuffer {
+    /// Number of updates, 0 until the first one.
+    #[getter]
+    #[pyo3(name = "sequence")]
+    fn py_sequence(&self) -> u64 {
+        self.sequence()
+    }
+
+    /// Consistent copy of the latest state, allocating a new position.
+    #[pyo3(name = "read")]
+    fn py_read(&self) -> FullBodyPosition {
+        self.read()
+    }
+
+    fn __len__(&self) -> usize {
+        STATE_BUFFER_LEN
+    }
+
+    /// Read-only view on the floats, updated in place by the control loop.
+    #[gen_stub(skip)]
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: std::os::raw::c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("View is null"));
+        }
+        if flags & ffi::PyBUF_WRITABLE == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err("The state buffer is read-only"));
+        }
+        let buf = slf.get().values.as_ptr() as *mut std::os::raw::c_void;
+        // SAFETY: `view` is a valid Py_buffer given by the interpreter, and the memory
+        // stays alive as long as the view holds a reference to `slf`.
+        unsafe {
+            (*view).obj = slf.into_any().into_ptr();
+            (*view).buf = buf;
+            (*view).len = (STATE_BUFFER_LEN * size_of::<f64>()) as ffi::Py_ssize_t;
+            (*view).readonly = 1;
+            (*view).itemsize = size_of::<f64>() as ffi::Py_ssize_t;
+            (*view).format = match flags & ffi::PyBUF_FORMAT == ffi::PyBUF_FORMAT {
+                true => c"d".as_ptr() as *mut _,
+                false => std::ptr::null_mut(),
+            };
+            (*view).ndim = 1;
+            (*view).shape = match flags & ffi::PyBUF_ND == ffi::PyBUF_ND {
+                true => SHAPE.as_ptr() as *mut _,
+                false => std::ptr::null_mut(),
+            };
+            (*view).strides = match flags & ffi::PyBUF_STRIDES == ffi::PyBUF_STRIDES {
+                true => STRIDES.as_ptr() as *mut _,
+                false => std::ptr::null_mut(),
+            };
+            (*view).suboffsets = std::ptr::null_mut();
+            (*view).internal = std::ptr::null_mut();
+        }
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("StateBuffer(sequence={})", self.sequence())
+    }
+}
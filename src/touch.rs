@@ -0,0 +1,145 @@
+//! Detection of the user touching the robot, e.g. petting its head or holding an antenna.
+//!
+//! A part is touched when one of its motors draws more current than usual while its
+//! tracking error (distance between the goal and the measured position) grows: the
+//! motor is fighting an external force. Callbacks registered with
+//! [`crate::control_loop::ReachyMiniControlLoop::on_touch`] fire once per touch, the
+//! touch ends when both the current and the tracking error are back to low values.
+//!
+//! The currents are only read while a callback is registered and the torque is on.
+
+use std::ops::Range;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::{clock::wall_now, smoothing::NUM_JOINTS};
+
+/// Parts which can be touched, with their joints.
+const PARTS: [(&str, Range<usize>); 3] = [
+    ("stewart_platform", 1..7),
+    ("right_antenna", 7..8),
+    ("left_antenna", 8..9),
+];
+
+/// Current threshold (mA) at sensitivity 0 and 1.
+const CURRENT_THRESHOLD: (f64, f64) = (400.0, 60.0);
+/// Tracking error threshold (rad) at sensitivity 0 and 1.
+const ERROR_THRESHOLD: (f64, f64) = (0.2, 0.02);
+/// A touch ends when the current and the tracking error fall below this fraction of
+/// their thresholds, so a noisy measure does not fire the callback several times.
+const RELEASE_RATIO: f64 = 0.5;
+
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone)]
+pub struct Touch {
+    /// "stewart_platform", "right_antenna" or "left_antenna".
+    pub part: String,
+    /// Highest absolute current among the motors of the part (mA).
+    pub current: f64,
+    /// Highest tracking error among the joints of the part (rad).
+    pub tracking_error: f64,
+    /// Seconds since the UNIX epoch.
+    pub timestamp: f64,
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl Touch {
+    fn __repr__(&self) -> String {
+        format!(
+            "Touch(part={:?}, current={:.0}, tracking_error={:.3}, timestamp={:.3})",
+            self.part, self.current, self.tracking_error, self.timestamp
+        )
+    }
+}
+
+pub type TouchCallback = Box<dyn Fn(&Touch) + Send>;
+
+/// A touch callback, with the thresholds matching its sensitivity.
+pub(crate) struct TouchListener {
+    callback: TouchCallback,
+    current_threshold: f64,
+    error_threshold: f64,
+    touched: [bool; PARTS.len()],
+}
+
+impl TouchListener {
+    /// `sensitivity` goes from 0 (only strong pushes) to 1 (light touches, more false positives).
+    pub(crate) fn new(callback: TouchCallback, sensitivity: f64) -> Self {
+        // NaN falls back to the least sensitive
+        let sensitivity = if sensitivity.is_nan() {
+            0.0
+        } else {
+            sensitivity.clamp(0.0, 1.0)
+        };
+        let lerp = |(low, high): (f64, f64)| low + (high - low) * sensitivity;
+        TouchListener {
+            callback,
+            current_threshold: lerp(CURRENT_THRESHOLD),
+            error_threshold: lerp(ERROR_THRESHOLD),
+            touched: [false; PARTS.len()],
+        }
+    }
+}
+
+/// Follows the tracking errors from one tick to the next.
+#[derive(Default)]
+pub(crate) struct TouchDetector {
+    previous_errors: Option<[f64; NUM_JOINTS]>,
+}
+
+impl TouchDetector {
+    /// Check the measures of a tick, calling the listeners on the new touches.
+    pub(crate) fn update(
+        &mut self,
+        listeners: &mut [TouchListener],
+        goals: [f64; NUM_JOINTS],
+        positions: [f64; NUM_JOINTS],
+        currents: [i16; NUM_JOINTS],
+    ) {
+        let errors: [f64; NUM_JOINTS] = std::array::from_fn(|i| (goals[i] - positions[i]).abs());
+
+        for (part, (name, joints)) in PARTS.iter().enumerate() {
+            let current = joints
+                .clone()
+                .map(|i| (currents[i] as f64).abs())
+                .fold(0.0, f64::max);
+            let error = joints.clone().map(|i| errors[i]).fold(0.0, f64::max);
+            let growing = self
+                .previous_errors
+                .is_some_and(|previous| joints.clone().any(|i| errors[i] > previous[i]));
+
+            for listener in listeners.iter_mut() {
+                let touched = &mut listener.touched[part];
+                if !*touched {
+                    if current >= listener.current_threshold
+                        && error >= listener.error_threshold
+                        && growing
+                    {
+                        *touched = true;
+                        (listener.callback)(&Touch {
+                            part: name.to_string(),
+                            current,
+                            tracking_error: error,
+                            timestamp: wall_now(),
+                        });
+                    }
+                } else if current < listener.current_threshold * RELEASE_RATIO
+                    && error < listener.error_threshold * RELEASE_RATIO
+                {
+                    *touched = false;
+                }
+            }
+        }
+        self.previous_errors = Some(errors);
+    }
+
+    /// Forget the previous tick, e.g. when the goals are unknown.
+    pub(crate) fn reset(&mut self) {
+        self.previous_errors = None;
+    }
+}
@@ -0,0 +1,98 @@
+//! Stream of the commanded goals next to the measured positions, to monitor the
+//! tracking error live.
+//!
+//! Once enabled with
+//! [`crate::control_loop::ReachyMiniControlLoop::set_tracking_stream_size`], the control
+//! loop publishes at each read, while the torque is on, the last goal written to each
+//! joint with the position read at that tick. Dashboards get them with
+//! [`crate::control_loop::ReachyMiniControlLoop::get_tracking_since`] without having to
+//! work out which goal was active when a position was read.
+
+use std::collections::VecDeque;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::smoothing::NUM_JOINTS;
+
+/// Number of samples kept by the stream (10 s at 100 Hz) once enabled with its default.
+pub const DEFAULT_TRACKING_STREAM_SIZE: usize = 1000;
+
+/// Goal and measured position of each joint at a read tick.
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TrackingSample {
+    /// Last goal written to each joint (rad), in joint order.
+    pub goal: [f64; NUM_JOINTS],
+    /// Position read at this tick (rad), in joint order.
+    pub position: [f64; NUM_JOINTS],
+    /// Seconds since the UNIX epoch, as the timestamp of the position.
+    pub timestamp: f64,
+    /// Same instant on the monotonic clock (s), see [`crate::clock`].
+    pub monotonic_timestamp: f64,
+}
+
+impl TrackingSample {
+    /// Goal minus position of each joint (rad).
+    pub fn error(&self) -> [f64; NUM_JOINTS] {
+        std::array::from_fn(|joint| self.goal[joint] - self.position[joint])
+    }
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl TrackingSample {
+    /// Goal minus position of each joint (rad).
+    #[pyo3(name = "error")]
+    fn py_error(&self) -> [f64; NUM_JOINTS] {
+        self.error()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "TrackingSample(goal={:?}, position={:?}, timestamp={:.3})",
+            self.goal, self.position, self.timestamp
+        )
+    }
+}
+
+/// Ring buffer of the last samples, oldest first. Disabled with a zero capacity.
+#[derive(Default)]
+pub(crate) struct TrackingStream {
+    capacity: usize,
+    samples: VecDeque<TrackingSample>,
+}
+
+impl TrackingStream {
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub(crate) fn push(&mut self, sample: TrackingSample) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        let excess = self.samples.len().saturating_sub(capacity);
+        self.samples.drain(..excess);
+        self.capacity = capacity;
+    }
+
+    /// Samples with a timestamp after `timestamp` (seconds since UNIX epoch), oldest first.
+    pub(crate) fn since(&self, timestamp: f64) -> Vec<TrackingSample> {
+        let first = self
+            .samples
+            .partition_point(|sample| sample.timestamp <= timestamp);
+        self.samples.range(first..).copied().collect()
+    }
+}
@@ -0,0 +1,866 @@
+//! Time-parametrized joint trajectories, played by the control loop.
+//!
+//! A [`Trajectory`] is a list of keyframes (time, joint positions) interpolated with
+//! the [`Easing`] of each keyframe, linear by default. Once sent to the control loop (see
+//! [`crate::control_loop::ReachyMiniControlLoop::play_trajectory`]) it is sampled at
+//! each tick, so the goals do not depend on the timing of the command queue. The
+//! playback can be paused, resumed and re-timed with a speed factor while it runs,
+//! e.g. to stay in sync with an audio track. Trajectories can also be played on a
+//! [`Track`] (body, head or antennas) alone, over the other motions, e.g. an antenna
+//! wiggle over a head trajectory.
+//!
+//! Trajectories are saved to and loaded from text files, one keyframe per line: the
+//! time (s) followed by the 9 joint positions (rad), separated by commas or spaces
+//! (the format of the `stream` tool), then optionally the name of the easing. They can also be built from the positions
+//! recorded by the control loop while the robot is moved by hand, see
+//! [`Trajectory::from_recording`].
+
+use std::{fmt, path::Path, time::Instant};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::{
+    beat::BeatGrid,
+    kinematics::{HeadPose, Kinematics, KinematicsError},
+    position::FullBodyPosition,
+    position_bounds::JOINT_NAMES,
+    smoothing::NUM_JOINTS,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+    /// Time from the start of the trajectory (s).
+    pub time: f64,
+    /// Joint positions (rad), in the order of [`crate::control_loop::FullBodyPosition::joints`].
+    pub positions: [f64; NUM_JOINTS],
+    /// Timing of the move from this keyframe to the next one.
+    pub easing: Easing,
+}
+
+impl Keyframe {
+    /// A keyframe with a linear move to the next one.
+    pub fn new(time: f64, positions: [f64; NUM_JOINTS]) -> Self {
+        Keyframe {
+            time,
+            positions,
+            easing: Easing::Linear,
+        }
+    }
+}
+
+/// Timing of the move between two keyframes, as the interpolation modes of animation
+/// tools (e.g. Blender).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    /// Constant velocity.
+    #[default]
+    Linear,
+    /// Starts slowly and accelerates (quadratic).
+    EaseIn,
+    /// Starts fast and decelerates (quadratic).
+    EaseOut,
+    /// Accelerates then decelerates (quadratic).
+    EaseInOut,
+    /// Accelerates then decelerates with a zero velocity at both keyframes (cubic
+    /// smoothstep).
+    Cubic,
+    /// Holds the keyframe positions until the next keyframe, then jumps to it.
+    Hold,
+}
+
+impl Easing {
+    pub const ALL: [Easing; 6] = [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+        Easing::Cubic,
+        Easing::Hold,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Easing::Linear => "linear",
+            Easing::EaseIn => "ease_in",
+            Easing::EaseOut => "ease_out",
+            Easing::EaseInOut => "ease_in_out",
+            Easing::Cubic => "cubic",
+            Easing::Hold => "hold",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Easing> {
+        Easing::ALL.into_iter().find(|e| e.name() == name)
+    }
+
+    /// Progress (0 to 1) of the move at the fraction `t` (0 to 1) of its duration.
+    pub fn apply(&self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut if t < 0.5 => 2.0 * t * t,
+            Easing::EaseInOut => 1.0 - 2.0 * (1.0 - t) * (1.0 - t),
+            Easing::Cubic => t * t * (3.0 - 2.0 * t),
+            Easing::Hold => 0.0,
+        }
+    }
+
+    /// Derivative of [`Self::apply`] at `t`.
+    pub fn derivative(&self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => 1.0,
+            Easing::EaseIn => 2.0 * t,
+            Easing::EaseOut => 2.0 - 2.0 * t,
+            Easing::EaseInOut if t < 0.5 => 4.0 * t,
+            Easing::EaseInOut => 4.0 * (1.0 - t),
+            Easing::Cubic => 6.0 * t * (1.0 - t),
+            Easing::Hold => 0.0,
+        }
+    }
+}
+
+impl fmt::Display for Easing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Group of joints a trajectory can be played on alone, as a track overlaid on the other
+/// motions (see [`crate::control_loop::ReachyMiniControlLoop::play_track`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Track {
+    /// The body rotation.
+    Body,
+    /// The 6 stewart platform joints.
+    Head,
+    /// The right and left antennas.
+    Antennas,
+}
+
+impl Track {
+    pub const ALL: [Track; 3] = [Track::Body, Track::Head, Track::Antennas];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Track::Body => "body",
+            Track::Head => "head",
+            Track::Antennas => "antennas",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Track> {
+        Track::ALL.into_iter().find(|t| t.name() == name)
+    }
+
+    /// Indices of the joints of the track, in joint order.
+    pub fn joints(&self) -> std::ops::Range<usize> {
+        match self {
+            Track::Body => 0..1,
+            Track::Head => 1..7,
+            Track::Antennas => 7..9,
+        }
+    }
+
+    pub(crate) fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl fmt::Display for Track {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass)]
+#[derive(Debug, Clone)]
+pub struct Trajectory {
+    keyframes: Vec<Keyframe>,
+}
+
+/// A list of keyframes not forming a valid [`Trajectory`].
+#[derive(Debug, Clone)]
+pub struct InvalidTrajectory(pub String);
+
+impl std::error::Error for InvalidTrajectory {}
+impl fmt::Display for InvalidTrajectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid trajectory: {}", self.0)
+    }
+}
+
+impl Trajectory {
+    /// Keyframes must be ordered by strictly increasing time, starting at 0 or later,
+    /// with finite positions.
+    pub fn new(keyframes: Vec<Keyframe>) -> Result<Self, InvalidTrajectory> {
+        if keyframes.is_empty() {
+            return Err(InvalidTrajectory("no keyframe".to_string()));
+        }
+        let mut previous = None;
+        for (i, keyframe) in keyframes.iter().enumerate() {
+            if !keyframe.time.is_finite() || keyframe.time < 0.0 {
+                return Err(InvalidTrajectory(format!(
+                    "keyframe {} has an invalid time ({})",
+                    i, keyframe.time
+                )));
+            }
+            if previous.is_some_and(|previous| keyframe.time <= previous) {
+                return Err(InvalidTrajectory(format!(
+                    "keyframe {} is not after the previous one ({} s)",
+                    i, keyframe.time
+                )));
+            }
+            if keyframe.positions.iter().any(|p| !p.is_finite()) {
+                return Err(InvalidTrajectory(format!(
+                    "keyframe {} has a non finite position",
+                    i
+                )));
+            }
+            previous = Some(keyframe.time);
+        }
+        Ok(Trajectory { keyframes })
+    }
+
+    /// Build a trajectory from positions recorded by the control loop (e.g. with
+    /// [`crate::control_loop::ReachyMiniControlLoop::get_since`] during a teach session),
+    /// timed from the first one. Positions not recorded after the previous one are
+    /// skipped.
+    pub fn from_recording(positions: &[FullBodyPosition]) -> Result<Self, InvalidTrajectory> {
+        let Some(first) = positions.first() else {
+            return Err(InvalidTrajectory("no recorded position".to_string()));
+        };
+        let mut keyframes: Vec<Keyframe> = Vec::with_capacity(positions.len());
+        for position in positions {
+            let time = position.timestamp - first.timestamp;
+            if keyframes.last().is_some_and(|last| time <= last.time) {
+                continue;
+            }
+            keyframes.push(Keyframe::new(time, position.joints()));
+        }
+        Trajectory::new(keyframes)
+    }
+
+    /// Parse a trajectory file, see the [module documentation](self). The times are
+    /// counted from the first keyframe and a header line (e.g. of a CSV file) is skipped.
+    pub fn parse(text: &str) -> Result<Self, InvalidTrajectory> {
+        let mut keyframes: Vec<Keyframe> = Vec::new();
+        let mut start = None;
+        for (index, line) in text.lines().enumerate() {
+            let keyframe = match parse_keyframe(line) {
+                Ok(Some(keyframe)) => keyframe,
+                Ok(None) => continue,
+                Err(_) if index == 0 => continue,
+                Err(e) => return Err(InvalidTrajectory(format!("line {}: {}", index + 1, e))),
+            };
+            let start = *start.get_or_insert(keyframe.time);
+            keyframes.push(Keyframe {
+                time: keyframe.time - start,
+                ..keyframe
+            });
+        }
+        Trajectory::new(keyframes)
+    }
+
+    /// Read and parse a trajectory file, see [`Self::parse`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Write the trajectory as CSV, with a header line naming the joints.
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let mut text = format!("time,{},easing\n", JOINT_NAMES.join(","));
+        for keyframe in &self.keyframes {
+            text.push_str(&keyframe.time.to_string());
+            for position in keyframe.positions {
+                text.push(',');
+                text.push_str(&position.to_string());
+            }
+            text.push(',');
+            text.push_str(keyframe.easing.name());
+            text.push('\n');
+        }
+        std::fs::write(path, text)
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    pub(crate) fn keyframes_mut(&mut self) -> &mut [Keyframe] {
+        &mut self.keyframes
+    }
+
+    /// Time of the last keyframe (s).
+    pub fn duration(&self) -> f64 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// Joint positions at `time`, holding the first and last keyframes outside of the
+    /// trajectory.
+    pub fn sample(&self, time: f64) -> [f64; NUM_JOINTS] {
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        if next == 0 {
+            return self.keyframes[0].positions;
+        }
+        let Some(b) = self.keyframes.get(next) else {
+            return self.keyframes[next - 1].positions;
+        };
+        let a = &self.keyframes[next - 1];
+        let t = a.easing.apply((time - a.time) / (b.time - a.time));
+        std::array::from_fn(|i| a.positions[i] + (b.positions[i] - a.positions[i]) * t)
+    }
+
+    /// Joint velocities (rad/s) at `time`, 0 outside of the trajectory.
+    pub fn velocity(&self, time: f64) -> [f64; NUM_JOINTS] {
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        let (Some(a), Some(b)) = (
+            next.checked_sub(1).map(|i| &self.keyframes[i]),
+            self.keyframes.get(next),
+        ) else {
+            return [0.0; NUM_JOINTS];
+        };
+        let duration = b.time - a.time;
+        let rate = a.easing.derivative((time - a.time) / duration) / duration;
+        std::array::from_fn(|i| (b.positions[i] - a.positions[i]) * rate)
+    }
+
+    /// Start from `positions` at time 0 if the first keyframe comes later, instead of
+    /// jumping to it.
+    pub fn starting_from(mut self, positions: [f64; NUM_JOINTS]) -> Self {
+        if self.keyframes[0].time > 0.0 {
+            self.keyframes.insert(0, Keyframe::new(0.0, positions));
+        }
+        self
+    }
+
+    /// Move each keyframe to the nearest whole beat of `grid`.
+    ///
+    /// Keyframes falling on the same beat are merged, keeping the last one.
+    pub fn quantized(&self, grid: &BeatGrid) -> Self {
+        let mut keyframes: Vec<Keyframe> = Vec::with_capacity(self.keyframes.len());
+        for keyframe in &self.keyframes {
+            let keyframe = Keyframe {
+                time: grid.quantize(keyframe.time),
+                ..*keyframe
+            };
+            match keyframes.last_mut() {
+                Some(last) if last.time >= keyframe.time => *last = keyframe,
+                _ => keyframes.push(keyframe),
+            }
+        }
+        Trajectory { keyframes }
+    }
+
+    /// Swap the right and left antenna positions.
+    pub fn mirrored_antennas(&self) -> Self {
+        let mut mirrored = self.clone();
+        for keyframe in &mut mirrored.keyframes {
+            keyframe.positions.swap(7, 8);
+        }
+        mirrored
+    }
+
+    /// Left/right mirror of the whole motion: opposite body yaw, mirrored head poses
+    /// (see [`HeadPose::mirrored`]) and swapped antennas.
+    pub fn mirrored(&self, kinematics: &dyn Kinematics) -> Result<Self, KinematicsError> {
+        let mut mirrored = self.retargeted(kinematics, |pose| pose.mirrored())?;
+        for keyframe in &mut mirrored.keyframes {
+            keyframe.positions[0] = -keyframe.positions[0];
+        }
+        Ok(mirrored.mirrored_antennas())
+    }
+
+    /// Scale each joint position by its factor, in joint order, e.g. to make an antenna
+    /// wiggle wider. The head is better scaled in pose space, see [`Self::retargeted`].
+    pub fn scaled(&self, factors: [f64; NUM_JOINTS]) -> Result<Self, InvalidTrajectory> {
+        let mut keyframes = self.keyframes.clone();
+        for keyframe in &mut keyframes {
+            for (position, factor) in keyframe.positions.iter_mut().zip(factors) {
+                *position *= factor;
+            }
+        }
+        Trajectory::new(keyframes)
+    }
+
+    /// Add `offset` (rad) to the body rotation, e.g. to play a motion facing another
+    /// direction.
+    pub fn with_body_yaw_offset(&self, offset: f64) -> Result<Self, InvalidTrajectory> {
+        let mut keyframes = self.keyframes.clone();
+        for keyframe in &mut keyframes {
+            keyframe.positions[0] += offset;
+        }
+        Trajectory::new(keyframes)
+    }
+
+    /// Transform the head pose of each keyframe: its stewart joints are converted to a
+    /// head pose with the forward kinematics, transformed, then back to joints with the
+    /// inverse kinematics. Fails if a transformed pose is out of reach.
+    pub fn retargeted(
+        &self,
+        kinematics: &dyn Kinematics,
+        mut transform: impl FnMut(HeadPose) -> HeadPose,
+    ) -> Result<Self, KinematicsError> {
+        let mut retargeted = self.clone();
+        for keyframe in &mut retargeted.keyframes {
+            let stewart = keyframe.positions[1..7].try_into().expect("6 joints");
+            let pose = transform(kinematics.forward(&stewart)?);
+            let stewart = kinematics.inverse(&pose)?;
+            if stewart.iter().any(|p| !p.is_finite()) {
+                return Err(KinematicsError(format!("no joints for {:?}", pose)));
+            }
+            keyframe.positions[1..7].copy_from_slice(&stewart);
+        }
+        Ok(retargeted)
+    }
+}
+
+#[cfg(feature = "python")]
+impl Trajectory {
+    /// Build a trajectory from Python (time, positions) or (time, positions, easing)
+    /// tuples.
+    pub(crate) fn from_py_keyframes(keyframes: Vec<Bound<'_, PyAny>>) -> PyResult<Self> {
+        let keyframes = keyframes
+            .iter()
+            .map(|keyframe| {
+                let (time, positions, easing) = match keyframe
+                    .extract::<(f64, [f64; NUM_JOINTS], String)>()
+                {
+                    Ok(keyframe) => keyframe,
+                    Err(_) => {
+                        let (time, positions) = keyframe.extract::<(f64, [f64; NUM_JOINTS])>()?;
+                        (time, positions, Easing::Linear.name().to_string())
+                    }
+                };
+                let easing = Easing::from_name(&easing).ok_or_else(|| {
+                    pyo3::exceptions::PyValueError::new_err(format!("Unknown easing {:?}", easing))
+                })?;
+                Ok(Keyframe {
+                    time,
+                    positions,
+                    easing,
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        Trajectory::new(keyframes)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+}
+
+/// Keyframe of a line of a trajectory file, `None` for an empty or comment (`#`) line.
+pub fn parse_keyframe(line: &str) -> Result<Option<Keyframe>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let mut values: Vec<&str> = line
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|value| !value.is_empty())
+        .collect();
+    let easing = match values.last().filter(|value| value.parse::<f64>().is_err()) {
+        Some(name) => {
+            let easing =
+                Easing::from_name(name).ok_or_else(|| format!("unknown easing {:?}", name))?;
+            values.pop();
+            easing
+        }
+        None => Easing::Linear,
+    };
+    let values = values
+        .into_iter()
+        .map(|value| {
+            value
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number {:?}", value))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some(positions) = values.get(1..).and_then(|goals| goals.try_into().ok()) else {
+        return Err(format!(
+            "expected a time and {} positions, got {} values",
+            NUM_JOINTS,
+            values.len()
+        ));
+    };
+    if values.iter().any(|v| !v.is_finite()) {
+        return Err("non finite value".to_string());
+    }
+    Ok(Some(Keyframe {
+        time: values[0],
+        positions,
+        easing,
+    }))
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl Trajectory {
+    /// Build a trajectory from a list of (time in seconds, 9 joint positions), by
+    /// increasing time, optionally followed by the easing of the move to the next
+    /// keyframe: "linear" (default), "ease_in", "ease_out", "ease_in_out", "cubic" or
+    /// "hold".
+    #[new]
+    fn py_new(
+        #[gen_stub(override_type(
+            type_repr = "typing.Sequence[tuple[float, typing.Sequence[float]] | tuple[float, typing.Sequence[float], str]]",
+            imports = ("typing",)
+        ))]
+        keyframes: Vec<Bound<'_, PyAny>>,
+    ) -> PyResult<Self> {
+        Trajectory::from_py_keyframes(keyframes)
+    }
+
+    /// Load a trajectory file: one keyframe per line, the time (s) followed by the 9
+    /// joint positions (rad), separated by commas or spaces.
+    #[staticmethod]
+    #[pyo3(name = "from_file")]
+    fn py_from_file(path: std::path::PathBuf) -> PyResult<Self> {
+        Trajectory::load(path).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+
+    /// Build a trajectory from positions recorded by the control loop (e.g. with
+    /// `get_since()` while the robot was moved by hand), timed from the first one.
+    #[staticmethod]
+    #[pyo3(name = "from_recording")]
+    fn py_from_recording(positions: Vec<FullBodyPosition>) -> PyResult<Self> {
+        Trajectory::from_recording(&positions)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+
+    /// Import an animation made in an animation tool, as CSV or JSON (`.json` files).
+    ///
+    /// The keyframes give a subset of the joints by name, or a head pose converted with
+    /// the kinematics, and are timed by time or frame number. Every position is checked
+    /// against the bounds.
+    ///
+    /// # Arguments
+    /// * `fps` - Frame rate of the animations timed by frame number.
+    /// * `degrees` - Whether the angles are in degrees instead of radians.
+    /// * `bounds` - Bounds of the 9 joints, the default position bounds if not given.
+    /// * `kinematics` - Object with `inverse(pose: HeadPose) -> 6 stewart joints`, needed
+    ///   for the head poses.
+    #[staticmethod]
+    #[pyo3(signature = (path, fps = 24.0, degrees = false, bounds = None, kinematics = None))]
+    fn import_animation(
+        path: std::path::PathBuf,
+        fps: f64,
+        degrees: bool,
+        bounds: Option<[crate::position_bounds::PositionBounds; NUM_JOINTS]>,
+        kinematics: Option<Py<PyAny>>,
+    ) -> PyResult<Self> {
+        let mut options = crate::animation::ImportOptions {
+            fps,
+            degrees,
+            ..Default::default()
+        };
+        if let Some(bounds) = bounds {
+            options.bounds = bounds;
+        }
+        let kinematics = kinematics.map(crate::bindings::PyKinematics);
+        crate::animation::import_file(
+            path,
+            &options,
+            kinematics
+                .as_ref()
+                .map(|kinematics| kinematics as &dyn crate::kinematics::Kinematics),
+        )
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+
+    /// Write the trajectory to a CSV file, loadable with `from_file()`.
+    #[pyo3(name = "save")]
+    fn py_save(&self, path: std::path::PathBuf) -> PyResult<()> {
+        self.save(path)
+            .map_err(|e| pyo3::exceptions::PyOSError::new_err(e.to_string()))
+    }
+
+    /// Swap the right and left antenna positions.
+    #[pyo3(name = "mirrored_antennas")]
+    fn py_mirrored_antennas(&self) -> Self {
+        self.mirrored_antennas()
+    }
+
+    /// Left/right mirror of the whole motion: opposite body yaw, mirrored head poses and
+    /// swapped antennas.
+    ///
+    /// # Arguments
+    /// * `kinematics` - Object with `inverse(pose: HeadPose) -> 6 stewart joints` and `forward(joints) -> HeadPose`.
+    #[pyo3(name = "mirrored")]
+    fn py_mirrored(&self, kinematics: Py<PyAny>) -> PyResult<Self> {
+        self.mirrored(&crate::bindings::PyKinematics(kinematics))
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+
+    /// Scale each joint position by its factor (9 factors, in joint order).
+    #[pyo3(name = "scaled")]
+    fn py_scaled(&self, factors: [f64; NUM_JOINTS]) -> PyResult<Self> {
+        self.scaled(factors)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+
+    /// Add an offset (rad) to the body rotation.
+    #[pyo3(name = "with_body_yaw_offset")]
+    fn py_with_body_yaw_offset(&self, offset: f64) -> PyResult<Self> {
+        self.with_body_yaw_offset(offset)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+
+    /// Transform the head pose of each keyframe, through the forward then inverse
+    /// kinematics.
+    ///
+    /// # Arguments
+    /// * `kinematics` - Object with `inverse(pose: HeadPose) -> 6 stewart joints` and `forward(joints) -> HeadPose`.
+    /// * `transform` - Called with the HeadPose of each keyframe, returns the new one.
+    #[pyo3(name = "retargeted")]
+    fn py_retargeted(
+        &self,
+        py: Python<'_>,
+        kinematics: Py<PyAny>,
+        #[gen_stub(override_type(
+            type_repr = "typing.Callable[[HeadPose], HeadPose]",
+            imports = ("typing",)
+        ))]
+        transform: Py<PyAny>,
+    ) -> PyResult<Self> {
+        // A failing transform keeps the pose, its first error is raised at the end
+        let mut error = None;
+        let kinematics = crate::bindings::PyKinematics(kinematics);
+        let retargeted = self.retargeted(&kinematics, |pose| {
+            let transformed = transform.call1(py, (pose,));
+            match transformed.and_then(|pose| pose.extract(py)) {
+                Ok(pose) => pose,
+                Err(e) => {
+                    error.get_or_insert(e);
+                    pose
+                }
+            }
+        });
+        if let Some(error) = error {
+            return Err(error);
+        }
+        retargeted.map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+
+    /// Time of the last keyframe (s).
+    #[getter]
+    #[pyo3(name = "duration")]
+    fn py_duration(&self) -> f64 {
+        self.duration()
+    }
+
+    /// List of (time in seconds, 9 joint positions, easing).
+    #[getter]
+    #[pyo3(name = "keyframes")]
+    fn py_keyframes(&self) -> Vec<(f64, [f64; NUM_JOINTS], &'static str)> {
+        self.keyframes
+            .iter()
+            .map(|keyframe| (keyframe.time, keyframe.positions, keyframe.easing.name()))
+            .collect()
+    }
+
+    /// Joint positions at a time (s), holding the first and last keyframes outside of
+    /// the trajectory.
+    #[pyo3(name = "sample")]
+    fn py_sample(&self, time: f64) -> [f64; NUM_JOINTS] {
+        self.sample(time)
+    }
+
+    fn __len__(&self) -> usize {
+        self.keyframes.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Trajectory(keyframes={}, duration={:.3})",
+            self.keyframes.len(),
+            self.duration()
+        )
+    }
+}
+
+/// Playback state of the trajectory executed by the control loop.
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, Copy)]
+pub struct TrajectoryStatus {
+    /// Current time in the trajectory (s).
+    pub time: f64,
+    /// Total duration of the trajectory (s).
+    pub duration: f64,
+    /// Speed factor of the playback (1 is real time).
+    pub speed: f64,
+    pub paused: bool,
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl TrajectoryStatus {
+    fn __repr__(&self) -> String {
+        format!(
+            "TrajectoryStatus(time={:.3}, duration={:.3}, speed={:.2}, paused={})",
+            self.time, self.duration, self.speed, self.paused
+        )
+    }
+}
+
+/// Outcome of a trajectory played by the control loop, with its tracking errors.
+///
+/// The errors are the distances between the goals of the trajectory and the positions
+/// read at the same ticks, so they include the lag of the motors.
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TrajectoryResult {
+    /// Whether it played to its end, rather than being stopped or replaced.
+    pub completed: bool,
+    /// Time reached in the trajectory (s).
+    pub time: f64,
+    /// Total duration of the trajectory (s).
+    pub duration: f64,
+    /// Root mean square of the tracking error of each joint (rad), in joint order.
+    pub rms_error: [f64; NUM_JOINTS],
+    /// Largest tracking error of each joint (rad), in joint order.
+    pub max_error: [f64; NUM_JOINTS],
+    /// Number of position reads compared to the goals.
+    pub samples: u64,
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl TrajectoryResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "TrajectoryResult(completed={}, time={:.3}, duration={:.3}, rms_error={:.4?}, max_error={:.4?}, samples={})",
+            self.completed, self.time, self.duration, self.rms_error, self.max_error, self.samples
+        )
+    }
+}
+
+/// Profile velocities written from the velocity of the trajectories while they play,
+/// see [`crate::control_loop::ReachyMiniControlLoop::enable_velocity_feedforward`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VelocityFeedforward {
+    /// Profile velocity over the trajectory velocity, above 1 to let the motors catch
+    /// up with their lag.
+    pub gain: f64,
+    /// Lowest profile velocity (rad/s), so a lagging joint is not frozen by a slow or
+    /// still part of the trajectory.
+    pub min_velocity: f64,
+}
+
+impl Default for VelocityFeedforward {
+    fn default() -> Self {
+        VelocityFeedforward {
+            gain: 1.2,
+            min_velocity: 0.5,
+        }
+    }
+}
+
+impl VelocityFeedforward {
+    /// Profile velocity (rad/s) of a joint moving at `velocity`.
+    pub fn profile_velocity(&self, velocity: f64) -> f64 {
+        (velocity.abs() * self.gain).max(self.min_velocity)
+    }
+}
+
+/// Advances the trajectory time with the control loop clock.
+pub(crate) struct TrajectoryPlayer {
+    trajectory: Trajectory,
+    time: f64,
+    speed: f64,
+    paused: bool,
+    last_update: Option<Instant>,
+    squared_errors: [f64; NUM_JOINTS],
+    max_errors: [f64; NUM_JOINTS],
+    samples: u64,
+}
+
+impl TrajectoryPlayer {
+    /// The trajectory holds its first keyframe until `start`, if given.
+    pub(crate) fn new(trajectory: Trajectory, start: Option<Instant>) -> Self {
+        TrajectoryPlayer {
+            trajectory,
+            time: 0.0,
+            speed: 1.0,
+            paused: false,
+            last_update: start,
+            squared_errors: [0.0; NUM_JOINTS],
+            max_errors: [0.0; NUM_JOINTS],
+            samples: 0,
+        }
+    }
+
+    /// Move the trajectory time forward to `now` and return the goals at that time.
+    pub(crate) fn advance(&mut self, now: Instant) -> [f64; NUM_JOINTS] {
+        if let Some(last_update) = self.last_update
+            && !self.paused
+        {
+            let dt = now.saturating_duration_since(last_update).as_secs_f64();
+            self.time = (self.time + dt * self.speed).min(self.trajectory.duration());
+        }
+        // Not before the start
+        self.last_update = Some(self.last_update.map_or(now, |last| last.max(now)));
+        self.trajectory.sample(self.time)
+    }
+
+    /// Joint velocities (rad/s) of the playback, with its speed factor.
+    pub(crate) fn velocity(&self) -> [f64; NUM_JOINTS] {
+        if self.paused {
+            return [0.0; NUM_JOINTS];
+        }
+        self.trajectory.velocity(self.time).map(|v| v * self.speed)
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.time >= self.trajectory.duration()
+    }
+
+    pub(crate) fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Negative or NaN speeds are taken as 0, which holds the current goals.
+    pub(crate) fn set_speed(&mut self, speed: f64) {
+        self.speed = if speed.is_nan() { 0.0 } else { speed.max(0.0) };
+    }
+
+    /// Account for the tracking error between the `goals` and the `positions` read.
+    pub(crate) fn track(&mut self, goals: &[f64; NUM_JOINTS], positions: &[f64; NUM_JOINTS]) {
+        for i in 0..NUM_JOINTS {
+            let error = (goals[i] - positions[i]).abs();
+            self.squared_errors[i] += error * error;
+            self.max_errors[i] = self.max_errors[i].max(error);
+        }
+        self.samples += 1;
+    }
+
+    pub(crate) fn result(&self, completed: bool) -> TrajectoryResult {
+        let samples = self.samples.max(1) as f64;
+        TrajectoryResult {
+            completed,
+            time: self.time,
+            duration: self.trajectory.duration(),
+            rms_error: self.squared_errors.map(|sum| (sum / samples).sqrt()),
+            max_error: self.max_errors,
+            samples: self.samples,
+        }
+    }
+
+    pub(crate) fn status(&self) -> TrajectoryStatus {
+        TrajectoryStatus {
+            time: self.time,
+            duration: self.trajectory.duration(),
+            speed: self.speed,
+            paused: self.paused,
+        }
+    }
+}
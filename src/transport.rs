@@ -0,0 +1,230 @@
+//! Byte transport between the controller and the motors bus.
+//!
+//! [`crate::ReachyMiniMotorController`] builds and parses the Dynamixel packets, the
+//! [`Transport`] only moves the bytes. It is the serial port by default
+//! ([`SerialTransport`]), and can be anything able to send and receive bytes with a
+//! timeout: a TCP bridge to a remote robot, a mock answering canned packets in a test...
+//!
+//! ```no_run
+//! use std::{io, time::Duration};
+//!
+//! use reachy_mini_motor_controller::{ReachyMiniMotorController, transport::Transport};
+//!
+//! struct TcpTransport(std::net::TcpStream);
+//!
+//! impl Transport for TcpTransport {
+//!     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+//!         io::Read::read(&mut self.0, buf)
+//!     }
+//!
+//!     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+//!         io::Write::write(&mut self.0, buf)
+//!     }
+//!
+//!     fn flush(&mut self) -> io::Result<()> {
+//!         io::Write::flush(&mut self.0)
+//!     }
+//!
+//!     fn timeout(&self) -> Duration {
+//!         self.0.read_timeout().ok().flatten().unwrap_or(Duration::ZERO)
+//!     }
+//!
+//!     fn set_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+//!         self.0.set_read_timeout(Some(timeout))
+//!     }
+//! }
+//!
+//! let stream = std::net::TcpStream::connect("reachy-mini.local:4000")?;
+//! let mut controller = ReachyMiniMotorController::with_transport(TcpTransport(stream));
+//! controller.enable_torque()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{io, time::Duration};
+
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+/// The serial port, the transport of [`crate::ReachyMiniMotorController::new`].
+pub type SerialTransport = Box<dyn SerialPort>;
+
+/// Sends and receives the bytes of the Dynamixel packets.
+pub trait Transport: Send {
+    /// Read the bytes received, waiting up to [`Self::timeout`] for the first ones.
+    ///
+    /// Nothing received in time is an [`io::ErrorKind::TimedOut`] error, which the
+    /// controller reports as a bus timeout.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize>;
+
+    fn flush(&mut self) -> io::Result<()>;
+
+    fn timeout(&self) -> Duration;
+
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()>;
+
+    /// Number of bytes received and not read yet.
+    ///
+    /// Used to drop what is left of a corrupted answer before the next request, and to
+    /// collect the answers of [`crate::ReachyMiniMotorController::write_raw_packet`].
+    /// Transports unable to tell keep the default, 0.
+    fn bytes_to_read(&self) -> io::Result<u32> {
+        Ok(0)
+    }
+}
+
+impl Transport for SerialTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io::Read::read(self, buf)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::Write::write(self, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::Write::flush(self)
+    }
+
+    fn timeout(&self) -> Duration {
+        SerialPort::timeout(self.as_ref())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        Ok(SerialPort::set_timeout(self.as_mut(), timeout)?)
+    }
+
+    fn bytes_to_read(&self) -> io::Result<u32> {
+        Ok(SerialPort::bytes_to_read(self.as_ref())?)
+    }
+}
+
+/// A [`Transport`] seen as a serial port, as rustypot only talks to serial ports.
+///
+/// The line settings (baud rate, parity...) are left to the transport: they are reported
+/// as the ones of the bus and their changes ignored.
+pub(crate) struct TransportPort<T>(pub(crate) T);
+
+impl<T: Transport> io::Read for TransportPort<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<T: Transport> io::Write for TransportPort<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl<T: Transport> SerialPort for TransportPort<T> {
+    fn name(&self) -> Option<String> {
+        None
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(crate::DEFAULT_BAUD_RATE)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.0.timeout()
+    }
+
+    fn set_baud_rate(&mut self, _baud_rate: u32) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, _data_bits: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, _flow_control: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_parity(&mut self, _parity: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, _stop_bits: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        Ok(self.0.set_timeout(timeout)?)
+    }
+
+    fn write_request_to_send(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        Ok(self.0.bytes_to_read()?)
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, _buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        Err(serialport::Error::new(
+            serialport::ErrorKind::Io(io::ErrorKind::Unsupported),
+            "The buffers of a transport cannot be cleared",
+        ))
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Err(serialport::Error::new(
+            serialport::ErrorKind::Io(io::ErrorKind::Unsupported),
+            "A transport cannot be cloned",
+        ))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
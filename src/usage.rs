@@ -0,0 +1,216 @@
+//! Wear counters: cumulative torque-on time, joint travel and overload events.
+//!
+//! They are accumulated by the control loop and, once a usage file is set (see
+//! [`crate::control_loop::ReachyMiniControlLoop::set_usage_file`]), persisted across
+//! runs so maintainers can estimate the wear of the XL330s.
+//!
+//! The file is a small text file, one counter per line:
+//! ```text
+//! torque_on_time 3600.5
+//! joint_travel 12.1 3.4 3.3 3.5 3.4 3.3 3.6 20.2 19.8
+//! overload_events 0 0 0 1 0 0 0 0 0
+//! ```
+
+use std::{
+    f64::consts::PI,
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::smoothing::NUM_JOINTS;
+
+/// How often the counters are written to the usage file.
+pub const USAGE_SAVE_PERIOD: Duration = Duration::from_secs(60);
+
+/// Overload bit of the hardware error status register.
+const OVERLOAD_ERROR: u8 = 1 << 5;
+/// Position changes below one encoder tick are noise, not travel.
+const TRAVEL_DEADBAND: f64 = 2.0 * PI / 4096.0;
+
+#[cfg_attr(feature = "python", gen_stub_pyclass, pyclass(get_all))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct UsageCounters {
+    /// Cumulative time with the torque on (s).
+    pub torque_on_time: f64,
+    /// Cumulative travel of each joint (rad), in joint order.
+    pub joint_travel: [f64; NUM_JOINTS],
+    /// Number of overload errors raised by each motor, in joint order.
+    pub overload_events: [u64; NUM_JOINTS],
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl UsageCounters {
+    fn __repr__(&self) -> String {
+        format!(
+            "UsageCounters(torque_on_time={:.0}s, joint_travel={:.1?}, overload_events={:?})",
+            self.torque_on_time, self.joint_travel, self.overload_events
+        )
+    }
+}
+
+impl UsageCounters {
+    /// Read counters saved with [`UsageCounters::save`].
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid usage file line: {:?}", line),
+            )
+        };
+
+        let mut counters = UsageCounters::default();
+        for line in std::fs::read_to_string(path)?.lines() {
+            let mut values = line.split_whitespace();
+            match values.next() {
+                Some("torque_on_time") => {
+                    counters.torque_on_time = values
+                        .next()
+                        .and_then(|v| v.parse().ok())
+                        .ok_or_else(|| invalid(line))?;
+                }
+                Some("joint_travel") => {
+                    for travel in counters.joint_travel.iter_mut() {
+                        *travel = values
+                            .next()
+                            .and_then(|v| v.parse().ok())
+                            .ok_or_else(|| invalid(line))?;
+                    }
+                }
+                Some("overload_events") => {
+                    for events in counters.overload_events.iter_mut() {
+                        *events = values
+                            .next()
+                            .and_then(|v| v.parse().ok())
+                            .ok_or_else(|| invalid(line))?;
+                    }
+                }
+                // Unknown counters (e.g. from a newer version) are ignored
+                _ => {}
+            }
+        }
+        Ok(counters)
+    }
+
+    /// Write the counters, replacing the file atomically.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let join = |values: Vec<String>| values.join(" ");
+        let content = format!(
+            "torque_on_time {}\njoint_travel {}\noverload_events {}\n",
+            self.torque_on_time,
+            join(self.joint_travel.iter().map(|v| v.to_string()).collect()),
+            join(self.overload_events.iter().map(|v| v.to_string()).collect()),
+        );
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, content)?;
+        std::fs::rename(tmp, path)
+    }
+
+    fn add(&mut self, other: &UsageCounters) {
+        self.torque_on_time += other.torque_on_time;
+        for i in 0..NUM_JOINTS {
+            self.joint_travel[i] += other.joint_travel[i];
+            self.overload_events[i] += other.overload_events[i];
+        }
+    }
+}
+
+/// Control loop side of the counters: turns the successive readings into usage.
+pub(crate) struct UsageTracker {
+    path: Option<PathBuf>,
+    last_positions: Option<[f64; NUM_JOINTS]>,
+    overloaded: [bool; NUM_JOINTS],
+    last_save: Instant,
+}
+
+impl UsageTracker {
+    pub(crate) fn new() -> Self {
+        UsageTracker {
+            path: None,
+            last_positions: None,
+            overloaded: [false; NUM_JOINTS],
+            last_save: Instant::now(),
+        }
+    }
+
+    /// Persist the counters to `path`, adding the ones it already holds.
+    pub(crate) fn set_path(
+        &mut self,
+        path: PathBuf,
+        counters: &mut UsageCounters,
+    ) -> io::Result<()> {
+        match UsageCounters::load(&path) {
+            Ok(saved) => counters.add(&saved),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        counters.save(&path)?;
+        self.path = Some(path);
+        self.last_save = Instant::now();
+        Ok(())
+    }
+
+    /// Account for a control loop tick of `dt` seconds.
+    pub(crate) fn tick(
+        &mut self,
+        counters: &mut UsageCounters,
+        dt: f64,
+        torque_on: bool,
+        positions: Option<[f64; NUM_JOINTS]>,
+    ) {
+        if torque_on {
+            counters.torque_on_time += dt;
+        }
+        let Some(positions) = positions else {
+            return;
+        };
+        match self.last_positions.as_mut() {
+            Some(last) => {
+                for i in 0..NUM_JOINTS {
+                    // The body rotation reading wraps around while spinning
+                    let delta = ((positions[i] - last[i] + PI).rem_euclid(2.0 * PI) - PI).abs();
+                    if delta >= TRAVEL_DEADBAND {
+                        counters.joint_travel[i] += delta;
+                        last[i] = positions[i];
+                    }
+                }
+            }
+            None => self.last_positions = Some(positions),
+        }
+    }
+
+    /// Count the new overloads, an overload stays latched by the motor until it is rebooted.
+    pub(crate) fn error_status(&mut self, counters: &mut UsageCounters, status: [u8; NUM_JOINTS]) {
+        for ((status, was_overloaded), events) in status
+            .iter()
+            .zip(self.overloaded.iter_mut())
+            .zip(counters.overload_events.iter_mut())
+        {
+            let overloaded = status & OVERLOAD_ERROR != 0;
+            if overloaded && !*was_overloaded {
+                *events += 1;
+            }
+            *was_overloaded = overloaded;
+        }
+    }
+
+    /// Save the counters if a usage file is set and `force` or the save period elapsed.
+    pub(crate) fn save(&mut self, counters: &UsageCounters, force: bool) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if !force && self.last_save.elapsed() < USAGE_SAVE_PERIOD {
+            return Ok(());
+        }
+        self.last_save = Instant::now();
+        counters.save(path)
+    }
+}
@@ -0,0 +1,79 @@
+//! Fixtures shared by the integration tests, run against simulated motors.
+
+// Each test file only uses some of them
+#![allow(dead_code)]
+
+use std::{f64::consts::PI, sync::Arc, time::Duration};
+
+use proptest::prelude::*;
+use reachy_mini_motor_controller::{
+    ReachyMiniMotorController,
+    clock::ManualClock,
+    control_loop::{MotorSet, ReachyMiniControlLoop},
+    position_bounds::{JOINT_NAMES, PositionBounds},
+    simulation::{Simulation, SimulationConfig},
+    smoothing::NUM_JOINTS,
+};
+
+/// Resolution of the motor positions (rad).
+pub const POSITION_UNIT: f64 = 2.0 * PI / 4096.0;
+
+/// Period of the control loops driven by a [`ManualClock`].
+pub const PERIOD: Duration = Duration::from_millis(10);
+
+pub fn simulation() -> (Simulation, ReachyMiniMotorController) {
+    let sim = Simulation::new(SimulationConfig {
+        realtime: false,
+        ..Default::default()
+    });
+    let controller = sim.controller();
+    (sim, controller)
+}
+
+/// Let the simulated motors settle on their goals.
+pub fn settle(sim: &Simulation) {
+    for _ in 0..100 {
+        sim.advance(Duration::from_millis(100));
+    }
+}
+
+/// A control loop ticking only when the returned clock is stepped.
+pub fn manual_control_loop(
+    controller: ReachyMiniMotorController,
+) -> (Arc<ManualClock>, ReachyMiniControlLoop) {
+    let clock = Arc::new(ManualClock::new());
+    let control_loop = ReachyMiniControlLoop::with_clock(
+        controller,
+        clock.clone(),
+        PERIOD,
+        None,
+        5,
+        Duration::from_secs(1),
+        MotorSet::default(),
+    )
+    .unwrap();
+    (clock, control_loop)
+}
+
+pub fn joints() -> impl Strategy<Value = [f64; NUM_JOINTS]> {
+    prop::array::uniform9(-3.0f64..3.0)
+}
+
+pub fn bounds() -> impl Strategy<Value = [PositionBounds; NUM_JOINTS]> {
+    prop::array::uniform9((-4.0f64..4.0, 0.0f64..4.0))
+        .prop_map(|b| b.map(|(min, range)| PositionBounds::new(min, min + range)))
+}
+
+pub fn assert_close(actual: &[f64], expected: &[f64], tolerance: f64) {
+    assert_eq!(actual.len(), expected.len());
+    for (joint, (a, e)) in actual.iter().zip(expected).enumerate() {
+        assert!(
+            (a - e).abs() <= tolerance,
+            "{}: {} instead of {} (tolerance {})",
+            JOINT_NAMES[joint],
+            a,
+            e,
+            tolerance
+        );
+    }
+}
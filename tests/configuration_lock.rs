@@ -0,0 +1,67 @@
+//! Tests of the commands rejected while the configuration is locked.
+
+mod common;
+
+use common::{manual_control_loop, simulation};
+use reachy_mini_motor_controller::{
+    HomingConfig,
+    control_law::{ControlInput, ControlLaw, ControlOutput},
+    control_loop::{MotorCommand, MotorError},
+};
+
+/// Drives the joints to their target, i.e. as if no control law was installed.
+struct Passthrough;
+
+impl ControlLaw for Passthrough {
+    fn update(&mut self, input: &ControlInput) -> ControlOutput {
+        ControlOutput {
+            goals: Some(input.target),
+            stewart_currents: None,
+        }
+    }
+}
+
+#[test]
+fn configuration_lock_allows_motion() {
+    let goal = MotorCommand::SetAntennasPositions {
+        positions: [0.0; 2],
+    };
+    let mode = MotorCommand::SetStewartPlatformOperatingMode { mode: 5 };
+    assert!(goal.is_motion() && !goal.is_configuration());
+    assert!(mode.is_configuration() && !mode.is_motion());
+    let wrapped = MotorCommand::WithDeadline {
+        deadline: 0.0,
+        command: Box::new(mode),
+    };
+    assert!(wrapped.is_configuration());
+}
+
+#[test]
+fn configuration_lock_rejects_control_laws_and_homing() {
+    let (_, controller) = simulation();
+    let (_, control_loop) = manual_control_loop(controller);
+    let (tx, _) = std::sync::mpsc::channel();
+    let homing = MotorCommand::HomeBodyRotation {
+        config: HomingConfig::default(),
+        tx,
+    };
+    assert!(homing.is_configuration());
+
+    control_loop.lock_configuration("kiosk").unwrap();
+    assert!(matches!(
+        control_loop.set_control_law(Passthrough),
+        Err(MotorError::ConfigurationLocked())
+    ));
+    assert!(matches!(
+        control_loop.clear_control_law(),
+        Err(MotorError::ConfigurationLocked())
+    ));
+    assert!(matches!(
+        control_loop.home_body_rotation(HomingConfig::default()),
+        Err(MotorError::ConfigurationLocked())
+    ));
+
+    control_loop.unlock_configuration("kiosk").unwrap();
+    assert!(control_loop.set_control_law(Passthrough).is_ok());
+    control_loop.close();
+}
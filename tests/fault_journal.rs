@@ -0,0 +1,37 @@
+//! Tests of the fault journal file rotation.
+
+use reachy_mini_motor_controller::{
+    events::Event,
+    fault_journal::{FaultJournal, read_fault_journal},
+};
+
+#[test]
+fn fault_journal_rotates() {
+    let path = std::env::temp_dir().join(format!("faults-{}.log", std::process::id()));
+    let mut journal = FaultJournal::open(&path, 120, 2).unwrap();
+    let events: Vec<Event> = (0..10)
+        .map(|i| Event {
+            kind: "fault".to_string(),
+            message: format!("Hardware error 0x20 on stewart_{}", i % 6 + 1),
+            timestamp: 1000.0 + i as f64,
+            monotonic_timestamp: i as f64,
+        })
+        .collect();
+    for event in &events {
+        journal.append(event).unwrap();
+    }
+    // Two lines per file, the current one and two rotated ones are kept
+    let read = read_fault_journal(&path).unwrap();
+    assert_eq!(read.len(), 6);
+    for (read, event) in read.iter().zip(&events[4..]) {
+        assert_eq!((&read.kind, &read.message), (&event.kind, &event.message));
+        assert_eq!(read.timestamp, event.timestamp);
+    }
+    for file in [
+        path.clone(),
+        path.with_extension("log.1"),
+        path.with_extension("log.2"),
+    ] {
+        std::fs::remove_file(file).unwrap();
+    }
+}
@@ -0,0 +1,26 @@
+//! Tests of the last state file written by the control loop.
+
+use reachy_mini_motor_controller::last_state::LastState;
+
+#[test]
+fn saving_the_last_state_leaves_the_other_files() {
+    let dir = std::env::temp_dir().join(format!("last-state-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("robot.state");
+    let sibling = dir.join("robot.tmp");
+    std::fs::write(&sibling, "unrelated").unwrap();
+
+    let state = LastState {
+        timestamp: 1000.0,
+        positions: [0.1; 9],
+        torque_enabled: true,
+        control_mode: Some(3),
+        safe_mode: false,
+        clean_shutdown: false,
+    };
+    state.save(&path).unwrap();
+    assert_eq!(LastState::load(&path).unwrap(), state);
+    assert_eq!(std::fs::read_to_string(&sibling).unwrap(), "unrelated");
+    assert!(!dir.join("robot.state.tmp").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,428 @@
+//! Property tests of the joint order and unit conversions, run against simulated motors.
+//!
+//! Each joint gets a distinct value so a scrambled array layout (wrong joint order,
+//! wrong motor ID) or a wrong unit conversion shows up as a mismatch.
+
+use std::{f64::consts::PI, time::Duration};
+
+mod common;
+
+use common::{POSITION_UNIT, assert_close, bounds, joints, settle, simulation};
+use proptest::prelude::*;
+use reachy_mini_motor_controller::{
+    IDENTITY_SIZE, MotorControllerError,
+    animation::{ImportError, ImportOptions, import_csv},
+    control_loop::FullBodyPosition,
+    dry_run::DryRunLog,
+    kinematics::{HeadPose, Kinematics, KinematicsError},
+    last_state::LastState,
+    position_bounds::{JOINT_NAMES, clamp_positions, validate_positions},
+    read_tuning::{ReadStrategy, auto_tune_reads},
+    simulation::SimulatedFault,
+    smoothing::{AntennaSmoother, NUM_JOINTS},
+    trajectory::{Easing, Keyframe, Track, Trajectory},
+};
+
+/// Resolution of the motor velocities (rad/s).
+const VELOCITY_UNIT: f64 = 0.229 * 2.0 * PI / 60.0;
+/// Homing offset register of the XL330.
+const HOMING_OFFSET: u8 = 20;
+
+/// Kinematics with the head pose values as joint positions, to test pose transforms.
+struct PoseAsJoints;
+
+impl Kinematics for PoseAsJoints {
+    fn inverse(&self, pose: &HeadPose) -> Result<[f64; 6], KinematicsError> {
+        let [x, y, z] = pose.translation;
+        let [roll, pitch, yaw] = pose.rotation;
+        Ok([x, y, z, roll, pitch, yaw])
+    }
+
+    fn forward(&self, joints: &[f64; 6]) -> Result<HeadPose, KinematicsError> {
+        Ok(HeadPose {
+            translation: [joints[0], joints[1], joints[2]],
+            rotation: [joints[3], joints[4], joints[5]],
+        })
+    }
+}
+
+#[test]
+fn joint_names_match_motor_ids() {
+    let (_, controller) = simulation();
+    let name_id = controller.get_motor_name_id();
+    assert_eq!(name_id.len(), NUM_JOINTS);
+    for (joint, name) in JOINT_NAMES.iter().enumerate() {
+        assert_eq!(
+            controller.joint_index(name_id[*name]),
+            Some(joint),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn auto_tune_reads_applies_the_best_configuration() {
+    let (_, mut controller) = simulation();
+    let tuning = auto_tune_reads(&mut controller, &[0, 5], 10).unwrap();
+    // Every strategy with each return delay time, the firmware supports the fast sync read
+    assert_eq!(tuning.benchmarks.len(), 2 * ReadStrategy::ALL.len());
+    assert!(tuning.benchmarks.iter().all(|b| b.failures == 0));
+    assert!(
+        tuning
+            .benchmarks
+            .iter()
+            .all(|b| !b.is_better_than(&tuning.best))
+    );
+    assert_eq!(controller.read_strategy(), tuning.best.strategy);
+    let delay = tuning.best.return_delay_time.unwrap();
+    assert_eq!(
+        controller.read_all_return_delay_times().unwrap(),
+        [delay; NUM_JOINTS]
+    );
+}
+
+#[test]
+fn bus_glitches_are_transient_errors() {
+    let (sim, mut controller) = simulation();
+    let body_rotation = controller.get_motor_name_id()["body_rotation"];
+
+    sim.set_fault(body_rotation, Some(SimulatedFault::Timeout));
+    let e = controller.read_body_rotation_operating_mode().unwrap_err();
+    assert!(matches!(e, MotorControllerError::MotorNotResponding { id } if id == body_rotation));
+    assert!(e.is_transient());
+    assert_eq!(e.motor_id(), Some(body_rotation));
+
+    sim.set_fault(body_rotation, Some(SimulatedFault::Checksum));
+    let e = controller.read_all_positions().unwrap_err();
+    assert!(matches!(e, MotorControllerError::Checksum));
+    assert!(e.is_transient());
+
+    sim.set_fault(body_rotation, None);
+    let e = controller
+        .write_identity(&[0; IDENTITY_SIZE + 1])
+        .unwrap_err();
+    assert!(matches!(e, MotorControllerError::OutOfBounds(_)));
+    assert!(!e.is_transient());
+}
+
+#[test]
+fn tracks_cover_each_joint_once() {
+    let mut joints: Vec<usize> = Track::ALL.iter().flat_map(Track::joints).collect();
+    joints.sort();
+    assert_eq!(joints, (0..NUM_JOINTS).collect::<Vec<_>>());
+    for track in Track::ALL {
+        assert_eq!(Track::from_name(track.name()), Some(track));
+    }
+}
+
+proptest! {
+    #[test]
+    fn from_joints_roundtrip(joints in joints(), timestamp in 0.0f64..1e9) {
+        let position = FullBodyPosition::from_joints(joints, timestamp);
+        prop_assert_eq!(position.joints(), joints);
+        prop_assert_eq!(position.timestamp, timestamp);
+    }
+
+    #[test]
+    fn trajectory_file_roundtrip(joints in joints(), duration in 0.01f64..100.0) {
+        let trajectory = Trajectory::new(vec![
+            Keyframe::new(0.0, joints),
+            Keyframe { time: duration, positions: joints.map(|p| -p), easing: Easing::Cubic },
+        ])
+        .unwrap();
+        let path = std::env::temp_dir().join(format!("trajectory-{}.csv", std::process::id()));
+        trajectory.save(&path).unwrap();
+        let loaded = Trajectory::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        prop_assert_eq!(loaded.keyframes(), trajectory.keyframes());
+    }
+
+    #[test]
+    fn animation_import_checks_bounds(joints in joints(), bounds in bounds(), fps in 1.0f64..120.0) {
+        let degrees: Vec<String> = joints.iter().map(|p| p.to_degrees().to_string()).collect();
+        let text = format!(
+            "frame,{}\n10,{}\n20,{}\n",
+            JOINT_NAMES.join(","),
+            degrees.join(","),
+            degrees.join(",")
+        );
+        let options = ImportOptions { fps, degrees: true, bounds };
+        match (import_csv(&text, &options, None), validate_positions(0, &joints, &bounds)) {
+            (Ok(trajectory), Ok(())) => {
+                let last = trajectory.keyframes()[1];
+                prop_assert!((last.time - 10.0 / fps).abs() < 1e-9);
+                assert_close(&last.positions, &joints, 1e-9);
+            }
+            (Err(ImportError::OutOfBounds { keyframe, error }), Err(expected)) => {
+                prop_assert_eq!(keyframe, 0);
+                prop_assert_eq!(error.joint, expected.joint);
+            }
+            (imported, expected) => {
+                prop_assert!(false, "imported {:?}, expected {:?}", imported.err(), expected)
+            }
+        }
+    }
+
+    #[test]
+    fn mirrored_trajectory(joints in joints()) {
+        let trajectory = Trajectory::new(vec![Keyframe::new(0.0, joints)]).unwrap();
+        let mirrored = trajectory.mirrored(&PoseAsJoints).unwrap();
+        let [body, x, y, z, roll, pitch, yaw, right, left] = joints;
+        prop_assert_eq!(
+            mirrored.keyframes()[0].positions,
+            [-body, x, -y, z, -roll, pitch, -yaw, left, right]
+        );
+        let twice = mirrored.mirrored(&PoseAsJoints).unwrap();
+        prop_assert_eq!(twice.keyframes(), trajectory.keyframes());
+    }
+
+    #[test]
+    fn eased_samples_stay_between_keyframes(
+        joints in joints(),
+        easing in prop::sample::select(Easing::ALL.to_vec()),
+        t in 0.0f64..1.0,
+    ) {
+        let trajectory = Trajectory::new(vec![
+            Keyframe { time: 0.0, positions: [0.0; NUM_JOINTS], easing },
+            Keyframe::new(1.0, joints),
+        ])
+        .unwrap();
+        for (sample, joint) in trajectory.sample(t).iter().zip(joints) {
+            prop_assert!(sample.abs() <= joint.abs() + 1e-12 && sample * joint >= 0.0);
+        }
+        prop_assert_eq!(trajectory.sample(0.0), [0.0; NUM_JOINTS]);
+        prop_assert_eq!(trajectory.sample(1.0), joints);
+    }
+
+    #[test]
+    fn trajectory_velocity_is_the_sample_derivative(
+        joints in joints(),
+        easing in prop::sample::select(Easing::ALL.to_vec()),
+        duration in 0.1f64..10.0,
+        t in 0.01f64..0.99,
+    ) {
+        let trajectory = Trajectory::new(vec![
+            Keyframe { time: 0.0, positions: [0.0; NUM_JOINTS], easing },
+            Keyframe::new(duration, joints),
+        ])
+        .unwrap();
+        let (time, dt) = (t * duration, 1e-6 * duration);
+        let before = trajectory.sample(time - dt);
+        let after = trajectory.sample(time + dt);
+        let derivative: Vec<f64> = (0..NUM_JOINTS).map(|i| (after[i] - before[i]) / (2.0 * dt)).collect();
+        assert_close(&trajectory.velocity(time), &derivative, 1e-4);
+    }
+
+    #[test]
+    fn damped_antenna_smoother_settles_without_overshoot(
+        start in prop::array::uniform2(-PI..PI),
+        targets in prop::array::uniform2(-PI..PI),
+        frequency in 0.5f64..20.0,
+        damping in 1.0f64..3.0,
+    ) {
+        let mut smoother = AntennaSmoother::new(frequency, damping, start);
+        smoother.set_targets(targets);
+        for _ in 0..100_000 {
+            let positions = smoother.step(0.01);
+            for i in 0..2 {
+                let (low, high) = (start[i].min(targets[i]), start[i].max(targets[i]));
+                prop_assert!(positions[i] >= low - 1e-6 && positions[i] <= high + 1e-6);
+            }
+            if smoother.is_settled() {
+                break;
+            }
+        }
+        prop_assert!(smoother.is_settled());
+        assert_close(&smoother.positions(), &targets, 1e-3);
+    }
+
+    #[test]
+    fn last_state_file_roundtrip(
+        joints in joints(),
+        timestamp in 0.0f64..1e9,
+        control_mode in prop::option::of(0u8..16),
+        flags in prop::array::uniform3(any::<bool>()),
+    ) {
+        let state = LastState {
+            timestamp,
+            positions: joints,
+            torque_enabled: flags[0],
+            control_mode,
+            safe_mode: flags[1],
+            clean_shutdown: flags[2],
+        };
+        let path = std::env::temp_dir().join(format!("last-state-{}.txt", std::process::id()));
+        state.save(&path).unwrap();
+        let loaded = LastState::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        prop_assert_eq!(loaded, state);
+        prop_assert!(loaded.is_pose_unchanged(&joints, 0.0));
+    }
+
+    #[test]
+    fn new_matches_joint_order(joints in joints()) {
+        let position = FullBodyPosition::new(joints[0], joints[1..7].to_vec(), joints[7..].to_vec());
+        prop_assert_eq!(position.joints(), joints);
+    }
+
+    #[test]
+    fn clamp_to_limits_is_joint_wise(joints in joints(), bounds in bounds()) {
+        let lower = FullBodyPosition::from_joints(bounds.map(|b| b.min), 0.0);
+        let upper = FullBodyPosition::from_joints(bounds.map(|b| b.max), 0.0);
+        let clamped = FullBodyPosition::from_joints(joints, 0.0).clamp_to_limits(&lower, &upper);
+        let expected = clamp_positions(0, &joints, &bounds).unwrap();
+        prop_assert_eq!(clamped.joints().to_vec(), expected);
+    }
+
+    #[test]
+    fn clamped_positions_are_valid(
+        joints in joints(),
+        bounds in bounds(),
+        first in 0..NUM_JOINTS,
+        len in 0..=NUM_JOINTS,
+    ) {
+        let positions = &joints[..len.min(NUM_JOINTS - first)];
+        let clamped = clamp_positions(first, positions, &bounds).unwrap();
+        prop_assert_eq!(clamped.len(), positions.len());
+        prop_assert!(validate_positions(first, &clamped, &bounds).is_ok());
+        // Idempotent, and only moves the out of bounds goals
+        prop_assert_eq!(&clamp_positions(first, &clamped, &bounds).unwrap(), &clamped);
+        for (i, (&before, &after)) in positions.iter().zip(&clamped).enumerate() {
+            if bounds[first + i].contains(before) {
+                prop_assert_eq!(before, after);
+            }
+        }
+        if validate_positions(first, positions, &bounds).is_ok() {
+            prop_assert_eq!(clamped.as_slice(), positions);
+        }
+    }
+
+    #[test]
+    fn validation_reports_the_faulty_joint(joints in joints(), bounds in bounds()) {
+        match validate_positions(0, &joints, &bounds) {
+            Ok(()) => prop_assert!(joints.iter().zip(&bounds).all(|(v, b)| b.contains(*v))),
+            Err(e) => {
+                prop_assert!(!bounds[e.joint].contains(joints[e.joint]));
+                prop_assert_eq!(e.value, joints[e.joint]);
+                prop_assert!(joints[..e.joint].iter().zip(&bounds).all(|(v, b)| b.contains(*v)));
+            }
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn read_positions_in_joint_order(joints in joints()) {
+        let (sim, mut controller) = simulation();
+        for (joint, &position) in joints.iter().enumerate() {
+            sim.set_position(joint, position);
+        }
+        let read = controller.read_all_positions().unwrap();
+        assert_close(&read, &joints, POSITION_UNIT / 2.0 + 1e-9);
+    }
+
+    #[test]
+    fn read_strategies_read_the_same_positions(joints in joints()) {
+        let (sim, mut controller) = simulation();
+        for (joint, &position) in joints.iter().enumerate() {
+            sim.set_position(joint, position);
+        }
+        controller.detect_firmware().unwrap();
+        for strategy in ReadStrategy::ALL {
+            controller.set_read_strategy(strategy).unwrap();
+            let read = controller.read_all_positions().unwrap();
+            assert_close(&read, &joints, POSITION_UNIT / 2.0 + 1e-9);
+            let read = controller.read_joint_positions(&[0, 3, 8]).unwrap();
+            assert_close(&read, &[joints[0], joints[3], joints[8]], POSITION_UNIT / 2.0 + 1e-9);
+        }
+    }
+
+    #[test]
+    fn goal_positions_in_joint_order(joints in joints()) {
+        let (sim, mut controller) = simulation();
+        controller.enable_torque().unwrap();
+        controller.set_all_goal_positions(joints).unwrap();
+        settle(&sim);
+        assert_close(&sim.positions(), &joints, POSITION_UNIT);
+    }
+
+    #[test]
+    fn group_goals_only_move_their_joints(joints in joints()) {
+        let (sim, mut controller) = simulation();
+        controller.enable_torque().unwrap();
+
+        controller.set_stewart_platform_position(joints[1..7].try_into().unwrap()).unwrap();
+        settle(&sim);
+        let mut expected = [0.0; NUM_JOINTS];
+        expected[1..7].copy_from_slice(&joints[1..7]);
+        assert_close(&sim.positions(), &expected, POSITION_UNIT);
+
+        controller.set_antennas_positions(joints[7..].try_into().unwrap()).unwrap();
+        controller.set_body_rotation(joints[0]).unwrap();
+        settle(&sim);
+        assert_close(&sim.positions(), &joints, POSITION_UNIT);
+    }
+
+    #[test]
+    fn dry_run_records_goals_without_moving(joints in joints()) {
+        let (sim, mut controller) = simulation();
+        let log = DryRunLog::default();
+        controller.set_dry_run(Some(log.clone()));
+        controller.enable_torque().unwrap();
+        controller.set_all_goal_positions(joints).unwrap();
+        settle(&sim);
+        prop_assert!(sim.positions().iter().all(|&p| p == 0.0));
+
+        let writes = log.take();
+        prop_assert_eq!(writes.len(), 2);
+        prop_assert_eq!(&writes[1].register, "goal_position");
+        prop_assert_eq!(writes[1].ids.len(), NUM_JOINTS);
+        prop_assert_eq!(&writes[1].values, &format!("{:?}", joints));
+    }
+
+    #[test]
+    fn grouped_goal_write_matches_sync_write(joints in joints()) {
+        let (sim, mut controller) = simulation();
+        controller.enable_torque().unwrap();
+        controller.set_all_goal_positions_by_group(joints).unwrap();
+        settle(&sim);
+        assert_close(&sim.positions(), &joints, POSITION_UNIT);
+    }
+
+    #[test]
+    fn body_spin_velocity(velocity in -3.0f64..3.0) {
+        let (sim, mut controller) = simulation();
+        controller.enable_torque().unwrap();
+        controller.spin_body(velocity).unwrap();
+        for _ in 0..100 {
+            sim.advance(Duration::from_millis(10));
+        }
+        let travel = sim.positions()[0];
+        prop_assert!(
+            (travel - velocity).abs() <= VELOCITY_UNIT / 2.0 + 1e-9,
+            "travelled {} rad in 1 s at {} rad/s", travel, velocity
+        );
+        prop_assert!(sim.positions()[1..].iter().all(|&p| p == 0.0));
+    }
+
+    #[test]
+    fn homing_offset_shifts_one_joint(joints in joints(), joint in 0..NUM_JOINTS, offset in -1024i32..1024) {
+        let (sim, mut controller) = simulation();
+        for (joint, &position) in joints.iter().enumerate() {
+            sim.set_position(joint, position);
+        }
+        let before = controller.read_all_positions().unwrap();
+
+        let id = controller.get_motor_name_id()[JOINT_NAMES[joint]];
+        controller.write_raw_bytes(id, HOMING_OFFSET, &offset.to_le_bytes()).unwrap();
+        // The offset applies from the next position update
+        sim.set_position(joint, joints[joint]);
+
+        let mut expected = before;
+        expected[joint] += offset as f64 * POSITION_UNIT;
+        assert_close(&controller.read_all_positions().unwrap(), &expected, 1e-9);
+    }
+}
@@ -0,0 +1,77 @@
+//! Tests of the safe mode limits, run against simulated motors.
+
+mod common;
+
+use common::{bounds, manual_control_loop, simulation};
+use proptest::prelude::*;
+use reachy_mini_motor_controller::{
+    HomingConfig,
+    control_loop::{MotorCommand, MotorError},
+    safe_mode::SafeModeLimits,
+};
+
+#[test]
+fn enabling_safe_mode_caps_the_goal_currents() {
+    let (sim, mut controller) = simulation();
+    controller
+        .set_stewart_platform_goal_current([1000; 6])
+        .unwrap();
+    let (_, control_loop) = manual_control_loop(sim.controller());
+
+    control_loop.safe_mode(true).unwrap();
+    // The commands are handled in order, between the ticks
+    control_loop.async_read_raw_bytes(11, 102, 2).unwrap();
+    let max = SafeModeLimits::default().max_goal_current as i16;
+    assert_eq!(
+        controller.read_stewart_platform_goal_current().unwrap(),
+        [max; 6]
+    );
+    control_loop.close();
+}
+
+#[test]
+fn safe_mode_rejects_raw_writes() {
+    let (_, controller) = simulation();
+    let (_, control_loop) = manual_control_loop(controller);
+    let write = MotorCommand::WriteRawBytes {
+        id: 11,
+        addr: 102,
+        data: vec![0xFF, 0x7F],
+    };
+    assert!(write.bypasses_safe_mode());
+    assert!(control_loop.push_configuration(write.clone()).is_ok());
+
+    control_loop.safe_mode(true).unwrap();
+    assert!(matches!(
+        control_loop.push_configuration(write),
+        Err(MotorError::ControllerError(_))
+    ));
+    control_loop.close();
+}
+
+#[test]
+fn safe_mode_rejects_homing() {
+    let (_, controller) = simulation();
+    let (_, control_loop) = manual_control_loop(controller);
+
+    control_loop.safe_mode(true).unwrap();
+    assert!(matches!(
+        control_loop.home_body_rotation(HomingConfig::default()),
+        Err(MotorError::ControllerError(_))
+    ));
+    control_loop.close();
+}
+
+proptest! {
+    #[test]
+    fn safe_workspace_is_within_the_bounds(bounds in bounds(), scale in 0.01f64..=1.0) {
+        let limits = SafeModeLimits { workspace_scale: scale, ..Default::default() };
+        prop_assert!(limits.validate().is_ok());
+        for (safe, bounds) in limits.workspace(&bounds).iter().zip(&bounds) {
+            prop_assert!(safe.min >= bounds.min - 1e-12 && safe.max <= bounds.max + 1e-12);
+            prop_assert!((safe.min + safe.max - bounds.min - bounds.max).abs() < 1e-9);
+            prop_assert!(((safe.max - safe.min) - scale * (bounds.max - bounds.min)).abs() < 1e-9);
+        }
+    }
+
+}
@@ -0,0 +1,36 @@
+//! Tests of the shutdown on SIGINT/SIGTERM, which must leave the process running.
+#![cfg(unix)]
+
+mod common;
+
+use std::time::Duration;
+
+use common::{PERIOD, manual_control_loop, simulation};
+use reachy_mini_motor_controller::control_loop::MotorCommand;
+
+#[test]
+fn signal_shutdown_stops_the_loop_without_exiting() {
+    let (_, controller) = simulation();
+    let (clock, control_loop) = manual_control_loop(controller);
+    control_loop
+        .push_command(MotorCommand::EnableTorque())
+        .unwrap();
+    control_loop
+        .enable_signal_shutdown(Duration::from_millis(10))
+        .unwrap();
+    // The handlers are installed once the command is handled, before the next tick
+    control_loop.async_read_raw_bytes(11, 102, 2).unwrap();
+    assert!(clock.step(PERIOD));
+    assert!(control_loop.is_torque_enabled().unwrap());
+
+    unsafe { libc::kill(libc::getpid(), libc::SIGTERM) };
+    for _ in 0..100 {
+        if control_loop.get_shutdown_signal().is_some() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(control_loop.get_shutdown_signal(), Some(128 + 15));
+    assert!(!control_loop.is_torque_enabled().unwrap());
+    control_loop.close();
+}
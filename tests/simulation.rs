@@ -0,0 +1,37 @@
+//! Tests of the dynamics of the simulated motors.
+
+mod common;
+
+use std::time::Duration;
+
+use common::{POSITION_UNIT, assert_close, settle};
+use reachy_mini_motor_controller::{
+    simulation::{JointDynamics, Simulation, SimulationConfig},
+    smoothing::NUM_JOINTS,
+};
+
+#[test]
+fn simulated_joints_are_velocity_limited_and_noisy() {
+    let sim = Simulation::new(SimulationConfig {
+        realtime: false,
+        ..SimulationConfig::uniform(JointDynamics {
+            max_velocity: 1.0,
+            noise: 0.01,
+            ..Default::default()
+        })
+    });
+    let mut controller = sim.controller();
+    controller.enable_torque().unwrap();
+    controller
+        .set_all_goal_positions([0.5; NUM_JOINTS])
+        .unwrap();
+
+    sim.advance(Duration::from_millis(100));
+    assert_close(&sim.positions(), &[0.1; NUM_JOINTS], 1e-9);
+    let read = controller.read_all_positions().unwrap();
+    assert_close(&read, &sim.positions(), 0.01 + POSITION_UNIT);
+    assert!(read.iter().any(|&p| (p - 0.1).abs() > POSITION_UNIT));
+
+    settle(&sim);
+    assert_close(&sim.positions(), &[0.5; NUM_JOINTS], POSITION_UNIT);
+}
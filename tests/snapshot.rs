@@ -0,0 +1,23 @@
+//! Tests of the snapshot of the state of the motors at a loop tick.
+
+mod common;
+
+use common::{PERIOD, POSITION_UNIT, assert_close, manual_control_loop, simulation};
+
+#[test]
+fn snapshot_comes_from_one_tick() {
+    let (sim, controller) = simulation();
+    let (clock, control_loop) = manual_control_loop(controller);
+    assert!(clock.step(PERIOD));
+    sim.set_position(0, 0.1);
+    assert!(clock.step(PERIOD));
+
+    let snapshot = control_loop.get_snapshot();
+    assert_close(&[snapshot.position.body_yaw], &[0.1], POSITION_UNIT);
+    assert!(snapshot.velocity[0] > 0.0);
+    assert!(snapshot.velocity[1..].iter().all(|&v| v == 0.0));
+    assert_eq!(snapshot.torque_enabled, Some(false));
+    assert!(snapshot.health.feedback_ok);
+    assert!(snapshot.stats.is_none());
+    control_loop.close();
+}
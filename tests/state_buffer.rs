@@ -0,0 +1,39 @@
+//! Tests of the state buffer updated in place by the control loop.
+
+mod common;
+
+use std::time::Duration;
+
+use common::{POSITION_UNIT, assert_close, simulation};
+use reachy_mini_motor_controller::control_loop::{MotorSet, ReachyMiniControlLoop};
+
+#[test]
+fn state_buffer_follows_the_reads() {
+    let (sim, controller) = simulation();
+    let joints = [0.5, -0.2, -0.1, 0.0, 0.1, 0.2, 0.3, -1.0, 1.0];
+    for (joint, &position) in joints.iter().enumerate() {
+        sim.set_position(joint, position);
+    }
+    let control_loop = ReachyMiniControlLoop::with_controller(
+        controller,
+        Duration::from_millis(1),
+        None,
+        5,
+        Duration::from_secs(1),
+        MotorSet::default(),
+    )
+    .unwrap();
+    let buffer = control_loop.get_state_buffer();
+    assert!(buffer.sequence() >= 1);
+    assert_close(&buffer.read().joints(), &joints, POSITION_UNIT);
+
+    sim.set_position(0, -0.5);
+    let sequence = buffer.sequence();
+    while buffer.sequence() < sequence + 2 {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    let position = buffer.read();
+    assert_close(&[position.body_yaw], &[-0.5], POSITION_UNIT);
+    assert!(position.monotonic_timestamp > 0.0);
+    control_loop.close();
+}
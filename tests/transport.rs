@@ -0,0 +1,75 @@
+//! Tests of the controller driving the motors through other transports than the serial
+//! port.
+
+mod common;
+
+use std::{
+    io::{self, Read, Write},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use common::{POSITION_UNIT, assert_close, settle, simulation};
+use reachy_mini_motor_controller::{
+    ReachyMiniMotorController, simulation::SimulatedPort, transport::Transport,
+};
+
+/// Transport to the simulated motors counting the bytes sent.
+struct CountingTransport {
+    port: SimulatedPort,
+    written: Arc<AtomicUsize>,
+}
+
+impl Transport for CountingTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.port.read(buf)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written.fetch_add(buf.len(), Ordering::Relaxed);
+        self.port.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+
+    fn timeout(&self) -> Duration {
+        serialport::SerialPort::timeout(&self.port)
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        Ok(serialport::SerialPort::set_timeout(
+            &mut self.port,
+            timeout,
+        )?)
+    }
+
+    fn bytes_to_read(&self) -> io::Result<u32> {
+        Ok(serialport::SerialPort::bytes_to_read(&self.port)?)
+    }
+}
+
+#[test]
+fn controller_drives_any_transport() {
+    let (sim, _) = simulation();
+    let written = Arc::new(AtomicUsize::new(0));
+    let mut controller = ReachyMiniMotorController::with_transport(CountingTransport {
+        port: sim.port(),
+        written: written.clone(),
+    });
+    let joints = [0.5, -0.2, -0.1, 0.0, 0.1, 0.2, 0.3, -1.0, 1.0];
+
+    controller.enable_torque().unwrap();
+    controller.set_all_goal_positions(joints).unwrap();
+    settle(&sim);
+    assert_close(
+        &controller.read_all_positions().unwrap(),
+        &joints,
+        POSITION_UNIT,
+    );
+    assert!(written.load(Ordering::Relaxed) > 0);
+}